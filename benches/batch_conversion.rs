@@ -0,0 +1,47 @@
+use battlesnake_game_types::compact_representation::StandardCellBoard4Snakes11x11;
+use battlesnake_game_types::types::build_snake_id_map;
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const GAMES: usize = 1000;
+
+fn recorded_games() -> Vec<DEGame> {
+    let game_fixture = include_str!("../fixtures/late_stage.json");
+    let g: DEGame = serde_json::from_str(game_fixture).expect("the json literal is valid");
+    vec![g; GAMES]
+}
+
+fn bench_batch_conversion(c: &mut Criterion) {
+    let games = recorded_games();
+    let id_map = build_snake_id_map(&games[0]);
+
+    c.bench_function("convert one at a time", |b| {
+        b.iter(|| {
+            for g in games.iter() {
+                black_box(
+                    StandardCellBoard4Snakes11x11::convert_from_game(g.clone(), &id_map).unwrap(),
+                );
+            }
+        })
+    });
+    c.bench_function("convert_many", |b| {
+        b.iter(|| {
+            StandardCellBoard4Snakes11x11::convert_many(games.iter().cloned(), &id_map).for_each(
+                |board| {
+                    black_box(board.unwrap());
+                },
+            )
+        })
+    });
+    #[cfg(feature = "rayon")]
+    c.bench_function("par_convert_many", |b| {
+        b.iter(|| {
+            black_box(StandardCellBoard4Snakes11x11::par_convert_many(
+                &games, &id_map,
+            ))
+        })
+    });
+}
+
+criterion_group!(benches, bench_batch_conversion);
+criterion_main!(benches);
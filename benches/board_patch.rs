@@ -0,0 +1,88 @@
+use battlesnake_game_types::compact_representation::standard::CellBoard16Snakes50x50;
+use battlesnake_game_types::types::SimulatorInstruments;
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use battlesnake_game_types::{
+    compact_representation::StandardCellBoard4Snakes11x11,
+    types::{build_snake_id_map, Move, SimulableGame, SnakeId},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+fn start_of_game(size: u32) -> DEGame {
+    let game_fixture = include_str!("../fixtures/start_of_game.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let mut g = g.expect("the json literal is valid");
+    g.board.width = size;
+    g.board.height = size;
+    g
+}
+
+fn moves() -> Vec<(SnakeId, Move)> {
+    vec![
+        (SnakeId(0), Move::Up),
+        (SnakeId(1), Move::Right),
+        (SnakeId(2), Move::Down),
+        (SnakeId(3), Move::Left),
+    ]
+}
+
+fn copy_vs_patch_silly(c: &mut Criterion) {
+    let g = start_of_game(50);
+    let snake_id_mapping = build_snake_id_map(&g);
+    let compact: CellBoard16Snakes50x50 = g.as_cell_board(&snake_id_mapping).unwrap();
+    let instruments = Instruments {};
+    let moves = moves();
+
+    c.bench_function("silly 50x50 - copy", |b| {
+        b.iter(|| {
+            black_box(&compact)
+                .simulate_with_moves(&instruments, moves.iter().map(|(id, m)| (*id, [*m])))
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+
+    let mut board = compact;
+    c.bench_function("silly 50x50 - patch", |b| {
+        b.iter(|| {
+            let patch = black_box(&mut board).apply_moves(&moves);
+            board.undo(patch);
+        })
+    });
+}
+
+fn copy_vs_patch_standard(c: &mut Criterion) {
+    let g = start_of_game(11);
+    let snake_id_mapping = build_snake_id_map(&g);
+    let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+    let instruments = Instruments {};
+    let moves = moves();
+
+    c.bench_function("standard 11x11 - copy", |b| {
+        b.iter(|| {
+            black_box(&compact)
+                .simulate_with_moves(&instruments, moves.iter().map(|(id, m)| (*id, [*m])))
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+
+    let mut board = compact;
+    c.bench_function("standard 11x11 - patch", |b| {
+        b.iter(|| {
+            let patch = black_box(&mut board).apply_moves(&moves);
+            board.undo(patch);
+        })
+    });
+}
+
+criterion_group!(benches, copy_vs_patch_silly, copy_vs_patch_standard);
+criterion_main!(benches);
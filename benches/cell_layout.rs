@@ -0,0 +1,99 @@
+//! what wider cells cost: the same boards simulated with `u8`, `u16` and 32 bit cell indices,
+//! which differ only in the size of each cell's `idx` field. The 32 bit index makes an 8 byte
+//! cell, the size a cell packed in to a u64 would be
+
+use battlesnake_game_types::compact_representation::dimensions::Square;
+use battlesnake_game_types::compact_representation::standard::CellBoard;
+use battlesnake_game_types::compact_representation::CellNum;
+use battlesnake_game_types::types::{
+    build_snake_id_map, Move, SimulableGame, SimulatorInstruments, SnakeIDGettableGame,
+};
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+/// a cell index that makes cells 8 bytes wide, standing in for a u64 per cell
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Wide(u32);
+
+impl std::fmt::Display for Wide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl CellNum for Wide {
+    const MAX_CELLS: usize = u16::MAX as usize + 1;
+
+    fn as_usize(&self) -> usize {
+        self.0 as usize
+    }
+
+    fn from_i32(i: i32) -> Self {
+        Wide(i as u32)
+    }
+
+    fn from_usize(i: usize) -> Self {
+        Wide(i as u32)
+    }
+}
+
+type Board<T> = CellBoard<T, Square, { 11 * 11 }, 4>;
+
+fn board<T: CellNum>(fixture: &str) -> Board<T> {
+    let g: Result<DEGame, _> = serde_json::from_slice(fixture.as_bytes());
+    let g = g.expect("the json literal is valid");
+    let snake_id_mapping = build_snake_id_map(&g);
+    g.as_cell_board(&snake_id_mapping).unwrap()
+}
+
+fn bench_layout<T: CellNum>(c: &mut Criterion, name: &str) {
+    let instruments = Instruments {};
+    let mut group = c.benchmark_group(format!(
+        "{} cells, {} byte board",
+        name,
+        std::mem::size_of::<Board<T>>()
+    ));
+    for (fixture_name, fixture) in [
+        (
+            "start of game",
+            include_str!("../fixtures/start_of_game.json"),
+        ),
+        ("late stage", include_str!("../fixtures/late_stage.json")),
+    ] {
+        let compact = board::<T>(fixture);
+        let moves = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| (id, Move::all()))
+            .collect::<Vec<_>>();
+        group.bench_function(fixture_name, |b| {
+            b.iter(|| {
+                black_box(&compact)
+                    .simulate_with_moves(&instruments, moves.clone())
+                    .for_each(|child| {
+                        black_box(child);
+                    })
+            })
+        });
+        group.bench_function(format!("{} copy", fixture_name), |b| {
+            b.iter(|| black_box(*black_box(&compact)))
+        });
+    }
+    group.finish();
+}
+
+fn cell_layout(c: &mut Criterion) {
+    bench_layout::<u8>(c, "u8");
+    bench_layout::<u16>(c, "u16");
+    bench_layout::<Wide>(c, "8 byte");
+}
+
+criterion_group!(benches, cell_layout);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use battlesnake_game_types::compact_representation::standard::CellBoard8Snakes15x15;
+use battlesnake_game_types::types::{
+    build_snake_id_map, Move, SimulableGame, SimulatorInstruments, SnakeIDGettableGame, Vector,
+};
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::SmallRng, SeedableRng};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+/// start of game on a 15x15 board with a copy of every snake moved up and to the right
+fn eight_snakes() -> CellBoard8Snakes15x15 {
+    let game_fixture = include_str!("../fixtures/start_of_game.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let mut g = g.expect("the json literal is valid");
+    g.board.width = 15;
+    g.board.height = 15;
+    g.board.food.clear();
+    let copies = g
+        .board
+        .snakes
+        .iter()
+        .map(|snake| {
+            let mut copy = snake.clone();
+            copy.id = format!("{}-copy", snake.id);
+            copy.head = copy.head.add_vec(Vector { x: 5, y: 5 });
+            for p in copy.body.iter_mut() {
+                *p = p.add_vec(Vector { x: 5, y: 5 });
+            }
+            copy
+        })
+        .collect::<Vec<_>>();
+    g.board.snakes.extend(copies);
+    let snake_id_mapping = build_snake_id_map(&g);
+    g.as_cell_board(&snake_id_mapping).unwrap()
+}
+
+fn royale(c: &mut Criterion) {
+    let compact = eight_snakes();
+    let instruments = Instruments {};
+    let moves = compact
+        .get_snake_ids()
+        .into_iter()
+        .map(|id| (id, Move::all()))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("8 snakes 15x15");
+    group.sample_size(10);
+    group.bench_function("every child", |b| {
+        b.iter(|| {
+            black_box(&compact)
+                .simulate_with_moves(&instruments, moves.clone())
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+    group.bench_function("first 256 children", |b| {
+        b.iter(|| {
+            black_box(&compact)
+                .simulate_with_moves(&instruments, moves.clone())
+                .take(256)
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+    let mut rng = SmallRng::seed_from_u64(0);
+    group.bench_function("256 sampled children", |b| {
+        b.iter(|| {
+            black_box(&compact)
+                .simulate_with_moves_sampled(&instruments, moves.clone(), 256, &mut rng)
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, royale);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+use battlesnake_game_types::compact_representation::standard::CellBoard8Snakes25x25;
+use battlesnake_game_types::compact_representation::wrapped::CellBoard8SnakesSquare25x25;
+use battlesnake_game_types::fixtures::bench_fixtures;
+use battlesnake_game_types::types::{
+    build_snake_id_map, Move, ReasonableMovesGame, SimulableGame, SimulatorInstruments,
+    SnakeIDGettableGame,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+/// simulates every combination of reasonable moves, which keeps the 8 snake scenario to a
+/// sensible number of children
+fn bench_board<B>(c: &mut Criterion, scenario: &str, board: B)
+where
+    B: SimulableGame<Instruments, 8> + ReasonableMovesGame + SnakeIDGettableGame,
+{
+    let instruments = Instruments {};
+    let moves: Vec<(B::SnakeIDType, Vec<Move>)> = board.reasonable_moves_for_each_snake().collect();
+
+    c.bench_function(&format!("{} reasonable children", scenario), |b| {
+        b.iter(|| {
+            black_box(&board)
+                .simulate_with_moves(&instruments, moves.clone())
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+}
+
+fn scenarios(c: &mut Criterion) {
+    for bench in bench_fixtures() {
+        let game = bench.fixture.game();
+        let id_map = build_snake_id_map(&game);
+        if game.is_wrapped() {
+            let board: CellBoard8SnakesSquare25x25 = game.as_wrapped_cell_board(&id_map).unwrap();
+            bench_board(c, bench.scenario, board);
+        } else {
+            let board: CellBoard8Snakes25x25 = game.as_cell_board(&id_map).unwrap();
+            bench_board(c, bench.scenario, board);
+        }
+    }
+}
+
+criterion_group!(benches, scenarios);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+use battlesnake_game_types::compact_representation::{
+    SimulationContext, StandardCellBoard4Snakes11x11,
+};
+use battlesnake_game_types::types::{
+    build_snake_id_map, Move, SimulableGame, SimulatorInstruments, SnakeIDGettableGame,
+};
+use battlesnake_game_types::wire_representation::Game as DEGame;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+fn bench_simulation_context(c: &mut Criterion) {
+    let game_fixture = include_str!("../fixtures/late_stage.json");
+    let g: DEGame = serde_json::from_str(game_fixture).expect("the json literal is valid");
+    let compact =
+        StandardCellBoard4Snakes11x11::convert_from_game(g.clone(), &build_snake_id_map(&g))
+            .unwrap();
+    let moves = compact
+        .get_snake_ids()
+        .into_iter()
+        .map(|sid| (sid, Move::all()))
+        .collect::<Vec<_>>();
+
+    c.bench_function("simulate_with_moves", |b| {
+        b.iter(|| {
+            compact
+                .simulate_with_moves(&Instruments {}, moves.iter().copied())
+                .for_each(|child| {
+                    black_box(child);
+                })
+        })
+    });
+    let mut ctx = SimulationContext::new();
+    c.bench_function("simulate_with_moves_in", |b| {
+        b.iter(|| {
+            compact
+                .simulate_with_moves_in(&ctx, &Instruments {}, moves.iter().copied())
+                .for_each(|child| {
+                    black_box(child);
+                });
+            ctx.reset();
+        })
+    });
+}
+
+criterion_group!(benches, bench_simulation_context);
+criterion_main!(benches);
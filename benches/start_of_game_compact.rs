@@ -68,10 +68,29 @@ fn late_stage_compact_repr(c: &mut Criterion) {
     });
 }
 
+fn duel_compact_repr(c: &mut Criterion) {
+    let game_fixture = include_str!("../fixtures/start_of_game.json");
+    let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+    let mut g = g.expect("the json literal is valid");
+    let you = g.you.id.clone();
+    g.board.snakes.retain(|s| s.id == you);
+    g.board.snakes.push(g.board.snakes[0].clone());
+    g.board.snakes[1].id = "duel opponent".to_string();
+    g.board.snakes[1].head.y -= 2;
+    g.board.snakes[1].body.iter_mut().for_each(|p| p.y -= 2);
+    let snake_id_mapping = build_snake_id_map(&g);
+    let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+    let instruments = Instruments {};
+    c.bench_function("compact duel - all moves", |b| {
+        b.iter(|| bench_compact_full(black_box(&compact), &instruments))
+    });
+}
+
 criterion_group!(
     benches,
     bench_compact_repr_start_of_game,
     bench_compact_repr_start_of_game_full,
     late_stage_compact_repr,
+    duel_compact_repr,
 );
 criterion_main!(benches);
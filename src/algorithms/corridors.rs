@@ -0,0 +1,257 @@
+//! labels every open cell with how enclosed it is and how deep in to a dead end it sits, so that
+//! trap avoidance heuristics can look it up cheaply during search instead of flood filling
+
+use std::collections::{HashMap, HashSet};
+
+use super::all_native_positions;
+use crate::types::{
+    HazardQueryableGame, HeadGettableGame, NeighborDeterminableGame, SizeDeterminableGame,
+};
+
+/// What the corridor analysis knows about a single open cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorridorInfo {
+    /// how many of this cell's neighbors are open, 1 or 2 means the cell is in a single width
+    /// corridor
+    pub open_neighbors: u8,
+    /// Some if this cell is in a dead end: the number of cells from here to the end of the dead
+    /// end, including this one. A snake that goes in has at most this much room before having to
+    /// turn back. None for cells with a way around
+    pub dead_end_depth: Option<u16>,
+}
+
+impl CorridorInfo {
+    /// whether this cell is walled in on at least two sides
+    pub fn is_corridor(&self) -> bool {
+        self.open_neighbors <= 2
+    }
+}
+
+/// The result of corridor analysis over a whole board
+#[derive(Debug, Clone)]
+pub struct CorridorMap<P> {
+    cells: HashMap<P, CorridorInfo>,
+}
+
+impl<P: Eq + std::hash::Hash> CorridorMap<P> {
+    /// the info for a given cell, None for walls
+    pub fn corridor_info(&self, pos: &P) -> Option<CorridorInfo> {
+        self.cells.get(pos).copied()
+    }
+}
+
+/// Caches the corridor analysis of static wall layouts (e.g. the hazard walls of Arcade Maze) by
+/// map name, so it only has to be done once per map rather than once per turn
+#[derive(Debug, Clone)]
+pub struct CorridorCache<P> {
+    maps: HashMap<String, CorridorMap<P>>,
+}
+
+impl<P> Default for CorridorCache<P> {
+    fn default() -> Self {
+        Self {
+            maps: HashMap::new(),
+        }
+    }
+}
+
+impl<P: Eq + std::hash::Hash> CorridorCache<P> {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the [CorridorAnalyzableGame::static_corridor_map] for the given map, computed from `game`
+    /// the first time the map is seen
+    pub fn get_or_compute<G>(&mut self, map: &str, game: &G) -> &CorridorMap<P>
+    where
+        G: CorridorAnalyzableGame<NativePositionType = P> + ?Sized,
+    {
+        self.maps
+            .entry(map.to_string())
+            .or_insert_with(|| game.static_corridor_map())
+    }
+}
+
+/// A game whose corridors and dead ends can be analyzed. This is implemented for every game that
+/// can query hazards, heads, neighbors and its size
+pub trait CorridorAnalyzableGame:
+    HazardQueryableGame + HeadGettableGame + NeighborDeterminableGame + SizeDeterminableGame
+{
+    /// analyzes the current board, with snake bodies (including heads) as the walls
+    fn corridor_map(&self) -> CorridorMap<Self::NativePositionType> {
+        let heads: HashSet<_> = self
+            .get_snake_ids()
+            .iter()
+            .map(|sid| self.get_head_as_native_position(sid))
+            .collect();
+        self.corridor_map_with_walls(|pos| {
+            heads.contains(pos) || self.position_is_snake_body(pos.clone())
+        })
+    }
+
+    /// analyzes the board with only hazards as the walls, for maps like Arcade Maze where the
+    /// hazards never change and act as walls. See [CorridorCache] to only do this once per map
+    fn static_corridor_map(&self) -> CorridorMap<Self::NativePositionType> {
+        self.corridor_map_with_walls(|pos| self.is_hazard(pos))
+    }
+
+    /// analyzes the board with the given cells as walls. Dead ends are found by repeatedly
+    /// peeling off open cells with at most one open neighbor, the round a cell is peeled in is
+    /// its dead end depth
+    fn corridor_map_with_walls(
+        &self,
+        is_wall: impl Fn(&Self::NativePositionType) -> bool,
+    ) -> CorridorMap<Self::NativePositionType> {
+        let open: HashSet<_> = all_native_positions(self)
+            .filter(|pos| !is_wall(pos))
+            .collect();
+        let mut degrees: HashMap<_, u8> = open
+            .iter()
+            .map(|pos| {
+                let degree = self.neighbors(pos).filter(|n| open.contains(n)).count();
+                (pos.clone(), degree as u8)
+            })
+            .collect();
+        let open_neighbors = degrees.clone();
+
+        let mut depths = HashMap::new();
+        let mut frontier: Vec<_> = degrees
+            .iter()
+            .filter(|(_, degree)| **degree <= 1)
+            .map(|(pos, _)| pos.clone())
+            .collect();
+        let mut round = 1;
+        while !frontier.is_empty() {
+            for pos in frontier.iter() {
+                depths.insert(pos.clone(), round);
+            }
+
+            let mut next = vec![];
+            for pos in frontier.drain(..) {
+                for neighbor in self.neighbors(&pos) {
+                    if !open.contains(&neighbor) || depths.contains_key(&neighbor) {
+                        continue;
+                    }
+                    let degree = degrees.get_mut(&neighbor).unwrap();
+                    *degree = degree.saturating_sub(1);
+                    if *degree <= 1 && !next.contains(&neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            frontier = next;
+            round += 1;
+        }
+
+        let cells = open_neighbors
+            .into_iter()
+            .map(|(pos, open_neighbors)| {
+                let dead_end_depth = depths.get(&pos).copied();
+                (
+                    pos,
+                    CorridorInfo {
+                        open_neighbors,
+                        dead_end_depth,
+                    },
+                )
+            })
+            .collect();
+
+        CorridorMap { cells }
+    }
+}
+
+impl<G> CorridorAnalyzableGame for G where
+    G: HazardQueryableGame
+        + HeadGettableGame
+        + NeighborDeterminableGame
+        + SizeDeterminableGame
+        + ?Sized
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{wrapped, StandardCellBoard4Snakes11x11},
+        game_fixture,
+        types::{build_snake_id_map, PositionGettableGame},
+        wire_representation::Position,
+    };
+
+    #[test]
+    fn test_dead_end_depths() {
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        // a wall along x = 1 with a gap at the top turns x = 0 in to a dead end
+        let is_wall = |pos: Position| pos.x == 1 && pos.y < 10;
+        let wire_map = g.corridor_map_with_walls(|pos| is_wall(*pos));
+        let compact_map =
+            compact.corridor_map_with_walls(|pos| is_wall(compact.position_from_native(*pos)));
+
+        for y in 0..10 {
+            let info = wire_map.corridor_info(&Position::new(0, y)).unwrap();
+            assert_eq!(info.dead_end_depth, Some(y as u16 + 1));
+            assert!(info.is_corridor());
+        }
+        let depth = |pos| wire_map.corridor_info(&pos).unwrap().dead_end_depth;
+        assert_eq!(depth(Position::new(0, 10)), Some(11));
+        assert_eq!(depth(Position::new(1, 10)), Some(12));
+        assert_eq!(depth(Position::new(2, 10)), None);
+        assert_eq!(depth(Position::new(5, 5)), None);
+        assert_eq!(wire_map.corridor_info(&Position::new(1, 5)), None);
+
+        for x in 0..11 {
+            for y in 0..11 {
+                let pos = Position::new(x, y);
+                assert_eq!(
+                    wire_map.corridor_info(&pos),
+                    compact_map.corridor_info(&compact.native_from_position(pos))
+                );
+                assert_eq!(
+                    g.corridor_map().corridor_info(&pos),
+                    compact
+                        .corridor_map()
+                        .corridor_info(&compact.native_from_position(pos))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_static_corridor_cache() {
+        let g = game_fixture(include_str!("../../fixtures/arcade_maze_map.json"));
+        let compact = match wrapped::ToBestCellBoard::to_best_cell_board(g.clone()).unwrap() {
+            wrapped::BestCellBoard::ArcadeMaze(board) => board,
+            _ => panic!("expected an arcade maze board"),
+        };
+
+        let mut cache = CorridorCache::new();
+        let static_map = cache.get_or_compute("arcade_maze", &g).clone();
+        assert!(g
+            .board
+            .hazards
+            .iter()
+            .all(|pos| static_map.corridor_info(pos).is_none()));
+
+        let compact_map = compact.static_corridor_map();
+        for pos in g.board.snakes.iter().map(|s| s.head) {
+            assert_eq!(
+                static_map.corridor_info(&pos),
+                compact_map.corridor_info(&compact.native_from_position(pos))
+            );
+        }
+        let open_cells = (0..g.board.width as i32)
+            .flat_map(|x| (0..g.board.height as i32).map(move |y| Position::new(x, y)))
+            .filter(|pos| static_map.corridor_info(pos).is_some())
+            .count();
+        assert_eq!(
+            open_cells,
+            (g.board.width * g.board.height) as usize - g.board.hazards.len()
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! reconstructs what happened between two boards of the same game as a list of [GameEvent]s, so
+//! that analysis ("why did we lose?") doesn't have to diff boards by hand
+
+use super::all_native_positions;
+use crate::types::{
+    FoodGettableGame, HazardQueryableGame, HeadGettableGame, HealthGettableGame, Move,
+    NeighborDeterminableGame, SizeDeterminableGame,
+};
+
+/// Something that happened during a turn
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameEvent<S, P> {
+    /// a snake that survived the turn made this move
+    Moved(S, Move),
+    /// food at this position was eaten by the snake, None if whoever ate it was also eliminated
+    /// this turn. When snakes meet head to head on food they all eat it before the collision is
+    /// resolved, so this is the snake that won the head to head and grew, or None if it was a tie
+    /// and every one of them was eliminated
+    FoodEaten(Option<S>, P),
+    /// a snake was eliminated
+    Eliminated(S),
+    /// food was placed at this position
+    FoodSpawned(P),
+    /// this position became a hazard
+    HazardSpawned(P),
+}
+
+/// A game whose turns can be described as [GameEvent]s. This is implemented for every game that
+/// can query its snakes, food, hazards and neighbors
+pub trait EventDiffableGame:
+    HeadGettableGame
+    + HealthGettableGame
+    + FoodGettableGame
+    + HazardQueryableGame
+    + NeighborDeterminableGame
+    + SizeDeterminableGame
+{
+    /// The events that turned `previous` in to this board, in the order the rules apply them:
+    /// moves, food being eaten, eliminations, then food and hazards spawning. A snake that
+    /// was eliminated has no move, as its head is no longer on the board
+    fn events_since(
+        &self,
+        previous: &Self,
+    ) -> Vec<GameEvent<Self::SnakeIDType, Self::NativePositionType>> {
+        let alive = |game: &Self| {
+            game.get_snake_ids()
+                .into_iter()
+                .filter(|sid| game.is_alive(sid))
+                .collect::<Vec<_>>()
+        };
+        let alive_before = alive(previous);
+        let alive_after = alive(self);
+        let mut events = vec![];
+
+        for sid in alive_before.iter().filter(|sid| alive_after.contains(sid)) {
+            let old_head = previous.get_head_as_native_position(sid);
+            let new_head = self.get_head_as_native_position(sid);
+            if let Some((mv, _)) = previous
+                .possible_moves(&old_head)
+                .find(|(_, pos)| *pos == new_head)
+            {
+                events.push(GameEvent::Moved(sid.clone(), mv));
+            }
+        }
+
+        let food_before = previous.get_all_food_as_native_positions();
+        let food_after = self.get_all_food_as_native_positions();
+        for position in food_before.iter().filter(|pos| !food_after.contains(pos)) {
+            let snake = alive_after
+                .iter()
+                .find(|sid| self.get_head_as_native_position(sid) == *position)
+                .cloned();
+            events.push(GameEvent::FoodEaten(snake, position.clone()));
+        }
+
+        for sid in alive_before.iter().filter(|sid| !alive_after.contains(sid)) {
+            events.push(GameEvent::Eliminated(sid.clone()));
+        }
+
+        for position in food_after.iter().filter(|pos| !food_before.contains(pos)) {
+            events.push(GameEvent::FoodSpawned(position.clone()));
+        }
+
+        events.extend(
+            all_native_positions(self)
+                .filter(|pos| self.is_hazard(pos) && !previous.is_hazard(pos))
+                .map(GameEvent::HazardSpawned),
+        );
+
+        events
+    }
+}
+
+impl<G> EventDiffableGame for G where
+    G: HeadGettableGame
+        + HealthGettableGame
+        + FoodGettableGame
+        + HazardQueryableGame
+        + NeighborDeterminableGame
+        + SizeDeterminableGame
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{
+            build_snake_id_map, HazardSettableGame, LengthGettableGame, PositionGettableGame,
+            SimulableGame, SimulatorInstruments, SnakeIDGettableGame, SnakeId,
+        },
+        wire_representation::Position,
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    #[test]
+    fn test_events_since() {
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        assert!(compact.events_since(&compact).is_empty());
+
+        // you eat the food to your left while snake 3 runs off the bottom of the board
+        let moves = vec![
+            (SnakeId(0), [Move::Left].as_slice()),
+            (SnakeId(3), [Move::Down].as_slice()),
+        ];
+        let (_, mut child) = compact
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+        let hazard = child.native_from_position(Position::new(0, 0));
+        child.set_hazard(hazard);
+
+        let food = child.native_from_position(Position::new(4, 8));
+        assert_eq!(
+            child.events_since(&compact),
+            vec![
+                GameEvent::Moved(SnakeId(0), Move::Left),
+                GameEvent::FoodEaten(Some(SnakeId(0)), food),
+                GameEvent::Eliminated(SnakeId(3)),
+                GameEvent::HazardSpawned(hazard),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_head_to_head_on_food() {
+        // the longer snake beats the shorter one to the food at (6,5), and two snakes of the
+        // same length meet on the food at (2,9)
+        let g = game_fixture(include_str!("../../fixtures/head_to_head_on_food.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let (long, short) = (id_map["long"], id_map["short"]);
+        let (left_twin, right_twin) = (id_map["left_twin"], id_map["right_twin"]);
+        let moves = vec![
+            (long, [Move::Right].as_slice()),
+            (short, [Move::Left].as_slice()),
+            (left_twin, [Move::Right].as_slice()),
+            (right_twin, [Move::Left].as_slice()),
+        ];
+        let (_, child) = compact
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+
+        assert_eq!(child.get_snake_ids(), vec![long]);
+        assert_eq!(child.get_length(&long), 5);
+        assert_eq!(child.get_health(&long), 100);
+        assert_eq!(child.food_count(), 0);
+
+        let contested = child.native_from_position(Position::new(6, 5));
+        let tied = child.native_from_position(Position::new(2, 9));
+        assert_eq!(
+            child.events_since(&compact),
+            vec![
+                GameEvent::Moved(long, Move::Right),
+                GameEvent::FoodEaten(Some(long), contested),
+                GameEvent::FoodEaten(None, tied),
+                GameEvent::Eliminated(short),
+                GameEvent::Eliminated(left_twin),
+                GameEvent::Eliminated(right_twin),
+            ]
+        );
+    }
+}
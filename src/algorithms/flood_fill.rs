@@ -0,0 +1,153 @@
+//! area control by flood filling out from every snake's head at once, counting the cells each
+//! snake gets to first. This is the plain count most snakes use for voronoi style heuristics, see
+//! [crate::algorithms::territory] for tie breaks and per cell owners
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    types::{NeighborDeterminableGame, SnakeBodyGettableGame},
+    wire_representation::Game,
+};
+
+/// A game for which the number of cells each snake controls can be counted. The default method
+/// works for any game, boards with a faster way to count override it, like the compact boards
+/// do
+pub trait AreaControlGame: NeighborDeterminableGame + SnakeBodyGettableGame {
+    /// The number of cells each alive snake reaches before any other snake, in
+    /// [crate::types::SnakeIDGettableGame::get_snake_ids] order. See [spread_from_heads]
+    fn spread_from_heads(&self) -> Vec<(Self::SnakeIDType, usize)> {
+        spread_from_heads(self)
+    }
+}
+
+impl AreaControlGame for Game {}
+
+/// Spreads out from every alive snake's head one step at a time, all snakes together. Each cell
+/// goes to the snake that reaches it first, including the cell its head is on. A cell reached by
+/// several snakes on the same step is contested, it goes to none of them and is not spread
+/// beyond. Snake bodies and walls block the spread and other hazards are ignored. Returns the
+/// number of cells each snake gets, in [crate::types::SnakeIDGettableGame::get_snake_ids] order
+pub fn spread_from_heads<G>(game: &G) -> Vec<(G::SnakeIDType, usize)>
+where
+    G: NeighborDeterminableGame + SnakeBodyGettableGame + ?Sized,
+{
+    let bodies = game
+        .get_snake_ids()
+        .into_iter()
+        .filter_map(|sid| {
+            let body = game.try_get_snake_body_vec(&sid)?;
+            (!body.is_empty()).then_some((sid, body))
+        })
+        .collect::<Vec<_>>();
+    let blocked = bodies
+        .iter()
+        .flat_map(|(_, body)| body.iter().cloned())
+        .collect::<HashSet<_>>();
+
+    // the index of the snake owning each reached cell, None for contested cells
+    let mut owners = HashMap::new();
+    let mut frontier = vec![];
+    for (i, (_, body)) in bodies.iter().enumerate() {
+        owners.insert(body[0].clone(), Some(i));
+        frontier.push((body[0].clone(), i));
+    }
+
+    while !frontier.is_empty() {
+        let mut claims: HashMap<G::NativePositionType, Option<usize>> = HashMap::new();
+        for (pos, i) in frontier.drain(..) {
+            for neighbor in game.neighbors(&pos) {
+                if owners.contains_key(&neighbor) || blocked.contains(&neighbor) {
+                    continue;
+                }
+                let claim = claims.entry(neighbor).or_insert(Some(i));
+                if *claim != Some(i) {
+                    *claim = None;
+                }
+            }
+        }
+
+        for (pos, claim) in claims {
+            if let Some(i) = claim {
+                frontier.push((pos.clone(), i));
+            }
+            owners.insert(pos, claim);
+        }
+    }
+
+    let mut counts = vec![0; bodies.len()];
+    for i in owners.values().flatten() {
+        counts[*i] += 1;
+    }
+    bodies
+        .into_iter()
+        .zip(counts)
+        .map(|((sid, _), count)| (sid, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithms::territory::{CellOwner, TerritoryEvaluatableGame, TieBreak},
+        compact_representation::{standard, wrapped},
+        fixtures::{self, check_each},
+        types::build_snake_id_map,
+    };
+
+    type Standard = standard::CellBoard8Snakes25x25;
+    type Wrapped = wrapped::CellBoard8SnakesSquare25x25;
+
+    #[test]
+    fn test_compact_boards_match_the_generic_spread() {
+        // a couple of fixtures have snakes off the board, which the representations don't agree on
+        let on_board = fixtures::all().iter().copied().filter(|fixture| {
+            let game = fixture.game();
+            game.board
+                .snakes
+                .iter()
+                .flat_map(|s| s.body.iter())
+                .all(|p| !game.off_board(*p))
+        });
+        check_each(on_board, |fixture| {
+            let game = fixture.game();
+            let id_map = build_snake_id_map(&game);
+            let (fast, generic) = if game.is_wrapped() {
+                let compact: Wrapped = game.as_wrapped_cell_board(&id_map)?;
+                (compact.spread_from_heads(), spread_from_heads(&compact))
+            } else {
+                let compact: Standard = game.as_cell_board(&id_map)?;
+                (compact.spread_from_heads(), spread_from_heads(&compact))
+            };
+            if fast != generic {
+                return Err(format!("{:?} != {:?}", fast, generic).into());
+            }
+
+            let mut wire = game
+                .spread_from_heads()
+                .into_iter()
+                .map(|(id, count)| (id_map[&id], count))
+                .collect::<Vec<_>>();
+            wire.sort_by_key(|(sid, _)| sid.as_usize());
+            if wire != fast {
+                return Err(format!("wire {:?} != compact {:?}", wire, fast).into());
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_spread_matches_split_territory() {
+        let game = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let territory = game.territory(TieBreak::Split);
+        for (sid, count) in game.spread_from_heads() {
+            let owned = territory
+                .owners
+                .values()
+                .filter(|owner| **owner == CellOwner::Snake(sid.clone()))
+                .count();
+            assert_eq!(count, owned);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! exports the cells a snake can move through as a graph, so external graph algorithms (max flow
+//! for territory splits, articulation points for chokes, ...) can run over a board without
+//! converting it by hand
+
+use std::collections::{HashMap, HashSet};
+
+use super::all_native_positions;
+use crate::types::{
+    HeadGettableGame, NeighborDeterminableGame, SizeDeterminableGame, WallQueryableGame,
+};
+use crate::wire_representation::Game;
+
+/// The traversable cells of a board as a weighted, directed graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdjacencyList<P> {
+    /// the cells, a node's index is its index in this list
+    pub nodes: Vec<P>,
+    /// for each node, the nodes a snake can move to from it along with the cost of moving there:
+    /// 1, plus the hazard damage if the cell moved to is a hazard
+    pub edges: Vec<Vec<(usize, u32)>>,
+}
+
+impl<P: Eq + std::hash::Hash> AdjacencyList<P> {
+    /// the index of the node for a cell, None if the cell isn't traversable
+    pub fn node_index(&self, pos: &P) -> Option<usize> {
+        self.nodes.iter().position(|n| n == pos)
+    }
+
+    /// the number of edges in the graph
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// every edge as a `(from, to, weight)` triple of node indices, the shape e.g.
+    /// `petgraph::Graph::from_edges` takes
+    pub fn edge_list(&self) -> impl Iterator<Item = (usize, usize, u32)> + '_ {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |&(to, weight)| (from, to, weight)))
+    }
+}
+
+/// A game that can be exported as an [AdjacencyList]. This is implemented for every game that can
+/// query hazards, heads, neighbors and its size
+pub trait AdjacencyListGame:
+    WallQueryableGame + HeadGettableGame + NeighborDeterminableGame + SizeDeterminableGame
+{
+    /// The graph of the cells snakes can move through: every cell that isn't a wall or part of a
+    /// snake's body, plus the snakes' heads so searches can start from them. Heads only have edges out,
+    /// as a head always leaves a body segment behind, so apart from them every edge has a
+    /// matching edge back. Nodes are in row order, then the heads
+    fn to_adjacency_list(&self) -> AdjacencyList<Self::NativePositionType> {
+        let heads: HashSet<_> = self
+            .get_snake_ids()
+            .iter()
+            .map(|sid| self.get_head_as_native_position(sid))
+            .collect();
+        let mut nodes: Vec<_> = all_native_positions(self)
+            .filter(|pos| {
+                !heads.contains(pos)
+                    && !self.is_wall(pos)
+                    && !self.position_is_snake_body(pos.clone())
+            })
+            .collect();
+        let open = nodes.len();
+        nodes.extend(all_native_positions(self).filter(|pos| heads.contains(pos)));
+
+        let index: HashMap<_, _> = nodes[..open]
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| (pos.clone(), i))
+            .collect();
+        let hazard_cost = 1 + self.get_hazard_damage() as u32;
+        let edges = nodes
+            .iter()
+            .map(|pos| {
+                self.neighbors(pos)
+                    .filter_map(|neighbor| {
+                        let weight = if self.is_hazard(&neighbor) {
+                            hazard_cost
+                        } else {
+                            1
+                        };
+                        index.get(&neighbor).map(|&to| (to, weight))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        AdjacencyList { nodes, edges }
+    }
+}
+
+impl<G> AdjacencyListGame for G where
+    G: WallQueryableGame
+        + HeadGettableGame
+        + NeighborDeterminableGame
+        + SizeDeterminableGame
+        + ?Sized
+{
+}
+
+/// A game that can find its choke points. The default implementation works over any board, compact
+/// boards override it with one that indexes cells directly instead of hashing positions
+pub trait ArticulationCellsGame:
+    HeadGettableGame + NeighborDeterminableGame + SizeDeterminableGame + WallQueryableGame
+{
+    /// The cells no snake is on whose occupation would split the region of free cells they're in
+    /// into more than one, i.e. the articulation points of the graph of cells no snake is on.
+    /// Walls are never free, food and other hazards don't block. A snake moving on to one of
+    /// these cuts the board in two, so they are candidates for cutting off an opponent, or cells
+    /// to be wary of. Returned in row order
+    fn articulation_cells(&self) -> Vec<Self::NativePositionType> {
+        let heads: HashSet<_> = self
+            .get_snake_ids()
+            .iter()
+            .map(|sid| self.get_head_as_native_position(sid))
+            .collect();
+        let cells: Vec<_> = all_native_positions(self).collect();
+        let index: HashMap<_, _> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| (pos.clone(), i))
+            .collect();
+        let open: Vec<bool> = cells
+            .iter()
+            .map(|pos| {
+                !heads.contains(pos)
+                    && !self.is_wall(pos)
+                    && !self.position_is_snake_body(pos.clone())
+            })
+            .collect();
+
+        articulation_points(
+            cells.len(),
+            |i| open[i],
+            |i| {
+                self.neighbors(&cells[i])
+                    .map(|neighbor| index[&neighbor])
+                    .collect::<Vec<_>>()
+            },
+        )
+        .map(|i| cells[i].clone())
+        .collect()
+    }
+}
+
+impl ArticulationCellsGame for Game {}
+
+/// The articulation points of the graph over the nodes `0..node_count` for which `open` holds,
+/// in increasing order, using an iterative version of Tarjan's algorithm so large boards can't
+/// overflow the stack. `neighbors` may include closed nodes, they're skipped
+pub(crate) fn articulation_points<I: IntoIterator<Item = usize>>(
+    node_count: usize,
+    open: impl Fn(usize) -> bool,
+    neighbors: impl Fn(usize) -> I,
+) -> impl Iterator<Item = usize> {
+    // discovery times start at 1, so 0 means a node hasn't been visited yet
+    let mut discovered = vec![0u32; node_count];
+    let mut low = vec![0u32; node_count];
+    let mut parent = vec![usize::MAX; node_count];
+    let mut is_cut = vec![false; node_count];
+    let mut time = 0;
+    let mut stack = vec![];
+
+    for root in (0..node_count).filter(|&i| open(i)) {
+        if discovered[root] != 0 {
+            continue;
+        }
+        time += 1;
+        discovered[root] = time;
+        low[root] = time;
+        let mut root_children = 0;
+        stack.push((root, neighbors(root).into_iter()));
+
+        while let Some((node, edges)) = stack.last_mut() {
+            let node = *node;
+            match edges.next() {
+                Some(next) if !open(next) => {}
+                Some(next) if discovered[next] == 0 => {
+                    time += 1;
+                    discovered[next] = time;
+                    low[next] = time;
+                    parent[next] = node;
+                    if node == root {
+                        root_children += 1;
+                    }
+                    stack.push((next, neighbors(next).into_iter()));
+                }
+                Some(next) => {
+                    if next != parent[node] {
+                        low[node] = low[node].min(discovered[next]);
+                    }
+                }
+                None => {
+                    stack.pop();
+                    if let Some((up, _)) = stack.last() {
+                        let up = *up;
+                        low[up] = low[up].min(low[node]);
+                        if up != root && low[node] >= discovered[up] {
+                            is_cut[up] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        // the root has no parent to be cut off from, it only splits things if the search had to
+        // start more than one subtree from it
+        is_cut[root] = root_children > 1;
+    }
+
+    is_cut
+        .into_iter()
+        .enumerate()
+        .filter(|(_, cut)| *cut)
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        game_fixture,
+        types::{
+            build_snake_id_map, HazardSettableGame, PositionGettableGame, SnakeIDGettableGame,
+            SnakeId,
+        },
+        wire_representation::Position,
+    };
+
+    #[test]
+    fn test_adjacency_list() {
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let hazard = compact.native_from_position(Position::new(5, 5));
+        compact.set_hazard(hazard);
+
+        let graph = compact.to_adjacency_list();
+        let wire_graph = g.to_adjacency_list();
+        assert_eq!(graph.nodes.len(), wire_graph.nodes.len());
+        assert_eq!(graph.edge_count(), wire_graph.edge_count());
+
+        // the heads of the 4 snakes come last
+        let heads: HashSet<_> = (0..4)
+            .map(|i| compact.get_head_as_native_position(&SnakeId(i)))
+            .collect();
+        let (open, head_nodes) = graph.nodes.split_at(graph.nodes.len() - 4);
+        assert_eq!(head_nodes.iter().copied().collect::<HashSet<_>>(), heads);
+        assert!(open.iter().all(|pos| !compact.position_is_snake_body(*pos)));
+        let you = graph
+            .node_index(&compact.get_head_as_native_position(&SnakeId(0)))
+            .unwrap();
+        assert!(graph
+            .edges
+            .iter()
+            .all(|e| e.iter().all(|(to, _)| *to != you)));
+
+        let corner = graph
+            .node_index(&compact.native_from_position(Position::new(0, 0)))
+            .unwrap();
+        assert_eq!(graph.edges[corner].len(), 2);
+        let cost_into = |to: Position| {
+            let to = graph.node_index(&compact.native_from_position(to)).unwrap();
+            graph
+                .edge_list()
+                .find(|(_, t, _)| *t == to)
+                .map(|(_, _, weight)| weight)
+        };
+        assert_eq!(cost_into(Position::new(5, 5)), Some(1 + 15));
+        assert_eq!(cost_into(Position::new(5, 4)), Some(1));
+        // apart from the heads every edge goes both ways
+        for (from, to, _) in graph.edge_list().filter(|(from, _, _)| *from < open.len()) {
+            assert!(graph.edges[to].iter().any(|(back, _)| *back == from));
+        }
+    }
+
+    /// the number of regions the free cells form with `blocked` filled in, by flood fill
+    fn regions_without(game: &Game, blocked: Option<Position>) -> usize {
+        let open = |pos: &Position| {
+            Some(*pos) != blocked
+                && !game
+                    .get_snake_ids()
+                    .iter()
+                    .any(|sid| game.get_head_as_native_position(sid) == *pos)
+                && !game.is_wall(pos)
+                && !game.position_is_snake_body(*pos)
+        };
+        let mut seen = HashSet::new();
+        let mut regions = 0;
+        for start in all_native_positions(game).filter(|pos| open(pos)) {
+            if !seen.insert(start) {
+                continue;
+            }
+            regions += 1;
+            let mut frontier = vec![start];
+            while let Some(pos) = frontier.pop() {
+                for neighbor in game.neighbors(&pos) {
+                    if open(&neighbor) && seen.insert(neighbor) {
+                        frontier.push(neighbor);
+                    }
+                }
+            }
+        }
+        regions
+    }
+
+    #[test]
+    fn test_articulation_cells() {
+        crate::fixtures::check_each(crate::fixtures::all().iter().copied(), |fixture| {
+            let game = fixture.game();
+            let regions = regions_without(&game, None);
+            let expected: Vec<_> = all_native_positions(&game)
+                .filter(|pos| {
+                    !game.position_is_snake_body(*pos)
+                        && regions_without(&game, Some(*pos)) > regions
+                })
+                .collect();
+            let cells = game.articulation_cells();
+            if cells != expected {
+                return Err(format!("found {:?}, expected {:?}", cells, expected).into());
+            }
+
+            if game.board.width == 11 && game.board.height == 11 && game.board.snakes.len() <= 4 {
+                let id_map = build_snake_id_map(&game);
+                let compact: Vec<_> = if game.is_wrapped() {
+                    let board: WrappedCellBoard4Snakes11x11 =
+                        game.as_wrapped_cell_board(&id_map)?;
+                    board
+                        .articulation_cells()
+                        .into_iter()
+                        .map(|c| board.position_from_native(c))
+                        .collect()
+                } else {
+                    let board: StandardCellBoard4Snakes11x11 = game.as_cell_board(&id_map)?;
+                    board
+                        .articulation_cells()
+                        .into_iter()
+                        .map(|c| board.position_from_native(c))
+                        .collect()
+                };
+                if compact != expected {
+                    return Err(format!("compact found {:?}", compact).into());
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        // the snake in the bottom right corner leaves a two cell pocket along the wall, walled in
+        // by its own neck, that hangs off the rest of the board
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        assert_eq!(
+            g.articulation_cells(),
+            vec![Position::new(10, 1), Position::new(10, 2)]
+        );
+    }
+}
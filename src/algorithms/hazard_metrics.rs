@@ -0,0 +1,208 @@
+//! summary metrics about how much of the board is covered by hazards, and how long until a given
+//! cell is swallowed by a royale shrink
+
+use std::collections::HashSet;
+
+use super::all_native_positions;
+use crate::types::{HazardQueryableGame, NeighborDeterminableGame, SizeDeterminableGame};
+use crate::wire_representation::Game;
+
+/// The royale shrink schedule for a game, as seen from the current turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RoyaleSchedule {
+    /// how many turns pass between each shrink
+    pub shrink_every_n_turns: u32,
+    /// how many turns until the next shrink happens, 1 means the next turn
+    pub turns_until_next_shrink: u32,
+}
+
+impl RoyaleSchedule {
+    /// builds the schedule from a wire game, returns None if the game has no royale settings
+    pub fn from_game(game: &Game) -> Option<Self> {
+        let royale = game.game.ruleset.settings.as_ref()?.royale?;
+        if royale.shrink_every_n_turns < 1 {
+            return None;
+        }
+        let shrink_every_n_turns = royale.shrink_every_n_turns as u32;
+        let turn = game.turn.max(0) as u32;
+
+        Some(Self {
+            shrink_every_n_turns,
+            turns_until_next_shrink: shrink_every_n_turns - turn % shrink_every_n_turns,
+        })
+    }
+}
+
+/// A game for which hazard summary metrics can be computed. This is implemented for every game
+/// that can query hazards, neighbors and its size
+pub trait HazardMetricsGame:
+    HazardQueryableGame + NeighborDeterminableGame + SizeDeterminableGame
+{
+    /// the fraction of cells on the board that are hazards, from 0.0 to 1.0
+    fn hazard_coverage(&self) -> f32 {
+        let total = self.get_width() * self.get_height();
+        if total == 0 {
+            return 0.0;
+        }
+        let hazards = all_native_positions(self)
+            .filter(|pos| self.is_hazard(pos))
+            .count();
+
+        hazards as f32 / total as f32
+    }
+
+    /// the largest connected set of cells that are not hazards. Snakes and food are ignored, this
+    /// only considers the hazard layout
+    fn largest_hazard_free_region(&self) -> Vec<Self::NativePositionType> {
+        let mut seen = HashSet::new();
+        let mut largest = vec![];
+
+        for start in all_native_positions(self) {
+            if self.is_hazard(&start) || seen.contains(&start) {
+                continue;
+            }
+
+            seen.insert(start.clone());
+            let mut region = vec![];
+            let mut stack = vec![start];
+            while let Some(pos) = stack.pop() {
+                for neighbor in self.neighbors(&pos) {
+                    if !self.is_hazard(&neighbor) && seen.insert(neighbor.clone()) {
+                        stack.push(neighbor);
+                    }
+                }
+                region.push(pos);
+            }
+
+            if region.len() > largest.len() {
+                largest = region;
+            }
+        }
+
+        largest
+    }
+
+    /// the earliest number of turns until the given cell could be hazarded by a royale shrink.
+    /// Royale picks the side to shrink at random, so this is the pessimistic estimate where every
+    /// shrink comes from the side closest to this cell. Returns Some(0) if the cell is already a
+    /// hazard and None if the schedule never shrinks
+    fn estimated_turns_until_cell_hazarded(
+        &self,
+        pos: &Self::NativePositionType,
+        schedule: &RoyaleSchedule,
+    ) -> Option<u32> {
+        if self.is_hazard(pos) {
+            return Some(0);
+        }
+        if schedule.shrink_every_n_turns == 0 {
+            return None;
+        }
+
+        let safe = all_native_positions(self)
+            .filter(|p| !self.is_hazard(p))
+            .map(|p| self.position_from_native(p));
+        let (min_x, max_x, min_y, max_y) = safe.fold(
+            (i32::MAX, i32::MIN, i32::MAX, i32::MIN),
+            |(min_x, max_x, min_y, max_y), p| {
+                (
+                    min_x.min(p.x),
+                    max_x.max(p.x),
+                    min_y.min(p.y),
+                    max_y.max(p.y),
+                )
+            },
+        );
+
+        let p = self.position_from_native(pos.clone());
+        let shrinks_needed = (p.x - min_x)
+            .min(max_x - p.x)
+            .min(p.y - min_y)
+            .min(max_y - p.y) as u32;
+
+        Some(schedule.turns_until_next_shrink + shrinks_needed * schedule.shrink_every_n_turns)
+    }
+}
+
+impl<G: HazardQueryableGame + NeighborDeterminableGame + SizeDeterminableGame> HazardMetricsGame
+    for G
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11, game_fixture,
+        types::build_snake_id_map, wire_representation::Position,
+    };
+
+    #[test]
+    fn test_metrics_match_across_representations() {
+        // hazards cover the three leftmost columns
+        let g = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        assert_eq!(g.hazard_coverage(), 33.0 / 121.0);
+        assert_eq!(compact.hazard_coverage(), 33.0 / 121.0);
+
+        assert_eq!(g.largest_hazard_free_region().len(), 88);
+        assert_eq!(compact.largest_hazard_free_region().len(), 88);
+    }
+
+    #[test]
+    fn test_estimated_turns_until_cell_hazarded() {
+        let g = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let schedule = RoyaleSchedule {
+            shrink_every_n_turns: 25,
+            turns_until_next_shrink: 3,
+        };
+
+        assert_eq!(
+            g.estimated_turns_until_cell_hazarded(&Position::new(0, 4), &schedule),
+            Some(0)
+        );
+        // the left edge of the safe area goes next
+        assert_eq!(
+            g.estimated_turns_until_cell_hazarded(&Position::new(3, 4), &schedule),
+            Some(3)
+        );
+        // two columns from the safe edge on the left, five from the top and bottom
+        assert_eq!(
+            g.estimated_turns_until_cell_hazarded(&Position::new(5, 5), &schedule),
+            Some(3 + 2 * 25)
+        );
+        // the right edge of the board is also an edge of the safe area
+        assert_eq!(
+            g.estimated_turns_until_cell_hazarded(&Position::new(10, 5), &schedule),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_royale_schedule_from_game() {
+        let mut g = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        assert_eq!(RoyaleSchedule::from_game(&g), None);
+
+        g.game.ruleset.settings = Some(crate::wire_representation::Settings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: Some(crate::wire_representation::RoyaleSettings {
+                shrink_every_n_turns: 25,
+            }),
+            squad: None,
+            extra: Default::default(),
+        });
+        g.turn = 60;
+        assert_eq!(
+            RoyaleSchedule::from_game(&g),
+            Some(RoyaleSchedule {
+                shrink_every_n_turns: 25,
+                turns_until_next_shrink: 15,
+            })
+        );
+    }
+}
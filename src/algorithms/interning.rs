@@ -0,0 +1,232 @@
+//! stores each distinct board once and hands out small handles to it, so that search trees over
+//! millions of positions can hold a `u32` per node instead of a whole board
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hash, Hasher},
+};
+
+use fxhash::FxHasher64;
+
+/// A handle to a board in an [InternedBoards] store. Handles are only meaningful for the store
+/// that made them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoardHandle(u32);
+
+impl BoardHandle {
+    /// the handle as a plain integer, e.g. to store in a tree node
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// the handle stored with [Self::as_u32]
+    pub fn from_u32(handle: u32) -> Self {
+        Self(handle)
+    }
+}
+
+/// marks the end of a chain of boards with the same hash
+const END_OF_CHAIN: u32 = u32::MAX;
+
+/// Deduplicated storage for boards. Interning a board that is already stored gives back the
+/// existing handle, so each distinct board is kept exactly once however many times it's reached.
+/// Boards are found by their hash, and boards whose hashes collide are told apart with `Eq`.
+///
+/// Compact boards on different turns aren't equal. To share one entry between the same position
+/// reached on different turns, intern `board.with_turn(0)` and keep the turn beside the handle
+#[derive(Debug, Clone)]
+pub struct InternedBoards<B> {
+    boards: Vec<B>,
+    /// for each board, the next board with the same hash
+    next: Vec<u32>,
+    /// the first board with each hash
+    by_hash: HashMap<u64, u32, BuildHasherDefault<FxHasher64>>,
+}
+
+impl<B> Default for InternedBoards<B> {
+    fn default() -> Self {
+        Self {
+            boards: Vec::new(),
+            next: Vec::new(),
+            by_hash: HashMap::default(),
+        }
+    }
+}
+
+impl<B: Hash + Eq> InternedBoards<B> {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a store with room for `capacity` boards before it reallocates
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            boards: Vec::with_capacity(capacity),
+            next: Vec::with_capacity(capacity),
+            by_hash: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    fn hash_of(board: &B) -> u64 {
+        let mut hasher = FxHasher64::default();
+        board.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// walks the chain of boards with this hash looking for `board`
+    fn find(&self, hash: u64, board: &B) -> Option<BoardHandle> {
+        let mut idx = *self.by_hash.get(&hash)?;
+        while idx != END_OF_CHAIN {
+            if self.boards[idx as usize] == *board {
+                return Some(BoardHandle(idx));
+            }
+            idx = self.next[idx as usize];
+        }
+        None
+    }
+
+    /// Stores `board` if it isn't stored already, and returns its handle either way.
+    ///
+    /// # Panics
+    /// if the store already holds `u32::MAX` boards
+    pub fn intern(&mut self, board: B) -> BoardHandle {
+        let hash = Self::hash_of(&board);
+        if let Some(handle) = self.find(hash, &board) {
+            return handle;
+        }
+        assert!(
+            self.boards.len() < END_OF_CHAIN as usize,
+            "an interned board store holds fewer than u32::MAX boards"
+        );
+        let idx = self.boards.len() as u32;
+        let next = self.by_hash.insert(hash, idx).unwrap_or(END_OF_CHAIN);
+        self.boards.push(board);
+        self.next.push(next);
+        BoardHandle(idx)
+    }
+
+    /// the handle of `board`, None if it was never interned
+    pub fn get_handle(&self, board: &B) -> Option<BoardHandle> {
+        self.find(Self::hash_of(board), board)
+    }
+}
+
+impl<B> InternedBoards<B> {
+    /// the board behind `handle`, None if this store didn't make it
+    pub fn get(&self, handle: BoardHandle) -> Option<&B> {
+        self.boards.get(handle.0 as usize)
+    }
+
+    /// The board behind `handle`, e.g. to expand a tree node.
+    ///
+    /// # Panics
+    /// if this store didn't make the handle
+    pub fn resolve(&self, handle: BoardHandle) -> &B {
+        &self.boards[handle.0 as usize]
+    }
+
+    /// how many distinct boards are stored
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    #[allow(missing_docs)]
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+
+    /// drops every board, invalidating all handles, while keeping the allocations, e.g. between
+    /// searches
+    pub fn clear(&mut self) {
+        self.boards.clear();
+        self.next.clear();
+        self.by_hash.clear();
+    }
+
+    /// every stored board along with its handle, in the order they were interned
+    pub fn iter(&self) -> impl Iterator<Item = (BoardHandle, &B)> + '_ {
+        self.boards
+            .iter()
+            .enumerate()
+            .map(|(i, board)| (BoardHandle(i as u32), board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{dimensions::Square, standard::CellBoard},
+        game_fixture,
+        types::{build_snake_id_map, SimulableGame, SimulatorInstruments, SnakeIDGettableGame},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    type Board = CellBoard<u8, Square, { 11 * 11 }, 4>;
+
+    #[test]
+    fn test_interning_deduplicates_boards() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: Board = g.as_cell_board(&id_map).unwrap();
+
+        let mut store = InternedBoards::new();
+        let root = store.intern(compact);
+        assert_eq!(store.intern(compact), root);
+        assert_eq!(store.len(), 1);
+        // the same position on another turn is another board, the turn can be kept beside the
+        // handle instead by interning boards at turn 0
+        let later = store.intern(compact.with_turn(12));
+        assert_ne!(later, root);
+        assert_eq!(store.resolve(later), &compact.with_turn(12));
+        assert_eq!(store.len(), 2);
+
+        let children: Vec<Board> = compact
+            .simulate(&Instruments, compact.get_snake_ids().to_vec())
+            .map(|(_, child)| child)
+            .collect();
+        // interning every child twice only stores each once
+        let handles: Vec<_> = children.iter().map(|c| store.intern(*c)).collect();
+        for (child, handle) in children.iter().zip(handles.iter()) {
+            assert_eq!(store.intern(*child), *handle);
+            assert_eq!(store.resolve(*handle), child);
+            assert_eq!(store.get_handle(child), Some(*handle));
+            assert_eq!(
+                store.get(BoardHandle::from_u32(handle.as_u32())),
+                Some(child)
+            );
+        }
+        let distinct = children.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(store.len(), distinct.len() + 2);
+        assert_eq!(store.iter().next(), Some((root, &compact)));
+
+        store.clear();
+        assert!(store.is_empty());
+        assert_eq!(store.get(root), None);
+        assert_eq!(store.get_handle(&compact), None);
+    }
+
+    #[test]
+    fn test_interning_tells_colliding_boards_apart() {
+        #[derive(Debug, PartialEq, Eq)]
+        struct Colliding(u8);
+        impl Hash for Colliding {
+            fn hash<H: Hasher>(&self, _: &mut H) {}
+        }
+
+        let mut store = InternedBoards::new();
+        let handles: Vec<_> = (0..4).map(|i| store.intern(Colliding(i))).collect();
+        assert_eq!(store.len(), 4);
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(store.intern(Colliding(i as u8)), *handle);
+            assert_eq!(store.resolve(*handle), &Colliding(i as u8));
+        }
+        assert_eq!(store.get_handle(&Colliding(9)), None);
+    }
+}
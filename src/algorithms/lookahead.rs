@@ -0,0 +1,96 @@
+//! one step lookahead over where the other snakes can be next turn, the set nearly every
+//! avoidance heuristic starts from
+
+use crate::types::{HeadGettableGame, NeighborDeterminableGame, ReasonableMovesGame};
+
+/// A game in which the cells enemy heads can reach next turn can be found. This is implemented
+/// for every game that can get heads, neighbors and each snake's reasonable moves
+pub trait EnemyLookaheadGame:
+    ReasonableMovesGame + HeadGettableGame + NeighborDeterminableGame
+{
+    /// Every cell some snake other than `perspective` can move its head on to next turn, going by
+    /// [ReasonableMovesGame]. Each cell is listed once, in the order the snakes and their moves
+    /// are visited. A snake with no reasonable moves contributes the move it would be forced in
+    /// to, if that stays on the board
+    fn enemy_next_head_cells(
+        &self,
+        perspective: &Self::SnakeIDType,
+    ) -> Vec<Self::NativePositionType> {
+        let mut cells = vec![];
+        for (sid, moves) in self.reasonable_moves_for_each_snake() {
+            if sid == *perspective {
+                continue;
+            }
+            let head = self.get_head_as_native_position(&sid);
+            for (_, cell) in self
+                .possible_moves(&head)
+                .filter(|(m, _)| moves.contains(m))
+            {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+        cells
+    }
+}
+
+impl<G: ReasonableMovesGame + HeadGettableGame + NeighborDeterminableGame> EnemyLookaheadGame
+    for G
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{build_snake_id_map, PositionGettableGame, SnakeIDGettableGame},
+        wire_representation::Position,
+    };
+
+    #[test]
+    fn test_enemy_next_head_cells_match_across_representations() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        let mut wire_cells = g.enemy_next_head_cells(&g.you.id);
+        let mut compact_cells = compact
+            .enemy_next_head_cells(&id_map[&g.you.id])
+            .into_iter()
+            .map(|c| compact.position_from_native(c))
+            .collect::<Vec<_>>();
+        wire_cells.sort();
+        compact_cells.sort();
+        assert!(!wire_cells.is_empty());
+        assert_eq!(wire_cells, compact_cells);
+    }
+
+    #[test]
+    fn test_enemy_next_head_cells_leave_out_the_perspective_snake() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let everyone = g.get_snake_ids();
+        for perspective in everyone.iter() {
+            let cells = g.enemy_next_head_cells(perspective);
+            let head = g.get_head_as_position(perspective);
+            let others_reach = |cell: &Position| {
+                everyone
+                    .iter()
+                    .filter(|sid| *sid != perspective)
+                    .any(|sid| {
+                        let other = g.get_head_as_position(sid);
+                        (other.x - cell.x).abs() + (other.y - cell.y).abs() == 1
+                    })
+            };
+            assert!(cells.iter().all(others_reach));
+            // cells only you can reach never show up
+            for (_, cell) in g.possible_moves(&head) {
+                if !others_reach(&cell) {
+                    assert!(!cells.contains(&cell));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! algorithms that work over any board implementing the getter traits in [crate::types]
+
+pub mod corridors;
+pub mod events;
+pub mod flood_fill;
+pub mod graph;
+pub mod hazard_metrics;
+pub mod interning;
+pub mod lookahead;
+pub mod pathing;
+pub mod phase;
+pub mod replay;
+pub mod rollout;
+pub mod territory;
+pub mod tunnel;
+
+use crate::types::{PositionGettableGame, SizeDeterminableGame};
+use crate::wire_representation::Position;
+
+/// every cell on the board, row by row, as native positions
+pub(crate) fn all_native_positions<G: SizeDeterminableGame + PositionGettableGame + ?Sized>(
+    game: &G,
+) -> impl Iterator<Item = G::NativePositionType> + '_ {
+    let width = game.get_width() as i32;
+    let height = game.get_height() as i32;
+
+    (0..height)
+        .flat_map(move |y| (0..width).map(move |x| Position { x, y }))
+        .map(move |pos| game.native_from_position(pos))
+}
@@ -0,0 +1,231 @@
+//! shortest paths and distances between cells by breadth first search, every move costing the
+//! same
+
+use std::collections::{HashMap, VecDeque};
+
+use super::all_native_positions;
+use crate::types::{HazardQueryableGame, NeighborDeterminableGame, SizeDeterminableGame};
+use crate::wire_representation::Game;
+
+/// Which cells a path may go through besides walls, which it never can. A path can always leave
+/// the cell it starts from, whatever is on it, e.g. a snake's head
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PathOptions {
+    /// don't go through hazards
+    pub avoid_hazards: bool,
+    /// don't go through any part of a snake, heads included
+    pub avoid_snake_bodies: bool,
+}
+
+/// A game in which shortest paths can be found. The default implementation works over any board,
+/// compact boards override it with one that keeps its distances in arrays indexed by cell instead
+/// of hashing positions
+pub trait PathfindingGame:
+    NeighborDeterminableGame + HazardQueryableGame + SizeDeterminableGame
+{
+    /// The cells along a shortest path from `from` to `to`, starting with `from` and ending with
+    /// `to`. None if `to` can't be reached
+    fn shortest_path(
+        &self,
+        from: &Self::NativePositionType,
+        to: &Self::NativePositionType,
+        options: PathOptions,
+    ) -> Option<Vec<Self::NativePositionType>> {
+        let mut came_from = HashMap::new();
+        came_from.insert(from.clone(), from.clone());
+        let mut queue = VecDeque::from(vec![from.clone()]);
+        while let Some(pos) = queue.pop_front() {
+            if pos == *to {
+                let mut path = vec![pos];
+                while path.last() != Some(from) {
+                    path.push(came_from[path.last().unwrap()].clone());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.neighbors(&pos) {
+                if !came_from.contains_key(&neighbor) && passable(self, &neighbor, options) {
+                    came_from.insert(neighbor.clone(), pos.clone());
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+
+    /// The fewest moves it takes to get from `from` to every cell, in row order like
+    /// [crate::algorithms::hazard_metrics::HazardMetricsGame] visits cells. None for cells that
+    /// can't be reached
+    fn distance_matrix(
+        &self,
+        from: &Self::NativePositionType,
+        options: PathOptions,
+    ) -> Vec<Option<u32>> {
+        let mut distances = HashMap::new();
+        distances.insert(from.clone(), 0);
+        let mut queue = VecDeque::from(vec![from.clone()]);
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[&pos] + 1;
+            for neighbor in self.neighbors(&pos) {
+                if !distances.contains_key(&neighbor) && passable(self, &neighbor, options) {
+                    distances.insert(neighbor.clone(), distance);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        all_native_positions(self)
+            .map(|pos| distances.get(&pos).copied())
+            .collect()
+    }
+}
+
+impl PathfindingGame for Game {}
+
+fn passable<G: HazardQueryableGame + ?Sized>(
+    game: &G,
+    pos: &G::NativePositionType,
+    options: PathOptions,
+) -> bool {
+    (!options.avoid_hazards || !game.is_hazard(pos))
+        && (!options.avoid_snake_bodies || !game.position_is_snake_body(pos.clone()))
+}
+
+/// Breadth first search over the cells `0..cell_count` out from `from`, stopping early once `to`
+/// is reached. Returns the distance to each cell, `u32::MAX` for cells that weren't reached, and
+/// the cell each cell was reached from
+pub(crate) fn indexed_bfs<I: IntoIterator<Item = usize>>(
+    cell_count: usize,
+    from: usize,
+    to: Option<usize>,
+    passable: impl Fn(usize) -> bool,
+    neighbors: impl Fn(usize) -> I,
+) -> (Vec<u32>, Vec<usize>) {
+    let mut distances = vec![u32::MAX; cell_count];
+    let mut came_from = vec![from; cell_count];
+    // every cell is queued at most once, so the queue never has to grow or wrap
+    let mut queue = Vec::with_capacity(cell_count);
+    distances[from] = 0;
+    queue.push(from);
+
+    let mut next = 0;
+    while next < queue.len() && Some(queue[next]) != to {
+        let cell = queue[next];
+        next += 1;
+        for neighbor in neighbors(cell) {
+            if distances[neighbor] == u32::MAX && passable(neighbor) {
+                distances[neighbor] = distances[cell] + 1;
+                came_from[neighbor] = cell;
+                queue.push(neighbor);
+            }
+        }
+    }
+    (distances, came_from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        fixtures::{self, check_each},
+        game_fixture,
+        types::build_snake_id_map,
+        wire_representation::Position,
+    };
+
+    fn all_options() -> Vec<PathOptions> {
+        let mut options = vec![];
+        for avoid_hazards in [false, true] {
+            for avoid_snake_bodies in [false, true] {
+                options.push(PathOptions {
+                    avoid_hazards,
+                    avoid_snake_bodies,
+                });
+            }
+        }
+        options
+    }
+
+    /// checks a board against the wire game it was converted from
+    fn check_matches_wire<B>(wire: &Game, compact: &B) -> Result<(), Box<dyn std::error::Error>>
+    where
+        B: PathfindingGame,
+    {
+        let from = wire.you.head;
+        let compact_from = compact.native_from_position(from);
+        for options in all_options() {
+            let distances = wire.distance_matrix(&from, options);
+            if compact.distance_matrix(&compact_from, options) != distances {
+                return Err(format!("different distances with {:?}", options).into());
+            }
+
+            for (to, distance) in all_native_positions(wire).zip(distances.iter()) {
+                let path = wire.shortest_path(&from, &to, options);
+                let compact_path = compact
+                    .shortest_path(&compact_from, &compact.native_from_position(to), options)
+                    .map(|p| {
+                        p.into_iter()
+                            .map(|c| compact.position_from_native(c))
+                            .collect::<Vec<_>>()
+                    });
+                let lengths = (
+                    path.as_ref().map(|p| p.len() as u32 - 1),
+                    compact_path.as_ref().map(|p| p.len() as u32 - 1),
+                );
+                if lengths != (*distance, *distance) {
+                    return Err(format!(
+                        "paths to {:?} with {:?} don't match the distance {:?}: {:?}",
+                        to, options, distance, lengths
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_paths_match_wire() {
+        let fits = fixtures::matching(|m| m.width == 11 && m.height == 11 && m.snake_count <= 4);
+        check_each(fits, |fixture| {
+            let wire = fixture.game();
+            let id_map = build_snake_id_map(&wire);
+            if wire.is_wrapped() {
+                let compact: WrappedCellBoard4Snakes11x11 = wire.as_wrapped_cell_board(&id_map)?;
+                check_matches_wire(&wire, &compact)
+            } else {
+                let compact: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&id_map)?;
+                check_matches_wire(&wire, &compact)
+            }
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        // hazards cover the three leftmost columns
+        let g = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let from = Position::new(4, 0);
+        let to = Position::new(0, 0);
+
+        let path = g.shortest_path(&from, &to, PathOptions::default()).unwrap();
+        assert_eq!(path.first(), Some(&from));
+        assert_eq!(path.last(), Some(&to));
+        // (2, 0) is a snake's head, so only going around it avoids snakes
+        assert_eq!(path.len(), 5);
+        let avoid_snakes = PathOptions {
+            avoid_snake_bodies: true,
+            ..Default::default()
+        };
+        assert!(g.shortest_path(&from, &to, avoid_snakes).unwrap().len() > 5);
+        let avoid_hazards = PathOptions {
+            avoid_hazards: true,
+            ..Default::default()
+        };
+        assert_eq!(g.shortest_path(&from, &to, avoid_hazards), None);
+        assert_eq!(
+            g.shortest_path(&from, &from, avoid_hazards),
+            Some(vec![from])
+        );
+    }
+}
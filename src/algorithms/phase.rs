@@ -0,0 +1,166 @@
+//! classifying a position as opening, midgame or endgame, so engines and datasets can branch on
+//! the phase of a game the same way
+
+use std::collections::HashSet;
+
+use super::all_native_positions;
+use crate::types::{
+    HazardQueryableGame, HealthGettableGame, SizeDeterminableGame, SnakeBodyGettableGame,
+};
+
+/// The phase a game is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GamePhase {
+    /// the first turns of a game, before snakes have had a chance to meet
+    Opening,
+    /// anything that is neither the opening nor the endgame
+    Midgame,
+    /// few snakes are left alive, or little of the board is free
+    Endgame,
+}
+
+/// The thresholds [PhaseGame::phase] decides the phase by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseThresholds {
+    /// the game is in the opening up to and including this turn
+    pub opening_turns: u64,
+    /// the game is in the endgame once this many snakes or fewer are alive. The default of 1 only
+    /// applies to solo games, so that duels go through a midgame too
+    pub endgame_snakes: usize,
+    /// the game is in the endgame once the free share of the board is this or lower
+    pub endgame_free_space: f32,
+}
+
+impl Default for PhaseThresholds {
+    fn default() -> Self {
+        Self {
+            opening_turns: 20,
+            endgame_snakes: 1,
+            endgame_free_space: 0.5,
+        }
+    }
+}
+
+/// A game whose phase can be determined. This is implemented for every game that can query
+/// hazards, healths, snake bodies and its size. The turn is passed in, so a position can be
+/// classified as if it were on any turn
+pub trait PhaseGame:
+    HazardQueryableGame + HealthGettableGame + SnakeBodyGettableGame + SizeDeterminableGame
+{
+    /// the fraction of cells on the board that are neither part of a snake nor a hazard, from 0.0
+    /// to 1.0
+    fn free_space_ratio(&self) -> f32 {
+        let total = self.get_width() * self.get_height();
+        if total == 0 {
+            return 0.0;
+        }
+        let snakes: HashSet<_> = self
+            .get_snake_ids()
+            .iter()
+            .filter(|id| self.is_alive(id))
+            .flat_map(|id| self.get_snake_body_iter(id))
+            .collect();
+        let free = all_native_positions(self)
+            .filter(|pos| !self.is_hazard(pos) && !snakes.contains(pos))
+            .count();
+
+        free as f32 / total as f32
+    }
+
+    /// The phase of the game on the given turn. The opening takes precedence, so a game is never
+    /// in the endgame before `opening_turns` have passed
+    fn phase(&self, turn: u64, thresholds: &PhaseThresholds) -> GamePhase {
+        if turn <= thresholds.opening_turns {
+            return GamePhase::Opening;
+        }
+        let alive = self
+            .get_snake_ids()
+            .iter()
+            .filter(|id| self.is_alive(id))
+            .count();
+        if alive <= thresholds.endgame_snakes
+            || self.free_space_ratio() <= thresholds.endgame_free_space
+        {
+            GamePhase::Endgame
+        } else {
+            GamePhase::Midgame
+        }
+    }
+}
+
+impl<T> PhaseGame for T where
+    T: HazardQueryableGame + HealthGettableGame + SnakeBodyGettableGame + SizeDeterminableGame
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        fixtures::{self, check_each},
+        game_fixture,
+        types::build_snake_id_map,
+    };
+
+    #[test]
+    fn test_compact_phase_matches_wire() {
+        let thresholds = PhaseThresholds::default();
+        let fits = fixtures::matching(|m| m.width == 11 && m.height == 11 && m.snake_count <= 4);
+        check_each(fits, |fixture| {
+            let wire = fixture.game();
+            let turn = wire.turn.max(0) as u64;
+            let id_map = build_snake_id_map(&wire);
+            let (ratio, phase) = if wire.is_wrapped() {
+                let compact: WrappedCellBoard4Snakes11x11 = wire.as_wrapped_cell_board(&id_map)?;
+                (compact.free_space_ratio(), compact.phase(turn, &thresholds))
+            } else {
+                let compact: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&id_map)?;
+                (compact.free_space_ratio(), compact.phase(turn, &thresholds))
+            };
+            if ratio != wire.free_space_ratio() {
+                return Err(format!("free space {} != {}", ratio, wire.free_space_ratio()).into());
+            }
+            if phase != wire.phase(turn, &thresholds) {
+                return Err(
+                    format!("phase {:?} != {:?}", phase, wire.phase(turn, &thresholds)).into(),
+                );
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_phase() {
+        let thresholds = PhaseThresholds::default();
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        assert_eq!(g.phase(0, &thresholds), GamePhase::Opening);
+
+        let g = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        assert_eq!(g.phase(100, &thresholds), GamePhase::Midgame);
+        let fewer_snakes = PhaseThresholds {
+            endgame_snakes: 4,
+            ..thresholds
+        };
+        assert_eq!(g.phase(100, &fewer_snakes), GamePhase::Endgame);
+        let crowded = PhaseThresholds {
+            endgame_free_space: g.free_space_ratio(),
+            ..thresholds
+        };
+        assert_eq!(g.phase(100, &crowded), GamePhase::Endgame);
+    }
+
+    #[test]
+    fn test_duel_phase() {
+        let thresholds = PhaseThresholds::default();
+        let duel = game_fixture(include_str!("../../fixtures/body_collision.json"));
+        assert_eq!(duel.phase(20, &thresholds), GamePhase::Opening);
+        assert_eq!(duel.phase(21, &thresholds), GamePhase::Midgame);
+        assert_eq!(duel.phase(200, &thresholds), GamePhase::Midgame);
+
+        // a duel still reaches the endgame once the board fills up
+        let late = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        assert_eq!(late.phase(150, &thresholds), GamePhase::Endgame);
+    }
+}
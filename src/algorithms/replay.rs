@@ -0,0 +1,297 @@
+//! records a game, e.g. a rollout, as its first board and the [Action] taken each turn, so any
+//! board along the way can be rebuilt, printed, or exported for the board viewer
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use super::all_native_positions;
+use crate::types::{
+    Action, FoodGettableGame, HazardQueryableGame, HealthGettableGame, SimulableGame,
+    SimulatorInstruments, SizeDeterminableGame, SnakeBodyGettableGame, SnakeIDMap, SnakeId,
+    TurnDeterminableGame,
+};
+use crate::wire_representation::{EngineFrame, EnginePoint, EngineSnake};
+
+/// A game as its first board and the [Action] taken on each turn after it. Boards are rebuilt by
+/// simulating the actions, so anything that isn't part of an action, like food placed between
+/// turns of a rollout, isn't replayed
+#[derive(Debug, Clone)]
+pub struct Replay<G, const N_SNAKES: usize> {
+    initial: G,
+    actions: Vec<Action<N_SNAKES>>,
+}
+
+impl<G, const N_SNAKES: usize> Replay<G, N_SNAKES> {
+    /// a replay of no turns starting from `initial`
+    pub fn new(initial: G) -> Self {
+        Replay {
+            initial,
+            actions: vec![],
+        }
+    }
+
+    /// records the action taken on the next turn
+    pub fn push(&mut self, action: Action<N_SNAKES>) {
+        self.actions.push(action);
+    }
+
+    /// the board the replay starts from
+    pub fn initial(&self) -> &G {
+        &self.initial
+    }
+
+    /// the action taken on each turn, in order
+    pub fn actions(&self) -> &[Action<N_SNAKES>] {
+        &self.actions
+    }
+
+    /// the number of turns recorded
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    #[allow(missing_docs)]
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl<G, const N_SNAKES: usize> Replay<G, N_SNAKES>
+where
+    G: Clone + SnakeBodyGettableGame<SnakeIDType = SnakeId>,
+{
+    /// Every board of the replay, the initial one first, so there's one more than [Replay::len].
+    /// Errors if an action doesn't produce a board
+    pub fn frames<I: SimulatorInstruments>(&self, instruments: &I) -> Result<Vec<G>, Box<dyn Error>>
+    where
+        G: SimulableGame<I, N_SNAKES>,
+    {
+        let mut frames = Vec::with_capacity(self.actions.len() + 1);
+        frames.push(self.initial.clone());
+        for (turn, action) in self.actions.iter().enumerate() {
+            let moves = action
+                .into_inner()
+                .iter()
+                .enumerate()
+                .filter_map(|(id, mv)| mv.map(|mv| (SnakeId(id as u8), [mv])))
+                .collect::<Vec<_>>();
+            let (_, next) = frames[turn]
+                .simulate_with_moves(instruments, moves)
+                .next()
+                .ok_or_else(|| format!("action {} of the replay produced no board", turn))?;
+            frames.push(next);
+        }
+        Ok(frames)
+    }
+
+    /// The board after `turn` actions, 0 being the initial board. Errors if `turn` is past the
+    /// end of the replay
+    pub fn state_at<I: SimulatorInstruments>(
+        &self,
+        instruments: &I,
+        turn: usize,
+    ) -> Result<G, Box<dyn Error>>
+    where
+        G: SimulableGame<I, N_SNAKES>,
+    {
+        if turn > self.actions.len() {
+            return Err(format!("the replay has {} turns, not {}", self.len(), turn).into());
+        }
+        let truncated = Replay {
+            initial: self.initial.clone(),
+            actions: self.actions[..turn].to_vec(),
+        };
+        Ok(truncated.frames(instruments)?.pop().unwrap())
+    }
+
+    /// Every board of the replay in the game engine's frame format, which the board viewer plays
+    /// back. Snakes are given their wire ids from `id_map`. A snake stays in the frames after
+    /// it's eliminated, with the body it had when it died and the turn it died on
+    pub fn engine_frames<I: SimulatorInstruments>(
+        &self,
+        instruments: &I,
+        id_map: &SnakeIDMap,
+    ) -> Result<Vec<EngineFrame>, Box<dyn Error>>
+    where
+        G: SimulableGame<I, N_SNAKES>
+            + HealthGettableGame
+            + FoodGettableGame
+            + HazardQueryableGame
+            + SizeDeterminableGame
+            + TurnDeterminableGame,
+    {
+        let names = id_map
+            .iter()
+            .map(|(name, id)| (*id, name.clone()))
+            .collect::<HashMap<_, _>>();
+        let point = |game: &G, native| EnginePoint::from(game.position_from_native(native));
+
+        let mut frames: Vec<EngineFrame> = vec![];
+        for game in self.frames(instruments)? {
+            let snakes = game
+                .get_snake_ids()
+                .into_iter()
+                .filter(|sid| game.is_alive(sid))
+                .map(|sid| {
+                    let name = names
+                        .get(&sid)
+                        .cloned()
+                        .unwrap_or_else(|| format!("snake {}", sid.0));
+                    let body = game
+                        .get_snake_body_vec(&sid)
+                        .into_iter()
+                        .map(|native| point(&game, native))
+                        .collect();
+                    EngineSnake::new(name.clone(), name, body, game.get_health_i64(&sid) as i32)
+                })
+                .collect();
+            let hazards = all_native_positions(&game)
+                .flat_map(|native| {
+                    let count = game.get_hazard_count(&native) as usize;
+                    std::iter::repeat_n(point(&game, native), count)
+                })
+                .collect();
+            let mut frame = EngineFrame {
+                turn: game.turn() as i32,
+                snakes,
+                food: game
+                    .get_all_food_as_native_positions()
+                    .into_iter()
+                    .map(|native| point(&game, native))
+                    .collect(),
+                hazards,
+            };
+            if let Some(previous) = frames.last() {
+                frame.carry_over(previous);
+            }
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+}
+
+/// Renders every board of a [Replay] one after another, see [Replay::display]
+#[derive(Debug)]
+pub struct ReplayDisplay<'a, G, I, const N_SNAKES: usize> {
+    replay: &'a Replay<G, N_SNAKES>,
+    instruments: &'a I,
+}
+
+impl<G, const N_SNAKES: usize> Replay<G, N_SNAKES> {
+    /// something to print every board of the replay with, headed by the number of actions taken
+    /// to reach it
+    pub fn display<'a, I>(&'a self, instruments: &'a I) -> ReplayDisplay<'a, G, I, N_SNAKES> {
+        ReplayDisplay {
+            replay: self,
+            instruments,
+        }
+    }
+}
+
+impl<G, I, const N_SNAKES: usize> Display for ReplayDisplay<'_, G, I, N_SNAKES>
+where
+    G: Clone + Display + SnakeBodyGettableGame<SnakeIDType = SnakeId> + SimulableGame<I, N_SNAKES>,
+    I: SimulatorInstruments,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let frames = self
+            .replay
+            .frames(self.instruments)
+            .map_err(|_| std::fmt::Error)?;
+        for (turn, frame) in frames.iter().enumerate() {
+            writeln!(f, "turn {}:", turn)?;
+            writeln!(f, "{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{
+            build_snake_id_map, Move, NoopInstruments, ReasonableMovesGame, VictorDeterminableGame,
+        },
+    };
+
+    #[test]
+    fn test_replay() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let start: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        let mut replay = Replay::new(start);
+        let mut expected = vec![start];
+        let mut board = start;
+        while !board.is_over() && replay.len() < 10 {
+            let moves = board
+                .reasonable_moves_for_each_snake()
+                .map(|(sid, moves)| (sid, [moves[0]]))
+                .collect::<Vec<_>>();
+            let (action, next) = board
+                .simulate_with_moves(&NoopInstruments, moves)
+                .next()
+                .unwrap();
+            replay.push(action);
+            expected.push(next);
+            board = next;
+        }
+        assert!(!replay.is_empty());
+
+        let frames = replay.frames(&NoopInstruments).unwrap();
+        assert_eq!(frames, expected);
+        assert_eq!(replay.state_at(&NoopInstruments, 2).unwrap(), expected[2]);
+        assert!(replay.state_at(&NoopInstruments, replay.len() + 1).is_err());
+
+        let rendered = replay.display(&NoopInstruments).to_string();
+        assert!(rendered.starts_with("turn 0:\n"));
+        assert!(rendered.contains(&format!("turn {}:\n", replay.len())));
+
+        let exported = replay.engine_frames(&NoopInstruments, &id_map).unwrap();
+        assert_eq!(exported.len(), replay.len() + 1);
+        // compact boards don't keep names or the order of the food
+        let wire = g.to_engine_frame();
+        let first = &exported[0];
+        assert_eq!(first.hazards.len(), wire.hazards.len());
+        assert_eq!(first.food.len(), wire.food.len());
+        for snake in &wire.snakes {
+            let exported = first.snakes.iter().find(|s| s.id == snake.id).unwrap();
+            assert_eq!(exported.body, snake.body);
+            assert_eq!(exported.health, snake.health);
+        }
+        assert_eq!(exported[1].turn, g.turn + 1);
+    }
+
+    #[test]
+    fn test_replay_records_deaths() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let start: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        // you move back in to your own neck
+        let mut replay = Replay::new(start);
+        let (action, _) = start
+            .simulate_with_moves(
+                &NoopInstruments,
+                vec![(SnakeId(0), [Move::Down]), (SnakeId(1), [Move::Up])],
+            )
+            .next()
+            .unwrap();
+        replay.push(action);
+
+        let exported = replay.engine_frames(&NoopInstruments, &id_map).unwrap();
+        let you = |frame: &EngineFrame| {
+            frame
+                .snakes
+                .iter()
+                .find(|s| s.id == g.you.id)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(you(&exported[0]).death, None);
+        assert_eq!(you(&exported[1]).death.unwrap().turn, g.turn + 1);
+        assert_eq!(you(&exported[1]).body, you(&exported[0]).body);
+    }
+}
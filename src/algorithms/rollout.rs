@@ -0,0 +1,371 @@
+//! plays a game out with random reasonable moves, the building block of monte carlo style search
+
+use rand::Rng;
+
+use super::events::{EventDiffableGame, GameEvent};
+use crate::types::{
+    HealthGettableGame, Move, RandomReasonableMovesGame, SimulableGame, SimulatorInstruments,
+    StandardFoodPlaceableGame, VictorDeterminableGame, YouDeterminableGame, N_MOVES,
+};
+
+/// How a random rollout ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RolloutOutcome<S> {
+    /// the winner, None for draws, solo games, and rollouts that hit the turn limit
+    pub winner: Option<S>,
+    /// how many of the simulated turns you were still alive at the end of
+    pub turns_survived: u64,
+    /// how many turns were simulated
+    pub turns: u64,
+    /// whether the game ended before the turn limit
+    pub finished: bool,
+    /// whether the game was in solo mode, see [VictorDeterminableGame::is_solo]
+    pub solo: bool,
+}
+
+impl<S: PartialEq> RolloutOutcome<S> {
+    /// The score of this rollout for `you`. Surviving is the only goal in solo games, so the score
+    /// is the number of turns survived. Otherwise it's 1 for a win, 0 for a loss, and 0.5 for a
+    /// draw or a rollout that hit the turn limit
+    pub fn score(&self, you: &S) -> f64 {
+        if self.solo {
+            return self.turns_survived as f64;
+        }
+        match &self.winner {
+            Some(winner) if winner == you => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+/// The rollouts played after one of your root moves, see [RolloutStats]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MoveStats {
+    /// how many rollouts were recorded
+    pub visits: u64,
+    #[allow(missing_docs)]
+    pub wins: u64,
+    /// rollouts without a winner, including solo games and rollouts that hit the turn limit
+    pub draws: u64,
+    #[allow(missing_docs)]
+    pub losses: u64,
+    /// the sum of the rollouts' [RolloutOutcome::turns]
+    pub total_turns: u64,
+    /// the sum of the rollouts' [RolloutOutcome::score]s
+    pub total_score: f64,
+}
+
+impl MoveStats {
+    /// the average [RolloutOutcome::score], None without any visits
+    pub fn mean_score(&self) -> Option<f64> {
+        self.average(self.total_score)
+    }
+
+    /// the average number of turns a rollout lasted, None without any visits
+    pub fn average_game_length(&self) -> Option<f64> {
+        self.average(self.total_turns as f64)
+    }
+
+    fn average(&self, total: f64) -> Option<f64> {
+        (self.visits > 0).then(|| total / self.visits as f64)
+    }
+
+    /// adds the rollouts recorded in `other` to these
+    pub fn merge(&mut self, other: &MoveStats) {
+        self.visits += other.visits;
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.total_turns += other.total_turns;
+        self.total_score += other.total_score;
+    }
+}
+
+/// Aggregates rollout outcomes by the move you made at the root of a search. Each worker thread
+/// keeps its own stats and [RolloutStats::merge]s them in to one when it's done, so no locking is
+/// needed while rolling out
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RolloutStats {
+    moves: [MoveStats; N_MOVES],
+}
+
+impl RolloutStats {
+    /// stats without any rollouts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// records a rollout that started with you making `root_move`
+    pub fn record<S: PartialEq>(&mut self, root_move: Move, outcome: &RolloutOutcome<S>, you: &S) {
+        let stats = &mut self.moves[root_move.as_index()];
+        stats.visits += 1;
+        match &outcome.winner {
+            _ if outcome.solo => stats.draws += 1,
+            Some(winner) if winner == you => stats.wins += 1,
+            Some(_) => stats.losses += 1,
+            None => stats.draws += 1,
+        }
+        stats.total_turns += outcome.turns;
+        stats.total_score += outcome.score(you);
+    }
+
+    /// adds the rollouts recorded in `other` to these
+    pub fn merge(&mut self, other: &RolloutStats) {
+        for (stats, other) in self.moves.iter_mut().zip(other.moves.iter()) {
+            stats.merge(other);
+        }
+    }
+
+    /// the stats of the rollouts that started with `root_move`
+    pub fn for_move(&self, root_move: Move) -> &MoveStats {
+        &self.moves[root_move.as_index()]
+    }
+
+    /// every root move with its stats, in [Move::all] order
+    pub fn iter(&self) -> impl Iterator<Item = (Move, &MoveStats)> {
+        Move::all_iter().zip(self.moves.iter())
+    }
+
+    /// the total number of rollouts recorded
+    pub fn visits(&self) -> u64 {
+        self.moves.iter().map(|m| m.visits).sum()
+    }
+
+    /// the average number of turns a rollout lasted over every root move, None without any visits
+    pub fn average_game_length(&self) -> Option<f64> {
+        let visits = self.visits();
+        let turns: u64 = self.moves.iter().map(|m| m.total_turns).sum();
+        (visits > 0).then(|| turns as f64 / visits as f64)
+    }
+
+    /// the root move with the most visits, the usual final pick of a monte carlo search. None
+    /// without any visits
+    pub fn most_visited(&self) -> Option<Move> {
+        self.iter()
+            .filter(|(_, stats)| stats.visits > 0)
+            .max_by_key(|(_, stats)| stats.visits)
+            .map(|(mv, _)| mv)
+    }
+}
+
+impl std::iter::Sum for RolloutStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(), |mut total, stats| {
+            total.merge(&stats);
+            total
+        })
+    }
+}
+
+/// A game that can be played out with random reasonable moves. This is implemented for every
+/// game that can be simulated, pick random moves, and place food
+pub trait RolloutGame<I: SimulatorInstruments, const N_SNAKES: usize>:
+    SimulableGame<I, N_SNAKES>
+    + RandomReasonableMovesGame
+    + VictorDeterminableGame
+    + StandardFoodPlaceableGame
+    + YouDeterminableGame
+    + HealthGettableGame
+{
+    /// Plays this game out for at most `max_turns` turns, every snake making a random reasonable
+    /// move each turn and food being placed after each turn
+    fn random_rollout(
+        &self,
+        instruments: &I,
+        rng: &mut impl Rng,
+        max_turns: u64,
+    ) -> RolloutOutcome<Self::SnakeIDType> {
+        self.random_rollout_observed(instruments, rng, max_turns, |_, _| {})
+    }
+
+    /// [RolloutGame::random_rollout], also returning every board the rollout passed through
+    /// along with the [GameEvent]s of the turn that produced it
+    #[allow(clippy::type_complexity)]
+    fn random_rollout_with_events(
+        &self,
+        instruments: &I,
+        rng: &mut impl Rng,
+        max_turns: u64,
+    ) -> (
+        RolloutOutcome<Self::SnakeIDType>,
+        Vec<(
+            Self,
+            Vec<GameEvent<Self::SnakeIDType, Self::NativePositionType>>,
+        )>,
+    )
+    where
+        Self: EventDiffableGame + Clone,
+    {
+        let mut turns = vec![];
+        let outcome = self.random_rollout_observed(instruments, rng, max_turns, |before, after| {
+            turns.push((after.clone(), after.events_since(before)))
+        });
+        (outcome, turns)
+    }
+
+    /// [RolloutGame::random_rollout], calling `on_turn` with the boards before and after each
+    /// turn
+    fn random_rollout_observed(
+        &self,
+        instruments: &I,
+        rng: &mut impl Rng,
+        max_turns: u64,
+        mut on_turn: impl FnMut(&Self, &Self),
+    ) -> RolloutOutcome<Self::SnakeIDType> {
+        let mut game = None;
+        let mut turns_survived = 0;
+        let mut turns = 0;
+
+        loop {
+            let current: &Self = game.as_ref().unwrap_or(self);
+            if current.is_over() || turns >= max_turns {
+                return RolloutOutcome {
+                    winner: current.get_winner(),
+                    turns_survived,
+                    turns,
+                    finished: current.is_over(),
+                    solo: current.is_solo(),
+                };
+            }
+
+            let moves = current
+                .random_reasonable_move_for_each_snake(rng)
+                .map(|(sid, mv)| (sid, [mv]))
+                .collect::<Vec<_>>();
+            let mut next = current
+                .simulate_with_moves(instruments, moves)
+                .next()
+                .expect("one move for each snake makes one child")
+                .1;
+            next.place_food(rng);
+            on_turn(current, &next);
+
+            turns += 1;
+            if next.is_alive(next.you_id()) {
+                turns_survived += 1;
+            }
+            game = Some(next);
+        }
+    }
+}
+
+impl<G, I: SimulatorInstruments, const N_SNAKES: usize> RolloutGame<I, N_SNAKES> for G where
+    G: SimulableGame<I, N_SNAKES>
+        + RandomReasonableMovesGame
+        + VictorDeterminableGame
+        + StandardFoodPlaceableGame
+        + YouDeterminableGame
+        + HealthGettableGame
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        compact_representation::{dimensions::Square, standard::CellBoard},
+        game_fixture,
+        types::{build_snake_id_map, ReasonableMovesGame, SnakeId},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    #[test]
+    fn test_solo_rollout_scores_survival() {
+        let g = game_fixture(include_str!("../../fixtures/solo_minimal.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u8, Square, { 7 * 7 }, 2> = g.as_cell_board(&id_map).unwrap();
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        let capped = compact.random_rollout(&Instruments, &mut rng, 1);
+        assert!(!capped.finished);
+        assert_eq!(capped.score(&SnakeId(0)), 1.0);
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        let outcome = compact.random_rollout(&Instruments, &mut rng, 10_000);
+        assert!(outcome.finished && outcome.solo);
+        assert_eq!(outcome.winner, None);
+        assert!(outcome.turns_survived >= 1);
+        assert_eq!(outcome.score(&SnakeId(0)), outcome.turns_survived as f64);
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        let (with_events, turns) =
+            compact.random_rollout_with_events(&Instruments, &mut rng, 10_000);
+        assert_eq!(with_events, outcome);
+        assert_eq!(turns.len() as u64, outcome.turns_survived + 1);
+        let (last, last_events) = turns.last().unwrap();
+        assert!(last.is_over());
+        assert!(last_events.contains(&GameEvent::Eliminated(SnakeId(0))));
+        assert!(turns[..turns.len() - 1]
+            .iter()
+            .all(|(_, events)| matches!(events[0], GameEvent::Moved(SnakeId(0), _))));
+    }
+
+    #[test]
+    fn test_rollout_stats_merge_across_workers() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u8, Square, { 11 * 11 }, 4> = g.as_cell_board(&id_map).unwrap();
+        let root_moves = compact
+            .reasonable_moves_for_each_snake()
+            .find(|(sid, _)| *sid == SnakeId(0))
+            .unwrap()
+            .1;
+
+        let worker = |seed: u64| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut stats = RolloutStats::new();
+            for &mv in root_moves.iter() {
+                let moves = vec![(SnakeId(0), [mv])];
+                for (_, child) in compact.simulate_with_moves(&Instruments, moves) {
+                    let outcome = child.random_rollout(&Instruments, &mut rng, 100);
+                    stats.record(mv, &outcome, &SnakeId(0));
+                }
+            }
+            stats
+        };
+        let merged: RolloutStats = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|seed| scope.spawn(move || worker(seed)))
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+
+        let one_worker = worker(0);
+        assert_eq!(merged.visits(), 4 * one_worker.visits());
+        for (mv, stats) in merged.iter() {
+            assert_eq!(stats.visits, 4 * one_worker.for_move(mv).visits);
+            assert_eq!(stats.wins + stats.draws + stats.losses, stats.visits);
+            if let Some(score) = stats.mean_score() {
+                assert!((0.0..=1.0).contains(&score));
+            }
+        }
+        assert!(root_moves.contains(&merged.most_visited().unwrap()));
+        assert!(merged.average_game_length().unwrap() <= 100.0);
+        assert_eq!(RolloutStats::new().most_visited(), None);
+    }
+
+    #[test]
+    fn test_rollout_scores_wins() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u8, Square, { 11 * 11 }, 4> = g.as_cell_board(&id_map).unwrap();
+
+        let mut rng = SmallRng::seed_from_u64(3);
+        let outcome = compact.random_rollout(&Instruments, &mut rng, 10_000);
+        assert!(outcome.finished && !outcome.solo);
+        let score = outcome.score(&SnakeId(0));
+        match outcome.winner {
+            Some(SnakeId(0)) => assert_eq!(score, 1.0),
+            Some(_) => assert_eq!(score, 0.0),
+            None => assert_eq!(score, 0.5),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! voronoi style territory scoring, where every cell belongs to the snake whose head can reach it
+//! first
+
+use std::collections::HashMap;
+
+use crate::types::{
+    HeadGettableGame, HealthGettableGame, LengthGettableGame, NeighborDeterminableGame,
+    SnakeIDGettableGame,
+};
+
+/// How to decide who owns a cell that multiple snakes reach on the same turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TieBreak {
+    /// the longest snake takes the cell, equal lengths leave it contested
+    Length,
+    /// the healthiest snake takes the cell, equal healths leave it contested
+    Health,
+    /// the cell is always contested
+    Split,
+}
+
+/// Who owns a given cell after territory evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellOwner<S> {
+    /// this cell belongs to the given snake
+    Snake(S),
+    /// this cell is reached at the same time by all of the given snakes, and the tie break
+    /// couldn't pick a single owner
+    Contested(Vec<S>),
+}
+
+/// The result of territory evaluation
+#[derive(Debug, Clone)]
+pub struct Territory<S, P> {
+    /// territory score per snake, in the same order as `get_snake_ids`. Contested cells are
+    /// shared equally between the snakes contesting them
+    pub scores: Vec<(S, f32)>,
+    /// the owner of every cell that was reached, unreachable cells are absent
+    pub owners: HashMap<P, CellOwner<S>>,
+}
+
+impl<S: PartialEq, P> Territory<S, P> {
+    /// the territory score for a given snake, 0.0 for unknown snakes
+    pub fn score(&self, sid: &S) -> f32 {
+        self.scores
+            .iter()
+            .find(|(s, _)| s == sid)
+            .map(|(_, score)| *score)
+            .unwrap_or(0.0)
+    }
+}
+
+/// A game for which each snake's territory can be evaluated
+pub trait TerritoryEvaluatableGame:
+    SnakeIDGettableGame
+    + HeadGettableGame
+    + NeighborDeterminableGame
+    + LengthGettableGame
+    + HealthGettableGame
+{
+    /// run a simultaneous breadth first search from every alive snake's head. Snake bodies block
+    /// the search, hazards are ignored. Contested cells do not spread any further
+    fn territory(
+        &self,
+        tie_break: TieBreak,
+    ) -> Territory<Self::SnakeIDType, Self::NativePositionType> {
+        let snake_ids = self
+            .get_snake_ids()
+            .into_iter()
+            .filter(|sid| self.is_alive(sid))
+            .collect::<Vec<_>>();
+
+        let mut owners = HashMap::new();
+        let mut frontier = vec![];
+        for sid in snake_ids.iter() {
+            let head = self.get_head_as_native_position(sid);
+            owners.insert(head.clone(), CellOwner::Snake(sid.clone()));
+            frontier.push((head, sid.clone()));
+        }
+
+        while !frontier.is_empty() {
+            let mut claims: HashMap<Self::NativePositionType, Vec<Self::SnakeIDType>> =
+                HashMap::new();
+            for (pos, sid) in frontier.drain(..) {
+                for neighbor in self.neighbors(&pos) {
+                    if owners.contains_key(&neighbor)
+                        || self.position_is_snake_body(neighbor.clone())
+                    {
+                        continue;
+                    }
+                    let claimants = claims.entry(neighbor).or_default();
+                    if !claimants.contains(&sid) {
+                        claimants.push(sid.clone());
+                    }
+                }
+            }
+
+            for (pos, claimants) in claims {
+                let owner = if claimants.len() == 1 {
+                    Some(claimants[0].clone())
+                } else {
+                    match tie_break {
+                        TieBreak::Length => {
+                            unique_max_by_key(&claimants, |s| self.get_length_i64(s))
+                        }
+                        TieBreak::Health => {
+                            unique_max_by_key(&claimants, |s| self.get_health_i64(s))
+                        }
+                        TieBreak::Split => None,
+                    }
+                };
+
+                match owner {
+                    Some(sid) => {
+                        owners.insert(pos.clone(), CellOwner::Snake(sid.clone()));
+                        frontier.push((pos, sid));
+                    }
+                    None => {
+                        owners.insert(pos, CellOwner::Contested(claimants));
+                    }
+                }
+            }
+        }
+
+        let mut scores = snake_ids.into_iter().map(|s| (s, 0.0)).collect::<Vec<_>>();
+        for owner in owners.values() {
+            match owner {
+                CellOwner::Snake(sid) => {
+                    if let Some((_, score)) = scores.iter_mut().find(|(s, _)| s == sid) {
+                        *score += 1.0;
+                    }
+                }
+                CellOwner::Contested(sids) => {
+                    let share = 1.0 / sids.len() as f32;
+                    for (_, score) in scores.iter_mut().filter(|(s, _)| sids.contains(s)) {
+                        *score += share;
+                    }
+                }
+            }
+        }
+
+        Territory { scores, owners }
+    }
+}
+
+fn unique_max_by_key<S: Clone>(snakes: &[S], key: impl Fn(&S) -> i64) -> Option<S> {
+    let max = snakes.iter().map(&key).max()?;
+    let mut best = snakes.iter().filter(|s| key(s) == max);
+    let first = best.next()?;
+    if best.next().is_some() {
+        None
+    } else {
+        Some(first.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11, game_fixture,
+        types::build_snake_id_map,
+    };
+
+    #[test]
+    fn test_territory_matches_across_representations() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        for tie_break in [TieBreak::Length, TieBreak::Health, TieBreak::Split] {
+            let wire_territory = g.territory(tie_break);
+            let compact_territory = compact.territory(tie_break);
+
+            for (wire_id, compact_id) in id_map.iter() {
+                assert_eq!(
+                    wire_territory.score(wire_id),
+                    compact_territory.score(compact_id)
+                );
+            }
+            assert_eq!(wire_territory.owners.len(), compact_territory.owners.len());
+
+            let total: f32 = compact_territory.scores.iter().map(|(_, s)| s).sum();
+            assert!((total - compact_territory.owners.len() as f32).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_split_contests_equidistant_cells() {
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let territory = g.territory(TieBreak::Split);
+
+        assert!(territory
+            .owners
+            .values()
+            .any(|owner| matches!(owner, CellOwner::Contested(_))));
+    }
+}
@@ -0,0 +1,155 @@
+//! detection of two snakes facing each other down a single width corridor, where whoever turns
+//! away first loses the tunnel and neither can get past the other
+
+use crate::types::{
+    HeadGettableGame, HealthGettableGame, NeighborDeterminableGame, SizeDeterminableGame,
+};
+
+/// A game in which head on tunnels can be detected. This is implemented for every game that can
+/// get heads, healths, neighbors and its size
+pub trait TunnelDetectableGame:
+    HeadGettableGame + HealthGettableGame + NeighborDeterminableGame + SizeDeterminableGame
+{
+    /// The number of empty cells separating two heads, if they are joined by a single width
+    /// corridor: every cell between them has snake bodies or walls on both sides. Adjacent heads
+    /// are separated by 0 cells. Returns None if there is no such corridor
+    fn tunnel_length_between(
+        &self,
+        head_a: &Self::NativePositionType,
+        head_b: &Self::NativePositionType,
+    ) -> Option<usize> {
+        let passable = |pos: &Self::NativePositionType| {
+            pos == head_a || pos == head_b || !self.position_is_snake_body(pos.clone())
+        };
+        let max_steps = (self.get_width() * self.get_height()) as usize;
+
+        let mut shortest: Option<usize> = None;
+        for first in self.neighbors(head_a) {
+            if first == *head_b {
+                return Some(0);
+            }
+            if !passable(&first) {
+                continue;
+            }
+
+            let mut previous = head_a.clone();
+            let mut current = first;
+            for length in 1..=max_steps {
+                let onward = self
+                    .neighbors(&current)
+                    .filter(|n| passable(n))
+                    .collect::<Vec<_>>();
+                if onward.len() != 2 {
+                    break;
+                }
+                let next = if onward[0] == previous {
+                    onward[1].clone()
+                } else {
+                    onward[0].clone()
+                };
+                if next == *head_b {
+                    shortest = Some(shortest.map_or(length, |s| s.min(length)));
+                    break;
+                }
+                if next == *head_a {
+                    break;
+                }
+                previous = std::mem::replace(&mut current, next);
+            }
+        }
+
+        shortest
+    }
+
+    /// whether two living snakes face each other down a single width corridor, see
+    /// [TunnelDetectableGame::tunnel_length_between]
+    fn facing_head_on(&self, sid_a: &Self::SnakeIDType, sid_b: &Self::SnakeIDType) -> bool {
+        sid_a != sid_b
+            && self.is_alive(sid_a)
+            && self.is_alive(sid_b)
+            && self
+                .tunnel_length_between(
+                    &self.get_head_as_native_position(sid_a),
+                    &self.get_head_as_native_position(sid_b),
+                )
+                .is_some()
+    }
+}
+
+impl<G> TunnelDetectableGame for G where
+    G: HeadGettableGame + HealthGettableGame + NeighborDeterminableGame + SizeDeterminableGame
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::build_snake_id_map,
+        wire_representation::{BattleSnake, Game, Position},
+    };
+
+    fn snake(g: &Game, id: &str, body: impl Iterator<Item = (i32, i32)>) -> BattleSnake {
+        let mut snake = g.board.snakes[0].clone();
+        snake.id = id.to_string();
+        snake.body = body.map(|(x, y)| Position::new(x, y)).collect();
+        snake.head = snake.body[0];
+        snake
+    }
+
+    /// two snakes with their heads at either end of the corridor along y = 5 between x = 2 and
+    /// x = 8, their bodies forming its walls. The lower wall stops at `lower_wall_end`
+    fn corridor_game(lower_wall_end: i32) -> Game {
+        let mut g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let a = snake(
+            &g,
+            "a",
+            std::iter::once((1, 5)).chain((1..=lower_wall_end).map(|x| (x, 4))),
+        );
+        let b = snake(
+            &g,
+            "b",
+            std::iter::once((9, 5)).chain((1..=9).rev().map(|x| (x, 6))),
+        );
+        g.you = a.clone();
+        g.board.snakes = vec![a, b];
+        g.board.food.clear();
+        g
+    }
+
+    #[test]
+    fn test_tunnel_detection() {
+        let g = corridor_game(8);
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let a = "a".to_string();
+        let b = "b".to_string();
+
+        assert_eq!(
+            g.tunnel_length_between(&Position::new(1, 5), &Position::new(9, 5)),
+            Some(7)
+        );
+        assert!(g.facing_head_on(&a, &b));
+        assert!(compact.facing_head_on(id_map.get(&a).unwrap(), id_map.get(&b).unwrap()));
+        assert_eq!(
+            compact.tunnel_length_between(
+                &compact.get_head_as_native_position(id_map.get(&a).unwrap()),
+                &compact.get_head_as_native_position(id_map.get(&b).unwrap()),
+            ),
+            Some(7)
+        );
+
+        let widened = corridor_game(5);
+        assert!(!widened.facing_head_on(&a, &b));
+        assert_eq!(
+            widened.tunnel_length_between(&Position::new(1, 5), &Position::new(9, 5)),
+            None
+        );
+
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let ids = start.snake_ids();
+        assert!(!start.facing_head_on(&ids[0], &ids[1]));
+    }
+}
@@ -0,0 +1,220 @@
+//! Simulation on a background thread for bots running inside an async web server. Simulating a
+//! board is CPU bound, so running it inline blocks the executor thread and every other request
+//! scheduled on it. And the boxed iterators [SimulableGame] returns borrow the board, so they
+//! can't be handed to another thread on their own. Instead these functions take the board, the
+//! instruments and the moves by value, so the work is owned and `'static`, and collect the
+//! children on a worker thread. The returned [BackgroundSimulation] is a future that completes
+//! with them. It doesn't depend on any runtime, so it can be awaited from tokio, async-std or a
+//! hand rolled executor alike. Only available with the `async-simulation` feature.
+//!
+//! Every call spawns a new OS thread rather than handing the work to a blocking pool, which
+//! would need a runtime or a pool of our own. Spawning a thread costs tens of microseconds and
+//! nothing bounds how many run at once, so batch the boards a search wants expanded with
+//! [simulate_batch_in_background] instead of spawning one simulation per board. If the
+//! simulation panics the panic is resumed in the task polling the future.
+
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::types::{Action, Move, SimulableGame, SimulatorInstruments};
+
+/// the children of one board, in the order [SimulableGame::simulate_with_moves] produces them
+pub type Children<B, const N_SNAKES: usize> = Vec<(Action<N_SNAKES>, B)>;
+
+/// the moves to simulate for each snake, as passed to [SimulableGame::simulate_with_moves]
+pub type SnakeMoves<B> = Vec<(
+    <B as crate::types::SnakeIDGettableGame>::SnakeIDType,
+    Vec<Move>,
+)>;
+
+#[derive(Debug)]
+struct Shared<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// A simulation running on its own thread, resolving to its result once the thread finishes.
+/// Dropping it doesn't stop the thread, the result is just thrown away. Polling it after the
+/// simulation panicked resumes the panic
+#[derive(Debug)]
+pub struct BackgroundSimulation<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T: Send + 'static> BackgroundSimulation<T> {
+    fn spawn(work: impl FnOnce() -> T + Send + 'static) -> Self {
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            waker: None,
+        }));
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work));
+            let mut shared = worker.lock().expect("the simulation lock isn't poisoned");
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+
+        Self { shared }
+    }
+}
+
+impl<T> Future for BackgroundSimulation<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut shared = self
+            .shared
+            .lock()
+            .expect("the simulation lock isn't poisoned");
+        match shared.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => {
+                drop(shared);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Simulates `board` with the given moves on a background thread, resolving to every child.
+/// This spawns a thread per call, so to expand several boards at once use
+/// [simulate_batch_in_background]
+pub fn simulate_in_background<B, I, const N_SNAKES: usize>(
+    board: B,
+    instruments: I,
+    snake_ids_and_moves: SnakeMoves<B>,
+) -> BackgroundSimulation<Children<B, N_SNAKES>>
+where
+    B: SimulableGame<I, N_SNAKES> + Send + 'static,
+    B::SnakeIDType: Send,
+    I: SimulatorInstruments + Send + 'static,
+{
+    BackgroundSimulation::spawn(move || {
+        board
+            .simulate_with_moves(&instruments, snake_ids_and_moves)
+            .collect()
+    })
+}
+
+/// Simulates a batch of boards one after another on a single background thread, e.g. every leaf
+/// a search wants expanded this round, resolving to the children of each board in order
+pub fn simulate_batch_in_background<B, I, const N_SNAKES: usize>(
+    batch: Vec<(B, SnakeMoves<B>)>,
+    instruments: I,
+) -> BackgroundSimulation<Vec<Children<B, N_SNAKES>>>
+where
+    B: SimulableGame<I, N_SNAKES> + Send + 'static,
+    B::SnakeIDType: Send,
+    I: SimulatorInstruments + Send + 'static,
+{
+    BackgroundSimulation::spawn(move || {
+        batch
+            .into_iter()
+            .map(|(board, moves)| {
+                board
+                    .simulate_with_moves(&instruments, moves)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Wake;
+
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{build_snake_id_map, SnakeIDGettableGame},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    /// unparks the test thread when woken
+    struct Unpark(thread::Thread);
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn board(fixture: &str) -> StandardCellBoard4Snakes11x11 {
+        let g = game_fixture(fixture);
+        g.as_cell_board(&build_snake_id_map(&g)).unwrap()
+    }
+
+    fn every_move(
+        board: &StandardCellBoard4Snakes11x11,
+    ) -> SnakeMoves<StandardCellBoard4Snakes11x11> {
+        board
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_background_simulation_matches_simulate() {
+        let late_stage = board(include_str!("../fixtures/late_stage.json"));
+        let start = board(include_str!("../fixtures/start_of_game.json"));
+        let expected = |b: &StandardCellBoard4Snakes11x11| -> Children<_, 4> {
+            b.simulate_with_moves(&Instruments, every_move(b)).collect()
+        };
+
+        let children = block_on(simulate_in_background(
+            late_stage,
+            Instruments,
+            every_move(&late_stage),
+        ));
+        assert_eq!(children, expected(&late_stage));
+
+        let batch = vec![
+            (late_stage, every_move(&late_stage)),
+            (start, every_move(&start)),
+        ];
+        let children = block_on(simulate_batch_in_background(batch, Instruments));
+        assert_eq!(children, vec![expected(&late_stage), expected(&start)]);
+    }
+
+    #[test]
+    fn test_background_panics_reach_the_awaiting_task() {
+        let simulation = BackgroundSimulation::<()>::spawn(|| panic!("the simulation failed"));
+        let awaited = panic::catch_unwind(AssertUnwindSafe(|| block_on(simulation)));
+        let payload = awaited.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<&str>(),
+            Some(&"the simulation failed")
+        );
+    }
+}
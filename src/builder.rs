@@ -0,0 +1,295 @@
+//! Builds wire games for tests without writing out JSON, either by drawing the board (see
+//! [board!](crate::board)) or by adding snakes, food and hazards one at a time with a
+//! [BoardBuilder]. Convert the game to a compact board as usual, e.g. with
+//! [Game::as_cell_board]. Only available with the `test-utils` feature.
+
+use std::error::Error;
+
+use crate::wire_representation::{BattleSnake, Game, GameMode, Position, Ruleset};
+
+/// Builds a [Game]. Snakes are given the ids `snake0`, `snake1` and so on in the order they're
+/// added, and the first snake is `you` unless [BoardBuilder::you] says otherwise
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    width: u32,
+    height: u32,
+    turn: i32,
+    mode: GameMode,
+    hazard_damage: i32,
+    food: Vec<Position>,
+    hazards: Vec<Position>,
+    snakes: Vec<(Vec<Position>, i32)>,
+    you: usize,
+}
+
+impl BoardBuilder {
+    /// an empty standard board of the given size on turn 0, with 14 hazard damage
+    pub fn new(width: u32, height: u32) -> Self {
+        BoardBuilder {
+            width,
+            height,
+            turn: 0,
+            mode: GameMode::Standard,
+            hazard_damage: 14,
+            food: vec![],
+            hazards: vec![],
+            snakes: vec![],
+            you: 0,
+        }
+    }
+
+    /// Reads a board drawn as rows of characters, the top row first so it reads the way the
+    /// board is displayed:
+    /// - `.` an empty cell
+    /// - `f` food
+    /// - `x` a hazard
+    /// - `0` to `9` the head of that snake
+    /// - `a` to `j` the body of snake 0 to 9, or `s` for a body that can only be one snake's
+    ///
+    /// Snakes are added in order of their digit, and must be numbered from 0 without gaps. See
+    /// [Game::from_ascii] for how bodies are followed. Errors if the rows aren't all the same
+    /// length or a snake can't be followed
+    pub fn from_rows(rows: &[&str]) -> Result<Self, Box<dyn Error>> {
+        if let Some(c) = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .find(|c| !".fxs0123456789abcdefghij".contains(*c))
+        {
+            return Err(format!("unknown cell {:?}", c).into());
+        }
+        let ruleset = Ruleset {
+            name: GameMode::Standard,
+            version: String::new(),
+            settings: None,
+        };
+        let game = Game::from_ascii(&rows.join("\n"), ruleset)?;
+        let mut builder = BoardBuilder::new(game.board.width, game.board.height);
+        builder.food = game.board.food;
+        builder.hazards = game.board.hazards;
+        for snake in &game.board.snakes {
+            builder = builder.snake(&snake.body.iter().copied().collect::<Vec<_>>(), 100);
+        }
+        Ok(builder)
+    }
+
+    /// adds a snake with the given body, head first. Repeat the last position to stack its tail
+    pub fn snake(mut self, body: &[Position], health: i32) -> Self {
+        self.snakes.push((body.to_vec(), health));
+        self
+    }
+
+    /// stacks `count` more segments on the tail of the snake at `index`, as if it had just eaten
+    ///
+    /// # Panics
+    /// if there's no snake at `index`
+    pub fn stack_tail(mut self, index: usize, count: usize) -> Self {
+        let body = &mut self.snakes[index].0;
+        let tail = *body.last().expect("the snake has a body");
+        body.extend(std::iter::repeat_n(tail, count));
+        self
+    }
+
+    /// sets the health of the snake at `index`
+    ///
+    /// # Panics
+    /// if there's no snake at `index`
+    pub fn health(mut self, index: usize, health: i32) -> Self {
+        self.snakes[index].1 = health;
+        self
+    }
+
+    #[allow(missing_docs)]
+    pub fn food(mut self, pos: Position) -> Self {
+        self.food.push(pos);
+        self
+    }
+
+    /// adds a hazard, adding the same position again stacks them
+    pub fn hazard(mut self, pos: Position) -> Self {
+        self.hazards.push(pos);
+        self
+    }
+
+    /// makes the snake at `index` `you`
+    pub fn you(mut self, index: usize) -> Self {
+        self.you = index;
+        self
+    }
+
+    #[allow(missing_docs)]
+    pub fn turn(mut self, turn: i32) -> Self {
+        self.turn = turn;
+        self
+    }
+
+    #[allow(missing_docs)]
+    pub fn mode(mut self, mode: GameMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[allow(missing_docs)]
+    pub fn hazard_damage(mut self, damage: i32) -> Self {
+        self.hazard_damage = damage;
+        self
+    }
+
+    /// Builds the game
+    ///
+    /// # Panics
+    /// if there are no snakes, or `you` isn't one of them
+    pub fn build(self) -> Game {
+        let snakes = self
+            .snakes
+            .iter()
+            .enumerate()
+            .map(|(i, (body, health))| {
+                serde_json::from_value::<BattleSnake>(serde_json::json!({
+                    "id": format!("snake{}", i),
+                    "name": format!("snake{}", i),
+                    "head": body[0],
+                    "body": body,
+                    "health": health,
+                    "shout": null,
+                }))
+                .expect("the snake is valid")
+            })
+            .collect::<Vec<_>>();
+        let you = snakes
+            .get(self.you)
+            .cloned()
+            .expect("you is one of the snakes");
+
+        let mut game: Game = serde_json::from_value(serde_json::json!({
+            "game": {
+                "id": "built",
+                "ruleset": {
+                    "name": self.mode.as_str(),
+                    "version": "",
+                    "settings": {
+                        "foodSpawnChance": 0,
+                        "minimumFood": 0,
+                        "hazardDamagePerTurn": self.hazard_damage,
+                    },
+                },
+                "timeout": 500,
+            },
+            "turn": self.turn,
+            "board": {"width": self.width, "height": self.height, "snakes": []},
+            "you": you,
+        }))
+        .expect("the game is valid");
+        game.board.snakes = snakes;
+        game.board.food = self.food;
+        game.board.hazards = self.hazards;
+        game
+    }
+}
+
+/// Builds a [Game](crate::wire_representation::Game) from a board drawn as rows, see
+/// [BoardBuilder::from_rows] for what each character means
+///
+/// ```
+/// use battlesnake_game_types::board;
+/// use battlesnake_game_types::types::{HealthGettableGame, YouDeterminableGame};
+///
+/// let game = board![
+///     "..f..",
+///     ".0a.x",
+///     "...b1",
+/// ];
+/// assert_eq!(game.board.snakes.len(), 2);
+/// assert_eq!(game.you.body.len(), 2);
+/// ```
+///
+/// # Panics
+/// if the board isn't valid
+#[macro_export]
+macro_rules! board {
+    ($($row:expr),* $(,)?) => {
+        $crate::builder::BoardBuilder::from_rows(&[$($row),*])
+            .expect("the board is valid")
+            .build()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        types::{
+            build_snake_id_map, HazardQueryableGame, HealthGettableGame, LengthGettableGame,
+            PositionGettableGame, SnakeId,
+        },
+    };
+
+    #[test]
+    fn test_board_macro() {
+        let game = board!["f....x", ".aa0..", ".a....", "bb1..f",];
+        assert_eq!((game.board.width, game.board.height), (6, 4));
+        assert_eq!(game.you.id, "snake0");
+        assert_eq!(
+            game.you.body.iter().copied().collect::<Vec<_>>(),
+            vec![
+                Position::new(3, 2),
+                Position::new(2, 2),
+                Position::new(1, 2),
+                Position::new(1, 1),
+            ]
+        );
+        assert_eq!(game.board.snakes[1].head, Position::new(2, 0));
+        assert_eq!(
+            game.board.food,
+            vec![Position::new(0, 3), Position::new(5, 0)]
+        );
+        assert_eq!(game.board.hazards, vec![Position::new(5, 3)]);
+
+        assert!(BoardBuilder::from_rows(&["0..", ".."]).is_err());
+        assert!(BoardBuilder::from_rows(&["0.a"]).is_err());
+        assert!(BoardBuilder::from_rows(&["1a."]).is_err());
+        assert!(BoardBuilder::from_rows(&["a0a"]).is_err());
+        assert!(BoardBuilder::from_rows(&["0.?"]).is_err());
+    }
+
+    #[test]
+    fn test_board_builder() {
+        let game = BoardBuilder::from_rows(&[
+            "...........",
+            "...........",
+            "...........",
+            "...........",
+            "...........",
+            ".....0aa...",
+            "...........",
+            "...........",
+            "...........",
+            "...1b......",
+            "...........",
+        ])
+        .unwrap()
+        .stack_tail(1, 1)
+        .health(1, 40)
+        .hazard(Position::new(5, 4))
+        .hazard(Position::new(5, 4))
+        .food(Position::new(0, 0))
+        .you(1)
+        .turn(12)
+        .mode(GameMode::Royale)
+        .hazard_damage(20)
+        .build();
+        assert_eq!(game.you.id, "snake1");
+        assert_eq!(game.turn, 12);
+        assert_eq!(game.game.ruleset.name, GameMode::Royale);
+        assert_eq!(game.get_hazard_damage(), 20);
+
+        let id_map = build_snake_id_map(&game);
+        let compact: StandardCellBoard4Snakes11x11 = game.as_cell_board(&id_map).unwrap();
+        assert_eq!(game.you.body.len(), 3);
+        assert_eq!(compact.get_length(&SnakeId(0)), 3);
+        assert_eq!(compact.get_health(&SnakeId(0)), 40);
+        assert_eq!(compact.get_health(&SnakeId(1)), 100);
+        let stacked = compact.native_from_position(Position::new(5, 4));
+        assert_eq!(compact.get_hazard_count(&stacked), 2);
+    }
+}
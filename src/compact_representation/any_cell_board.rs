@@ -0,0 +1,163 @@
+//! a best cell board for any ruleset, so a server handler can convert every game the same way
+
+use std::error::Error;
+
+use super::{standard, wrapped, BestCellBoardVisitor, EvaluateMode};
+use crate::types::{Move, SimulatorInstruments, SnakeId};
+use crate::wire_representation::Game;
+
+/// Holds the [standard::BestCellBoard] or [wrapped::BestCellBoard] for a game, depending on its
+/// ruleset
+#[derive(Debug)]
+pub enum AnyCellBoard {
+    /// a game with any ruleset that doesn't wrap
+    Standard(standard::BestCellBoard),
+    /// a wrapped or wrapped constrictor game
+    Wrapped(wrapped::BestCellBoard),
+}
+
+/// Converts a game to the best sized board for its ruleset, picking a wrapped board for the
+/// wrapped modes (see [crate::wire_representation::GameMode::is_wrapped]) and a standard one
+/// otherwise. See [standard::ToBestCellBoard] for how the size is picked
+pub trait ToAnyCellBoard {
+    #[allow(missing_docs)]
+    fn to_best_cell_board(self) -> Result<AnyCellBoard, Box<dyn Error>>;
+}
+
+impl ToAnyCellBoard for Game {
+    fn to_best_cell_board(self) -> Result<AnyCellBoard, Box<dyn Error>> {
+        match EvaluateMode::from(self.game.mode()) {
+            EvaluateMode::Wrapped => Ok(AnyCellBoard::Wrapped(
+                wrapped::ToBestCellBoard::to_best_cell_board(self)?,
+            )),
+            EvaluateMode::Standard => Ok(AnyCellBoard::Standard(
+                standard::ToBestCellBoard::to_best_cell_board(self)?,
+            )),
+        }
+    }
+}
+
+impl AnyCellBoard {
+    /// whether this holds a wrapped board
+    pub fn is_wrapped(&self) -> bool {
+        matches!(self, AnyCellBoard::Wrapped(_))
+    }
+
+    /// the mode the board this holds simulates in
+    pub fn evaluate_mode(&self) -> EvaluateMode {
+        match self {
+            AnyCellBoard::Standard(_) => EvaluateMode::Standard,
+            AnyCellBoard::Wrapped(_) => EvaluateMode::Wrapped,
+        }
+    }
+
+    /// the size in bytes of the board this holds, see `standard::BestCellBoard::memory_footprint`
+    pub fn memory_footprint(&self) -> usize {
+        match self {
+            AnyCellBoard::Standard(b) => b.memory_footprint(),
+            AnyCellBoard::Wrapped(b) => b.memory_footprint(),
+        }
+    }
+
+    /// your snake id
+    pub fn you_id(&self) -> SnakeId {
+        match self {
+            AnyCellBoard::Standard(b) => b.you_id(),
+            AnyCellBoard::Wrapped(b) => b.you_id(),
+        }
+    }
+
+    /// whether the game is over
+    pub fn is_over(&self) -> bool {
+        match self {
+            AnyCellBoard::Standard(b) => b.is_over(),
+            AnyCellBoard::Wrapped(b) => b.is_over(),
+        }
+    }
+
+    /// the winner if the game is over
+    pub fn get_winner(&self) -> Option<SnakeId> {
+        match self {
+            AnyCellBoard::Standard(b) => b.get_winner(),
+            AnyCellBoard::Wrapped(b) => b.get_winner(),
+        }
+    }
+
+    /// the reasonable moves for each snake, see
+    /// [crate::types::ReasonableMovesGame::reasonable_moves_for_each_snake]
+    pub fn reasonable_moves_for_each_snake(&self) -> Vec<(SnakeId, Vec<Move>)> {
+        match self {
+            AnyCellBoard::Standard(b) => b.reasonable_moves_for_each_snake(),
+            AnyCellBoard::Wrapped(b) => b.reasonable_moves_for_each_snake(),
+        }
+    }
+
+    /// simulates the given moves, each child is the same variant as this board and comes with the
+    /// move each snake made to reach it
+    pub fn simulate_with_moves<I: SimulatorInstruments>(
+        &self,
+        instruments: &I,
+        snake_ids_and_moves: Vec<(SnakeId, Vec<Move>)>,
+    ) -> Vec<(Vec<(SnakeId, Move)>, AnyCellBoard)> {
+        match self {
+            AnyCellBoard::Standard(b) => b
+                .simulate_with_moves(instruments, snake_ids_and_moves)
+                .into_iter()
+                .map(|(moves, child)| (moves, AnyCellBoard::Standard(child)))
+                .collect(),
+            AnyCellBoard::Wrapped(b) => b
+                .simulate_with_moves(instruments, snake_ids_and_moves)
+                .into_iter()
+                .map(|(moves, child)| (moves, AnyCellBoard::Wrapped(child)))
+                .collect(),
+        }
+    }
+
+    /// runs `visitor` against the board this holds
+    pub fn visit<V: BestCellBoardVisitor>(&self, visitor: V) -> V::Output {
+        match self {
+            AnyCellBoard::Standard(b) => b.visit(visitor),
+            AnyCellBoard::Wrapped(b) => b.visit(visitor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game_fixture, types::NoopInstruments, wire_representation::GameMode};
+
+    #[test]
+    fn test_any_cell_board() {
+        let g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let standard = g.clone().to_best_cell_board().unwrap();
+        assert!(matches!(
+            standard,
+            AnyCellBoard::Standard(standard::BestCellBoard::MediumExact(_))
+        ));
+
+        let wrapped = game_fixture(include_str!("../../fixtures/wrapped_fixture.json"));
+        assert!(wrapped.is_wrapped());
+        let any = wrapped.clone().to_best_cell_board().unwrap();
+        assert!(any.is_wrapped());
+        let best = wrapped::ToBestCellBoard::to_best_cell_board(wrapped).unwrap();
+        assert_eq!(any.you_id(), best.you_id());
+        assert_eq!(any.memory_footprint(), best.memory_footprint());
+
+        let reasonable = any.reasonable_moves_for_each_snake();
+        assert_eq!(reasonable, best.reasonable_moves_for_each_snake());
+        let children = any.simulate_with_moves(&NoopInstruments, reasonable.clone());
+        let expected = best.simulate_with_moves(&NoopInstruments, reasonable);
+        assert_eq!(children.len(), expected.len());
+        for ((moves, child), (expected_moves, _)) in children.iter().zip(expected.iter()) {
+            assert_eq!(moves, expected_moves);
+            assert!(child.is_wrapped());
+        }
+
+        let mut wrapped_constrictor =
+            game_fixture(include_str!("../../fixtures/wrapped_fixture.json"));
+        wrapped_constrictor.game.ruleset.name = GameMode::WrappedConstrictor;
+        let any = wrapped_constrictor.to_best_cell_board().unwrap();
+        assert_eq!(any.evaluate_mode(), EvaluateMode::Wrapped);
+    }
+}
@@ -0,0 +1,137 @@
+//! methods shared by the standard and wrapped `BestCellBoard` enums, so that downstream code
+//! doesn't need an exhaustive match for every operation
+
+use crate::types::{Board, SnakeIDGettableGame, SnakeId};
+
+/// Generic code to run against whichever board a `BestCellBoard` holds, for the operations the
+/// enum doesn't forward itself. See `standard::BestCellBoard::visit`, or
+/// [crate::with_best_cell_board] to run a block of code instead
+pub trait BestCellBoardVisitor {
+    /// what visiting returns
+    type Output;
+
+    /// called with the board the enum holds
+    fn visit<B>(self, board: &B) -> Self::Output
+    where
+        B: Board + SnakeIDGettableGame<SnakeIDType = SnakeId>;
+}
+
+/// Runs `$body` against whichever board a `BestCellBoard` holds, with `$board` bound to the boxed
+/// board as its concrete type, so generic code can be written once instead of matching every
+/// variant by hand. Prefix the arguments with `wrapped,` for a
+/// [crate::compact_representation::wrapped::BestCellBoard]. Unlike a [BestCellBoardVisitor] the
+/// body can borrow from, return from or `?` out of the enclosing function, but it has to evaluate
+/// to the same type for every variant.
+///
+/// ```
+/// use battlesnake_game_types::compact_representation::standard::ToBestCellBoard;
+/// use battlesnake_game_types::types::{SnakeIDGettableGame, VictorDeterminableGame};
+/// use battlesnake_game_types::wire_representation::Game;
+/// use battlesnake_game_types::with_best_cell_board;
+///
+/// fn alive<B: SnakeIDGettableGame + VictorDeterminableGame>(board: &B) -> usize {
+///     board.get_snake_ids().len()
+/// }
+///
+/// let json = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/start_of_game.json"));
+/// let game: Game = serde_json::from_str(json).unwrap();
+/// let best = game.to_best_cell_board().unwrap();
+/// assert_eq!(with_best_cell_board!(&best, |board| alive(board.as_ref())), 4);
+/// ```
+#[macro_export]
+macro_rules! with_best_cell_board {
+    (@arms $best_enum:ident, $best:expr, $board:ident, $wrap:ident, $body:expr) => {
+        $crate::with_best_cell_board!(@variants $best_enum, $best, $board, $wrap, $body,
+            Tiny, SmallExact, Standard, MediumExact, LargestU8, LargeExact, ArcadeMaze,
+            ArcadeMaze8Snake, Large, Silly, LargeRoyale, SillyRoyale, SmallExactDuel, StandardDuel,
+            MediumExactDuel, LargeExactDuel)
+    };
+    (@variants $best_enum:ident, $best:expr, $board:ident, $wrap:ident, $body:expr, $($variant:ident),*) => {
+        match $best {
+            $($best_enum::$variant($board) => {
+                #[allow(unused_variables)]
+                let $wrap = |b| $best_enum::$variant(Box::new(b));
+                $body
+            })*
+        }
+    };
+    (wrapped, $best:expr, |$board:ident| $body:expr) => {{
+        use $crate::compact_representation::wrapped::BestCellBoard as Best;
+        $crate::with_best_cell_board!(@arms Best, $best, $board, _wrap, $body)
+    }};
+    ($best:expr, |$board:ident| $body:expr) => {{
+        use $crate::compact_representation::standard::BestCellBoard as Best;
+        $crate::with_best_cell_board!(@arms Best, $best, $board, _wrap, $body)
+    }};
+}
+
+/// Matches every variant of the `BestCellBoard` in scope, running `$body` with `$board` bound to
+/// the boxed board and `$wrap` to a function putting a board of the same type back in to the
+/// variant
+macro_rules! match_best_cell_board {
+    ($best:expr, |$board:ident, $wrap:ident| $body:expr) => {
+        with_best_cell_board!(@arms BestCellBoard, $best, $board, $wrap, $body)
+    };
+}
+
+/// Implements the forwarding methods on the `BestCellBoard` in scope
+macro_rules! impl_best_cell_board {
+    () => {
+        impl BestCellBoard {
+            /// the size in bytes of the board this holds, which is boxed so the enum itself is
+            /// only a pointer and a tag
+            pub fn memory_footprint(&self) -> usize {
+                match_best_cell_board!(self, |b, _wrap| std::mem::size_of_val(&**b))
+            }
+
+            /// your snake id
+            pub fn you_id(&self) -> SnakeId {
+                match_best_cell_board!(self, |b, _wrap| *b.you_id())
+            }
+
+            /// whether the game is over, see [VictorDeterminableGame::is_over]
+            pub fn is_over(&self) -> bool {
+                match_best_cell_board!(self, |b, _wrap| b.is_over())
+            }
+
+            /// the winner if the game is over, see [VictorDeterminableGame::get_winner]
+            pub fn get_winner(&self) -> Option<SnakeId> {
+                match_best_cell_board!(self, |b, _wrap| b.get_winner())
+            }
+
+            /// see [ReasonableMovesGame::reasonable_moves_for_each_snake]
+            pub fn reasonable_moves_for_each_snake(&self) -> Vec<(SnakeId, Vec<Move>)> {
+                match_best_cell_board!(self, |b, _wrap| b
+                    .reasonable_moves_for_each_snake()
+                    .collect())
+            }
+
+            /// simulates the given moves like [SimulableGame::simulate_with_moves], each child
+            /// is the same variant as this board and comes with the move each snake made to
+            /// reach it
+            pub fn simulate_with_moves<I: SimulatorInstruments>(
+                &self,
+                instruments: &I,
+                snake_ids_and_moves: Vec<(SnakeId, Vec<Move>)>,
+            ) -> Vec<(Vec<(SnakeId, Move)>, BestCellBoard)> {
+                match_best_cell_board!(self, |b, wrap| b
+                    .simulate_with_moves(instruments, snake_ids_and_moves)
+                    .map(|(action, child)| {
+                        let moves = action
+                            .into_inner()
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(i, mv)| mv.map(|mv| (SnakeId(i as u8), mv)))
+                            .collect();
+                        (moves, wrap(child))
+                    })
+                    .collect())
+            }
+
+            /// runs `visitor` against the board this holds
+            pub fn visit<V: BestCellBoardVisitor>(&self, visitor: V) -> V::Output {
+                match_best_cell_board!(self, |b, _wrap| visitor.visit(b.as_ref()))
+            }
+        }
+    };
+}
@@ -0,0 +1,42 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{Move, SnakeId},
+};
+
+use super::{CellBoard, EvaluateMode};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Evaluates a joint move on each board in `batch`, lazily and without boxing, yielding the
+    /// children in the same order. The state [Self::generate_state] prepares depends on where
+    /// each snake's head and tail are, so it is only reused while consecutive entries point at
+    /// the same board: keep the joint moves for one board next to each other to generate its
+    /// state once. Like [Self::apply_moves], moves that kill a snake are applied rather than
+    /// pruned
+    pub fn simulate_batch<'a>(
+        batch: impl IntoIterator<Item = (&'a Self, &'a [(SnakeId, Move)])> + 'a,
+        mode: EvaluateMode,
+    ) -> impl Iterator<Item = Self> + 'a
+    where
+        Self: 'a,
+    {
+        let mut every_move = [(SnakeId(0), Move::all()); MAX_SNAKES];
+        for (i, (id, _)) in every_move.iter_mut().enumerate() {
+            *id = SnakeId(i as u8);
+        }
+
+        let mut prepared = None;
+        batch.into_iter().map(move |(board, moves)| {
+            let states = match prepared {
+                Some((last, states)) if std::ptr::eq(last, board) => states,
+                _ => {
+                    let states = board.generate_state(every_move.iter(), mode);
+                    prepared = Some((board, states));
+                    states
+                }
+            };
+            board.evaluate_moves_with_state(moves, &states)
+        })
+    }
+}
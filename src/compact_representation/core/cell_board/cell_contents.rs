@@ -0,0 +1,77 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::SnakeId,
+};
+
+use super::{Cell, CellBoard, CellIndex};
+
+/// What a cell of a compact board holds, as returned by `iter_cells` on the standard and wrapped
+/// boards. A cell can hold several things at once, so the first of these that applies is used: a
+/// snake, a wall, food, a hazard and then a healing cell. A snake on a hazard is reported as the
+/// snake, use [crate::types::HazardQueryableGame::get_hazard_count] where both matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellContents {
+    /// nothing at all
+    Empty,
+    /// food, which may also be on a hazard
+    Food,
+    /// `count` stacked hazards, each of which deals damage
+    Hazard {
+        #[allow(missing_docs)]
+        count: u8,
+    },
+    /// a hazard that can't be moved on to at all
+    Wall,
+    /// a cell that restores health, see [crate::types::HealingQueryableGame]
+    Healing,
+    /// the head of a snake with a body
+    Head {
+        #[allow(missing_docs)]
+        sid: SnakeId,
+    },
+    /// a single piece of a snake's body, including its tail
+    BodySegment {
+        #[allow(missing_docs)]
+        sid: SnakeId,
+    },
+    /// two pieces of a snake's body on top of each other, a tail after eating
+    DoubleStacked {
+        #[allow(missing_docs)]
+        sid: SnakeId,
+    },
+    /// a whole snake of three pieces on one cell, which is how every snake starts
+    TripleStacked {
+        #[allow(missing_docs)]
+        sid: SnakeId,
+    },
+}
+
+impl CellContents {
+    fn of<T: CellNum>(cell: &Cell<T>) -> Self {
+        match cell.get_snake_id() {
+            Some(sid) if cell.is_triple_stacked_piece() => Self::TripleStacked { sid },
+            Some(sid) if cell.is_head() => Self::Head { sid },
+            Some(sid) if cell.is_double_stacked_piece() => Self::DoubleStacked { sid },
+            Some(sid) => Self::BodySegment { sid },
+            None if cell.is_wall() => Self::Wall,
+            None if cell.is_food() => Self::Food,
+            None if cell.is_hazard() => Self::Hazard {
+                count: cell.get_hazard_count(),
+            },
+            None if cell.is_healing() => Self::Healing,
+            None => Self::Empty,
+        }
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// every cell on the board along with what's in it, in index order
+    pub fn iter_cells(&self) -> impl Iterator<Item = (CellIndex<T>, CellContents)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (CellIndex::from_usize(i), CellContents::of(cell)))
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::CellMetadataGame,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> CellMetadataGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn get_cell_metadata(&self, pos: &Self::NativePositionType) -> u8 {
+        self.metadata[pos.as_usize()]
+    }
+
+    fn set_cell_metadata(&mut self, pos: &Self::NativePositionType, value: u8) {
+        self.metadata[pos.as_usize()] = value;
+    }
+
+    fn clear_cell_metadata(&mut self) {
+        self.metadata = [0; BOARD_SIZE];
+    }
+}
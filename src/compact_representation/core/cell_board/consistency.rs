@@ -0,0 +1,176 @@
+use crate::{
+    compact_representation::{
+        core::{dimensions::Dimensions, Cell, CellIndex},
+        CellNum,
+    },
+    consistency::InconsistencyReport,
+    types::SnakeId,
+};
+
+use super::{count_food_and_hazards, CellBoard};
+
+fn describe<T: CellNum>(cell: &Cell<T>) -> String {
+    match cell.get_snake_id() {
+        Some(sid) if cell.is_triple_stacked_piece() => format!("snake {}'s stacked head", sid.0),
+        Some(sid) if cell.is_head() => format!("snake {}'s head", sid.0),
+        Some(sid) => format!("a body segment of snake {}", sid.0),
+        None if cell.is_food() => "food".to_string(),
+        None => "an empty cell".to_string(),
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Checks that every live snake's head links to its tail and its body links back to its
+    /// head, covering as many cells as its length says, that no other cell claims to be part of
+    /// a snake, and that the food and hazard counts match the cells. Describes the first thing
+    /// that's wrong. Slower than [CellBoard::assert_consistency], which simulating runs on every
+    /// child
+    pub fn verify_consistency(&self) -> Result<(), InconsistencyReport> {
+        let width = self.stored_width();
+        let report = |snake: Option<SnakeId>,
+                      index: Option<CellIndex<T>>,
+                      expected: String,
+                      actual: String| InconsistencyReport {
+            snake,
+            cell: index.map(|index| index.into_position(width)),
+            expected,
+            actual,
+        };
+
+        let mut owners = vec![None; BOARD_SIZE];
+        for i in 0..MAX_SNAKES {
+            let sid = SnakeId(i as u8);
+            if !self.is_live_snake(sid) {
+                continue;
+            }
+            let head = self.heads[i];
+            let head_cell = self.get_cell(head);
+            if !head_cell.is_head() || head_cell.get_snake_id() != Some(sid) {
+                return Err(report(
+                    Some(sid),
+                    Some(head),
+                    format!("snake {}'s head", i),
+                    describe(&head_cell),
+                ));
+            }
+            owners[head.as_usize()] = Some(sid);
+
+            let mut index = head_cell.get_tail_position(head).unwrap();
+            let mut cells = 1;
+            while index != head {
+                let cell = self.get_cell(index);
+                if !cell.is_body_segment() || cell.get_snake_id() != Some(sid) {
+                    return Err(report(
+                        Some(sid),
+                        Some(index),
+                        format!("a body segment of snake {}", i),
+                        describe(&cell),
+                    ));
+                }
+                if owners[index.as_usize()].is_some() || cells >= BOARD_SIZE {
+                    return Err(report(
+                        Some(sid),
+                        Some(index),
+                        format!("a body leading back to snake {}'s head", i),
+                        "a loop".to_string(),
+                    ));
+                }
+                owners[index.as_usize()] = Some(sid);
+                cells += 1;
+                index = match cell.get_next_index() {
+                    Some(next) => next,
+                    None => {
+                        return Err(report(
+                            Some(sid),
+                            Some(index),
+                            format!("a segment leading to snake {}'s head", i),
+                            "a segment leading nowhere".to_string(),
+                        ))
+                    }
+                };
+            }
+
+            let expected = self.body_cell_count(sid) as usize;
+            if cells != expected {
+                return Err(report(
+                    Some(sid),
+                    None,
+                    format!("{} body cells for length {}", expected, self.lengths[i]),
+                    format!("{} body cells", cells),
+                ));
+            }
+        }
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            if let Some(sid) = cell.get_snake_id() {
+                if owners[i] != Some(sid) {
+                    return Err(report(
+                        Some(sid),
+                        Some(CellIndex::from_usize(i)),
+                        format!("no part of snake {}", sid.0),
+                        describe(cell),
+                    ));
+                }
+            }
+        }
+
+        let (food, hazards) = count_food_and_hazards(&self.cells);
+        if food != self.food_count {
+            return Err(report(
+                None,
+                None,
+                format!("a food count of {}", food),
+                format!("a food count of {}", self.food_count),
+            ));
+        }
+        if hazards != self.hazard_count {
+            return Err(report(
+                None,
+                None,
+                format!("a hazard count of {}", hazards),
+                format!("a hazard count of {}", self.hazard_count),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compact_representation::dimensions::Square,
+        game_fixture,
+        types::{build_snake_id_map, SnakeId},
+    };
+
+    use super::CellBoard;
+
+    #[test]
+    fn test_verify_consistency() {
+        let g = game_fixture(include_str!("../../../../fixtures/late_stage.json"));
+        let board = CellBoard::<u8, Square, { 11 * 11 }, 4>::convert_from_game(
+            g.clone(),
+            &build_snake_id_map(&g),
+        )
+        .unwrap();
+        assert_eq!(board.verify_consistency(), Ok(()));
+
+        let mut packed = board.pack_as_hash();
+        packed.get_mut("lengths").unwrap()[0] += 1;
+        let report = CellBoard::<u8, Square, { 11 * 11 }, 4>::from_packed_hash(&packed)
+            .verify_consistency()
+            .unwrap_err();
+        assert_eq!(report.snake, Some(SnakeId(0)));
+        assert_eq!(report.cell, None);
+        assert_eq!(report.actual, "7 body cells");
+
+        let inconsistent = include_str!("../../../../fixtures/inconsistent_fixture.json");
+        let hm = serde_json::from_str(inconsistent).unwrap();
+        let game = CellBoard::<u8, Square, { 11 * 11 }, 4>::from_packed_hash(&hm);
+        let report = game.verify_consistency().unwrap_err();
+        assert!(report.snake.is_some());
+        assert!(report.cell.is_some());
+    }
+}
@@ -1,11 +1,11 @@
 use std::borrow::Borrow;
 
-use itertools::Itertools;
 use tracing::instrument;
 
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::{self, HeadGettableGame, Move, SnakeId, N_MOVES},
+    types::{DeathCause, HeadGettableGame, Move, SnakeId, SnakeOutcome, TurnOutcome, N_MOVES},
+    wire_representation::GameMode,
 };
 
 use super::{CellBoard, CellIndex};
@@ -13,10 +13,22 @@ use super::{CellBoard, CellIndex};
 /// Which mode to evaluate in
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EvaluateMode {
+    /// snakes wrap around the edges of the board, for the wrapped boards
     Wrapped,
+    /// snakes die moving off the board, for the standard boards
     Standard,
 }
 
+impl From<&GameMode> for EvaluateMode {
+    fn from(mode: &GameMode) -> Self {
+        if mode.is_wrapped() {
+            EvaluateMode::Wrapped
+        } else {
+            EvaluateMode::Standard
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 /// Precomputed state for Move Evaluation
 /// for a single Snake Move
@@ -26,7 +38,7 @@ pub struct AliveMoveResult<T: CellNum> {
     /// CellIndex for where the head used to be
     old_head: CellIndex<T>,
     /// CellIndex where the new head will be
-    new_head: CellIndex<T>,
+    pub(super) new_head: CellIndex<T>,
     /// CellIndex where the tail was previously
     old_tail: CellIndex<T>,
     /// CellIndex where the tail will be
@@ -68,13 +80,13 @@ impl<T: CellNum> SinglePlayerMoveResult<T> {
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    pub fn generate_state<'a, S: 'a>(
+    pub fn generate_state<'a, S>(
         &self,
         moves: impl Iterator<Item = &'a (SnakeId, S)>,
         mode: EvaluateMode,
     ) -> [[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES]
     where
-        S: Borrow<[Move]>,
+        S: Borrow<[Move]> + 'a,
     {
         let mut new_heads = [[SinglePlayerMoveResult::Dead; 4]; MAX_SNAKES];
 
@@ -95,15 +107,16 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                         )
                     });
 
-                let new_head_position =
-                    old_head.into_position(Self::width()).add_vec(m.to_vector());
+                let new_head_position = old_head
+                    .into_position(self.get_actual_width())
+                    .add_vec(m.to_vector());
                 let new_head = match mode {
                     EvaluateMode::Wrapped => self.as_wrapped_cell_index(new_head_position),
                     EvaluateMode::Standard => {
                         if self.off_board(new_head_position) {
                             continue;
                         } else {
-                            CellIndex::<T>::new(new_head_position, Self::width())
+                            CellIndex::<T>::new(new_head_position, self.get_actual_width())
                         }
                     }
                 };
@@ -137,13 +150,8 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                         .expect("We specifically went to a tail so this shouldn't fail")
                 };
 
-                let mut new_health = self.healths[id.as_usize()];
-                new_health = new_health.saturating_sub(1);
-                if self.get_cell(new_head).is_hazard() {
-                    new_health = new_health.saturating_sub(self.hazard_damage);
-                }
-
                 let ate_food = self.get_cell(new_head).is_food();
+                let mut new_health = self.health_after_moving(id, new_head);
                 let mut new_length = self.lengths[id.as_usize()];
 
                 if ate_food {
@@ -172,17 +180,157 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         new_heads
     }
 
+    /// A snake's health after moving its head to `new_head` and taking hazard damage, before
+    /// any healing or food. As in the engine, hazards do nothing to a snake moving on to food, and
+    /// negative hazard damage heals
+    fn health_after_hazards(&self, id: &SnakeId, new_head: CellIndex<T>) -> u8 {
+        let cell = self.get_cell(new_head);
+        let health = self.healths[id.as_usize()].saturating_sub(1);
+        if !cell.is_hazard() || cell.is_food() {
+            return health;
+        }
+        // stacked hazards each do damage
+        let stack = cell.get_hazard_count();
+        health
+            .saturating_sub(self.hazard_damage.saturating_mul(stack))
+            .saturating_add(self.hazard_healing.saturating_mul(stack))
+            .min(100)
+    }
+
+    /// [Self::health_after_hazards] plus any healing cell, everything but eating
+    fn health_after_moving(&self, id: &SnakeId, new_head: CellIndex<T>) -> u8 {
+        let health = self.health_after_hazards(id, new_head);
+        // healing happens before elimination, so it can save a snake at 0 health
+        if self.get_cell(new_head).is_healing() {
+            health.saturating_add(self.healing_regen).min(100)
+        } else {
+            health
+        }
+    }
+
     #[instrument(level = "trace", skip_all)]
-    pub fn evaluate_moves_with_state<'a>(
+    pub fn evaluate_moves_with_state(
         &self,
-        moves: impl Iterator<Item = &'a (SnakeId, crate::types::Move)>,
+        moves: &[(SnakeId, Move)],
         new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
-    ) -> Self
-    where
-        <Self as types::SnakeIDGettableGame>::SnakeIDType: 'a,
-    {
-        let moves = moves.collect_vec();
+    ) -> Self {
         let mut new = *self;
+        new.evaluate_moves_in_place(moves, new_heads);
+        new
+    }
+
+    /// What happened to each snake when `moves` took this board to `child`, given the state
+    /// [Self::generate_state] prepared for them
+    pub fn turn_outcome(
+        &self,
+        moves: &[(SnakeId, Move)],
+        new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
+        child: &Self,
+        mode: EvaluateMode,
+    ) -> TurnOutcome<MAX_SNAKES> {
+        let width = self.get_actual_width();
+        let mut snakes = [None; MAX_SNAKES];
+        for (id, m) in moves.iter() {
+            let health = self.healths[id.as_usize()];
+            if health == 0 {
+                continue;
+            }
+            let new_head_position = self
+                .get_head_as_native_position(id)
+                .into_position(width)
+                .add_vec(m.to_vector());
+            let new_head = match mode {
+                EvaluateMode::Wrapped => self.as_wrapped_cell_index(new_head_position),
+                EvaluateMode::Standard if self.off_board(new_head_position) => {
+                    snakes[id.as_usize()] = Some(SnakeOutcome {
+                        ate_food: false,
+                        damage_taken: 0,
+                        died: Some(DeathCause::OutOfBounds),
+                    });
+                    continue;
+                }
+                EvaluateMode::Standard => CellIndex::new(new_head_position, width),
+            };
+            // the same health generate_state works out
+            let cell = self.get_cell(new_head);
+            let mut new_health = self.health_after_moving(id, new_head);
+            let damage_taken = health.saturating_sub(self.health_after_hazards(id, new_head));
+            if cell.is_food() {
+                new_health = 100;
+            }
+
+            let collision = if cell.get_snake_id() == Some(*id) {
+                DeathCause::SelfCollision
+            } else {
+                DeathCause::BodyCollision
+            };
+
+            let outcome = match new_heads[id.as_usize()][m.as_index()] {
+                SinglePlayerMoveResult::Alive(result) => {
+                    let died = if child.healths[id.as_usize()] > 0 {
+                        None
+                    } else if moves.iter().any(|(other, other_move)| {
+                        other != id
+                            && new_heads[other.as_usize()][other_move.as_index()]
+                                .to_alive_struct()
+                                .is_some_and(|o| o.new_head == result.new_head)
+                    }) {
+                        Some(DeathCause::HeadToHead)
+                    } else {
+                        Some(collision)
+                    };
+                    SnakeOutcome {
+                        ate_food: result.ate_food,
+                        damage_taken,
+                        died,
+                    }
+                }
+                SinglePlayerMoveResult::Dead if new_health == 0 => {
+                    let cause = if cell.is_wall() {
+                        DeathCause::Wall
+                    } else if health > 1 {
+                        DeathCause::Hazard
+                    } else {
+                        DeathCause::Starvation
+                    };
+                    SnakeOutcome {
+                        ate_food: false,
+                        damage_taken,
+                        died: Some(cause),
+                    }
+                }
+                // the only other way to die before collisions are resolved is moving back in to
+                // the neck, which never gets as far as taking damage
+                SinglePlayerMoveResult::Dead => SnakeOutcome {
+                    ate_food: false,
+                    damage_taken: 0,
+                    died: Some(collision),
+                },
+            };
+            snakes[id.as_usize()] = Some(outcome);
+        }
+        TurnOutcome::new(snakes)
+    }
+
+    /// Applies the moves to this board, the in place version of [Self::evaluate_moves_with_state]
+    pub(super) fn evaluate_moves_in_place(
+        &mut self,
+        moves: &[(SnakeId, Move)],
+        new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
+    ) {
+        #[cfg(feature = "snail-mode")]
+        let trails = self.snail_trails(moves);
+        self.turn += 1;
+
+        // the old heads get overwritten before the heads are moved, so remember which started
+        // out as a freshly spawned snake
+        let mut started_triple_stacked = [false; MAX_SNAKES];
+        for (id, m) in moves.iter() {
+            if let SinglePlayerMoveResult::Alive(result) = new_heads[id.as_usize()][m.as_index()] {
+                started_triple_stacked[id.as_usize()] =
+                    self.get_cell(result.old_head).is_triple_stacked_piece();
+            }
+        }
 
         for (id, m) in moves.iter() {
             let result = new_heads[id.as_usize()][m.as_index()];
@@ -191,6 +339,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                 SinglePlayerMoveResult::Alive(AliveMoveResult {
                     id,
                     old_head,
+                    new_head,
                     new_tail,
                     old_tail,
                     new_health,
@@ -203,29 +352,34 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     // impossible to correctly remove the tail if the snake dies.
 
                     // Remove old tail
-                    let old_tail_cell = new.get_cell(old_tail);
+                    let old_tail_cell = self.get_cell(old_tail);
                     if old_tail_cell.is_double_stacked_piece() {
-                        new.set_cell_body_piece(old_tail, id, old_tail_cell.get_idx());
+                        self.set_cell_body_piece(old_tail, id, old_tail_cell.get_idx());
                     } else {
-                        new.cell_remove(old_tail);
-                        new.set_cell_head(old_head, id, new_tail)
+                        self.cell_remove(old_tail);
+                        self.set_cell_head(old_head, id, new_tail)
                     }
 
                     // Apply new health
-                    new.healths[id.as_usize()] = new_health;
-                    new.lengths[id.as_usize()] = new_length;
+                    self.healths[id.as_usize()] = new_health;
+                    self.lengths[id.as_usize()] = new_length;
 
                     // Step 2: Any Battlesnake that has found food will consume it
                     // Reset health to max if ate food
                     if ate_food {
-                        let new_tail_cell = new.get_cell(new_tail);
-                        new.set_cell_double_stacked(new_tail, id, new_tail_cell.get_idx());
-
-                        // Food is removed naturally by overriding the Cell with the body, which will
-                        // happen later
+                        let new_tail_cell = self.get_cell(new_tail);
+                        self.set_cell_double_stacked(new_tail, id, new_tail_cell.get_idx());
+
+                        // Food is consumed even if the snake dies in a collision later on, as
+                        // feeding happens before elimination. Snakes meeting head to head on food
+                        // all eat it, see resolve_head_to_head for who keeps the growth
+                        if self.cells[new_head.0.as_usize()].is_food() {
+                            self.cells[new_head.0.as_usize()].clear_food();
+                            self.food_count -= 1;
+                        }
                     }
                 }
-                SinglePlayerMoveResult::Dead => new.kill_and_remove(*id),
+                SinglePlayerMoveResult::Dead => self.kill_and_remove(*id),
             }
         }
 
@@ -239,7 +393,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             let result = new_heads[id.as_usize()][m.as_index()];
 
             if let SinglePlayerMoveResult::Alive(AliveMoveResult { id, new_head, .. }) = result {
-                let new_head_cell = new.get_cell(new_head);
+                let new_head_cell = self.get_cell(new_head);
 
                 if new_head_cell.is_body_segment() || new_head_cell.is_head() {
                     to_kill[id.as_usize()] = true;
@@ -248,75 +402,49 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         }
 
         // Step 4e: Head to Head collisions
-        let grouped_heads = moves
-            .iter()
-            .map(|(id, m)| new_heads[id.as_usize()][m.as_index()])
-            .filter_map(|result| result.to_alive_struct())
-            .into_group_map_by(|t| t.new_head);
-        let head_to_head_collistions = grouped_heads
-            .iter()
-            .filter(|(_key, values)| values.len() >= 2);
-
-        for (head_to_head_collision_pos, snake_move_info) in head_to_head_collistions {
-            let max_length = snake_move_info
-                .iter()
-                .map(|i| (*i, new.get_length(i.id)))
-                .max_by_key(|x| x.1)
-                .unwrap()
-                .1;
-            let snake_ids = snake_move_info.iter().map(|i| i.id).collect_vec();
-            let cell = new.get_cell(*head_to_head_collision_pos);
-            // consider this board:
-            //   s . . f . . s s s 3 s
-            //   s s s . . . . s s . .
-            //   . . s . . . . . . . .
-            //   . f s . . . . . . . .
-            //   s s s . . . . . . . s
-            //   s s f . . s s s s s s
-            //   s s . . 2 s . . . . s
-            //   s s s s . . . . s . s
-            //   . . . . . . s s s . .
-            //   s s s s . . s . . 0 .
-            //   s . . . . . s . 1 s s
-            // it's a little hard to see, but if at the same time
-            // snake 3 moves up: it will warp around on the second column from the top row to the bottom row (from 10,9 to 0,9),
-            // snake 1 moves right from (0,8 to 0,9) it will also be on 0,9
-            // and snake 0 has a body segment (currently it's neck) on 0,
-            // this will cause a head to head collision between snake 1 and snake 3 on snake 0's neck.
-            // this statement needs to be added to the winner check, because if it isn't, the neck cell for
-            // snake 0 will be removed, causing the body to go in to an inconsistent state
-            let head_to_head_collision_on_another_snake = cell.is_body_segment()
-                && !cell.is_head()
-                && !snake_ids.contains(&cell.get_snake_id().unwrap());
-
-            let multiple_snakes_max_length = snake_move_info
-                .iter()
-                .filter(|x| new.get_length(x.id) == max_length)
-                .count()
-                != 1;
-
-            let winner = if multiple_snakes_max_length || head_to_head_collision_on_another_snake {
-                None
-            } else {
-                Some(
-                    snake_move_info
-                        .iter()
-                        .map(|i| (*i, new.get_length(i.id)))
-                        .max_by_key(|x| x.1)
-                        .unwrap()
-                        .0,
-                )
-            };
-
-            for AliveMoveResult { id: dead, .. } in snake_move_info
-                .iter()
-                .filter(|x| Some(x.id) != winner.map(|x| x.id))
-            {
-                to_kill[dead.as_usize()] = true;
+        if let [(a_id, a_move), (b_id, b_move)] = moves {
+            // duels are the most common late game position, with only two movers there is
+            // nothing to group, the snakes collide exactly when their new heads match
+            let a = new_heads[a_id.as_usize()][a_move.as_index()].to_alive_struct();
+            let b = new_heads[b_id.as_usize()][b_move.as_index()].to_alive_struct();
+            if let (Some(a), Some(b)) = (a, b) {
+                if a.new_head == b.new_head {
+                    self.resolve_head_to_head(a.new_head, [a, b].iter(), &mut to_kill);
+                }
             }
+        } else {
+            // there are at most MAX_SNAKES movers, so comparing every pair out of a fixed size
+            // array is cheaper than building a map for every evaluated child
+            let mut movers = [None; MAX_SNAKES];
+            for (slot, result) in movers.iter_mut().zip(
+                moves
+                    .iter()
+                    .filter_map(|(id, m)| new_heads[id.as_usize()][m.as_index()].to_alive_struct()),
+            ) {
+                *slot = Some(result);
+            }
+            let alive = || movers.iter().flatten();
+
+            for (i, first) in alive().enumerate() {
+                let head_to_head_collision_pos = first.new_head;
+                // each cell is resolved once, by the first snake that moved on to it
+                if alive()
+                    .take(i)
+                    .any(|r| r.new_head == head_to_head_collision_pos)
+                {
+                    continue;
+                }
+                let snake_move_info =
+                    alive().filter(move |r| r.new_head == head_to_head_collision_pos);
+                if snake_move_info.clone().count() < 2 {
+                    continue;
+                }
 
-            if winner.is_none() && !head_to_head_collision_on_another_snake {
-                new.cell_remove(*head_to_head_collision_pos);
+                self.resolve_head_to_head(
+                    head_to_head_collision_pos,
+                    snake_move_info,
+                    &mut to_kill,
+                );
             }
         }
 
@@ -334,22 +462,94 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             {
                 if to_kill[id.as_usize()] {
                     // Kill any player killed via collisions
-                    new.kill_and_remove(id);
+                    self.kill_and_remove(id);
                 } else {
                     // Move Head
-                    new.heads[id.as_usize()] = new_head;
-                    new.set_cell_head(new_head, id, new_tail);
-
-                    let old_head_cell = self.get_cell(old_head);
-                    if old_head_cell.is_triple_stacked_piece() {
-                        new.set_cell_double_stacked(old_head, id, new_head);
+                    self.heads[id.as_usize()] = new_head;
+                    self.set_cell_head(new_head, id, new_tail);
+
+                    // the old head is already the stacked tail if a length two snake just ate
+                    if started_triple_stacked[id.as_usize()]
+                        || self.get_cell(old_head).is_double_stacked_piece()
+                    {
+                        self.set_cell_double_stacked(old_head, id, new_head);
                     } else {
-                        new.set_cell_body_piece(old_head, id, new_head);
+                        self.set_cell_body_piece(old_head, id, new_head);
                     }
                 }
             }
         }
 
-        new
+        #[cfg(feature = "snail-mode")]
+        self.lay_snail_trails(&trails);
+    }
+
+    /// kills the losers of a head to head collision between `snake_move_info` on
+    /// `head_to_head_collision_pos`, clearing the cell if nobody survives
+    fn resolve_head_to_head<'a>(
+        &mut self,
+        head_to_head_collision_pos: CellIndex<T>,
+        snake_move_info: impl Iterator<Item = &'a AliveMoveResult<T>> + Clone,
+        to_kill: &mut [bool; MAX_SNAKES],
+    ) where
+        T: 'a,
+    {
+        // feeding happens before elimination, so every snake moving on to food has already
+        // grown and the lengths compared are the ones after eating. Only the winner keeps the
+        // growth, as everyone else is eliminated
+        let max_length = snake_move_info.clone().map(|i| i.new_length).max().unwrap();
+        let cell = self.get_cell(head_to_head_collision_pos);
+        // consider this board:
+        //   s . . f . . s s s 3 s
+        //   s s s . . . . s s . .
+        //   . . s . . . . . . . .
+        //   . f s . . . . . . . .
+        //   s s s . . . . . . . s
+        //   s s f . . s s s s s s
+        //   s s . . 2 s . . . . s
+        //   s s s s . . . . s . s
+        //   . . . . . . s s s . .
+        //   s s s s . . s . . 0 .
+        //   s . . . . . s . 1 s s
+        // it's a little hard to see, but if at the same time
+        // snake 3 moves up: it will warp around on the second column from the top row to the bottom row (from 10,9 to 0,9),
+        // snake 1 moves right from (0,8 to 0,9) it will also be on 0,9
+        // and snake 0 has a body segment (currently it's neck) on 0,
+        // this will cause a head to head collision between snake 1 and snake 3 on snake 0's neck.
+        // this statement needs to be added to the winner check, because if it isn't, the neck cell for
+        // snake 0 will be removed, causing the body to go in to an inconsistent state
+        let head_to_head_collision_on_another_snake = cell.is_body_segment()
+            && !cell.is_head()
+            && !snake_move_info
+                .clone()
+                .any(|i| Some(i.id) == cell.get_snake_id());
+
+        let multiple_snakes_max_length = snake_move_info
+            .clone()
+            .filter(|x| x.new_length == max_length)
+            .count()
+            != 1;
+
+        let winner = if multiple_snakes_max_length || head_to_head_collision_on_another_snake {
+            None
+        } else {
+            Some(
+                *snake_move_info
+                    .clone()
+                    .max_by_key(|i| i.new_length)
+                    .unwrap(),
+            )
+        };
+
+        for AliveMoveResult { id: dead, .. } in snake_move_info
+            .clone()
+            .filter(|x| Some(x.id) != winner.map(|x| x.id))
+        {
+            to_kill[dead.as_usize()] = true;
+        }
+
+        if winner.is_none() && !head_to_head_collision_on_another_snake {
+            self.cell_remove(head_to_head_collision_pos);
+        }
     }
 }
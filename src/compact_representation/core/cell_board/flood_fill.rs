@@ -0,0 +1,84 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::Move,
+};
+
+use super::{CellBoard, CellIndex, EvaluateMode};
+
+const UNCLAIMED: u8 = u8::MAX;
+const CONTESTED: u8 = u8::MAX - 1;
+const BLOCKED: u8 = u8::MAX - 2;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// The number of cells each snake reaches first, indexed by snake id, see
+    /// [crate::algorithms::flood_fill::spread_from_heads]. Every cell is queued at most once, so
+    /// the queue is a fixed size array and nothing is allocated
+    pub fn spread_from_heads(&self, mode: EvaluateMode) -> [u16; MAX_SNAKES] {
+        let mut owners = [UNCLAIMED; BOARD_SIZE];
+        let mut depths = [0u16; BOARD_SIZE];
+        let mut queue = [CellIndex::<T>::from_usize(0); BOARD_SIZE];
+        let (mut next, mut end) = (0, 0);
+
+        for (owner, cell) in owners.iter_mut().zip(self.cells.iter()) {
+            if cell.is_body() || cell.is_head() || cell.is_wall() {
+                *owner = BLOCKED;
+            }
+        }
+        for (sid, health) in self.healths.iter().enumerate() {
+            if *health > 0 {
+                let head = self.heads[sid];
+                owners[head.0.as_usize()] = sid as u8;
+                queue[end] = head;
+                end += 1;
+            }
+        }
+
+        let width = self.get_actual_width();
+        while next < end {
+            let ci = queue[next];
+            next += 1;
+            let owner = owners[ci.0.as_usize()];
+            if owner == CONTESTED {
+                continue;
+            }
+            let depth = depths[ci.0.as_usize()] + 1;
+            let pos = ci.into_position(width);
+
+            for mv in Move::all_iter() {
+                let neighbor = pos.add_vec(mv.to_vector());
+                let neighbor = match mode {
+                    EvaluateMode::Wrapped => self.as_wrapped_cell_index(neighbor),
+                    EvaluateMode::Standard => {
+                        if self.off_board(neighbor) {
+                            continue;
+                        }
+                        CellIndex::new(neighbor, width)
+                    }
+                };
+                let n = neighbor.0.as_usize();
+                match owners[n] {
+                    UNCLAIMED => {
+                        owners[n] = owner;
+                        depths[n] = depth;
+                        queue[end] = neighbor;
+                        end += 1;
+                    }
+                    // everything claimed on this step came from the step before, so a second
+                    // snake reaching the cell at the same depth contests it
+                    other if other < BLOCKED && other != owner && depths[n] == depth => {
+                        owners[n] = CONTESTED;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut counts = [0; MAX_SNAKES];
+        for owner in owners.iter().filter(|o| **o < BLOCKED) {
+            counts[*owner as usize] += 1;
+        }
+        counts
+    }
+}
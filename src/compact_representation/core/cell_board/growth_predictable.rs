@@ -0,0 +1,38 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{GrowthMarkableGame, GrowthPredictableGame},
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    GrowthPredictableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn will_grow_next_turn(&self, snake_id: &Self::SnakeIDType) -> bool {
+        if !self.is_live_snake(*snake_id) {
+            return false;
+        }
+        let head = self.heads[snake_id.as_usize()];
+        let tail = self
+            .get_cell(head)
+            .get_tail_position(head)
+            .expect("an alive snake has a tail");
+        self.get_cell(tail).is_stacked()
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> GrowthMarkableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn mark_will_grow(&mut self, snake_id: &Self::SnakeIDType) {
+        if !self.is_live_snake(*snake_id) || self.will_grow_next_turn(snake_id) {
+            return;
+        }
+        let head = self.heads[snake_id.as_usize()];
+        let tail = self.get_cell(head).get_tail_position(head).unwrap();
+        // an unstacked tail is a body piece, so it knows the next piece towards the head
+        let next = self.get_cell(tail).get_idx();
+        self.set_cell_double_stacked(tail, *snake_id, next);
+        self.lengths[snake_id.as_usize()] += 1;
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::HazardQueryableGame,
+    types::{HazardQueryableGame, WallQueryableGame},
 };
 
 use super::CellBoard;
@@ -15,4 +15,16 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     fn get_hazard_damage(&self) -> u8 {
         self.hazard_damage
     }
+
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.get_cell(*pos).get_hazard_count()
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> WallQueryableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+        self.get_cell(*pos).is_wall()
+    }
 }
@@ -9,10 +9,26 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn set_hazard(&mut self, pos: Self::NativePositionType) {
-        self.cells[pos.0.as_usize()].set_hazard();
+        if !self.cells[pos.0.as_usize()].is_hazard() {
+            self.cells[pos.0.as_usize()].set_hazard();
+            self.hazard_count += 1;
+        }
     }
 
     fn clear_hazard(&mut self, pos: Self::NativePositionType) {
-        self.cells[pos.0.as_usize()].clear_hazard();
+        if self.cells[pos.0.as_usize()].is_hazard() {
+            self.cells[pos.0.as_usize()].clear_hazard();
+            self.hazard_count -= 1;
+        }
+    }
+
+    fn set_hazard_count(&mut self, pos: Self::NativePositionType, count: u8) {
+        let cell = &mut self.cells[pos.0.as_usize()];
+        match (cell.is_hazard(), count > 0) {
+            (false, true) => self.hazard_count += 1,
+            (true, false) => self.hazard_count -= 1,
+            _ => {}
+        }
+        cell.set_hazard_count(count);
     }
 }
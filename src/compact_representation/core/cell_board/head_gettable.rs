@@ -23,4 +23,12 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     ) -> Self::NativePositionType {
         self.heads[snake_id.0.as_usize()]
     }
+
+    fn try_get_head(&self, snake_id: &Self::SnakeIDType) -> Option<Self::NativePositionType> {
+        if self.is_live_snake(*snake_id) {
+            Some(self.heads[snake_id.as_usize()])
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,30 @@
+use crate::{
+    compact_representation::{
+        core::{dimensions::Dimensions, CellIndex},
+        CellNum,
+    },
+    types::HealingQueryableGame,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    HealingQueryableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn healing_at(&self, pos: &Self::NativePositionType) -> u8 {
+        if self.get_cell(*pos).is_healing() {
+            self.healing_regen
+        } else {
+            0
+        }
+    }
+
+    fn healing_cells(&self) -> Vec<Self::NativePositionType> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_healing())
+            .map(|(i, _)| CellIndex(T::from_usize(i)))
+            .collect()
+    }
+}
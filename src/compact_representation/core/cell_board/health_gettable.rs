@@ -1,6 +1,6 @@
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::HealthGettableGame,
+    types::{HealthGettableGame, HealthSettableGame},
 };
 
 use super::CellBoard;
@@ -19,3 +19,18 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         self.get_health(snake_id) as i64
     }
 }
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> HealthSettableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn set_health(&mut self, snake_id: &Self::SnakeIDType, health: Self::HealthType) {
+        if !self.is_alive(snake_id) {
+            return;
+        }
+        if health == 0 {
+            self.kill_and_remove(*snake_id);
+        } else {
+            self.healths[snake_id.0.as_usize()] = health;
+        }
+    }
+}
@@ -23,7 +23,41 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
         self.get_length(*snake_id) as i64
     }
+
+    fn try_get_length(&self, snake_id: &Self::SnakeIDType) -> Option<Self::LengthType> {
+        if self.is_live_snake(*snake_id) {
+            Some(self.lengths[snake_id.as_usize()])
+        } else {
+            None
+        }
+    }
+}
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// writes the symbol for a single cell: the snake id for heads, `s` for the rest of a body,
+    /// `f` for food, `x` for hazards and `.` for empty cells
+    pub fn fmt_cell(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        cell_idx: CellIndex<T>,
+    ) -> std::fmt::Result {
+        if self.cell_is_snake_head(cell_idx) {
+            let id = self.get_snake_id_at(cell_idx);
+            write!(f, "{}", id.unwrap().as_usize())
+        } else if self.cell_is_body(cell_idx) {
+            write!(f, "s")
+        } else if self.cell_is_food(cell_idx) {
+            write!(f, "f")
+        } else if self.cell_is_hazard(cell_idx) {
+            write!(f, "x")
+        } else {
+            debug_assert!(self.cells[cell_idx.0.as_usize()].is_empty());
+            write!(f, ".")
+        }
+    }
 }
+
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
     for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
@@ -38,20 +72,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     x: x as i32,
                     y: y as i32,
                 };
-                let cell_idx = CellIndex::new(position, width);
-                if self.cell_is_snake_head(cell_idx) {
-                    let id = self.get_snake_id_at(cell_idx);
-                    write!(f, "{}", id.unwrap().as_usize())?;
-                } else if self.cell_is_food(cell_idx) {
-                    write!(f, "f")?
-                } else if self.cell_is_body(cell_idx) {
-                    write!(f, "s")?
-                } else if self.cell_is_hazard(cell_idx) {
-                    write!(f, "x")?
-                } else {
-                    debug_assert!(self.cells[cell_idx.0.as_usize()].is_empty());
-                    write!(f, ".")?
-                }
+                self.fmt_cell(f, CellIndex::new(position, width))?;
                 write!(f, " ")?;
             }
             writeln!(f)?;
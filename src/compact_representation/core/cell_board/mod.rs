@@ -2,38 +2,61 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::slice::Iter;
 
+use fxhash::FxHashMap;
 use itertools::Itertools;
 use rand::seq::IteratorRandom;
 
 use crate::types::EmptyCellGettableGame;
+use crate::types::HazardQueryableGame;
+use crate::types::HealthSettableGame;
 use crate::types::SnakeIDMap;
 use crate::types::SnakeId;
 use crate::types::StandardFoodPlaceableGame;
-use crate::wire_representation::Game;
+use crate::types::Vector;
+use crate::types::VictorDeterminableGame;
 use crate::wire_representation::Position;
+use crate::wire_representation::{BattleSnake, Game};
 
 use super::dimensions::Dimensions;
 use super::Cell;
 use super::CellIndex;
 use super::CellNum as CN;
 use super::{DOUBLE_STACK, TRIPLE_STACK};
+use serialization::SerializedCellBoard;
 
+mod batch;
+mod cell_contents;
+#[cfg(feature = "cell-metadata")]
+mod cell_metadata;
+mod consistency;
 mod eval;
+mod flood_fill;
 mod food_gettable;
+mod growth_predictable;
 mod hazard_queryable;
 mod hazard_settable;
 mod head_gettable;
+mod healing_queryable;
 mod health_gettable;
 mod length_gettable;
 mod neck_queryable;
+mod overlay;
+mod patch;
 mod position_gettable;
+mod serialization;
 mod size_determinable;
+#[cfg(feature = "snail-mode")]
+mod snail_mode;
 mod snake_body_gettable;
 mod snake_id_gettable;
+mod turn_determinable;
 mod victor_determinable;
 mod you_determinable;
 
+pub use cell_contents::CellContents;
 pub use eval::EvaluateMode;
+pub use overlay::OverlayDisplay;
+pub use patch::BoardPatch;
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
@@ -45,11 +68,64 @@ pub struct CellBoard<
     const MAX_SNAKES: usize,
 > {
     hazard_damage: u8,
+    /// health each hazard restores instead when the ruleset's hazard damage is negative, only one
+    /// of this and `hazard_damage` is ever non-zero
+    hazard_healing: u8,
+    /// health restored each turn on a healing cell, compact boards only support one amount
+    healing_regen: u8,
+    /// sandbox mode, see [crate::types::VictorDeterminableGame::is_solo]
+    solo: bool,
+    /// the least food [StandardFoodPlaceableGame::place_food] tops the board up to
+    min_food: u8,
+    /// the percent chance of [StandardFoodPlaceableGame::place_food] spawning food when there's
+    /// already enough
+    food_spawn_chance: u8,
     cells: [Cell<T>; BOARD_SIZE],
+    /// the number of cells with food, kept up to date so heuristics don't have to scan the cells
+    food_count: u32,
+    /// the number of hazard cells, kept up to date like `food_count`
+    hazard_count: u32,
     healths: [u8; MAX_SNAKES],
     heads: [CellIndex<T>; MAX_SNAKES],
     lengths: [u16; MAX_SNAKES],
+    /// squad of each snake, 0 for a snake not in a squad
+    squads: [u8; MAX_SNAKES],
     dimensions: DimensionsType,
+    /// the turn this board is on, counting up from the game's turn with every simulated move
+    turn: u32,
+    /// the caller's own data for each cell, copied along with the board when simulating
+    #[cfg(feature = "cell-metadata")]
+    metadata: [u8; BOARD_SIZE],
+    /// whether moving snakes leave trails of hazards behind, see [CellBoard::set_snail_mode]
+    #[cfg(feature = "snail-mode")]
+    snail_mode: bool,
+}
+
+/// the engine's food settings, for games that don't send their own
+const DEFAULT_MIN_FOOD: u8 = 1;
+const DEFAULT_FOOD_SPAWN_CHANCE: u8 = 15;
+
+fn count_food_and_hazards<T: CN>(cells: &[Cell<T>]) -> (u32, u32) {
+    cells.iter().fold((0, 0), |(food, hazards), cell| {
+        (
+            food + cell.is_food() as u32,
+            hazards + cell.is_hazard() as u32,
+        )
+    })
+}
+
+/// Buffers reused between conversions, see `convert_from_game_with` on the standard and wrapped
+/// boards
+#[derive(Debug, Default, Clone)]
+pub struct ConversionScratch {
+    counts: FxHashMap<Position, usize>,
+}
+
+fn count_segments(counts: &mut FxHashMap<Position, usize>, snake: &BattleSnake) {
+    counts.clear();
+    for pos in snake.body.iter() {
+        *counts.entry(*pos).or_insert(0) += 1;
+    }
 }
 
 #[allow(dead_code)]
@@ -67,11 +143,31 @@ fn get_snake_id(
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
+    /// Evaluated when a board type is built, so that a misconfigured board type fails to compile
+    /// rather than indexing out of bounds at runtime
+    const PARAMETERS_CHECK: () = {
+        if let Some(cells) = D::FIXED_CELLS {
+            assert!(
+                cells <= BOARD_SIZE,
+                "BOARD_SIZE is smaller than the fixed dimensions"
+            );
+        }
+        assert!(
+            BOARD_SIZE <= T::MAX_CELLS,
+            "BOARD_SIZE has more cells than the cell number type can index"
+        );
+        assert!(
+            MAX_SNAKES <= u8::MAX as usize + 1,
+            "MAX_SNAKES has more snakes than a SnakeId can identify"
+        );
+    };
+
     pub fn iter_healths(&self) -> Iter<'_, u8> {
         self.healths.iter()
     }
 
-    /// Asserts that all tails eventually loop back to a head and panics if the board is inconsistent
+    /// Asserts that all tails eventually loop back to a head and panics if the board is inconsistent.
+    /// See [CellBoard::verify_consistency] for a fuller check that says what's wrong
     pub fn assert_consistency(&self) -> bool {
         for i in 0..MAX_SNAKES {
             let snake_id = SnakeId(i as u8);
@@ -103,8 +199,8 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         true
     }
 
-    /// packs this as a hash. Doing this because getting serde to work
-    /// with const generics is hard
+    /// packs this as a hash. Predates the serde impls, which should be preferred, but is kept
+    /// for boards already stored this way
     pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
         let mut hash = HashMap::new();
         hash.insert("hazard_damage".to_string(), vec![self.hazard_damage as u32]);
@@ -112,6 +208,10 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             "actual_width".to_string(),
             vec![self.get_actual_width() as u32],
         );
+        hash.insert(
+            "actual_height".to_string(),
+            vec![self.get_actual_height() as u32],
+        );
         hash.insert(
             "healths".to_string(),
             self.healths.iter().map(|x| *x as u32).collect(),
@@ -128,97 +228,143 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             "cells".to_string(),
             self.cells.iter().map(|x| x.pack_as_u32()).collect(),
         );
+        if self.hazard_healing > 0 {
+            hash.insert(
+                "hazard_healing".to_string(),
+                vec![self.hazard_healing as u32],
+            );
+        }
+        if self.healing_regen > 0 {
+            hash.insert("healing_regen".to_string(), vec![self.healing_regen as u32]);
+        }
+        if self.solo {
+            hash.insert("solo".to_string(), vec![1]);
+        }
+        if (self.min_food, self.food_spawn_chance) != (DEFAULT_MIN_FOOD, DEFAULT_FOOD_SPAWN_CHANCE)
+        {
+            hash.insert("min_food".to_string(), vec![self.min_food as u32]);
+            hash.insert(
+                "food_spawn_chance".to_string(),
+                vec![self.food_spawn_chance as u32],
+            );
+        }
+        if self.has_squads() {
+            hash.insert(
+                "squads".to_string(),
+                self.squads.iter().map(|x| *x as u32).collect(),
+            );
+        }
+        #[cfg(feature = "cell-metadata")]
+        if self.metadata.iter().any(|m| *m != 0) {
+            hash.insert(
+                "metadata".to_string(),
+                self.metadata.iter().map(|x| *x as u32).collect(),
+            );
+        }
+        if self.cells.iter().any(|c| c.get_hazard_count() > 1) {
+            hash.insert(
+                "hazard_stacks".to_string(),
+                self.cells
+                    .iter()
+                    .map(|c| c.get_hazard_count() as u32)
+                    .collect(),
+            );
+        }
+        #[cfg(feature = "snail-mode")]
+        if self.snail_mode {
+            hash.insert("snail_mode".to_string(), vec![1]);
+        }
+        if self.turn > 0 {
+            hash.insert("turn".to_string(), vec![self.turn]);
+        }
         hash
     }
 
-    /// unpacks a packed hash repr back in to a CellBoard
+    /// unpacks a packed hash repr back in to a CellBoard, panicking if a required key is missing
     pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
-        let hazard_damage = hash.get("hazard_damage").unwrap()[0] as u8;
-        let actual_width = hash.get("actual_width").unwrap()[0] as u8;
-        let actual_height = hash
-            .get("actual_height")
-            .map(|h| h[0] as u8)
-            .unwrap_or(actual_width);
-
-        let mut healths = [0; MAX_SNAKES];
-        let healths_iter = hash.get("healths").unwrap().iter().map(|x| *x as u8);
-        for (idx, health) in healths_iter.enumerate() {
-            healths[idx] = health;
-        }
-
-        let mut lengths = [0; MAX_SNAKES];
-        let lengths_iter = hash.get("lengths").unwrap().iter().map(|x| *x as u16);
-        for (idx, length) in lengths_iter.enumerate() {
-            lengths[idx] = length;
-        }
-
-        let mut heads = [CellIndex::<T>::from_usize(0); MAX_SNAKES];
-        let heads_iter = hash.get("heads").unwrap().iter().map(|x| *x as usize);
-        for (idx, head) in heads_iter.enumerate() {
-            heads[idx] = CellIndex::<T>::from_usize(head);
-        }
+        Self::try_from_packed_hash(hash).expect("the packed hash is complete")
+    }
 
-        let mut cells = [Cell::<T>::empty(); BOARD_SIZE];
-        let cells_iter = hash.get("cells").unwrap().iter().cloned();
-        for (idx, cell) in cells_iter.enumerate() {
-            cells[idx] = Cell::<T>::from_u32(cell);
+    /// unpacks a packed hash repr back in to a CellBoard
+    pub fn try_from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Result<Self, Box<dyn Error>> {
+        for key in [
+            "hazard_damage",
+            "actual_width",
+            "healths",
+            "lengths",
+            "heads",
+            "cells",
+        ] {
+            match hash.get(key) {
+                Some(values) if !values.is_empty() => {}
+                _ => return Err(format!("packed hash is missing {}", key).into()),
+            }
         }
+        let values = |key: &str| hash.get(key).cloned().unwrap_or_default();
+        let first = |key: &str| hash.get(key).and_then(|v| v.first()).copied();
+        let actual_width = first("actual_width").unwrap() as u8;
+
+        Self::from_serialized(SerializedCellBoard {
+            width: actual_width,
+            height: first("actual_height").map_or(actual_width, |h| h as u8),
+            hazard_damage: first("hazard_damage").unwrap() as u8,
+            hazard_healing: first("hazard_healing").unwrap_or(0) as u8,
+            healing_regen: first("healing_regen").unwrap_or(0) as u8,
+            solo: first("solo") == Some(1),
+            min_food: first("min_food").map_or(DEFAULT_MIN_FOOD, |m| m as u8),
+            food_spawn_chance: first("food_spawn_chance")
+                .map_or(DEFAULT_FOOD_SPAWN_CHANCE, |c| c as u8),
+            cells: values("cells"),
+            healths: values("healths").into_iter().map(|h| h as u8).collect(),
+            lengths: values("lengths").into_iter().map(|l| l as u16).collect(),
+            heads: values("heads"),
+            squads: values("squads").into_iter().map(|s| s as u8).collect(),
+            metadata: values("metadata").into_iter().map(|m| m as u8).collect(),
+            hazard_stacks: values("hazard_stacks")
+                .into_iter()
+                .map(|s| s as u8)
+                .collect(),
+            snail_mode: first("snail_mode") == Some(1),
+            turn: first("turn").unwrap_or(0),
+        })
+    }
 
-        let dimensions = D::from_dimensions(actual_width, actual_height);
+    pub fn as_wrapped_cell_index(&self, new_head_position: Position) -> CellIndex<T> {
+        CellIndex::<T>::new(
+            self.normalize_position(new_head_position),
+            self.get_actual_width(),
+        )
+    }
 
-        CellBoard {
-            hazard_damage,
-            cells,
-            healths,
-            heads,
-            lengths,
-            dimensions,
-        }
+    /// wraps a position on to the board as if it were a torus, see [Position::wrap_to]
+    pub fn normalize_position(&self, pos: Position) -> Position {
+        pos.wrap_to(
+            self.get_actual_width() as u32,
+            self.get_actual_height() as u32,
+        )
     }
 
-    pub fn as_wrapped_cell_index(&self, mut new_head_position: Position) -> CellIndex<T> {
-        if self.off_board(new_head_position) {
-            if new_head_position.x < 0 {
-                debug_assert!(new_head_position.x == -1);
-                debug_assert!(
-                    new_head_position.y >= 0
-                        && new_head_position.y < self.get_actual_height() as i32
-                );
-                new_head_position.x = self.get_actual_width() as i32 - 1;
-            } else if new_head_position.x >= self.get_actual_width() as i32 {
-                debug_assert!(new_head_position.x == self.get_actual_width() as i32);
-                debug_assert!(
-                    new_head_position.y >= 0
-                        && new_head_position.y < self.get_actual_height() as i32
-                );
-                new_head_position.x = 0;
-            } else if new_head_position.y < 0 {
-                debug_assert!(new_head_position.y == -1);
-                debug_assert!(
-                    new_head_position.x >= 0
-                        && new_head_position.x < self.get_actual_width() as i32
-                );
-                new_head_position.y = self.get_actual_height() as i32 - 1;
-            } else if new_head_position.y >= self.get_actual_height() as i32 {
-                debug_assert!(new_head_position.y == self.get_actual_height() as i32);
-                debug_assert!(
-                    new_head_position.x >= 0
-                        && new_head_position.x < self.get_actual_width() as i32
-                );
-                new_head_position.y = 0;
-            } else {
-                panic!("We should never get here");
-            }
-            CellIndex::<T>::new(new_head_position, Self::width())
-        } else {
-            CellIndex::<T>::new(new_head_position, Self::width())
-        }
+    /// the shortest displacement from `a` to `b` with the board wrapped in to a torus, see
+    /// [Position::torus_delta]
+    pub fn torus_delta(&self, a: Position, b: Position) -> Vector {
+        a.torus_delta(
+            &b,
+            self.get_actual_width() as u32,
+            self.get_actual_height() as u32,
+        )
     }
 
     pub fn get_actual_width(&self) -> u8 {
         self.dimensions.width()
     }
 
+    /// the number of cells per row in the cell array, i.e. the width to give [CellIndex::new]
+    /// and [CellIndex::into_position]. Rows aren't padded, so this is the actual width, but index
+    /// math should use this rather than assuming the board is square
+    pub fn stored_width(&self) -> u8 {
+        self.dimensions.width()
+    }
+
     pub fn get_actual_height(&self) -> u8 {
         self.dimensions.height()
     }
@@ -245,9 +391,25 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     }
 
     /// Builds a cellboard from a given game, will return an error if the game doesn't match
-    /// the provided BOARD_SIZE or MAX_SNAKES. You are encouraged to use `CellBoard4Snakes11x11`
-    /// for the common game layout
+    /// the provided BOARD_SIZE or MAX_SNAKES, if a snake is malformed or missing from `snake_ids`,
+    /// or if a snake's health doesn't fit in a u8 (a `HealthOutOfRange`, use
+    /// `Game::saturate_healths` to clamp them first). You are encouraged to use
+    /// `CellBoard4Snakes11x11` for the common game layout.
+    ///
+    /// Dead snakes are skipped, but an alive snake missing from `snake_ids` is an error. Before
+    /// 0.18 it panicked instead
     pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
+        Self::convert_from_game_with(&game, snake_ids, &mut ConversionScratch::default())
+    }
+
+    /// [CellBoard::convert_from_game] reusing the buffers in `scratch`, for converting many games
+    pub fn convert_from_game_with(
+        game: &Game,
+        snake_ids: &SnakeIDMap,
+        scratch: &mut ConversionScratch,
+    ) -> Result<Self, Box<dyn Error>> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::PARAMETERS_CHECK;
         if game.board.width * game.board.height > BOARD_SIZE as u32 {
             return Err("game size doesn't fit in the given board size".into());
         }
@@ -256,11 +418,64 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             return Err("too many snakes".into());
         }
 
+        if game.allows_body_collisions() {
+            return Err(
+                "compact boards can't simulate squad snakes moving through each other".into(),
+            );
+        }
+
+        let regens = game
+            .board
+            .healing_cells
+            .iter()
+            .map(|c| c.regen)
+            .unique()
+            .collect_vec();
+        if regens.len() > 1 {
+            return Err("compact boards only support one healing regen amount".into());
+        }
+        let healing_regen = regens.first().copied().unwrap_or(0);
+
+        let counts = &mut scratch.counts;
         for snake in &game.board.snakes {
-            let counts = &snake.body.iter().counts();
+            count_segments(counts, snake);
             if counts.values().any(|v| *v == TRIPLE_STACK) && counts.len() != 1 {
                 return Err(format!("snake {} has a bad body stack (3 segs on same square and more than one unique position)", snake.id).into());
             }
+            if let (_, Some(out_of_range)) = snake.saturating_health() {
+                return Err(Box::new(out_of_range));
+            }
+            if snake.health == 0 {
+                continue;
+            }
+            match snake_ids.get(&snake.id) {
+                Some(sid) if sid.as_usize() < MAX_SNAKES => {}
+                Some(_) => {
+                    return Err(format!("snake {} has an id that's too big", snake.id).into())
+                }
+                None => return Err(format!("snake {} isn't in the snake id map", snake.id).into()),
+            }
+            if snake.body.front() != Some(&snake.head) {
+                return Err(format!(
+                    "snake {} has a head that isn't its first body segment",
+                    snake.id
+                )
+                .into());
+            }
+            if counts.get(&snake.head) == Some(&DOUBLE_STACK) {
+                return Err(format!("snake {} has a double stacked head", snake.id).into());
+            }
+            // the compact boards tolerate snakes hanging off the board as long as every segment
+            // still lands on a cell
+            let cell_count = (game.board.width * game.board.height).min(BOARD_SIZE as u32) as i32;
+            let width = game.board.width as i32;
+            if snake
+                .body
+                .iter()
+                .any(|pos| !(0..cell_count).contains(&(pos.y * width + pos.x)))
+            {
+                return Err(format!("snake {} is outside of the board's cells", snake.id).into());
+            }
         }
         let width = game.board.width as u8;
         let height = game.board.height as u8;
@@ -269,6 +484,14 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         let mut healths: [u8; MAX_SNAKES] = [0; MAX_SNAKES];
         let mut heads: [CellIndex<T>; MAX_SNAKES] = [CellIndex::from_i32(0); MAX_SNAKES];
         let mut lengths: [u16; MAX_SNAKES] = [0; MAX_SNAKES];
+        let mut squads: [u8; MAX_SNAKES] = [0; MAX_SNAKES];
+        let squad_names = game
+            .board
+            .snakes
+            .iter()
+            .filter_map(|s| s.squad.as_ref())
+            .unique()
+            .collect_vec();
 
         for snake in &game.board.snakes {
             let snake_id = match get_snake_id(snake, snake_ids) {
@@ -276,13 +499,18 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                 None => continue,
             };
 
+            if let Some(squad) = &snake.squad {
+                let squad_index = squad_names.iter().position(|s| *s == squad).unwrap();
+                squads[snake_id.0 as usize] = squad_index as u8 + 1;
+            }
+
             healths[snake_id.0 as usize] = snake.health as u8;
             if snake.health == 0 {
                 continue;
             }
             lengths[snake_id.0 as usize] = snake.body.len() as u16;
 
-            let counts = &snake.body.iter().counts();
+            count_segments(counts, snake);
 
             let head_idx = CellIndex::new(snake.head, width);
             let mut next_index = head_idx;
@@ -309,39 +537,55 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                 next_index = cell_idx;
             }
         }
-        for y in 0..height {
-            for x in 0..width {
-                let position = Position {
-                    x: x as i32,
-                    y: y as i32,
-                };
-                let cell_idx: CellIndex<T> = CellIndex::new(position, width);
-
-                if game.board.hazards.contains(&position) {
-                    cells[cell_idx.0.as_usize()].set_hazard();
-                }
-
-                if game.board.food.contains(&position) {
-                    cells[cell_idx.0.as_usize()].set_food();
-                }
+        let on_board = |pos: &Position| {
+            (0..width as i32).contains(&pos.x) && (0..height as i32).contains(&pos.y)
+        };
+        let hazard_walls = game.has_hazard_walls();
+        for pos in game.board.hazards.iter().filter(|p| on_board(p)) {
+            let idx = CellIndex::<T>::new(*pos, width).0.as_usize();
+            // a hazard listed more than once is stacked
+            let hazards = cells[idx].get_hazard_count().saturating_add(1);
+            cells[idx].set_hazard_count(hazards);
+            if hazard_walls {
+                cells[idx].set_wall();
+            }
+        }
+        for pos in game.board.food.iter().filter(|p| on_board(p)) {
+            cells[CellIndex::<T>::new(*pos, width).0.as_usize()].set_food();
+        }
+        for cell in game.board.healing_cells.iter() {
+            if on_board(&cell.position) {
+                cells[CellIndex::<T>::new(cell.position, width).0.as_usize()].set_healing();
             }
         }
 
         let dimensions = D::from_dimensions(width, height);
+        let (food_count, hazard_count) = count_food_and_hazards(&cells);
+        let (min_food, food_spawn_chance) = game.food_spawn_settings();
 
         Ok(CellBoard {
             cells,
+            food_count,
+            hazard_count,
+            healing_regen,
+            solo: game.is_solo(),
+            min_food: min_food.min(u8::MAX as usize) as u8,
+            food_spawn_chance: (food_spawn_chance * 100.0).round() as u8,
             heads,
             healths,
             lengths,
+            squads,
             dimensions,
-            hazard_damage: game
-                .game
-                .ruleset
-                .settings
-                .as_ref()
-                .map(|s| s.hazard_damage_per_turn)
-                .unwrap_or(15) as u8,
+            turn: game.turn.max(0) as u32,
+            #[cfg(feature = "cell-metadata")]
+            metadata: [0; BOARD_SIZE],
+            #[cfg(feature = "snail-mode")]
+            snail_mode: game.is_snail_mode_map(),
+            hazard_damage: game.get_hazard_damage(),
+            hazard_healing: game
+                .hazard_damage_per_turn()
+                .clamp(-(u8::MAX as i32), 0)
+                .unsigned_abs() as u8,
         })
     }
     fn get_cell(&self, cell_index: CellIndex<T>) -> Cell<T> {
@@ -356,10 +600,52 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             || position.y >= self.get_actual_height() as i32
     }
 
+    /// whether the given id is in range for this board and belongs to a living snake
+    pub fn is_live_snake(&self, sid: SnakeId) -> bool {
+        self.healths.get(sid.as_usize()).is_some_and(|h| *h != 0)
+    }
+
+    /// whether the snakes on this board are playing in squads
+    pub fn has_squads(&self) -> bool {
+        self.squads.iter().any(|squad| *squad != 0)
+    }
+
+    /// whether two snakes are different members of the same squad
+    pub fn are_squadmates(&self, a: SnakeId, b: SnakeId) -> bool {
+        a != b
+            && self.squads[a.as_usize()] != 0
+            && self.squads[a.as_usize()] == self.squads[b.as_usize()]
+    }
+
     /// Get the length for a given snake
     pub fn get_length(&self, snake_id: SnakeId) -> u16 {
         self.lengths[snake_id.0 as usize]
     }
+
+    /// the number of cells with food on them, without scanning the board
+    pub fn food_count(&self) -> usize {
+        self.food_count as usize
+    }
+
+    /// the number of hazard cells, without scanning the board
+    pub fn hazard_count(&self) -> usize {
+        self.hazard_count as usize
+    }
+
+    /// the number of cells a snake's body covers, which is less than its length while segments
+    /// are stacked on its tail. 0 for an eliminated snake
+    pub fn body_cell_count(&self, snake_id: SnakeId) -> u16 {
+        if !self.is_live_snake(snake_id) {
+            return 0;
+        }
+        let head = self.heads[snake_id.as_usize()];
+        let stacked = match self.get_cell(head).get_tail_position(head) {
+            Some(tail) if self.get_cell(tail).is_triple_stacked_piece() => 2,
+            Some(tail) if self.get_cell(tail).is_double_stacked_piece() => 1,
+            _ => 0,
+        };
+        self.lengths[snake_id.as_usize()] - stacked
+    }
     /// Mutibaly call remove on the specified cell
     pub fn cell_remove(&mut self, cell_index: CellIndex<T>) {
         let mut old_cell = self.get_cell(cell_index);
@@ -412,6 +698,11 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.get_cell(cell_idx).is_hazard()
     }
 
+    /// determines if this cell is a wall, which nothing can move on to
+    pub fn cell_is_wall(&self, cell_idx: CellIndex<T>) -> bool {
+        self.get_cell(cell_idx).is_wall()
+    }
+
     /// determines if this cell is a snake head (including triple stacked)
     pub fn cell_is_snake_head(&self, cell_idx: CellIndex<T>) -> bool {
         self.get_cell(cell_idx).is_head()
@@ -427,6 +718,44 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.get_cell(cell_idx).is_body()
     }
 
+    /// this board with hazards dealing `hazard_damage` instead, which also stops them healing
+    pub fn with_hazard_damage(mut self, hazard_damage: u8) -> Self {
+        self.hazard_damage = hazard_damage;
+        self.hazard_healing = 0;
+        self
+    }
+
+    /// this board with healing cells restoring `healing_regen` instead
+    pub fn with_healing_regen(mut self, healing_regen: u8) -> Self {
+        self.healing_regen = healing_regen;
+        self
+    }
+
+    /// this board on `turn` instead
+    pub fn with_turn(mut self, turn: u32) -> Self {
+        self.turn = turn;
+        self
+    }
+
+    /// the least food on the board and the percent chance of spawning more, see
+    /// [StandardFoodPlaceableGame::place_food]
+    pub fn food_spawn_settings(&self) -> (u8, u8) {
+        (self.min_food, self.food_spawn_chance)
+    }
+
+    /// Changes the settings [StandardFoodPlaceableGame::place_food] uses, which come from the
+    /// game's ruleset when converting. Chances above 100 percent are treated as 100
+    pub fn set_food_spawn_settings(&mut self, min_food: u8, food_spawn_chance: u8) {
+        self.min_food = min_food;
+        self.food_spawn_chance = food_spawn_chance.min(100);
+    }
+
+    /// this board with a snake's health changed, see [HealthSettableGame::set_health]
+    pub fn with_health(mut self, snake_id: SnakeId, health: u8) -> Self {
+        self.set_health(&snake_id, health);
+        self
+    }
+
     pub fn cell_is_single_tail(&self, cell_idx: CellIndex<T>) -> bool {
         let cell = self.get_cell(cell_idx);
         if !cell.is_snake_body_piece()
@@ -444,20 +773,19 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             false
         }
     }
-
-    /// determin the width of the CellBoard
-    pub fn width() -> u8 {
-        (BOARD_SIZE as f32).sqrt() as u8
-    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> EmptyCellGettableGame
     for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_> {
+    fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
+        // cells past the actual width and height only pad the array out to BOARD_SIZE, e.g. for a
+        // 7x7 game on an 11x11 board, and are never part of the game
+        let cell_count = self.get_actual_width() as usize * self.get_actual_height() as usize;
         Box::new(
             self.cells
                 .iter()
+                .take(cell_count)
                 .enumerate()
                 .filter(|(_, cell)| cell.is_empty())
                 .map(|(idx, _)| CellIndex::from_usize(idx)),
@@ -469,16 +797,11 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     StandardFoodPlaceableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn place_food(&mut self, rng: &mut impl rand::Rng) {
-        // TODO: Get these constants from the game
-        let min_food = 1;
-        let food_spawn_chance = 0.15;
-
-        // This is an optimization when min_food is 1. We know we don't need to spawn food if there if any of the board
-        // so we can short circuit on the first food we find
-        let food_to_add = if !self.cells.iter().any(|c| c.is_food()) {
-            min_food
+        let min_food = self.min_food as u32;
+        let food_to_add = if self.food_count < min_food {
+            (min_food - self.food_count) as usize
         } else {
-            usize::from(rng.gen_bool(food_spawn_chance))
+            usize::from(rng.gen_bool(self.food_spawn_chance as f64 / 100.0))
         };
 
         if food_to_add == 0 {
@@ -489,6 +812,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         let random = empty.choose_multiple(rng, food_to_add);
         for pos in random {
             self.cells[pos.0.as_usize()].set_food();
+            self.food_count += 1;
         }
     }
 }
@@ -0,0 +1,100 @@
+use std::fmt::Display;
+
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    wire_representation::Position,
+};
+
+use super::{CellBoard, CellIndex};
+
+/// the characters used for the overlay, from the smallest value to the largest
+const HEAT: &[u8] = b".:-=+*#%@";
+
+/// A board drawn with a value for each cell, e.g. flood fill or visit counts from a search, see
+/// `display_overlay` on the standard and wrapped boards. Snake heads are drawn as their id and
+/// bodies as `s`, every other cell gets one of `.:-=+*#%@` scaled between the smallest and
+/// largest value on the board, or is left blank if it has no value. The scale is written after
+/// the board
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayDisplay<
+    'a,
+    T: CellNum,
+    D: Dimensions,
+    V,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+> {
+    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    values: &'a [V],
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// draws `values`, indexed by [CellIndex], over this board
+    pub fn display_overlay<'a, V>(
+        &'a self,
+        values: &'a [V],
+    ) -> OverlayDisplay<'a, T, D, V, BOARD_SIZE, MAX_SNAKES> {
+        OverlayDisplay {
+            board: self,
+            values,
+        }
+    }
+}
+
+impl<'a, T: CellNum, D: Dimensions, V, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
+    for OverlayDisplay<'a, T, D, V, BOARD_SIZE, MAX_SNAKES>
+where
+    V: Copy + Into<f64>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let board = self.board;
+        let width = board.get_actual_width();
+        let height = board.get_actual_height();
+        let cell_count = width as usize * height as usize;
+        let value_at = |i: usize| self.values.get(i).map(|v| (*v).into());
+
+        let (min, max) = (0..cell_count)
+            .filter_map(value_at)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+
+        writeln!(f)?;
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let cell_idx = CellIndex::new(Position::new(x as i32, y as i32), width);
+                if board.cell_is_body(cell_idx) || board.cell_is_snake_head(cell_idx) {
+                    board.fmt_cell(f, cell_idx)?;
+                } else {
+                    match value_at(cell_idx.0.as_usize()) {
+                        Some(v) => {
+                            let scaled = if max > min {
+                                (v - min) / (max - min)
+                            } else {
+                                0.0
+                            };
+                            let heat = (scaled * (HEAT.len() - 1) as f64).round() as usize;
+                            write!(f, "{}", HEAT[heat] as char)?;
+                        }
+                        None => write!(f, " ")?,
+                    }
+                }
+                write!(f, " ")?;
+            }
+            writeln!(f)?;
+        }
+        if min <= max {
+            writeln!(
+                f,
+                "{} = {}, {} = {}",
+                HEAT[0] as char,
+                min,
+                HEAT[HEAT.len() - 1] as char,
+                max
+            )?;
+        }
+        Ok(())
+    }
+}
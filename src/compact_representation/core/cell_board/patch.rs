@@ -0,0 +1,114 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{Move, SnakeId, N_MOVES},
+};
+
+use super::{eval::SinglePlayerMoveResult, Cell, CellBoard, CellIndex, EvaluateMode};
+
+/// The cells and snake state a joint move changed on a board, so that it can be undone with
+/// [undo](crate::compact_representation::standard::CellBoard::undo). Only the moving snakes'
+/// bodies and the cells their heads moved on to are saved, which is far smaller than a copy of a
+/// large board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardPatch<T: CellNum, const MAX_SNAKES: usize> {
+    cells: Vec<(CellIndex<T>, Cell<T>)>,
+    food_count: u32,
+    healths: [u8; MAX_SNAKES],
+    heads: [CellIndex<T>; MAX_SNAKES],
+    lengths: [u16; MAX_SNAKES],
+    /// in snail mode every hazard can change, so every hazard cell is saved along with the count
+    #[cfg(feature = "snail-mode")]
+    hazard_count: Option<u32>,
+}
+
+impl<T: CellNum, const MAX_SNAKES: usize> BoardPatch<T, MAX_SNAKES> {
+    /// the number of cells that will be restored when this is undone
+    pub fn touched_cells(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Applies one joint move to this board in place, returning what's needed to undo it. The
+    /// board ends up the same as the child [Self::evaluate_moves_with_state] would return. Unlike
+    /// simulating, moves that kill a snake are applied rather than pruned
+    pub fn apply_moves(
+        &mut self,
+        moves: &[(SnakeId, Move)],
+        mode: EvaluateMode,
+    ) -> BoardPatch<T, MAX_SNAKES> {
+        let mut single_moves = [(SnakeId(0), [Move::Up]); MAX_SNAKES];
+        for (slot, (id, m)) in single_moves.iter_mut().zip(moves.iter()) {
+            *slot = (*id, [*m]);
+        }
+        let new_heads = self.generate_state(single_moves[..moves.len()].iter(), mode);
+        self.apply_moves_with_state(moves, &new_heads)
+    }
+
+    /// [Self::apply_moves] for states that have already been generated
+    pub fn apply_moves_with_state(
+        &mut self,
+        moves: &[(SnakeId, Move)],
+        new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
+    ) -> BoardPatch<T, MAX_SNAKES> {
+        // any moving snake might die, so its whole body is saved. Nothing else is written to
+        // apart from the cells the heads move on to
+        let mut cells = vec![];
+        for (id, m) in moves.iter() {
+            if self.healths[id.as_usize()] > 0 {
+                let head = self.heads[id.as_usize()];
+                let mut current_index = self.get_cell(head).get_tail_position(head);
+                while let Some(i) = current_index {
+                    let cell = self.get_cell(i);
+                    cells.push((i, cell));
+                    current_index = cell.get_next_index();
+                }
+            }
+            if let SinglePlayerMoveResult::Alive(result) = new_heads[id.as_usize()][m.as_index()] {
+                cells.push((result.new_head, self.get_cell(result.new_head)));
+            }
+        }
+
+        #[cfg(feature = "snail-mode")]
+        let hazard_count = self.snail_mode.then(|| {
+            for (i, cell) in self.cells.iter().enumerate() {
+                if cell.is_hazard() {
+                    cells.push((CellIndex::from_usize(i), *cell));
+                }
+            }
+            self.hazard_count
+        });
+
+        let patch = BoardPatch {
+            cells,
+            food_count: self.food_count,
+            healths: self.healths,
+            heads: self.heads,
+            lengths: self.lengths,
+            #[cfg(feature = "snail-mode")]
+            hazard_count,
+        };
+        self.evaluate_moves_in_place(moves, new_heads);
+
+        patch
+    }
+
+    /// puts the board back the way it was before the [Self::apply_moves] call that returned
+    /// `patch`. Patches have to be undone in the reverse order they were applied
+    pub fn undo_patch(&mut self, patch: BoardPatch<T, MAX_SNAKES>) {
+        for (i, cell) in patch.cells.into_iter().rev() {
+            self.cells[i.0.as_usize()] = cell;
+        }
+        self.food_count = patch.food_count;
+        self.healths = patch.healths;
+        self.heads = patch.heads;
+        self.lengths = patch.lengths;
+        self.turn -= 1;
+        #[cfg(feature = "snail-mode")]
+        if let Some(hazard_count) = patch.hazard_count {
+            self.hazard_count = hazard_count;
+        }
+    }
+}
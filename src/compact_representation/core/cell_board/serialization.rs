@@ -0,0 +1,200 @@
+use std::error::Error;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::compact_representation::{core::dimensions::Dimensions, CellNum};
+
+use super::{
+    count_food_and_hazards, Cell, CellBoard, CellIndex, DEFAULT_FOOD_SPAWN_CHANCE, DEFAULT_MIN_FOOD,
+};
+
+/// The layout a [CellBoard] is serialized with: flat lists of plain integers, so it suits binary
+/// formats like bincode as well as JSON. Every field is always written, whichever features are on,
+/// so boards move between builds with different features. Lists may be shorter than the board
+/// type's arrays, the rest is filled in empty
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "CellBoard")]
+pub(super) struct SerializedCellBoard {
+    pub(super) width: u8,
+    pub(super) height: u8,
+    pub(super) hazard_damage: u8,
+    pub(super) healing_regen: u8,
+    pub(super) solo: bool,
+    /// missing from boards serialized before food settings were stored
+    #[serde(default = "default_min_food")]
+    pub(super) min_food: u8,
+    #[serde(default = "default_food_spawn_chance")]
+    pub(super) food_spawn_chance: u8,
+    /// each cell packed in to a u32, see `Cell::pack_as_u32`
+    pub(super) cells: Vec<u32>,
+    pub(super) healths: Vec<u8>,
+    pub(super) lengths: Vec<u16>,
+    pub(super) heads: Vec<u32>,
+    pub(super) squads: Vec<u8>,
+    /// empty unless some cell has metadata
+    pub(super) metadata: Vec<u8>,
+    /// how many hazards are on each cell, empty unless some hazard is stacked
+    pub(super) hazard_stacks: Vec<u8>,
+    pub(super) snail_mode: bool,
+    /// missing from boards serialized before the turn was stored
+    #[serde(default)]
+    pub(super) turn: u32,
+    /// missing from boards serialized before negative hazard damage was stored
+    #[serde(default)]
+    pub(super) hazard_healing: u8,
+}
+
+fn default_min_food() -> u8 {
+    DEFAULT_MIN_FOOD
+}
+
+fn default_food_spawn_chance() -> u8 {
+    DEFAULT_FOOD_SPAWN_CHANCE
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    pub(super) fn as_serialized(&self) -> SerializedCellBoard {
+        #[cfg(feature = "cell-metadata")]
+        let metadata = if self.metadata.iter().any(|m| *m != 0) {
+            self.metadata.to_vec()
+        } else {
+            vec![]
+        };
+        #[cfg(not(feature = "cell-metadata"))]
+        let metadata = vec![];
+        let hazard_stacks = if self.cells.iter().any(|c| c.get_hazard_count() > 1) {
+            self.cells.iter().map(Cell::get_hazard_count).collect()
+        } else {
+            vec![]
+        };
+        #[cfg(feature = "snail-mode")]
+        let snail_mode = self.snail_mode;
+        #[cfg(not(feature = "snail-mode"))]
+        let snail_mode = false;
+
+        SerializedCellBoard {
+            width: self.get_actual_width(),
+            height: self.get_actual_height(),
+            hazard_damage: self.hazard_damage,
+            hazard_healing: self.hazard_healing,
+            healing_regen: self.healing_regen,
+            solo: self.solo,
+            min_food: self.min_food,
+            food_spawn_chance: self.food_spawn_chance,
+            cells: self.cells.iter().map(Cell::pack_as_u32).collect(),
+            healths: self.healths.to_vec(),
+            lengths: self.lengths.to_vec(),
+            heads: self.heads.iter().map(|h| h.as_usize() as u32).collect(),
+            squads: self.squads.to_vec(),
+            metadata,
+            hazard_stacks,
+            snail_mode,
+            turn: self.turn,
+        }
+    }
+
+    /// Builds a board back up from its serialized layout. Only checks that everything fits in
+    /// this board type, not that the snakes are consistent, so that broken boards can still be
+    /// loaded to debug them
+    pub(super) fn from_serialized(board: SerializedCellBoard) -> Result<Self, Box<dyn Error>> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::PARAMETERS_CHECK;
+        let too_many = |what: &str| format!("board has too many {} for its type", what);
+        if board.width as usize * board.height as usize > BOARD_SIZE {
+            return Err(too_many("cells").into());
+        }
+        if board.cells.len() > BOARD_SIZE {
+            return Err(too_many("cells").into());
+        }
+        if board.metadata.len() > BOARD_SIZE {
+            return Err(too_many("metadata").into());
+        }
+        if board.hazard_stacks.len() > BOARD_SIZE {
+            return Err(too_many("hazard stacks").into());
+        }
+        for (what, len) in [
+            ("healths", board.healths.len()),
+            ("lengths", board.lengths.len()),
+            ("heads", board.heads.len()),
+            ("squads", board.squads.len()),
+        ] {
+            if len > MAX_SNAKES {
+                return Err(too_many(what).into());
+            }
+        }
+        if let Some(head) = board.heads.iter().find(|h| **h as usize >= BOARD_SIZE) {
+            return Err(format!("head {} is off the board", head).into());
+        }
+
+        let mut cells = [Cell::<T>::empty(); BOARD_SIZE];
+        for (cell, packed) in cells.iter_mut().zip(board.cells) {
+            *cell = Cell::<T>::from_u32(packed);
+        }
+        let mut healths = [0; MAX_SNAKES];
+        healths[..board.healths.len()].copy_from_slice(&board.healths);
+        let mut lengths = [0; MAX_SNAKES];
+        lengths[..board.lengths.len()].copy_from_slice(&board.lengths);
+        let mut heads = [CellIndex::<T>::from_usize(0); MAX_SNAKES];
+        for (head, packed) in heads.iter_mut().zip(board.heads) {
+            *head = CellIndex::<T>::from_u32(packed);
+        }
+        let mut squads = [0; MAX_SNAKES];
+        squads[..board.squads.len()].copy_from_slice(&board.squads);
+
+        #[cfg(feature = "cell-metadata")]
+        let mut metadata = [0; BOARD_SIZE];
+        #[cfg(feature = "cell-metadata")]
+        metadata[..board.metadata.len()].copy_from_slice(&board.metadata);
+
+        // the packed cells only know whether they're a hazard, so they need the stacks put back.
+        // A hazard with no stack listed is a single hazard
+        for (cell, stack) in cells.iter_mut().zip(board.hazard_stacks) {
+            if stack > 0 {
+                cell.set_hazard_count(stack);
+            }
+        }
+
+        let (food_count, hazard_count) = count_food_and_hazards(&cells);
+
+        Ok(CellBoard {
+            hazard_damage: board.hazard_damage,
+            hazard_healing: board.hazard_healing,
+            healing_regen: board.healing_regen,
+            solo: board.solo,
+            min_food: board.min_food,
+            food_spawn_chance: board.food_spawn_chance.min(100),
+            cells,
+            food_count,
+            hazard_count,
+            healths,
+            heads,
+            lengths,
+            squads,
+            dimensions: D::from_dimensions(board.width, board.height),
+            turn: board.turn,
+            #[cfg(feature = "cell-metadata")]
+            metadata,
+            #[cfg(feature = "snail-mode")]
+            snail_mode: board.snail_mode,
+        })
+    }
+}
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Serialize
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_serialized().serialize(serializer)
+    }
+}
+
+impl<'de, T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    Deserialize<'de> for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let board = SerializedCellBoard::deserialize(deserializer)?;
+        Self::from_serialized(board).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::{HazardQueryableGame, HazardSettableGame, Move, SnakeId},
+};
+
+use super::{CellBoard, CellIndex};
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// whether simulating applies the snail mode map's hazard trails
+    pub fn snail_mode(&self) -> bool {
+        self.snail_mode
+    }
+
+    /// In snail mode, at the end of every turn each stack of hazards loses one hazard, then every
+    /// snake that moved leaves a stack of as many hazards as it was long on the cell its tail
+    /// left. A snake whose tail is stacked, because it just ate or spawned, leaves nothing.
+    /// Converting a game on the `snail_mode` map turns this on
+    pub fn set_snail_mode(&mut self, snail_mode: bool) {
+        self.snail_mode = snail_mode;
+    }
+
+    /// where each moving snake leaves a trail this turn and how high it's stacked, worked out
+    /// before any of the snakes move
+    pub(super) fn snail_trails(
+        &self,
+        moves: &[(SnakeId, Move)],
+    ) -> [Option<(CellIndex<T>, u8)>; MAX_SNAKES] {
+        let mut trails = [None; MAX_SNAKES];
+        if !self.snail_mode {
+            return trails;
+        }
+        for (trail, (id, _)) in trails.iter_mut().zip(moves.iter()) {
+            if self.healths[id.as_usize()] == 0 {
+                continue;
+            }
+            let head = self.heads[id.as_usize()];
+            let tail = self
+                .get_cell(head)
+                .get_tail_position(head)
+                .expect("an alive snake has a tail");
+            if !self.get_cell(tail).is_stacked() {
+                let length = self.lengths[id.as_usize()].min(u8::MAX as u16) as u8;
+                *trail = Some((tail, length));
+            }
+        }
+        trails
+    }
+
+    /// decays every stack of hazards and then lays the trails from [Self::snail_trails]
+    pub(super) fn lay_snail_trails(&mut self, trails: &[Option<(CellIndex<T>, u8)>]) {
+        if !self.snail_mode {
+            return;
+        }
+        for i in 0..BOARD_SIZE {
+            let idx = CellIndex::from_usize(i);
+            let stack = self.get_hazard_count(&idx);
+            if stack > 0 {
+                self.set_hazard_count(idx, stack - 1);
+            }
+        }
+        for (tail, length) in trails.iter().flatten() {
+            let stack = self.get_hazard_count(tail).saturating_add(*length);
+            self.set_hazard_count(*tail, stack);
+        }
+    }
+}
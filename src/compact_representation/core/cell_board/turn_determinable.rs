@@ -0,0 +1,14 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::TurnDeterminableGame,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    TurnDeterminableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn turn(&self) -> u64 {
+        self.turn as u64
+    }
+}
@@ -11,10 +11,34 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     VictorDeterminableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn is_over(&self) -> bool {
+        if self.solo {
+            return self.healths[0] == 0;
+        }
+        if self.has_squads() {
+            // a snake outside of any squad plays as a squad of one
+            return self
+                .healths
+                .iter()
+                .enumerate()
+                .filter(|(_, health)| **health != 0)
+                .map(|(id, _)| match self.squads[id] {
+                    0 => (false, id as u8),
+                    squad => (true, squad),
+                })
+                .unique()
+                .count()
+                <= 1;
+        }
         self.healths[0] == 0 || self.healths.iter().filter(|h| **h != 0).count() <= 1
     }
 
     fn get_winner(&self) -> Option<Self::SnakeIDType> {
+        if self.solo {
+            return None;
+        }
+        if !self.has_squads() && self.alive_snake_count() > 1 {
+            return None;
+        }
         if self.is_over() {
             let winning_ids = self
                 .healths
@@ -37,6 +61,10 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         None
     }
 
+    fn is_solo(&self) -> bool {
+        self.solo
+    }
+
     fn alive_snake_count(&self) -> usize {
         self.healths.iter().filter(|h| **h != 0).count()
     }
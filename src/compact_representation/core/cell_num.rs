@@ -1,9 +1,23 @@
 use std::fmt::Display;
 
-/// Wrapper type for numbers to allow for shrinking board sizes
+/// Wrapper type for numbers to allow for shrinking board sizes. Cell numbers must be `Send + Sync`
+/// so that boards can be shared between search threads
 pub trait CellNum:
-    std::fmt::Debug + Copy + Clone + PartialEq + Eq + std::hash::Hash + Ord + Display + 'static
+    std::fmt::Debug
+    + Copy
+    + Clone
+    + PartialEq
+    + Eq
+    + std::hash::Hash
+    + Ord
+    + Display
+    + Send
+    + Sync
+    + 'static
 {
+    /// the most cells a board indexed by this type can have
+    const MAX_CELLS: usize;
+
     /// converts this cellnum to a usize
     fn as_usize(&self) -> usize;
     /// makes a cellnum from an i32
@@ -13,6 +27,8 @@ pub trait CellNum:
 }
 
 impl CellNum for u8 {
+    const MAX_CELLS: usize = u8::MAX as usize + 1;
+
     fn as_usize(&self) -> usize {
         *self as usize
     }
@@ -26,6 +42,8 @@ impl CellNum for u8 {
     }
 }
 impl CellNum for u16 {
+    const MAX_CELLS: usize = u16::MAX as usize + 1;
+
     fn as_usize(&self) -> usize {
         *self as usize
     }
@@ -14,6 +14,26 @@ macro_rules! impl_common_board_traits {
             fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
                 self.embedded.get_length_i64(snake_id)
             }
+
+            fn try_get_length(&self, snake_id: &Self::SnakeIDType) -> Option<Self::LengthType> {
+                self.embedded.try_get_length(snake_id)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            GrowthPredictableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn will_grow_next_turn(&self, snake_id: &Self::SnakeIDType) -> bool {
+                self.embedded.will_grow_next_turn(snake_id)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            GrowthMarkableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn mark_will_grow(&mut self, snake_id: &Self::SnakeIDType) {
+                self.embedded.mark_will_grow(snake_id)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -31,6 +51,14 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            HealthSettableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn set_health(&mut self, snake_id: &Self::SnakeIDType, health: Self::HealthType) {
+                self.embedded.set_health(snake_id, health)
+            }
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             VictorDeterminableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -42,6 +70,10 @@ macro_rules! impl_common_board_traits {
                 self.embedded.get_winner()
             }
 
+            fn is_solo(&self) -> bool {
+                self.embedded.is_solo()
+            }
+
             fn alive_snake_count(&self) -> usize {
                 self.embedded.alive_snake_count()
             }
@@ -87,6 +119,13 @@ macro_rules! impl_common_board_traits {
             ) -> Self::NativePositionType {
                 self.embedded.get_head_as_native_position(snake_id)
             }
+
+            fn try_get_head(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Option<Self::NativePositionType> {
+                self.embedded.try_get_head(snake_id)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -99,6 +138,10 @@ macro_rules! impl_common_board_traits {
             fn clear_hazard(&mut self, pos: Self::NativePositionType) {
                 self.embedded.clear_hazard(pos)
             }
+
+            fn set_hazard_count(&mut self, pos: Self::NativePositionType, count: u8) {
+                self.embedded.set_hazard_count(pos, count)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -111,6 +154,47 @@ macro_rules! impl_common_board_traits {
             fn get_hazard_damage(&self) -> u8 {
                 self.embedded.get_hazard_damage()
             }
+
+            fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+                self.embedded.get_hazard_count(pos)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            WallQueryableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+                self.embedded.is_wall(pos)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            HealingQueryableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn healing_at(&self, pos: &Self::NativePositionType) -> u8 {
+                self.embedded.healing_at(pos)
+            }
+
+            fn healing_cells(&self) -> Vec<Self::NativePositionType> {
+                self.embedded.healing_cells()
+            }
+        }
+
+        #[cfg(feature = "cell-metadata")]
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $crate::types::CellMetadataGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn get_cell_metadata(&self, pos: &Self::NativePositionType) -> u8 {
+                self.embedded.get_cell_metadata(pos)
+            }
+
+            fn set_cell_metadata(&mut self, pos: &Self::NativePositionType, value: u8) {
+                self.embedded.set_cell_metadata(pos, value)
+            }
+
+            fn clear_cell_metadata(&mut self) {
+                self.embedded.clear_cell_metadata()
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -142,9 +226,20 @@ macro_rules! impl_common_board_traits {
             fn get_snake_body_iter<'s>(
                 &'s self,
                 snake_id: &Self::SnakeIDType,
-            ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 's> {
+            ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + 's> {
                 self.embedded.get_snake_body_iter(snake_id)
             }
+
+            fn try_get_snake_body_vec(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Option<Vec<Self::NativePositionType>> {
+                self.embedded.try_get_snake_body_vec(snake_id)
+            }
+
+            fn will_be_vacated_this_turn(&self, pos: &Self::NativePositionType) -> bool {
+                self.embedded.will_be_vacated_this_turn(pos)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -159,6 +254,14 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            TurnDeterminableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn turn(&self) -> u64 {
+                self.embedded.turn()
+            }
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
             for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -210,6 +313,20 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            /// whether the snakes on this board are playing in squads
+            pub fn has_squads(&self) -> bool {
+                self.embedded.has_squads()
+            }
+
+            /// whether two snakes are different members of the same squad
+            pub fn are_squadmates(&self, a: SnakeId, b: SnakeId) -> bool {
+                self.embedded.are_squadmates(a, b)
+            }
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             MaxSnakes<MAX_SNAKES> for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -218,11 +335,112 @@ macro_rules! impl_common_board_traits {
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             EmptyCellGettableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
-            fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_> {
+            fn get_empty_cells(
+                &self,
+            ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
                 self.embedded.get_empty_cells()
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $crate::algorithms::territory::TerritoryEvaluatableGame
+            for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $crate::algorithms::graph::ArticulationCellsGame
+            for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn articulation_cells(&self) -> Vec<Self::NativePositionType> {
+                let cell_count = self.embedded.get_actual_width() as usize
+                    * self.embedded.get_actual_height() as usize;
+                $crate::algorithms::graph::articulation_points(
+                    cell_count,
+                    |i| {
+                        let ci = CellIndex::from_usize(i);
+                        self.embedded.get_snake_id_at(ci).is_none()
+                            && !self.embedded.cell_is_wall(ci)
+                    },
+                    |i| {
+                        self.neighbors(&CellIndex::from_usize(i))
+                            .map(|neighbor| neighbor.as_usize())
+                    },
+                )
+                .map(CellIndex::from_usize)
+                .collect()
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn path_search(
+                &self,
+                from: CellIndex<T>,
+                to: Option<CellIndex<T>>,
+                options: $crate::algorithms::pathing::PathOptions,
+            ) -> (Vec<u32>, Vec<usize>) {
+                let cell_count = self.embedded.get_actual_width() as usize
+                    * self.embedded.get_actual_height() as usize;
+                $crate::algorithms::pathing::indexed_bfs(
+                    cell_count,
+                    from.as_usize(),
+                    to.map(|to| to.as_usize()),
+                    |i| {
+                        let ci = CellIndex::from_usize(i);
+                        (!options.avoid_hazards || !self.embedded.is_hazard(&ci))
+                            && (!options.avoid_snake_bodies
+                                || self.embedded.get_snake_id_at(ci).is_none())
+                    },
+                    |i| {
+                        self.neighbors(&CellIndex::from_usize(i))
+                            .map(|neighbor| neighbor.as_usize())
+                    },
+                )
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $crate::algorithms::pathing::PathfindingGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn shortest_path(
+                &self,
+                from: &Self::NativePositionType,
+                to: &Self::NativePositionType,
+                options: $crate::algorithms::pathing::PathOptions,
+            ) -> Option<Vec<Self::NativePositionType>> {
+                let (distances, came_from) = self.path_search(*from, Some(*to), options);
+                if distances[to.as_usize()] == u32::MAX {
+                    return None;
+                }
+                let mut path = vec![*to];
+                while path.last() != Some(from) {
+                    let previous = came_from[path.last().unwrap().as_usize()];
+                    path.push(CellIndex::from_usize(previous));
+                }
+                path.reverse();
+                Some(path)
+            }
+
+            fn distance_matrix(
+                &self,
+                from: &Self::NativePositionType,
+                options: $crate::algorithms::pathing::PathOptions,
+            ) -> Vec<Option<u32>> {
+                self.path_search(*from, None, options)
+                    .0
+                    .into_iter()
+                    .map(|distance| (distance != u32::MAX).then_some(distance))
+                    .collect()
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> LegalMovesGame
+            for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             StandardFoodPlaceableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -230,5 +448,13 @@ macro_rules! impl_common_board_traits {
                 self.embedded.place_food(rng)
             }
         }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $crate::consistency::ConsistencyVerifiable for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn verify_consistency(&self) -> Result<(), $crate::consistency::InconsistencyReport> {
+                self.embedded.verify_consistency()
+            }
+        }
     };
 }
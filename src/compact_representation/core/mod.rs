@@ -8,9 +8,13 @@ use crate::{
     wire_representation::Position,
 };
 
-pub use cell_board::{CellBoard, EvaluateMode};
+pub use cell_board::{
+    BoardPatch, CellBoard, CellContents, ConversionScratch, EvaluateMode, OverlayDisplay,
+};
 pub use cell_num::CellNum;
-pub use simulate::simulate_with_moves;
+pub use simulate::{simulate_with_moves, simulate_with_moves_sampled, simulate_with_outcomes};
+#[cfg(feature = "bumpalo")]
+pub use simulate::{simulate_with_moves_in, SimulationContext};
 
 /// wrapper type for an index in to the board
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -56,26 +60,69 @@ impl<T: CellNum> CellIndex<T> {
     }
 }
 
+// The low three bits of a cell's flags are its kind
 const SNAKE_HEAD: u8 = 0x06;
 const SNAKE_BODY_PIECE: u8 = 0x01;
 const DOUBLE_STACKED_PIECE: u8 = 0x02;
 const TRIPLE_STACKED_PIECE: u8 = 0x03;
-const FOOD: u8 = 0x04;
+/// the kind older packed boards used for food, before food became a flag that can sit beneath a
+/// snake. Only read when unpacking
+const LEGACY_FOOD: u8 = 0x04;
 const EMPTY: u8 = 0x05;
 const KIND_MASK: u8 = 0x07;
 
-const IS_HAZARD: u8 = 0x10;
+// and the rest are flags that can be set on any kind of cell
+const HAS_FOOD: u8 = 0x08;
+/// only used when packing, a cell's hazards are counted in its `hazards` field
+const PACKED_HAZARD: u8 = 0x10;
+const IS_HEALING: u8 = 0x20;
+/// set on hazards that are really walls, see [crate::types::WallQueryableGame]
+const IS_WALL: u8 = 0x40;
+/// flag bits that no feature uses yet. In memory [PACKED_HAZARD] is free too, but a packed cell
+/// needs it
+#[allow(dead_code)]
+const FREE_FLAGS: u8 = 0x80;
 
 pub const TRIPLE_STACK: usize = 3;
 pub const DOUBLE_STACK: usize = 2;
 
 use super::dimensions;
 
+/// One square of a compact board.
+///
+/// In memory a cell is four fields:
+///
+/// | field     | size       | holds                                                          |
+/// |-----------|------------|----------------------------------------------------------------|
+/// | `flags`   | 1 byte     | the kind of cell in bits 0-2, then food, healing and wall bits |
+/// | `id`      | 1 byte     | the snake in this cell, only meaningful for snake kinds        |
+/// | `hazards` | 1 byte     | how many hazards are stacked here                              |
+/// | `idx`     | `T`        | the next segment towards the head, or the tail for heads       |
+///
+/// so with padding a cell is 4 bytes for `u8` indices and 6 for `u16`. Bits 4 and 7 of `flags`
+/// are free for new features. A feature that needs more than a couple of bits, like the hazard
+/// count, should get its own field rather than squeezing in to `flags`. Cells aren't a u64: in
+/// the `cell_layout` bench 8 byte cells take an 11x11 board from 524 to 1020 bytes, copy about 33%
+/// slower and simulate the late stage fixture about 23% slower than `u8` cells.
+///
+/// Packed cells ([Cell::pack_as_u32]) are what packed hashes and serialized boards store, and
+/// their layout can't change without breaking boards that were already saved:
+///
+/// | bits  | holds                                                                  |
+/// |-------|------------------------------------------------------------------------|
+/// | 0-7   | `flags`, with bit 4 set for hazards                                    |
+/// | 8-15  | `id`                                                                   |
+/// | 16-31 | `idx`                                                                  |
+///
+/// Only bit 7 is free there, anything else has to be stored next to the cells, like the hazard
+/// stacks
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Cell<T: CellNum> {
     flags: u8,
     id: SnakeId,
     idx: CellIndex<T>,
+    /// how many hazards are stacked on this cell, 0 if it isn't a hazard
+    hazards: u8,
 }
 
 impl<T: CellNum> Cell<T> {
@@ -91,10 +138,15 @@ impl<T: CellNum> Cell<T> {
         }
     }
 
+    /// packs everything but how many hazards are stacked, which boards store separately. A
+    /// hazard of any height packs as one hazard
     pub fn pack_as_u32(&self) -> u32 {
         let mut value: u32 = 0;
         // flags is a byte
         value |= self.flags as u32;
+        if self.is_hazard() {
+            value |= PACKED_HAZARD as u32;
+        }
         // ids are actually a u8
         value |= ((self.id.as_usize() as u32) & 0xff) << 8;
         // idx is at most a u16
@@ -103,14 +155,25 @@ impl<T: CellNum> Cell<T> {
     }
 
     pub fn from_u32(value: u32) -> Self {
-        let flags = (value & 0xff) as u8;
+        let mut flags = (value & 0xff) as u8;
+        if flags & KIND_MASK == LEGACY_FOOD {
+            flags = (flags & !KIND_MASK) | EMPTY | HAS_FOOD;
+        }
+        let hazards = (flags & PACKED_HAZARD != 0) as u8;
+        let flags = flags & !PACKED_HAZARD;
         let id = SnakeId(((value >> 8) & 0xff) as u8);
         let idx = CellIndex::from_u32((value >> 16) & 0xffff);
-        Self { flags, id, idx }
+        Self {
+            flags,
+            id,
+            idx,
+            hazards,
+        }
     }
 
+    /// no snake, no food and no wall, other hazards may still be present
     pub fn is_empty(&self) -> bool {
-        self.flags & KIND_MASK == EMPTY
+        self.flags & KIND_MASK == EMPTY && self.flags & (HAS_FOOD | IS_WALL) == 0
     }
 
     pub fn get_next_index(&self) -> Option<CellIndex<T>> {
@@ -121,20 +184,52 @@ impl<T: CellNum> Cell<T> {
         }
     }
 
+    /// whether there is food in this cell, which may also be occupied by a snake
     pub fn is_food(&self) -> bool {
-        self.flags & KIND_MASK == FOOD
+        self.flags & HAS_FOOD != 0
     }
 
+    /// makes this cell a hazard, leaving it as it is if it already is one
     pub fn set_hazard(&mut self) {
-        self.flags |= IS_HAZARD
+        self.hazards = self.hazards.max(1)
     }
 
     pub fn clear_hazard(&mut self) {
-        self.flags &= !IS_HAZARD
+        self.set_hazard_count(0)
     }
 
     pub fn is_hazard(&self) -> bool {
-        self.flags & IS_HAZARD != 0
+        self.hazards > 0
+    }
+
+    pub fn get_hazard_count(&self) -> u8 {
+        self.hazards
+    }
+
+    /// stacks `hazards` hazards on this cell, a count of 0 clears it of hazards and walls
+    pub fn set_hazard_count(&mut self, hazards: u8) {
+        self.hazards = hazards;
+        if hazards == 0 {
+            self.flags &= !IS_WALL;
+        }
+    }
+
+    /// makes this cell a wall, which is also a hazard
+    pub fn set_wall(&mut self) {
+        self.set_hazard();
+        self.flags |= IS_WALL
+    }
+
+    pub fn is_wall(&self) -> bool {
+        self.flags & IS_WALL != 0
+    }
+
+    pub fn set_healing(&mut self) {
+        self.flags |= IS_HEALING
+    }
+
+    pub fn is_healing(&self) -> bool {
+        self.flags & IS_HEALING != 0
     }
 
     pub fn is_body_segment(&self) -> bool {
@@ -147,7 +242,7 @@ impl<T: CellNum> Cell<T> {
         self.flags & KIND_MASK == SNAKE_HEAD || self.is_triple_stacked_piece()
     }
 
-    /// resets a cell to empty preserving the cell's hazard status
+    /// resets a cell to empty preserving the cell's hazard status and any food beneath the snake
     pub fn remove(&mut self) {
         let reset_to_empty = (self.flags & !KIND_MASK) | EMPTY;
         self.flags = reset_to_empty;
@@ -164,6 +259,7 @@ impl<T: CellNum> Cell<T> {
             flags: EMPTY,
             id: SnakeId(0),
             idx: CellIndex(T::from_i32(0)),
+            hazards: 0,
         }
     }
 
@@ -172,6 +268,7 @@ impl<T: CellNum> Cell<T> {
             flags: SNAKE_HEAD,
             id: sid,
             idx: tail_index,
+            hazards: 0,
         }
     }
 
@@ -180,6 +277,7 @@ impl<T: CellNum> Cell<T> {
             flags: SNAKE_BODY_PIECE,
             id: sid,
             idx: next_index,
+            hazards: 0,
         }
     }
 
@@ -188,6 +286,7 @@ impl<T: CellNum> Cell<T> {
             flags: DOUBLE_STACKED_PIECE,
             id: sid,
             idx: next_index,
+            hazards: 0,
         }
     }
 
@@ -196,6 +295,7 @@ impl<T: CellNum> Cell<T> {
             flags: TRIPLE_STACKED_PIECE,
             id: sid,
             idx: CellIndex(T::from_i32(0)),
+            hazards: 0,
         }
     }
 
@@ -217,8 +317,13 @@ impl<T: CellNum> Cell<T> {
             || self.is_triple_stacked_piece()
     }
 
+    /// adds food to this cell without disturbing any snake in it
     pub fn set_food(&mut self) {
-        self.flags = (self.flags & !KIND_MASK) | FOOD;
+        self.flags |= HAS_FOOD;
+    }
+
+    pub fn clear_food(&mut self) {
+        self.flags &= !HAS_FOOD;
     }
 
     pub fn set_head(&mut self, sid: SnakeId, tail_index: CellIndex<T>) {
@@ -251,3 +356,42 @@ impl<T: CellNum> Cell<T> {
         self.idx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_flags_dont_overlap() {
+        let kinds = [
+            SNAKE_HEAD,
+            SNAKE_BODY_PIECE,
+            DOUBLE_STACKED_PIECE,
+            TRIPLE_STACKED_PIECE,
+            LEGACY_FOOD,
+            EMPTY,
+        ];
+        for kind in kinds {
+            assert_eq!(kind & !KIND_MASK, 0);
+        }
+        let bits = [
+            KIND_MASK,
+            HAS_FOOD,
+            PACKED_HAZARD,
+            IS_HEALING,
+            IS_WALL,
+            FREE_FLAGS,
+        ];
+        assert_eq!(bits.iter().fold(0, |all, b| all | b), u8::MAX);
+        assert_eq!(bits.iter().map(|b| b.count_ones()).sum::<u32>(), 8);
+
+        // only the free bits are left over once a cell has every flag set
+        let mut c: Cell<u16> = Cell::make_body_piece(SnakeId(7), CellIndex(u16::MAX));
+        c.set_food();
+        c.set_healing();
+        c.set_wall();
+        assert_eq!(c.flags & (FREE_FLAGS | PACKED_HAZARD), 0);
+        assert_eq!(Cell::from_u32(c.pack_as_u32()), c);
+        assert_eq!(c.pack_as_u32() & FREE_FLAGS as u32, 0);
+    }
+}
@@ -1,15 +1,17 @@
-use std::{borrow::Borrow, time::Instant};
+use std::{borrow::Borrow, collections::HashSet, time::Instant};
 
 use itertools::Itertools;
+use rand::Rng;
 use tracing::instrument;
 
-use crate::types::{Action, Move, SimulatorInstruments, SnakeId, N_MOVES};
+use crate::types::{Action, Move, SimulatorInstruments, SnakeId, TurnOutcome, N_MOVES};
 
 use super::{cell_board::EvaluateMode, dimensions::Dimensions, CellBoard, CellNum};
 
 #[instrument(level = "trace", skip_all)]
 pub fn simulate_with_moves<
     'a,
+    B,
     S,
     I: SimulatorInstruments,
     T: CellNum,
@@ -17,12 +19,15 @@ pub fn simulate_with_moves<
     const BOARD_SIZE: usize,
     const MAX_SNAKES: usize,
 >(
-    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    board: B,
     instruments: &I,
     snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
     evaluate_mode: EvaluateMode,
-) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + 'a>
+) -> Box<
+    dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + Send + 'a,
+>
 where
+    B: Borrow<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> + Send + 'a,
     S: Borrow<[Move]>,
 {
     let start = Instant::now();
@@ -37,7 +42,9 @@ where
     // sid major, move minor
     // [ some_reulst_struct, some_dead_struct ]
     // [ some_dead_struct, some_dead_struct ] // snake we didn't simulate
-    let states = board.generate_state(snake_ids_and_moves.iter(), evaluate_mode);
+    let states = board
+        .borrow()
+        .generate_state(snake_ids_and_moves.iter(), evaluate_mode);
     let mut dead_snakes_table = [[false; N_MOVES]; MAX_SNAKES];
 
     for (sid, result_row) in states.iter().enumerate() {
@@ -46,7 +53,93 @@ where
         }
     }
 
-    let ids_and_moves_product = snake_ids_and_moves
+    let ids_and_moves_product = moves_to_simulate(snake_ids_and_moves, &dead_snakes_table)
+        .into_iter()
+        .multi_cartesian_product();
+    let results = ids_and_moves_product.into_iter().map(move |m| {
+        let action = Action::collect_from(m.iter());
+        let board = board.borrow();
+
+        let game = board.evaluate_moves_with_state(&m, &states);
+        if !game.assert_consistency() {
+            panic!(
+                "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
+                m, board, game
+            );
+        }
+        (action, game)
+    });
+    let return_value = Box::new(results);
+    let end = Instant::now();
+    instruments.observe_simulation(end - start);
+    return_value
+}
+
+/// Same as [simulate_with_moves], but each child comes with the [TurnOutcome] of the joint move
+/// that produced it
+#[allow(clippy::type_complexity)]
+#[instrument(level = "trace", skip_all)]
+pub fn simulate_with_outcomes<
+    'a,
+    B,
+    S,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: B,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+) -> Box<
+    dyn Iterator<
+            Item = (
+                Action<MAX_SNAKES>,
+                TurnOutcome<MAX_SNAKES>,
+                CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+            ),
+        > + 'a,
+>
+where
+    B: Borrow<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> + 'a,
+    S: Borrow<[Move]>,
+{
+    let start = Instant::now();
+    let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect_vec();
+
+    let states = board
+        .borrow()
+        .generate_state(snake_ids_and_moves.iter(), evaluate_mode);
+    let mut dead_snakes_table = [[false; N_MOVES]; MAX_SNAKES];
+    for (sid, result_row) in states.iter().enumerate() {
+        for (move_index, move_result) in result_row.iter().enumerate() {
+            dead_snakes_table[sid][move_index] = move_result.is_dead();
+        }
+    }
+
+    let results = moves_to_simulate(snake_ids_and_moves, &dead_snakes_table)
+        .into_iter()
+        .multi_cartesian_product()
+        .map(move |m| {
+            let action = Action::collect_from(m.iter());
+            let board = board.borrow();
+            let game = board.evaluate_moves_with_state(&m, &states);
+            let outcome = board.turn_outcome(&m, &states, &game, evaluate_mode);
+            (action, outcome, game)
+        });
+    instruments.observe_simulation(start.elapsed());
+    Box::new(results)
+}
+
+/// the moves each snake is simulated with, a snake's moves that are certain to kill it are
+/// dropped unless there is nothing else it can do
+fn moves_to_simulate<S: Borrow<[Move]>, const MAX_SNAKES: usize>(
+    snake_ids_and_moves: Vec<(SnakeId, S)>,
+    dead_snakes_table: &[[bool; N_MOVES]; MAX_SNAKES],
+) -> Vec<Vec<(SnakeId, Move)>> {
+    snake_ids_and_moves
         .into_iter()
         .map(|(snake_id, moves)| {
             let first_move = moves.borrow()[0];
@@ -62,11 +155,103 @@ where
                 mvs
             }
         })
-        .multi_cartesian_product();
-    let results = ids_and_moves_product.into_iter().map(move |m| {
+        .collect()
+}
+
+/// Same as [simulate_with_moves], but when there are more than `max_children` joint moves only
+/// `max_children` of them, picked uniformly at random without repeats, are simulated. The
+/// children that are simulated come out in the same order [simulate_with_moves] would produce
+/// them in. This keeps lobbies with many snakes from simulating (and allocating) the tens of
+/// thousands of children of every joint move. When there are no more than `max_children` joint
+/// moves they're all simulated lazily, like [simulate_with_moves] does
+#[allow(clippy::too_many_arguments)]
+#[instrument(level = "trace", skip_all)]
+pub fn simulate_with_moves_sampled<
+    'a,
+    B,
+    S,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: B,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+    max_children: usize,
+    rng: &mut impl Rng,
+) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + 'a>
+where
+    B: Borrow<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> + 'a,
+    S: Borrow<[Move]>,
+{
+    let start = Instant::now();
+    let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect_vec();
+
+    let states = board
+        .borrow()
+        .generate_state(snake_ids_and_moves.iter(), evaluate_mode);
+    let mut dead_snakes_table = [[false; N_MOVES]; MAX_SNAKES];
+    for (sid, result_row) in states.iter().enumerate() {
+        for (move_index, move_result) in result_row.iter().enumerate() {
+            dead_snakes_table[sid][move_index] = move_result.is_dead();
+        }
+    }
+    let per_snake_moves = moves_to_simulate(snake_ids_and_moves, &dead_snakes_table);
+
+    // the joint moves as the index of each snake's move, the last snake's changing fastest
+    let lens = per_snake_moves.iter().map(Vec::len).collect_vec();
+    let total = if lens.is_empty() {
+        Some(0)
+    } else {
+        lens.iter()
+            .try_fold(1usize, |acc, len| acc.checked_mul(*len))
+    };
+    let decode = move |mut index: usize| {
+        let mut picks = vec![0; lens.len()];
+        for (pick, len) in picks.iter_mut().zip(lens.iter()).rev() {
+            *pick = index % len;
+            index /= len;
+        }
+        picks
+    };
+    let picks: Box<dyn Iterator<Item = Vec<usize>> + 'a> = match total {
+        Some(total) if total <= max_children => Box::new((0..total).map(decode)),
+        Some(total) => {
+            let mut indices = rand::seq::index::sample(rng, total, max_children).into_vec();
+            indices.sort_unstable();
+            Box::new(indices.into_iter().map(decode))
+        }
+        None => {
+            // there are more joint moves than a usize can count, so each snake's move is picked
+            // separately. Repeats are all but impossible with this many to choose from
+            let mut sampled = HashSet::new();
+            while sampled.len() < max_children {
+                sampled.insert(
+                    per_snake_moves
+                        .iter()
+                        .map(|mvs| rng.gen_range(0..mvs.len()))
+                        .collect_vec(),
+                );
+            }
+            let mut sampled = sampled.into_iter().collect_vec();
+            sampled.sort_unstable();
+            Box::new(sampled.into_iter())
+        }
+    };
+
+    let results = picks.map(move |picks| {
+        let m = picks
+            .iter()
+            .zip(per_snake_moves.iter())
+            .map(|(pick, mvs)| mvs[*pick])
+            .collect_vec();
         let action = Action::collect_from(m.iter());
+        let board = board.borrow();
 
-        let game = board.evaluate_moves_with_state(m.iter(), &states);
+        let game = board.evaluate_moves_with_state(&m, &states);
         if !game.assert_consistency() {
             panic!(
                 "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
@@ -80,3 +265,131 @@ where
     instruments.observe_simulation(end - start);
     return_value
 }
+
+/// Reusable scratch space for
+/// [CellBoard::simulate_with_moves_in](crate::compact_representation::standard::CellBoard::simulate_with_moves_in).
+/// The per simulation buffers (the moves for each snake and the joint move currently being
+/// evaluated) are bump allocated out of this instead of the global allocator. Call
+/// [SimulationContext::reset] once the iterator from a simulation has been dropped to reuse the
+/// memory.
+#[cfg(feature = "bumpalo")]
+#[derive(Debug, Default)]
+pub struct SimulationContext {
+    bump: bumpalo::Bump,
+}
+
+#[cfg(feature = "bumpalo")]
+impl SimulationContext {
+    /// makes a new, empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// frees everything allocated by previous simulations, keeping the largest chunk around
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// the number of bytes currently allocated by this context
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+}
+
+/// Same as [simulate_with_moves] but takes all of its temporary buffers from `ctx`, so that
+/// evaluating each joint move does not allocate at all. Children are produced in the same order
+/// as [simulate_with_moves].
+#[cfg(feature = "bumpalo")]
+#[instrument(level = "trace", skip_all)]
+pub fn simulate_with_moves_in<
+    'a,
+    B,
+    S,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: B,
+    ctx: &'a SimulationContext,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + 'a>
+where
+    B: Borrow<CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>> + 'a,
+    S: Borrow<[Move]>,
+{
+    use bumpalo::collections::Vec as BumpVec;
+
+    let start = Instant::now();
+    let bump = &ctx.bump;
+    let snake_ids_and_moves = BumpVec::from_iter_in(snake_ids_and_moves, bump);
+
+    let states = board
+        .borrow()
+        .generate_state(snake_ids_and_moves.iter(), evaluate_mode);
+
+    let mut per_snake_moves = BumpVec::with_capacity_in(snake_ids_and_moves.len(), bump);
+    for (snake_id, moves) in snake_ids_and_moves.iter() {
+        let moves = moves.borrow();
+        let mut mvs = BumpVec::with_capacity_in(moves.len(), bump);
+        mvs.extend(
+            moves
+                .iter()
+                .filter(|mv| !states[snake_id.as_usize()][mv.as_index()].is_dead())
+                .map(|mv| (*snake_id, *mv)),
+        );
+        if mvs.is_empty() {
+            mvs.push((*snake_id, moves[0]));
+        }
+        per_snake_moves.push(mvs.into_bump_slice());
+    }
+    let per_snake_moves = per_snake_moves.into_bump_slice();
+
+    // an odometer over the moves of each snake, the last snake changes fastest which matches
+    // the order of `multi_cartesian_product`
+    let indices = bump.alloc_slice_fill_copy(per_snake_moves.len(), 0usize);
+    let current = bump.alloc_slice_fill_copy(per_snake_moves.len(), (SnakeId(0), Move::Up));
+    let mut exhausted = per_snake_moves.is_empty();
+
+    let results = std::iter::from_fn(move || {
+        if exhausted {
+            return None;
+        }
+        for ((slot, index), mvs) in current
+            .iter_mut()
+            .zip(indices.iter())
+            .zip(per_snake_moves.iter())
+        {
+            *slot = mvs[*index];
+        }
+
+        exhausted = true;
+        for (index, mvs) in indices.iter_mut().zip(per_snake_moves.iter()).rev() {
+            *index += 1;
+            if *index < mvs.len() {
+                exhausted = false;
+                break;
+            }
+            *index = 0;
+        }
+
+        let action = Action::collect_from(current.iter());
+        let board = board.borrow();
+
+        let game = board.evaluate_moves_with_state(current, &states);
+        if !game.assert_consistency() {
+            panic!(
+                "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
+                current, board, game
+            );
+        }
+        Some((action, game))
+    });
+    let return_value = Box::new(results);
+    let end = Instant::now();
+    instruments.observe_simulation(end - start);
+    return_value
+}
@@ -7,8 +7,13 @@
 use core::fmt::Debug;
 use std::hash::Hash;
 
-/// Trait that all different Dimensions must implement
-pub trait Dimensions: Debug + Copy + Hash {
+/// Trait that all different Dimensions must implement. Dimensions must be `Send + Sync` so that
+/// boards can be shared between search threads
+pub trait Dimensions: Debug + Copy + Hash + Send + Sync {
+    /// the number of cells on the board, for dimensions known at compile time. Board types check
+    /// this fits in their BOARD_SIZE when they're built
+    const FIXED_CELLS: Option<usize> = None;
+
     /// Convert from a width and a height to this dimension
     fn from_dimensions(width: u8, height: u8) -> Self;
 
@@ -48,6 +53,8 @@ impl Dimensions for Square {
 pub struct Fixed<const W: u8, const H: u8>;
 
 impl<const W: u8, const H: u8> Dimensions for Fixed<W, H> {
+    const FIXED_CELLS: Option<usize> = Some(W as usize * H as usize);
+
     fn width(&self) -> u8 {
         W
     }
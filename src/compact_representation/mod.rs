@@ -1,10 +1,22 @@
 //! A compact board representation that is efficient for simulation
+#[macro_use]
+mod best_cell_board;
+mod any_cell_board;
 mod core;
 pub mod standard;
 pub mod wrapped;
 
+pub use self::any_cell_board::{AnyCellBoard, ToAnyCellBoard};
+pub use self::best_cell_board::BestCellBoardVisitor;
+pub use self::core::BoardPatch;
+pub use self::core::CellContents;
 pub use self::core::CellIndex;
 pub use self::core::CellNum;
+pub use self::core::ConversionScratch;
+pub use self::core::EvaluateMode;
+pub use self::core::OverlayDisplay;
+#[cfg(feature = "bumpalo")]
+pub use self::core::SimulationContext;
 
 use self::dimensions::Square;
 
@@ -23,3 +35,134 @@ pub type WrappedCellBoard<T, D, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
 /// A wrapped mode board, 11x11 with 4 snakes
 pub type WrappedCellBoard4Snakes11x11 = WrappedCellBoard<u8, Square, { 11 * 11 }, 4>;
+
+/// Picks the cell number type for a board with the given number of cells, used by [cell_board]
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct CellNumFor<const FITS_IN_U8: bool>;
+
+#[doc(hidden)]
+pub trait PickCellNum {
+    type CellNum: CellNum;
+}
+
+impl PickCellNum for CellNumFor<true> {
+    type CellNum = u8;
+}
+
+impl PickCellNum for CellNumFor<false> {
+    type CellNum = u16;
+}
+
+/// The board type for a game of exactly the given width, height and number of snakes, with the
+/// smallest cell number type that can index it and [dimensions::Fixed] dimensions. Prefix the
+/// arguments with `wrapped,` for a wrapped board.
+///
+/// ```
+/// use battlesnake_game_types::cell_board;
+///
+/// type Duel = cell_board!(11, 11, 2);
+/// type WrappedDuel = cell_board!(wrapped, 11, 11, 2);
+/// type Wide = cell_board!(25, 15, 8);
+/// ```
+///
+/// Board types are checked when they're built, so hand written aliases whose `BOARD_SIZE` can't
+/// hold their dimensions fail to compile
+///
+/// ```compile_fail
+/// use battlesnake_game_types::compact_representation::{dimensions::Fixed, WrappedCellBoard};
+///
+/// type TooSmall = WrappedCellBoard<u8, Fixed<11, 11>, 100, 4>;
+/// TooSmall::try_from_packed_hash(&Default::default());
+/// ```
+#[macro_export]
+macro_rules! cell_board {
+    (wrapped, $width:expr, $height:expr, $snakes:expr) => {
+        $crate::compact_representation::WrappedCellBoard<
+            <$crate::compact_representation::CellNumFor<
+                { ($width as usize) * ($height as usize) <= <u8 as $crate::compact_representation::CellNum>::MAX_CELLS },
+            > as $crate::compact_representation::PickCellNum>::CellNum,
+            $crate::compact_representation::dimensions::Fixed<{ $width }, { $height }>,
+            { ($width as usize) * ($height as usize) },
+            { $snakes },
+        >
+    };
+    ($width:expr, $height:expr, $snakes:expr) => {
+        $crate::compact_representation::StandardCellBoard<
+            <$crate::compact_representation::CellNumFor<
+                { ($width as usize) * ($height as usize) <= <u8 as $crate::compact_representation::CellNum>::MAX_CELLS },
+            > as $crate::compact_representation::PickCellNum>::CellNum,
+            $crate::compact_representation::dimensions::Fixed<{ $width }, { $height }>,
+            { ($width as usize) * ($height as usize) },
+            { $snakes },
+        >
+    };
+}
+
+/// The size in bytes of the board [crate::cell_board] builds for the given width, height and number of
+/// snakes, standard or wrapped, in a const context e.g. for sizing a transposition table entry.
+/// Compact boards never allocate, so this is all the memory a board takes. The same as calling
+/// `memory_footprint` on the board type.
+///
+/// ```
+/// use battlesnake_game_types::{cell_board, compact_representation::cell_board_footprint};
+///
+/// const DUEL: usize = cell_board_footprint(11, 11, 2);
+/// assert_eq!(DUEL, <cell_board!(11, 11, 2)>::memory_footprint());
+/// assert!(cell_board_footprint(11, 11, 4) < cell_board_footprint(19, 21, 4));
+/// ```
+pub const fn cell_board_footprint(width: u8, height: u8, snakes: usize) -> usize {
+    let cells = width as usize * height as usize;
+    let index: usize = if cells <= <u8 as CellNum>::MAX_CELLS {
+        1
+    } else {
+        2
+    };
+    // flags, snake id and hazard count, then the index of the next segment, padded to its
+    // alignment
+    let cell = (3 + index).div_ceil(index) * index;
+    let metadata = if cfg!(feature = "cell-metadata") {
+        cells
+    } else {
+        0
+    };
+    // the snail mode flag
+    let snail_mode = if cfg!(feature = "snail-mode") { 1 } else { 0 };
+    // hazard damage, healing regen, solo and the two food settings, then the cells, then the food
+    // and hazard counts and the turn, then each snake's health, head, length and squad
+    let bytes = 5 + cells * cell + metadata + snail_mode + 3 * 4 + snakes * (1 + index + 2 + 1);
+    // padded to the alignment of the counts
+    bytes.div_ceil(4) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_board_footprint() {
+        macro_rules! check {
+            ($($width:literal x $height:literal, $snakes:literal);*) => {
+                $(
+                    assert_eq!(
+                        cell_board_footprint($width, $height, $snakes),
+                        std::mem::size_of::<cell_board!($width, $height, $snakes)>()
+                    );
+                    assert_eq!(
+                        cell_board_footprint($width, $height, $snakes),
+                        <cell_board!(wrapped, $width, $height, $snakes)>::memory_footprint()
+                    );
+                )*
+            };
+        }
+        check!(7 x 7, 4; 11 x 11, 2; 11 x 11, 4; 15 x 15, 8; 16 x 16, 4; 19 x 21, 4; 25 x 25, 8;
+            50 x 50, 16);
+
+        let g = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let best = standard::ToBestCellBoard::to_best_cell_board(g).unwrap();
+        assert_eq!(
+            best.memory_footprint(),
+            StandardCellBoard::<u8, dimensions::Fixed<11, 11>, { 11 * 11 }, 4>::memory_footprint()
+        );
+    }
+}
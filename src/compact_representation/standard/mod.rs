@@ -1,17 +1,20 @@
 //! A compact board representation that is efficient for simulation
+/// you almost certainly want to use the `convert_from_game` method to
+/// cast from a json represention to a `CellBoard`
+use crate::algorithms::flood_fill::AreaControlGame;
 use crate::compact_representation::core::CellNum as CN;
 use crate::impl_common_board_traits;
 use crate::types::*;
-/// you almost certainly want to use the `convert_from_game` method to
-/// cast from a json represention to a `CellBoard`
 use crate::types::{NeighborDeterminableGame, SnakeBodyGettableGame};
 use crate::wire_representation::Game;
+use fxhash::FxHasher64;
 use itertools::Itertools;
 use rand::seq::SliceRandom;
 use rand::Rng;
 use std::borrow::Borrow;
 use std::error::Error;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use tracing::instrument;
 
 use crate::{
@@ -21,14 +24,23 @@ use crate::{
 
 use super::core::CellBoard as CCB;
 use super::core::CellIndex;
+use super::core::ConversionScratch;
 use super::core::{simulate_with_moves, EvaluateMode};
 use super::dimensions::{ArcadeMaze, Custom, Dimensions, Fixed, Square};
+use super::BestCellBoardVisitor;
+use super::BoardPatch;
+use super::CellContents;
+use super::OverlayDisplay;
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// Serializes to a flat layout of plain integers that works with binary formats as well as JSON.
+/// The board type isn't written, deserializing in to a type too small for the board is an error
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent, bound = "")]
 pub struct CellBoard<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> {
-    embedded: CCB<T, D, BOARD_SIZE, MAX_SNAKES>,
+    pub(super) embedded: CCB<T, D, BOARD_SIZE, MAX_SNAKES>,
 }
 
 impl_common_board_traits!(CellBoard);
@@ -49,21 +61,75 @@ pub type CellBoard8Snakes25x25 = CellBoard<u16, Custom, { 25 * 25 }, 8>;
 /// Used to represent an absolutely silly game board
 pub type CellBoard16Snakes50x50 = CellBoard<u16, Custom, { 50 * 50 }, 16>;
 
+/// Used to represent a royale free-for-all on the largest UI Selectable board, with up to 32
+/// snakes
+pub type CellBoard32Snakes25x25 = CellBoard<u16, Custom, { 25 * 25 }, 32>;
+
+/// Used to represent a royale free-for-all on an absolutely silly game board
+pub type CellBoard32Snakes50x50 = CellBoard<u16, Custom, { 50 * 50 }, 32>;
+
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     /// Builds a cellboard from a given game, will return an error if the game doesn't match
-    /// the provided BOARD_SIZE or MAX_SNAKES. You are encouraged to use `CellBoard4Snakes11x11`
+    /// the provided BOARD_SIZE or MAX_SNAKES, or if a snake's health doesn't fit in a u8 (see
+    /// `Game::saturate_healths`). You are encouraged to use `CellBoard4Snakes11x11`
     /// for the common game layout
     pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
-        if game.game.ruleset.name == "wrapped" {
+        if game.is_wrapped() {
             return Err("Wrapped games are not supported".into());
         }
-
         let embedded = CCB::convert_from_game(game, snake_ids)?;
         Ok(CellBoard { embedded })
     }
 
+    /// [CellBoard::convert_from_game] reusing the buffers in `scratch`, for converting many games
+    pub fn convert_from_game_with(
+        game: &Game,
+        snake_ids: &SnakeIDMap,
+        scratch: &mut ConversionScratch,
+    ) -> Result<Self, Box<dyn Error>> {
+        if game.is_wrapped() {
+            return Err("Wrapped games are not supported".into());
+        }
+        let embedded = CCB::convert_from_game_with(game, snake_ids, scratch)?;
+        Ok(CellBoard { embedded })
+    }
+
+    /// Converts every game with the same `snake_ids`, in order, sharing one set of scratch buffers
+    /// between them instead of allocating them for each game
+    pub fn convert_many<'a, I>(
+        games: I,
+        snake_ids: &'a SnakeIDMap,
+    ) -> impl Iterator<Item = Result<Self, Box<dyn Error>>> + 'a
+    where
+        I: IntoIterator<Item = Game>,
+        I::IntoIter: 'a,
+    {
+        let mut scratch = ConversionScratch::default();
+        games
+            .into_iter()
+            .map(move |game| Self::convert_from_game_with(&game, snake_ids, &mut scratch))
+    }
+
+    /// [CellBoard::convert_many] spread over rayon's thread pool, with a set of scratch buffers per
+    /// thread. The boards are in the same order as `games`
+    #[cfg(feature = "rayon")]
+    pub fn par_convert_many(
+        games: &[Game],
+        snake_ids: &SnakeIDMap,
+    ) -> Vec<Result<Self, Box<dyn Error + Send + Sync>>> {
+        use rayon::prelude::*;
+
+        games
+            .par_iter()
+            .map_init(ConversionScratch::default, |scratch, game| {
+                Self::convert_from_game_with(game, snake_ids, scratch)
+                    .map_err(|e| e.to_string().into())
+            })
+            .collect()
+    }
+
     fn off_board(&self, new_head: Position) -> bool {
         new_head.x < 0
             || new_head.x >= self.embedded.get_actual_width() as i32
@@ -75,6 +141,267 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     pub fn get_all_empty(&self) -> impl Iterator<Item = CellIndex<T>> + '_ {
         self.embedded.get_empty_cells()
     }
+
+    /// Reinterprets this board as a wrapped board. The cells, snakes, and hazards are kept as
+    /// they are, only simulation changes: moves off an edge come back in on the opposite edge
+    /// instead of killing the snake
+    pub fn into_wrapped(self) -> super::wrapped::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES> {
+        super::wrapped::CellBoard {
+            embedded: self.embedded,
+        }
+    }
+
+    /// the width of the game being played
+    pub fn actual_width(&self) -> u8 {
+        self.embedded.get_actual_width()
+    }
+
+    /// the height of the game being played
+    pub fn actual_height(&self) -> u8 {
+        self.embedded.get_actual_height()
+    }
+
+    /// the number of cells per row in the cell array, i.e. the width to give [CellIndex::new]
+    /// and [CellIndex::into_position]. Rows aren't padded, so this is the actual width, but index
+    /// math should use this rather than assuming the board is square
+    pub fn stored_width(&self) -> u8 {
+        self.embedded.stored_width()
+    }
+
+    /// whether simulating applies the snail mode map's hazard trails, see
+    /// [CellBoard::set_snail_mode]
+    #[cfg(feature = "snail-mode")]
+    pub fn snail_mode(&self) -> bool {
+        self.embedded.snail_mode()
+    }
+
+    /// In snail mode, at the end of every turn each stack of hazards loses one hazard, then every
+    /// snake that moved leaves a stack of as many hazards as it was long on the cell its tail
+    /// left. A snake whose tail is stacked, because it just ate or spawned, leaves nothing.
+    /// Converting a game on the `snail_mode` map turns this on
+    #[cfg(feature = "snail-mode")]
+    pub fn set_snail_mode(&mut self, snail_mode: bool) {
+        self.embedded.set_snail_mode(snail_mode)
+    }
+
+    /// the least food on the board and the percent chance of spawning more, which
+    /// [StandardFoodPlaceableGame::place_food] uses
+    pub fn food_spawn_settings(&self) -> (u8, u8) {
+        self.embedded.food_spawn_settings()
+    }
+
+    /// Changes the settings [StandardFoodPlaceableGame::place_food] uses. They come from the
+    /// game's `minimumFood` and `foodSpawnChance` when converting, falling back to the engine's
+    /// defaults of 1 food and a 15 percent chance. Chances above 100 percent are treated as 100
+    pub fn set_food_spawn_settings(&mut self, min_food: u8, food_spawn_chance: u8) {
+        self.embedded
+            .set_food_spawn_settings(min_food, food_spawn_chance)
+    }
+
+    /// the size of this board type in bytes. Boards are `Copy` and never allocate, so this is
+    /// exactly what each board kept in e.g. a transposition table costs. See
+    /// [crate::compact_representation::cell_board_footprint] for picking a board size up front
+    pub const fn memory_footprint() -> usize {
+        std::mem::size_of::<Self>()
+    }
+
+    /// A copy of this board with hazards dealing `hazard_damage`, for exploring counterfactuals
+    /// like "what if hazards did no damage" without converting from the wire representation
+    /// again
+    pub fn with_hazard_damage(self, hazard_damage: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_hazard_damage(hazard_damage),
+        }
+    }
+
+    /// a copy of this board with healing cells restoring `healing_regen`, see
+    /// [Self::with_hazard_damage]
+    pub fn with_healing_regen(self, healing_regen: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_healing_regen(healing_regen),
+        }
+    }
+
+    /// a copy of this board on `turn`, see [Self::with_hazard_damage]. Simulating counts up from
+    /// it, as from the game's turn when converting
+    pub fn with_turn(self, turn: u32) -> Self {
+        Self {
+            embedded: self.embedded.with_turn(turn),
+        }
+    }
+
+    /// A copy of this board with a snake's health changed, see [Self::with_hazard_damage]. Like
+    /// [HealthSettableGame::set_health], a health of 0 eliminates the snake and an eliminated
+    /// snake stays eliminated
+    pub fn with_health(self, snake_id: SnakeId, health: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_health(snake_id, health),
+        }
+    }
+
+    /// the number of cells with food on them. Kept up to date as the board changes, so unlike
+    /// counting [FoodGettableGame::get_all_food_as_native_positions] it doesn't scan the board
+    pub fn food_count(&self) -> usize {
+        self.embedded.food_count()
+    }
+
+    /// the number of hazard cells, kept up to date like [Self::food_count]
+    pub fn hazard_count(&self) -> usize {
+        self.embedded.hazard_count()
+    }
+
+    /// the number of cells a snake's body covers, its length minus any segments stacked on its
+    /// tail. 0 for an eliminated snake
+    pub fn body_cell_count(&self, snake_id: SnakeId) -> u16 {
+        self.embedded.body_cell_count(snake_id)
+    }
+
+    /// draws `values`, indexed by [CellIndex], over this board as heat characters, e.g. to see
+    /// the visit counts behind a search's choice of move. See [OverlayDisplay]
+    pub fn display_overlay<'a, V>(
+        &'a self,
+        values: &'a [V],
+    ) -> OverlayDisplay<'a, T, D, V, BOARD_SIZE, MAX_SNAKES> {
+        self.embedded.display_overlay(values)
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but if there are more than `max_children`
+    /// joint moves only a random `max_children` of them are simulated. A safeguard for lobbies
+    /// with many snakes, where simulating every joint move (65536 of them for 8 snakes) is too
+    /// slow and uses too much memory
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_sampled<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        max_children: usize,
+        rng: &mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        Box::new(
+            super::core::simulate_with_moves_sampled(
+                self.embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Standard,
+                max_children,
+                rng,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but each child also comes with what happened
+    /// to every snake that moved, see [TurnOutcome]
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_outcomes<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, TurnOutcome<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_outcomes(
+                embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Standard,
+            )
+            .map(|(action, outcome, board)| (action, outcome, Self { embedded: board })),
+        )
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but `rule` is called on every child once the
+    /// standard rules have resolved the turn, with the joint move that produced it. This is the
+    /// place for rule add-ons like damage auras or scoring zones, e.g. through
+    /// [HealthSettableGame::set_health]. Children aren't checked for consistency after `rule`
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_post_turn_rule<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        rule: impl Fn(&Action<MAX_SNAKES>, &mut Self) + 'a,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_moves(
+                embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Standard,
+            )
+            .map(move |(action, board)| {
+                let mut board = Self { embedded: board };
+                rule(&action, &mut board);
+                (action, board)
+            }),
+        )
+    }
+
+    /// Applies one joint move in place instead of copying the board like
+    /// [SimulableGame::simulate_with_moves] does, for searches that walk down and back up a
+    /// large board. Pass the returned patch to [Self::undo] to get back to this board
+    pub fn apply_moves(&mut self, moves: &[(SnakeId, Move)]) -> BoardPatch<T, MAX_SNAKES> {
+        self.embedded.apply_moves(moves, EvaluateMode::Standard)
+    }
+
+    /// undoes an [Self::apply_moves], patches have to be undone in the reverse order they were
+    /// applied
+    pub fn undo(&mut self, patch: BoardPatch<T, MAX_SNAKES>) {
+        self.embedded.undo_patch(patch)
+    }
+
+    /// Evaluates one joint move on each board in `batch`, e.g. the leaves an MCTS expands in
+    /// parallel, yielding the children in order without boxing. The prepared move state is
+    /// reused while consecutive entries share a board, so keep each board's joint moves
+    /// together. Moves that kill a snake are applied rather than pruned
+    pub fn simulate_batch<'a>(
+        batch: &'a [(&'a Self, &'a [(SnakeId, Move)])],
+    ) -> impl Iterator<Item = Self> + 'a {
+        super::core::CellBoard::simulate_batch(
+            batch.iter().map(|(board, moves)| (&board.embedded, *moves)),
+            EvaluateMode::Standard,
+        )
+        .map(|embedded| Self { embedded })
+    }
+
+    /// every cell on the board along with what's in it, in index order, see [CellContents]
+    pub fn iter_cells(&self) -> impl Iterator<Item = (CellIndex<T>, CellContents)> + '_ {
+        self.embedded.iter_cells()
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but every temporary buffer used while
+    /// simulating is taken from `ctx` rather than the global allocator
+    #[cfg(feature = "bumpalo")]
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_in<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        ctx: &'a super::SimulationContext,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_moves_in(
+                embedded,
+                ctx,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Standard,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> TranspositionKeyGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn key(&self, turn: u64) -> TranspositionKey {
+        // the turn the board tracks isn't part of the position, only the parity passed in is
+        let mut hasher = FxHasher64::default();
+        self.with_turn(0).hash(&mut hasher);
+        TranspositionKey::new(hasher.finish(), turn, false)
+    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -96,30 +423,54 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Rea
 {
     fn reasonable_moves_for_each_snake(
         &self,
-    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Vec<Move>)> + '_> {
+    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Vec<Move>)> + Send + '_> {
         let width = self.embedded.get_actual_width();
+        let hazard_damage = self.get_hazard_damage();
         Box::new(
             self.embedded
                 .iter_healths()
                 .enumerate()
                 .filter(|(_, health)| **health > 0)
-                .map(move |(idx, _)| {
-                    let head_pos = self.get_head_as_position(&SnakeId(idx as u8));
+                .map(move |(idx, health)| {
+                    let sid = SnakeId(idx as u8);
+                    let head_pos = self.get_head_as_position(&sid);
 
-                    let mvs = IntoIterator::into_iter(Move::all())
+                    let mvs = Move::all_iter()
                         .filter(|mv| {
                             let new_head = head_pos.add_vec(mv.to_vector());
+                            if self.off_board(new_head) {
+                                return false;
+                            }
                             let ci = CellIndex::new(new_head, width);
+                            if self.embedded.cell_is_wall(ci) {
+                                return false;
+                            }
+                            // snakes eating on a hazard don't take its damage
+                            let lethal_hazard = self.embedded.cell_is_hazard(ci)
+                                && !self.embedded.cell_is_food(ci)
+                                && hazard_is_lethal(
+                                    *health as i64,
+                                    hazard_damage as i64,
+                                    self.get_hazard_count(&ci) as i64,
+                                );
 
-                            !self.off_board(new_head)
-                                && (!self.embedded.cell_is_body(ci)
-                                    || self.embedded.cell_is_single_tail(ci))
+                            (!self.embedded.cell_is_body(ci) || self.will_be_vacated_this_turn(&ci))
                                 && !self.embedded.cell_is_snake_head(ci)
+                                && !lethal_hazard
                         })
                         .collect_vec();
-                    let mvs = if mvs.is_empty() { vec![Move::Up] } else { mvs };
+                    let mvs = if mvs.is_empty() {
+                        let fallback = Move::all_iter().find(|mv| {
+                            let new_head = head_pos.add_vec(mv.to_vector());
+                            self.off_board(new_head)
+                                || !self.is_neck(&sid, &CellIndex::new(new_head, width))
+                        });
+                        vec![fallback.unwrap_or(Move::Up)]
+                    } else {
+                        mvs
+                    };
 
-                    (SnakeId(idx as u8), mvs)
+                    (sid, mvs)
                 }),
         )
     }
@@ -139,13 +490,15 @@ impl<
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + Send + '_>
     where
         S: Borrow<[Move]>,
     {
+        // borrow rather than copy the embedded board, this iterator is tied to self anyway
+        let embedded = &self.embedded;
         Box::new(
             simulate_with_moves(
-                &self.embedded,
+                embedded,
                 instruments,
                 snake_ids_and_moves,
                 EvaluateMode::Standard,
@@ -156,6 +509,39 @@ impl<
             }),
         )
     }
+
+    #[allow(clippy::type_complexity)]
+    fn into_simulate_with_moves<S>(
+        self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + Send>
+    where
+        S: Borrow<[Move]>,
+        Self: 'static,
+    {
+        Box::new(
+            simulate_with_moves(
+                self.embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Standard,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> AreaControlGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn spread_from_heads(&self) -> Vec<(SnakeId, usize)> {
+        let counts = self.embedded.spread_from_heads(EvaluateMode::Standard);
+        self.get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, counts[sid.as_usize()] as usize))
+            .collect()
+    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -164,7 +550,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + Send + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -176,7 +562,9 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 
                     (mv, new_head, ci)
                 })
-                .filter(move |(_mv, new_head, _)| !self.off_board(*new_head))
+                .filter(move |(_mv, new_head, ci)| {
+                    !self.off_board(*new_head) && !self.embedded.cell_is_wall(*ci)
+                })
                 .map(|(mv, _, ci)| (mv, ci)),
         )
     }
@@ -184,7 +572,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + Send + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -196,7 +584,9 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 
                     (new_head, ci)
                 })
-                .filter(move |(new_head, _)| !self.off_board(*new_head))
+                .filter(move |(new_head, ci)| {
+                    !self.off_board(*new_head) && !self.embedded.cell_is_wall(*ci)
+                })
                 .map(|(_, ci)| ci),
         )
     }
@@ -225,25 +615,60 @@ pub enum BestCellBoard {
     Large(Box<CellBoard8Snakes25x25>),
     /// A game that can have a max height and width of 50x50 and 16 snakes
     Silly(Box<CellBoard16Snakes50x50>),
+    /// A game that can have a max height and width of 25x25 and 32 snakes
+    LargeRoyale(Box<CellBoard32Snakes25x25>),
+    /// A game that can have a max height and width of 50x50 and 32 snakes
+    SillyRoyale(Box<CellBoard32Snakes50x50>),
+    /// A exactly 7x7 board with 2 snakes
+    SmallExactDuel(Box<CellBoard<u8, Fixed<7, 7>, { 7 * 7 }, 2>>),
+    /// A game that can have a max height and width of 11x11 and 2 snakes
+    StandardDuel(Box<CellBoard<u8, Square, { 11 * 11 }, 2>>),
+    /// A exactly 11x11 board with 2 snakes
+    MediumExactDuel(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 2>>),
+    /// A exactly 19x19 board with 2 snakes
+    LargeExactDuel(Box<CellBoard<u16, Fixed<19, 19>, { 19 * 19 }, 2>>),
 }
 
+impl_best_cell_board!();
+
 /// Trait to get the best sized cellboard for the given game. It returns the smallest Compact board
-/// that has enough room to fit the given Wire game. If the game can't fit in any of our Compact
-/// boards we panic. However the largest board available is MUCH larger than the biggest selectable
-/// board in the Battlesnake UI
+/// that has enough room to fit the given Wire game, using the 2 snake boards for duels. If the game
+/// can't fit in any of our Compact boards an error is returned. However the largest board available
+/// is MUCH larger than the biggest selectable board in the Battlesnake UI
 pub trait ToBestCellBoard {
     #[allow(missing_docs)]
     fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>>;
 }
 
 impl ToBestCellBoard for Game {
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>> {
+    fn to_best_cell_board(mut self) -> Result<BestCellBoard, Box<dyn Error>> {
         let width = self.board.width;
         let height = self.board.height;
-        let num_snakes = self.board.snakes.len();
+        // most ranked games are duels, and the smaller arrays of the 2 snake boards are kinder to
+        // the cache. Dead snakes don't need a slot, so they're dropped to fit
+        let alive_ids = 1 + self
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0 && s.id != self.you.id)
+            .count();
+        let duel = alive_ids <= 2;
+        if duel {
+            self.board.snakes.retain(|s| s.health > 0);
+        }
         let id_map = build_snake_id_map(&self);
+        // you always take an id, even once you've been eliminated and left off the board
+        let num_snakes = id_map.len();
 
-        let best_board = if width == 7 && height == 7 && num_snakes <= 4 {
+        let best_board = if duel && width == 7 && height == 7 {
+            BestCellBoard::SmallExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width == 11 && height == 11 {
+            BestCellBoard::MediumExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width <= 11 && height <= 11 {
+            BestCellBoard::StandardDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width == 19 && height == 19 {
+            BestCellBoard::LargeExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width == 7 && height == 7 && num_snakes <= 4 {
             BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width <= 7 && height <= 7 && num_snakes <= 4 {
             BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(self, &id_map)?))
@@ -259,12 +684,16 @@ impl ToBestCellBoard for Game {
             BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width == 19 && height == 21 && num_snakes <= 8 {
             BestCellBoard::ArcadeMaze8Snake(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 25 && height < 25 && num_snakes <= 8 {
+        } else if width <= 25 && height <= 25 && num_snakes <= 8 {
             BestCellBoard::Large(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width <= 25 && height <= 25 && num_snakes <= 32 {
+            BestCellBoard::LargeRoyale(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width <= 50 && height <= 50 && num_snakes <= 16 {
             BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width <= 50 && height <= 50 && num_snakes <= 32 {
+            BestCellBoard::SillyRoyale(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else {
-            panic!("No board was big enough")
+            return Err("no board was big enough".into());
         };
 
         Ok(best_board)
@@ -278,8 +707,11 @@ mod test {
 
     use super::*;
     use crate::{
-        compact_representation::core::Cell, game_fixture, types::build_snake_id_map,
-        wire_representation::Game as DEGame,
+        compact_representation::{core::Cell, wrapped},
+        game_fixture,
+        maps::{HealingCell, MapRegistry},
+        types::{build_snake_id_map, Vector},
+        wire_representation::{Game as DEGame, GameMode, HealthOutOfRange},
     };
     #[derive(Debug)]
     struct Instruments;
@@ -319,6 +751,55 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_malformed_games_are_errors() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let snake_ids = build_snake_id_map(&g);
+
+        let mut missing_ids = snake_ids.clone();
+        missing_ids.remove(&g.board.snakes[1].id);
+        assert!(CellBoard4Snakes11x11::convert_from_game(g.clone(), &missing_ids).is_err());
+
+        let mut bad_head = g.clone();
+        bad_head.board.snakes[1].head = Position::new(3, 3);
+        assert!(CellBoard4Snakes11x11::convert_from_game(bad_head, &snake_ids).is_err());
+
+        let mut outside = g.clone();
+        outside.board.snakes[1].body[2] = Position::new(5, 11);
+        assert!(CellBoard4Snakes11x11::convert_from_game(outside, &snake_ids).is_err());
+
+        let mut huge = g;
+        huge.board.width = 100;
+        assert!(Game::to_best_cell_board(huge).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_health() {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.snakes[1].health = 300;
+        g.board.snakes[2].health = -5;
+        let snake_ids = build_snake_id_map(&g);
+
+        let err = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap_err();
+        let out_of_range = err
+            .downcast_ref::<HealthOutOfRange>()
+            .expect("conversion fails with HealthOutOfRange");
+        assert_eq!(out_of_range.snake_id, g.board.snakes[1].id);
+        assert_eq!(out_of_range.health, 300);
+
+        let warnings = g.saturate_healths();
+        assert_eq!(
+            warnings.iter().map(|w| w.health).collect_vec(),
+            vec![300, -5]
+        );
+        let compact = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap();
+        let sid = |i: usize| snake_ids.get(&g.board.snakes[i].id).unwrap();
+        assert_eq!(compact.get_health(sid(1)), 255);
+        assert_eq!(compact.get_health(sid(2)), 0);
+        assert!(!compact.is_alive(sid(2)));
+        assert!(g.saturate_healths().is_empty());
+    }
+
     #[test]
     fn test_head_gettable() {
         let game_fixture = include_str!("../../../fixtures/late_stage.json");
@@ -364,111 +845,1422 @@ mod test {
     }
 
     #[test]
-    fn test_set_hazard() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        assert!(c.is_food());
-        c.set_hazard();
-        assert!(c.is_food());
-        assert!(c.is_hazard());
-        assert!(!c.is_head());
-        assert!(!c.is_body());
-    }
-
-    #[test]
-    fn test_clear_hazard() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        assert!(c.is_food());
-        c.set_hazard();
-        c.clear_hazard();
-        assert!(c.is_food());
-        assert!(!c.is_hazard());
-        assert!(!c.is_head());
-        assert!(!c.is_body());
-        let mut c: Cell<u8> = Cell::make_double_stacked_piece(SnakeId(0), CellIndex(0));
-        c.set_hazard();
-        c.clear_hazard();
-        assert!(c.is_body());
-        assert!(!c.is_hazard());
-    }
+    fn test_tail_vacate_resolution() {
+        let g = game_fixture(include_str!("../../../fixtures/stacked_tail.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let native = |x, y| compact.native_from_position(Position::new(x, y));
+        let (a, b, c) = (SnakeId(0), SnakeId(1), SnakeId(2));
 
-    #[test]
-    fn test_remove() {
-        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(3), CellIndex(17));
-        c.remove();
-        c.set_hazard();
-        assert!(c.is_empty());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().is_none());
-        assert!(c.get_idx() == CellIndex(0));
-    }
-    #[test]
-    fn test_set_food() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_food();
-        c.set_hazard();
-        assert!(c.is_food());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().is_none());
-        assert!(c.get_idx() == CellIndex(0));
-    }
+        // c's single tail moves out of the way, b's tail is stacked as b ate last turn
+        for (x, y, vacated) in [(3, 6, true), (4, 5, false), (5, 5, false), (3, 5, false)] {
+            assert_eq!(g.will_be_vacated_this_turn(&Position::new(x, y)), vacated);
+            assert_eq!(compact.will_be_vacated_this_turn(&native(x, y)), vacated);
+        }
+        let a_moves = compact.reasonable_moves_for_each_snake().next().unwrap().1;
+        assert_eq!(a_moves, vec![Move::Up, Move::Left]);
+        assert_eq!(
+            g.reasonable_moves_for_each_snake().next().unwrap().1,
+            a_moves
+        );
 
-    #[test]
-    fn test_set_head() {
-        let mut c: Cell<u8> = Cell::empty();
-        c.set_head(SnakeId(3), CellIndex(17));
-        c.set_hazard();
-        assert!(c.is_head());
-        assert!(c.is_hazard());
-        assert!(c.get_snake_id().unwrap() == SnakeId(3));
-        assert!(c.get_idx() == CellIndex(17));
-    }
+        let instruments = Instruments;
+        let step = |board: &CellBoard4Snakes11x11, moves: [Move; 3]| {
+            let moves = [a, b, c]
+                .iter()
+                .zip(moves.iter())
+                .map(|(sid, mv)| (*sid, vec![*mv]))
+                .collect_vec();
+            board
+                .simulate_with_moves(&instruments, moves)
+                .next()
+                .unwrap()
+                .1
+        };
 
-    #[test]
-    fn test_food_queryable() {
-        let game_fixture = include_str!("../../../fixtures/late_stage.json");
-        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
-        let g = g.expect("the json literal is valid");
-        let snake_id_mapping = build_snake_id_map(&g);
-        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let in_to_stacked_tail = step(&compact, [Move::Right, Move::Up, Move::Up]);
+        assert!(!in_to_stacked_tail.is_alive(&a));
 
-        assert!(!compact.is_food(&CellIndex(6 * 11 + 4)));
+        // c eating this turn doesn't keep its old tail in place
+        let in_to_eating_tail = step(&compact, [Move::Up, Move::Up, Move::Up]);
+        assert!(in_to_eating_tail.is_alive(&a));
+        assert_eq!(in_to_eating_tail.get_length(&c), 5);
+        assert_eq!(
+            in_to_eating_tail.get_snake_body_vec(&c),
+            vec![
+                native(1, 8),
+                native(1, 7),
+                native(2, 7),
+                native(3, 7),
+                native(3, 7)
+            ]
+        );
 
-        assert!(compact.is_food(&CellIndex(2 * 11)));
-        assert!(compact.is_food(&CellIndex(9 * 11)));
-        assert!(compact.is_food(&CellIndex(3 * 11 + 4)));
+        // a length two snake eating leaves its old head as a stacked tail
+        let mut short = g.clone();
+        short.board.snakes[1].body.truncate(2);
+        short.board.food.push(Position::new(7, 5));
+        let short: CellBoard4Snakes11x11 = short.as_cell_board(&id_map).unwrap();
+        let grown = step(&short, [Move::Up, Move::Right, Move::Up]);
+        assert_eq!(grown.get_length(&b), 3);
+        assert_eq!(
+            grown.get_snake_body_vec(&b),
+            vec![native(7, 5), native(6, 5), native(6, 5)]
+        );
+        assert!(!grown.will_be_vacated_this_turn(&native(6, 5)));
     }
 
     #[test]
-    fn test_neighbors_and_possible_moves_start_of_game() {
-        let game_fixture = include_str!("../../../fixtures/start_of_game.json");
-        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
-        let g = g.expect("the json literal is valid");
-        let snake_id_mapping = build_snake_id_map(&g);
-        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
-
-        let head = compact.get_head_as_native_position(&SnakeId(0));
-        assert_eq!(head, CellIndex(8 * 11 + 5));
+    fn test_growth_prediction() {
+        let mut g = game_fixture(include_str!("../../../fixtures/stacked_tail.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let (b, c) = (SnakeId(1), SnakeId(2));
+        let c_id = g.board.snakes[2].id.clone();
 
-        let expected_possible_moves = vec![
-            (Move::Up, CellIndex(9 * 11 + 5)),
-            (Move::Down, CellIndex(7 * 11 + 5)),
-            (Move::Left, CellIndex(8 * 11 + 4)),
-            (Move::Right, CellIndex(8 * 11 + 6)),
-        ];
+        // b ate last turn, c didn't
+        assert!(compact.will_grow_next_turn(&b));
+        assert!(!compact.will_grow_next_turn(&c));
+        assert!(!g.will_grow_next_turn(&c_id));
+        assert_eq!(compact.predicted_length_at(&c, 10, 0.2), 4.0 + 2.0);
+        assert_eq!(compact.predicted_length_at(&c, 10, 3.0), 4.0 + 10.0);
 
+        // once c is known to have eaten, its tail is no longer somewhere a can move
+        compact.mark_will_grow(&c);
+        g.mark_will_grow(&c_id);
+        assert!(compact.will_grow_next_turn(&c));
+        assert!(g.will_grow_next_turn(&c_id));
+        assert_eq!(compact.get_length(&c), 5);
+        assert_eq!(g.get_length(&c_id), 5);
+        let c_tail = compact.native_from_position(Position::new(3, 6));
+        assert!(!compact.will_be_vacated_this_turn(&c_tail));
+        let a_moves = compact.reasonable_moves_for_each_snake().next().unwrap().1;
+        assert_eq!(a_moves, vec![Move::Left]);
         assert_eq!(
-            compact.possible_moves(&head).collect::<Vec<_>>(),
-            expected_possible_moves
+            g.reasonable_moves_for_each_snake().next().unwrap().1,
+            a_moves
         );
-
         assert_eq!(
-            compact.neighbors(&head).collect::<Vec<_>>(),
-            expected_possible_moves
-                .into_iter()
-                .map(|(_, pos)| pos)
-                .collect::<Vec<_>>()
+            g.as_cell_board::<u8, Square, { 11 * 11 }, 4>(&id_map)
+                .unwrap(),
+            compact
+        );
+
+        // a snake only eats once a turn
+        compact.mark_will_grow(&c);
+        assert_eq!(compact.get_length(&c), 5);
+        assert!(compact.embedded.assert_consistency());
+    }
+
+    #[test]
+    fn test_healing_cells() {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.game.map = Some("springs".to_string());
+        let spring = |x, y, regen| HealingCell {
+            position: Position::new(x, y),
+            regen,
+        };
+        let mut registry = MapRegistry::new();
+        registry.register_healing_cells("springs", vec![spring(5, 7, 10), spring(5, 5, 10)]);
+        registry.apply(&mut g);
+        let you = g.you.id.clone();
+        g.board
+            .snakes
+            .iter_mut()
+            .find(|s| s.id == you)
+            .unwrap()
+            .health = 1;
+
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let sid = *id_map.get(&you).unwrap();
+        for pos in [
+            Position::new(0, 6),
+            Position::new(5, 5),
+            Position::new(0, 4),
+        ] {
+            assert_eq!(
+                compact.healing_at(&compact.native_from_position(pos)),
+                g.healing_at(&pos)
+            );
+        }
+        assert_eq!(compact.healing_cells().len(), 2);
+        assert!(g.is_healing(&Position::new(5, 7)));
+
+        let instruments = Instruments;
+        let results = compact
+            .simulate_with_moves(&instruments, vec![(sid, vec![Move::Down, Move::Right])])
+            .collect_vec();
+        let after = |mv| results.iter().find(|(a, _)| a.own_move() == mv).unwrap().1;
+        // the spring tops the snake up after it runs out of health moving on to it
+        assert_eq!(after(Move::Down).get_health(&sid), 10);
+        // moving right starves, so the simulator prunes it
+        assert!(results.iter().all(|(a, _)| a.own_move() != Move::Right));
+
+        g.board.healing_cells.push(spring(1, 1, 20));
+        assert!(g
+            .as_cell_board::<u8, Square, { 11 * 11 }, 4>(&id_map)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cell_board_macro() {
+        use crate::compact_representation::{dimensions::Fixed, StandardCellBoard};
+        use std::any::TypeId;
+
+        assert_eq!(
+            TypeId::of::<crate::cell_board!(11, 11, 2)>(),
+            TypeId::of::<StandardCellBoard<u8, Fixed<11, 11>, 121, 2>>()
+        );
+        assert_eq!(
+            TypeId::of::<crate::cell_board!(25, 15, 8)>(),
+            TypeId::of::<StandardCellBoard<u16, Fixed<25, 15>, 375, 8>>()
+        );
+
+        // a board that isn't square simulates with its real width
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.width = 13;
+        let id_map = build_snake_id_map(&g);
+        let compact: crate::cell_board!(13, 11, 4) = g.as_cell_board(&id_map).unwrap();
+        let head = compact.get_head_as_position(&SnakeId(0));
+        let instruments = Instruments;
+        let moved = compact
+            .simulate_with_moves(&instruments, vec![(SnakeId(0), [Move::Left].as_slice())])
+            .next()
+            .unwrap()
+            .1;
+        assert_eq!(
+            moved.get_head_as_position(&SnakeId(0)),
+            head.add_vec(Move::Left.to_vector())
+        );
+    }
+
+    #[test]
+    fn test_best_cell_board_forwarding() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 2> =
+            g.as_cell_board(&id_map).unwrap();
+        let best = g.to_best_cell_board().unwrap();
+        assert!(matches!(best, BestCellBoard::MediumExactDuel(_)));
+
+        assert_eq!(best.you_id(), *compact.you_id());
+        assert!(!best.is_over());
+        assert_eq!(best.get_winner(), None);
+        let reasonable = best.reasonable_moves_for_each_snake();
+        assert_eq!(
+            reasonable,
+            compact.reasonable_moves_for_each_snake().collect_vec()
+        );
+
+        let instruments = Instruments;
+        let children = best.simulate_with_moves(&instruments, reasonable.clone());
+        let expected = compact
+            .simulate_with_moves(&instruments, reasonable)
+            .collect_vec();
+        assert_eq!(children.len(), expected.len());
+        for ((moves, child), (action, expected)) in children.iter().zip(expected.iter()) {
+            assert_eq!(moves[0], (SnakeId(0), action.own_move()));
+            match child {
+                BestCellBoard::MediumExactDuel(child) => {
+                    assert_eq!(
+                        child.embedded.pack_as_hash(),
+                        expected.embedded.pack_as_hash()
+                    )
+                }
+                _ => panic!("children should be the same variant"),
+            }
+        }
+
+        struct CountSnakes;
+        impl BestCellBoardVisitor for CountSnakes {
+            type Output = usize;
+            fn visit<B>(self, board: &B) -> usize
+            where
+                B: Board + SnakeIDGettableGame<SnakeIDType = SnakeId>,
+            {
+                board.get_snake_ids().len()
+            }
+        }
+        assert_eq!(best.visit(CountSnakes), 2);
+    }
+
+    #[test]
+    fn test_with_best_cell_board() {
+        fn you_head(game: DEGame) -> Result<Position, Box<dyn Error>> {
+            let best = game.clone().to_best_cell_board()?;
+            let head = crate::with_best_cell_board!(best, |board| {
+                if board.is_over() {
+                    return Err("the game is over".into());
+                }
+                board.get_head_as_position(board.you_id())
+            });
+            let mut wrapped_game = game;
+            wrapped_game.game.ruleset.name = GameMode::Wrapped;
+            let best = wrapped::ToBestCellBoard::to_best_cell_board(wrapped_game)?;
+            let wrapped_head = crate::with_best_cell_board!(wrapped, &best, |board| board
+                .get_head_as_position(board.you_id()));
+            assert_eq!(head, wrapped_head);
+            Ok(head)
+        }
+
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        assert_eq!(you_head(g.clone()).unwrap(), g.you.head);
+        let mut over = g;
+        let you_id = over.you.id.clone();
+        over.board.snakes.retain(|s| s.id == you_id);
+        assert!(you_head(over).is_err());
+    }
+
+    #[cfg(feature = "cell-metadata")]
+    #[test]
+    fn test_cell_metadata_travels_with_children() {
+        use crate::types::CellMetadataGame;
+
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let marked = CellIndex::new(Position::new(2, 3), 11);
+        compact.set_cell_metadata(&marked, 7);
+        assert_eq!(compact.get_cell_metadata(&marked), 7);
+        assert_eq!(
+            compact.get_cell_metadata(&CellIndex::new(Position::new(3, 3), 11)),
+            0
+        );
+
+        let packed = compact.embedded.pack_as_hash();
+        assert_eq!(CCB::from_packed_hash(&packed), compact.embedded);
+
+        let instruments = Instruments;
+        let moves = vec![(SnakeId(0), vec![Move::Down])];
+        let (_, mut child) = compact
+            .simulate_with_moves(&instruments, moves)
+            .next()
+            .unwrap();
+        assert_eq!(child.get_cell_metadata(&marked), 7);
+
+        child.clear_cell_metadata();
+        assert_eq!(child.get_cell_metadata(&marked), 0);
+        assert_eq!(compact.get_cell_metadata(&marked), 7);
+    }
+
+    #[test]
+    fn test_apply_and_undo_patch() {
+        let instruments = Instruments;
+        for fixture in [
+            include_str!("../../../fixtures/start_of_game.json"),
+            include_str!("../../../fixtures/late_stage.json"),
+            include_str!("../../../fixtures/stacked_tail.json"),
+        ] {
+            let g = game_fixture(fixture);
+            let id_map = build_snake_id_map(&g);
+            let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+            let all_moves = compact
+                .get_snake_ids()
+                .into_iter()
+                .map(|sid| (sid, Move::all()))
+                .collect_vec();
+
+            for (action, child) in compact.simulate_with_moves(&instruments, all_moves) {
+                let moves = action
+                    .into_inner()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, mv)| mv.map(|mv| (SnakeId(i as u8), mv)))
+                    .collect_vec();
+                let mut board = compact;
+                let patch = board.apply_moves(&moves);
+                assert_eq!(board, child, "{:?}", moves);
+                assert!(patch.touched_cells() < 121);
+
+                board.undo(patch);
+                assert_eq!(board, compact);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solo_minimal_payload() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // no food or hazards keys at all, and a single snake
+        let g = game_fixture(include_str!("../../../fixtures/solo_minimal.json"));
+        assert!(g.board.food.is_empty() && g.board.hazards.is_empty());
+        assert!(g.is_solo());
+        assert!(!g.is_over());
+        assert_eq!(g.get_winner(), None);
+
+        let mut standard = g.clone();
+        standard.game.ruleset.name = GameMode::Standard;
+        assert!(standard.is_over());
+
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u8, Square, { 7 * 7 }, 2> = g.as_cell_board(&id_map).unwrap();
+        assert!(compact.is_solo());
+        assert!(!compact.is_over());
+        assert_eq!(compact.get_winner(), None);
+        let reasonable = compact.reasonable_moves_for_each_snake().collect_vec();
+        assert_eq!(
+            reasonable,
+            vec![(SnakeId(0), vec![Move::Up, Move::Left, Move::Right])]
+        );
+        let packed = compact.embedded.pack_as_hash();
+        assert!(CCB::<u8, Square, { 7 * 7 }, 2>::from_packed_hash(&packed).is_solo());
+
+        let instruments = Instruments;
+        let (_, child) = compact
+            .simulate_with_moves(&instruments, vec![(SnakeId(0), [Move::Up].as_slice())])
+            .next()
+            .unwrap();
+        assert!(!child.is_over());
+        let (_, starved) = child
+            .simulate_with_moves(&instruments, vec![(SnakeId(0), [Move::Up].as_slice())])
+            .next()
+            .unwrap();
+        assert!(starved.is_over());
+        assert_eq!(starved.get_winner(), None);
+
+        // with no food on the board the minimum food is spawned
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut fed = compact;
+        fed.place_food(&mut rng);
+        assert_eq!(fed.get_all_food_as_positions().len(), 1);
+        let mut wire = g;
+        wire.place_food(&mut rng);
+        assert_eq!(wire.board.food.len(), 1);
+    }
+
+    #[test]
+    fn test_food_spawn_settings() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // games without settings get the engine's defaults
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_eq!(compact.food_spawn_settings(), (1, 15));
+
+        let mut g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        g.board.food.clear();
+        let settings = g.game.ruleset.settings.as_mut().unwrap();
+        settings.minimum_food = 3;
+        let id_map = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        assert_eq!(compact.food_spawn_settings(), (3, 20));
+
+        // placing food uses up the same randomness as the wire game
+        let mut wire = g.clone();
+        let mut wire_rng = SmallRng::seed_from_u64(3);
+        let mut compact_rng = SmallRng::seed_from_u64(3);
+        for _ in 0..20 {
+            wire.place_food(&mut wire_rng);
+            compact.place_food(&mut compact_rng);
+            let mut wire_food = wire.get_all_food_as_positions();
+            let mut compact_food = compact.get_all_food_as_positions();
+            wire_food.sort();
+            compact_food.sort();
+            assert_eq!(wire_food, compact_food);
+        }
+        assert!(compact.food_count() >= 3);
+
+        let packed = compact.embedded.pack_as_hash();
+        assert_eq!(CCB::from_packed_hash(&packed), compact.embedded);
+        let json = serde_json::to_string(&compact).unwrap();
+        let back: CellBoard4Snakes11x11 = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.food_spawn_settings(), (3, 20));
+
+        let mut starved: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        starved.set_food_spawn_settings(0, 0);
+        starved.place_food(&mut compact_rng);
+        assert_eq!(starved.food_count(), 0);
+        starved.set_food_spawn_settings(0, 200);
+        assert_eq!(starved.food_spawn_settings(), (0, 100));
+        starved.place_food(&mut compact_rng);
+        assert_eq!(starved.food_count(), 1);
+    }
+
+    #[test]
+    fn test_food_stays_inside_a_padded_board() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // a 7x7 game on a board with room for 11x11, so the last 72 cells are padding
+        let ruleset = game_fixture(include_str!("../../../fixtures/late_stage.json"))
+            .game
+            .ruleset;
+        let mut wire = DEGame::from_ascii(
+            "
+            . . . . . . .
+            . 0 a a . . .
+            . . . a . f .
+            . . . . . . .
+            . f . . 1 b .
+            . . . . . b .
+            . . . . . . .
+            ",
+            ruleset,
+        )
+        .unwrap();
+        let mut compact: CellBoard4Snakes11x11 =
+            wire.as_cell_board(&build_snake_id_map(&wire)).unwrap();
+        assert_eq!(compact.embedded.get_actual_width(), 7);
+        assert!(compact
+            .get_empty_cells()
+            .all(|c| c.as_usize() < 7 * 7 && !compact.off_board(compact.position_from_native(c))));
+        assert_eq!(
+            compact
+                .get_empty_cells()
+                .map(|c| compact.position_from_native(c))
+                .collect_vec(),
+            wire.get_empty_cells().collect_vec()
+        );
+
+        // ask for more food than there is room for, so every empty cell is filled
+        compact.set_food_spawn_settings(u8::MAX, 0);
+        wire.game.ruleset.settings.as_mut().unwrap().minimum_food = u8::MAX as i32;
+        let mut wire_rng = SmallRng::seed_from_u64(5);
+        let mut compact_rng = SmallRng::seed_from_u64(5);
+        wire.place_food(&mut wire_rng);
+        compact.place_food(&mut compact_rng);
+        assert_eq!(compact.get_empty_cells().count(), 0);
+        let mut food = compact.get_all_food_as_positions();
+        assert!(food.iter().all(|pos| !compact.off_board(*pos)));
+        assert_eq!(compact.food_count(), food.len());
+        assert_eq!(food.len(), wire.board.food.len());
+        food.sort();
+        let mut wire_food = wire.get_all_food_as_positions();
+        wire_food.sort();
+        assert_eq!(food, wire_food);
+        assert!(compact.embedded.assert_consistency());
+    }
+
+    #[test]
+    fn test_display_overlay() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        let by_column = (0..121).map(|i| i % 11).collect_vec();
+        let drawn = compact.display_overlay(&by_column).to_string();
+        let lines = drawn.lines().collect_vec();
+        assert_eq!(lines[1], ". : - - = + * # # % @ ");
+        assert_eq!(lines[2], ". : - - = s * # # % @ ");
+        assert_eq!(lines[3], ". : - - = 0 * # # % @ ");
+        assert_eq!(lines[12], ". = 0, @ = 10");
+
+        // cells past the end of the values are left blank
+        let drawn = compact.display_overlay(&by_column[..11]).to_string();
+        assert_eq!(drawn.lines().nth(1).unwrap().trim(), "");
+        assert_eq!(drawn.lines().nth(11).unwrap(), ". : - - = + * # # 3 @ ");
+    }
+
+    #[test]
+    fn test_best_cell_board_duel_tiers() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        assert_eq!(g.board.snakes.len(), 4);
+        assert!(matches!(
+            g.clone().to_best_cell_board().unwrap(),
+            BestCellBoard::MediumExact(_)
+        ));
+
+        // with two of the snakes dead the game fits in a duel board, and the live snake that was
+        // listed after the dead ones takes the second id
+        let mut duel = g.clone();
+        let you_id = duel.you.id.clone();
+        let mut others = duel
+            .board
+            .snakes
+            .iter_mut()
+            .filter(|s| s.id != you_id)
+            .collect_vec();
+        others[0].health = 0;
+        others[1].health = 0;
+        let survivor = others[2].head;
+        let best = duel.clone().to_best_cell_board().unwrap();
+        match &best {
+            BestCellBoard::MediumExactDuel(b) => {
+                assert_eq!(b.get_head_as_position(&SnakeId(1)), survivor)
+            }
+            _ => panic!("expected a duel board"),
+        }
+        let mut wrapped_duel = duel.clone();
+        wrapped_duel.game.ruleset.name = GameMode::Wrapped;
+        assert!(matches!(
+            wrapped::ToBestCellBoard::to_best_cell_board(wrapped_duel).unwrap(),
+            wrapped::BestCellBoard::MediumExactDuel(_)
+        ));
+    }
+
+    /// a game on a `size` square board with `count` snakes in rows, each three cells long and
+    /// facing up
+    fn royale_game(size: u32, count: usize) -> DEGame {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.width = size;
+        g.board.height = size;
+        g.board.food.clear();
+        let template = g.you.clone();
+        g.board.snakes = (0..count)
+            .map(|i| {
+                let head = Position::new((i % 12) as i32 * 2, (i / 12) as i32 * 4 + 2);
+                let mut snake = template.clone();
+                snake.id = format!("snake-{}", i);
+                snake.head = head;
+                snake.body = (0..3).map(|d| Position::new(head.x, head.y - d)).collect();
+                snake
+            })
+            .collect();
+        g.you = g.board.snakes[0].clone();
+        g
+    }
+
+    #[test]
+    fn test_best_cell_board_royale_tiers() {
+        assert!(matches!(
+            royale_game(25, 20).to_best_cell_board().unwrap(),
+            BestCellBoard::LargeRoyale(_)
+        ));
+        assert!(matches!(
+            royale_game(40, 32).to_best_cell_board().unwrap(),
+            BestCellBoard::SillyRoyale(_)
+        ));
+        assert!(royale_game(40, 33).to_best_cell_board().is_err());
+        let mut wrapped_royale = royale_game(25, 20);
+        wrapped_royale.game.ruleset.name = GameMode::Wrapped;
+        assert!(matches!(
+            wrapped::ToBestCellBoard::to_best_cell_board(wrapped_royale).unwrap(),
+            wrapped::BestCellBoard::LargeRoyale(_)
+        ));
+
+        // a 25x25 game with up to 8 snakes still fits the 8 snake board
+        assert!(matches!(
+            royale_game(25, 8).to_best_cell_board().unwrap(),
+            BestCellBoard::Large(_)
+        ));
+        let mut wrapped_large = royale_game(25, 8);
+        wrapped_large.game.ruleset.name = GameMode::Wrapped;
+        assert!(matches!(
+            wrapped::ToBestCellBoard::to_best_cell_board(wrapped_large).unwrap(),
+            wrapped::BestCellBoard::Large(_)
+        ));
+    }
+
+    #[test]
+    fn test_royale_conversion_and_simulation() {
+        let g = royale_game(25, 32);
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard32Snakes25x25 = g.as_cell_board(&id_map).unwrap();
+        assert_eq!(compact.get_snake_ids().len(), 32);
+        for snake in &g.board.snakes {
+            let body = compact
+                .get_snake_body_vec(&id_map[&snake.id])
+                .into_iter()
+                .map(|c| compact.position_from_native(c))
+                .collect_vec();
+            assert_eq!(body, snake.body.iter().copied().collect_vec());
+        }
+
+        // the last snake turns back in to its own neck, everyone else moves up
+        let last = g.board.snakes.len() - 1;
+        let moves = g
+            .board
+            .snakes
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.id.clone(), if i == last { Move::Down } else { Move::Up }))
+            .collect_vec();
+        let (_, wire) = SimulableGame::<Instruments, 32>::simulate_with_moves(
+            &g,
+            &Instruments,
+            moves.iter().map(|(id, mv)| (id.clone(), [*mv])),
+        )
+        .next()
+        .unwrap();
+        let (_, child) = compact
+            .simulate_with_moves(
+                &Instruments,
+                moves.iter().map(|(id, mv)| (id_map[id], [*mv])),
+            )
+            .next()
+            .unwrap();
+        assert_eq!(wire.board.snakes.len(), 31);
+        assert!(!child.is_alive(&id_map[&g.board.snakes[last].id]));
+        for snake in &wire.board.snakes {
+            let id = &id_map[&snake.id];
+            assert_eq!(child.get_health_i64(id), snake.health as i64);
+            let body = child
+                .get_snake_body_vec(id)
+                .into_iter()
+                .map(|c| child.position_from_native(c))
+                .collect_vec();
+            assert_eq!(body, snake.body.iter().copied().collect_vec());
+        }
+    }
+
+    #[test]
+    fn test_convert_many() {
+        let start = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&start);
+        let mut late = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        for (snake, id) in late.board.snakes.iter_mut().zip(id_map.keys()) {
+            snake.id = id.clone();
+        }
+        let mut wrapped = start.clone();
+        wrapped.game.ruleset.name = GameMode::Wrapped;
+        let games = vec![start, late, wrapped];
+
+        let one_at_a_time = games
+            .iter()
+            .map(|g| CellBoard4Snakes11x11::convert_from_game(g.clone(), &id_map).ok())
+            .collect_vec();
+        assert!(one_at_a_time[0].is_some() && one_at_a_time[1].is_some());
+        assert!(one_at_a_time[2].is_none());
+
+        let batched = CellBoard4Snakes11x11::convert_many(games.clone(), &id_map)
+            .map(|b| b.ok())
+            .collect_vec();
+        assert_eq!(batched, one_at_a_time);
+
+        #[cfg(feature = "rayon")]
+        {
+            let parallel = CellBoard4Snakes11x11::par_convert_many(&games, &id_map)
+                .into_iter()
+                .map(|b| b.ok())
+                .collect_vec();
+            assert_eq!(parallel, one_at_a_time);
+        }
+    }
+
+    #[test]
+    fn test_eliminated_you() {
+        // you died but the game goes on, so you're only in the payload as `you` with no body
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let you_id = g.you.id.clone();
+        g.board.snakes.retain(|s| s.id != you_id);
+        let mut extra = g.board.snakes[0].clone();
+        extra.id = "late joiner".to_string();
+        extra.head = extra.head.add_vec(Vector { x: 0, y: 3 });
+        for p in extra.body.iter_mut() {
+            *p = p.add_vec(Vector { x: 0, y: 3 });
+        }
+        g.board.snakes.push(extra);
+        let mut json = serde_json::to_value(&g).unwrap();
+        json["you"]["health"] = serde_json::json!(0);
+        json["you"]["body"] = serde_json::json!([]);
+        json["you"].as_object_mut().unwrap().remove("head");
+        let g: Game = serde_json::from_value(json).unwrap();
+
+        let id_map = build_snake_id_map(&g);
+        assert_eq!(id_map[&you_id], SnakeId(0));
+        assert_eq!(id_map.len(), 5);
+
+        let best = g.clone().to_best_cell_board().unwrap();
+        let board = match best {
+            BestCellBoard::LargestU8(board) => board,
+            _ => panic!("expected a board with room for 5 snakes"),
+        };
+        assert!(!board.is_alive(&SnakeId(0)));
+        assert!(board.is_over());
+        assert_eq!(board.get_snake_ids().len(), 4);
+        assert_eq!(board.try_get_snake_body_vec(&SnakeId(0)), None);
+
+        // the rest of the game can still be played out
+        let instruments = Instruments;
+        let moves = board
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| (id, Move::all()))
+            .collect_vec();
+        let children = board.simulate_with_moves(&instruments, moves).collect_vec();
+        assert!(!children.is_empty());
+        assert!(children
+            .iter()
+            .all(|(action, child)| action.try_own_move().is_none()
+                && child.embedded.assert_consistency()));
+
+        // a board with room for only 4 snakes can't fit your id on top of the others, which is
+        // an error rather than a panic
+        let too_small: Result<CellBoard4Snakes11x11, _> = g.as_cell_board(&id_map);
+        assert!(too_small.is_err());
+    }
+
+    #[test]
+    fn test_cached_board_summaries() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let check = |board: &CellBoard4Snakes11x11| {
+            assert_eq!(
+                board.food_count(),
+                board.get_all_food_as_native_positions().len()
+            );
+            let hazards = (0..121)
+                .filter(|i| board.is_hazard(&CellIndex::from_usize(*i)))
+                .count();
+            assert_eq!(board.hazard_count(), hazards);
+            for sid in board.get_snake_ids() {
+                let cells = board.get_snake_body_vec(&sid).into_iter().unique().count();
+                assert_eq!(board.body_cell_count(sid) as usize, cells);
+            }
+        };
+
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut board: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        check(&board);
+        let hazard = CellIndex::from_usize(0);
+        board.set_hazard(hazard);
+        board.set_hazard(hazard);
+        check(&board);
+        board.clear_hazard(hazard);
+        check(&board);
+
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut board: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        assert_eq!(board.food_count(), 5);
+        assert_eq!(board.body_cell_count(SnakeId(0)), 2);
+        check(&board);
+
+        let mut rng = SmallRng::seed_from_u64(5);
+        let instruments = Instruments;
+        while !board.is_over() {
+            let moves = board
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .collect_vec();
+            let before = board;
+            let patch = board.apply_moves(&moves);
+            check(&board);
+            board.undo(patch);
+            assert_eq!(board, before);
+
+            let (_, child) = board
+                .simulate_with_moves(&instruments, moves.iter().map(|(id, mv)| (*id, [*mv])))
+                .next()
+                .unwrap();
+            board = child;
+            check(&board);
+            board.place_food(&mut rng);
+            check(&board);
+            let packed = board.embedded.pack_as_hash();
+            assert_eq!(CCB::from_packed_hash(&packed), board.embedded);
+        }
+        let eliminated = (0..4)
+            .map(SnakeId)
+            .find(|sid| !board.is_alive(sid))
+            .unwrap();
+        assert_eq!(board.body_cell_count(eliminated), 0);
+    }
+
+    #[test]
+    fn test_assumed_health() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let instruments = Instruments;
+        let opponent = SnakeId(1);
+
+        let (low, high) = compact.with_health_range(&opponent, 1, 100);
+        assert_eq!(low.get_health(&opponent), 1);
+        assert_eq!(high.get_health(&opponent), 100);
+        let step = |board: &CellBoard4Snakes11x11| {
+            board
+                .simulate_with_moves(&instruments, vec![(opponent, [Move::Up].as_slice())])
+                .next()
+                .unwrap()
+                .1
+        };
+        assert!(!step(&low).is_alive(&opponent));
+        assert!(step(&high).is_alive(&opponent));
+
+        // zero health eliminates the snake, and eliminated snakes stay that way
+        let eliminated = compact.with_assumed_health(&opponent, 0);
+        assert!(!eliminated.is_alive(&opponent));
+        assert!(!eliminated
+            .position_is_snake_body(eliminated.native_from_position(Position::new(1, 5))));
+        assert!(eliminated.embedded.assert_consistency());
+        let still_eliminated = eliminated.with_assumed_health(&opponent, 50);
+        assert!(!still_eliminated.is_alive(&opponent));
+    }
+
+    #[test]
+    fn test_simulate_eight_snakes() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // start of game on a 15x15 board, with a copy of every snake moved up and to the right
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.width = 15;
+        g.board.height = 15;
+        g.board.food.clear();
+        let copies = g
+            .board
+            .snakes
+            .iter()
+            .map(|snake| {
+                let mut copy = snake.clone();
+                copy.id = format!("{}-copy", snake.id);
+                copy.head = copy.head.add_vec(Vector { x: 5, y: 5 });
+                for p in copy.body.iter_mut() {
+                    *p = p.add_vec(Vector { x: 5, y: 5 });
+                }
+                copy
+            })
+            .collect_vec();
+        g.board.snakes.extend(copies);
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard8Snakes15x15 = g.as_cell_board(&id_map).unwrap();
+        let instruments = Instruments;
+        let moves = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|id| (id, Move::all()))
+            .collect_vec();
+
+        let all = compact
+            .simulate_with_moves(&instruments, moves.clone())
+            .map(|(action, _)| action)
+            .collect_vec();
+        assert!(all.len() > 1000);
+
+        // children are only evaluated as they are consumed
+        let first = compact
+            .simulate_with_moves(&instruments, moves.clone())
+            .next()
+            .unwrap();
+        assert_eq!(first.0, all[0]);
+
+        let mut rng = SmallRng::seed_from_u64(11);
+        let sampled = compact
+            .simulate_with_moves_sampled(&instruments, moves.clone(), 64, &mut rng)
+            .collect_vec();
+        assert_eq!(sampled.len(), 64);
+        let sampled_actions = sampled.iter().map(|(action, _)| *action).collect_vec();
+        assert!(sampled_actions.iter().all(|action| all.contains(action)));
+        assert_eq!(sampled_actions.iter().unique().count(), 64);
+        let positions = sampled_actions
+            .iter()
+            .map(|action| all.iter().position(|a| a == action).unwrap())
+            .collect_vec();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        let (action, child) = &sampled[0];
+        let expected = compact
+            .simulate_with_moves(
+                &instruments,
+                action
+                    .into_inner()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(id, mv)| mv.map(|mv| (SnakeId(id as u8), [mv]))),
+            )
+            .next()
+            .unwrap()
+            .1;
+        assert_eq!(*child, expected);
+
+        // under the limit every child is simulated
+        let everything = compact
+            .simulate_with_moves_sampled(&instruments, moves, all.len(), &mut rng)
+            .map(|(action, _)| action)
+            .collect_vec();
+        assert_eq!(everything, all);
+    }
+
+    #[test]
+    fn test_simulate_sampled_many_snakes() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        // snakes stacked on single cells three apart, so every one of them has all four moves
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.width = 25;
+        g.board.height = 25;
+        g.board.food.clear();
+        let template = g.board.snakes[0].clone();
+        g.board.snakes = (0..36)
+            .map(|i| {
+                let head = Position::new(1 + 3 * (i % 8), 1 + 3 * (i / 8));
+                let mut snake = template.clone();
+                snake.id = format!("snake{}", i);
+                snake.head = head;
+                snake.body = vec![head; 3].into();
+                snake
+            })
+            .collect();
+        g.you = g.board.snakes[0].clone();
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard<u16, Square, { 25 * 25 }, 36> = g.as_cell_board(&id_map).unwrap();
+        let moves_for = |snakes: usize| {
+            compact
+                .get_snake_ids()
+                .into_iter()
+                .take(snakes)
+                .map(|id| (id, Move::all()))
+                .collect_vec()
+        };
+        let mut rng = SmallRng::seed_from_u64(3);
+
+        // 4^16 joint moves without a cap are produced lazily rather than listed up front
+        let mut lazy =
+            compact.simulate_with_moves_sampled(&Instruments, moves_for(16), usize::MAX, &mut rng);
+        let first = lazy.next().unwrap().0;
+        assert_eq!(
+            first,
+            compact
+                .simulate_with_moves(&Instruments, moves_for(16))
+                .next()
+                .unwrap()
+                .0
+        );
+
+        // 4^36 joint moves don't fit in a usize, each snake's move is sampled instead
+        let sampled = compact
+            .simulate_with_moves_sampled(&Instruments, moves_for(36), 32, &mut rng)
+            .map(|(action, _)| action)
+            .collect_vec();
+        assert_eq!(sampled.len(), 32);
+        assert_eq!(sampled.iter().unique().count(), 32);
+        let as_indices = |action: &Action<36>| {
+            action
+                .into_inner()
+                .iter()
+                .map(|mv| mv.unwrap().as_index())
+                .collect_vec()
+        };
+        assert!(sampled
+            .windows(2)
+            .all(|w| as_indices(&w[0]) < as_indices(&w[1])));
+        assert!(sampled
+            .iter()
+            .all(|action| action.into_inner().iter().all(|mv| mv.is_some())));
+    }
+
+    #[test]
+    fn test_set_hazard() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        assert!(c.is_food());
+        c.set_hazard();
+        assert!(c.is_food());
+        assert!(c.is_hazard());
+        assert!(!c.is_head());
+        assert!(!c.is_body());
+    }
+
+    #[test]
+    fn test_clear_hazard() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        assert!(c.is_food());
+        c.set_hazard();
+        c.clear_hazard();
+        assert!(c.is_food());
+        assert!(!c.is_hazard());
+        assert!(!c.is_head());
+        assert!(!c.is_body());
+        let mut c: Cell<u8> = Cell::make_double_stacked_piece(SnakeId(0), CellIndex(0));
+        c.set_hazard();
+        c.clear_hazard();
+        assert!(c.is_body());
+        assert!(!c.is_hazard());
+    }
+
+    #[test]
+    fn test_walls() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_wall();
+        assert!(c.is_wall());
+        assert!(c.is_hazard());
+        assert_eq!(Cell::<u8>::from_u32(c.pack_as_u32()), c);
+        c.clear_hazard();
+        assert!(!c.is_wall());
+        assert!(!c.is_hazard());
+
+        let g = game_fixture(include_str!("../../../fixtures/arcade_maze_map.json"));
+        assert!(g.has_hazard_walls());
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: wrapped::CellBoard<u16, ArcadeMaze, { 19 * 21 }, 4> =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+        assert!(!g.board.hazards.is_empty());
+        for pos in g.board.hazards.iter() {
+            assert!(g.is_wall(pos));
+            assert!(compact.is_wall(&compact.native_from_position(*pos)));
+        }
+        let walls = (0..19 * 21)
+            .filter(|i| compact.is_wall(&CellIndex::from_usize(*i)))
+            .count();
+        assert_eq!(walls, compact.hazard_count());
+
+        // hazards off the arcade maze map aren't walls, even when they're deadly, as a snake
+        // eating food on one takes no damage
+        let mut g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let hazard = g.board.hazards[0];
+        g.game
+            .ruleset
+            .settings
+            .as_mut()
+            .unwrap()
+            .hazard_damage_per_turn = 100;
+        assert!(!g.has_hazard_walls());
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        assert!(compact.is_hazard(&compact.native_from_position(hazard)));
+        assert!(!compact.is_wall(&compact.native_from_position(hazard)));
+        assert!(!g.is_wall(&hazard));
+    }
+
+    #[test]
+    fn test_reasonable_moves_onto_food_in_deadly_hazards() {
+        // you can only go Up, on to food in a hazard that deals more damage than you have health
+        let mut ruleset = game_fixture(include_str!("../../../fixtures/late_stage.json"))
+            .game
+            .ruleset;
+        ruleset.settings.as_mut().unwrap().hazard_damage_per_turn = 100;
+        let mut g = DEGame::from_ascii(
+            "
+            . f .
+            . 0 .
+            a a .
+            ",
+            ruleset,
+        )
+        .unwrap();
+        g.board.hazards = vec![
+            Position::new(1, 2),
+            Position::new(0, 1),
+            Position::new(2, 1),
+        ];
+        g.board.snakes[0].health = 50;
+        g.you.health = 50;
+
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let expected = vec![(SnakeId(0), vec![Move::Up])];
+        assert_eq!(
+            compact.reasonable_moves_for_each_snake().collect_vec(),
+            expected
+        );
+        assert_eq!(
+            g.reasonable_moves_for_each_snake().collect_vec(),
+            vec![("snake0".to_string(), vec![Move::Up])]
+        );
+        let (_, child) = compact
+            .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Up])])
+            .next()
+            .unwrap();
+        assert_eq!(child.get_health_i64(&SnakeId(0)), 100);
+    }
+
+    #[test]
+    fn test_reasonable_moves_hazard_damage_boundary() {
+        // the move costs 1 health before the hazard on the right does its damage
+        let mut ruleset = game_fixture(include_str!("../../../fixtures/late_stage.json"))
+            .game
+            .ruleset;
+        ruleset.settings.as_mut().unwrap().hazard_damage_per_turn = 14;
+        let mut g = DEGame::from_ascii(
+            "
+            . . .
+            . 0 x
+            a a .
+            ",
+            ruleset,
+        )
+        .unwrap();
+
+        for (health, right_is_reasonable) in [(15, false), (16, true)] {
+            g.board.snakes[0].health = health;
+            g.you.health = health;
+            let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            let moves = compact.reasonable_moves_for_each_snake().next().unwrap().1;
+            assert_eq!(
+                moves.contains(&Move::Right),
+                right_is_reasonable,
+                "{}",
+                health
+            );
+            let wire_moves = g.reasonable_moves_for_each_snake().next().unwrap().1;
+            assert_eq!(wire_moves, moves);
+
+            let (_, child) = compact
+                .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Right])])
+                .next()
+                .unwrap();
+            assert_eq!(child.is_alive(&SnakeId(0)), right_is_reasonable);
+        }
+    }
+
+    #[test]
+    fn test_walls_are_not_traversable() {
+        use crate::algorithms::pathing::{PathOptions, PathfindingGame};
+
+        let g = game_fixture(include_str!("../../../fixtures/arcade_maze_map.json"));
+        let compact: wrapped::CellBoard<u16, ArcadeMaze, { 19 * 21 }, 4> =
+            g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+        for i in 0..19 * 21 {
+            let ci = CellIndex::from_usize(i);
+            let pos = compact.position_from_native(ci);
+            let neighbors = compact
+                .neighbors(&ci)
+                .map(|n| compact.position_from_native(n))
+                .collect_vec();
+            assert!(neighbors.iter().all(|n| !g.is_wall(n)));
+            assert_eq!(neighbors, g.neighbors(&pos).collect_vec());
+        }
+        for (sid, moves) in compact.reasonable_moves_for_each_snake() {
+            let head = compact.get_head_as_position(&sid);
+            for mv in moves {
+                let new_head = compact
+                    .embedded
+                    .as_wrapped_cell_index(head.add_vec(mv.to_vector()));
+                assert!(!compact.is_wall(&new_head));
+            }
+        }
+
+        let distances = g.distance_matrix(&g.you.head, PathOptions::default());
+        assert_eq!(
+            distances,
+            compact.distance_matrix(
+                &compact.native_from_position(g.you.head),
+                PathOptions::default()
+            )
+        );
+        for (i, distance) in distances.iter().enumerate() {
+            assert_eq!(
+                distance.is_none(),
+                compact.is_wall(&CellIndex::from_usize(i)),
+                "cell {} on the arcade maze",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_food_never_spawns_in_walls() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut g = game_fixture(include_str!("../../../fixtures/arcade_maze_map.json"));
+        let mut compact: wrapped::CellBoard<u16, ArcadeMaze, { 19 * 21 }, 4> =
+            g.as_wrapped_cell_board(&build_snake_id_map(&g)).unwrap();
+        let empty = compact.get_empty_cells().collect_vec();
+        assert!(empty.iter().all(|c| !compact.is_wall(c)));
+        assert_eq!(
+            empty
+                .iter()
+                .map(|c| compact.position_from_native(*c))
+                .collect_vec(),
+            g.get_empty_cells().collect_vec()
+        );
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        for _ in 0..200 {
+            compact.place_food(&mut rng);
+            g.place_food(&mut rng);
+        }
+        assert!(compact.food_count() > 20);
+        assert!(g.board.food.len() > 20);
+        for pos in g.board.food.iter() {
+            assert!(!g.is_wall(pos), "food spawned in the wall at {:?}", pos);
+        }
+        for i in 0..19 * 21 {
+            let ci = CellIndex::from_usize(i);
+            assert!(!(compact.is_wall(&ci) && compact.is_food(&ci)));
+        }
+        let samples = compact.sample_food_placements(50, &mut rng);
+        for (board, _) in samples {
+            for food in board.get_all_food_as_native_positions() {
+                assert!(!board.is_wall(&food));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hazard_counts() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_hazard_count(3);
+        assert_eq!(c.get_hazard_count(), 3);
+        // a packed cell only knows that it's a hazard
+        assert_eq!(Cell::<u8>::from_u32(c.pack_as_u32()).get_hazard_count(), 1);
+
+        // late_stage lists (0, 0) twice
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let corner = Position::new(0, 0);
+        assert_eq!(g.get_hazard_count(&corner), 2);
+        assert_eq!(
+            compact.get_hazard_count(&compact.native_from_position(corner)),
+            2
+        );
+        let mut wire = g.clone();
+        wire.set_hazard_count(corner, 3);
+        assert_eq!(wire.get_hazard_count(&corner), 3);
+        assert_eq!(wire.board.hazards.len(), g.board.hazards.len() + 1);
+
+        // each stacked hazard does damage
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let mut compact = compact.with_hazard_damage(15);
+        let below = compact.native_from_position(Position::new(5, 7));
+        compact.set_hazard_count(below, 2);
+        assert_eq!(compact.hazard_count(), 1);
+        let health = compact.get_health(&SnakeId(0));
+        let (_, child) = compact
+            .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Down])])
+            .next()
+            .unwrap();
+        assert_eq!(child.get_health(&SnakeId(0)), health - 1 - 2 * 15);
+
+        // and the counts survive serializing
+        let json = serde_json::to_string(&compact).unwrap();
+        let back: CellBoard4Snakes11x11 = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, compact);
+        assert_eq!(back.get_hazard_count(&below), 2);
+        let packed = compact.embedded.pack_as_hash();
+        assert_eq!(CCB::from_packed_hash(&packed), compact.embedded);
+
+        compact.set_hazard_count(below, 0);
+        assert!(!compact.is_hazard(&below));
+        assert_eq!(compact.hazard_count(), 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(3), CellIndex(17));
+        c.remove();
+        c.set_hazard();
+        assert!(c.is_empty());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().is_none());
+        assert!(c.get_idx() == CellIndex(0));
+    }
+    #[test]
+    fn test_set_food() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_food();
+        c.set_hazard();
+        assert!(c.is_food());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().is_none());
+        assert!(c.get_idx() == CellIndex(0));
+    }
+
+    #[test]
+    fn test_food_beneath_snake() {
+        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(1), CellIndex(4));
+        c.set_food();
+        assert!(c.is_food());
+        assert!(c.is_body());
+        assert_eq!(Cell::from_u32(c.pack_as_u32()), c);
+        c.remove();
+        assert!(c.is_food());
+        assert!(!c.is_empty());
+        c.clear_food();
+        assert!(c.is_empty());
+
+        // boards packed before food became a flag stored food as its own cell kind
+        let legacy = Cell::<u8>::from_u32(0x14);
+        assert!(legacy.is_food());
+        assert!(legacy.is_hazard());
+        assert!(legacy.get_snake_id().is_none());
+    }
+
+    #[test]
+    fn test_food_persists_beneath_bodies() {
+        let mut g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        // food beneath the tail and a middle segment of `you`, as maps that place food regardless
+        // of snakes produce
+        let tail = Position::new(5, 7);
+        let middle = Position::new(5, 5);
+        g.board.food.push(tail);
+        g.board.food.push(middle);
+        let id_map = build_snake_id_map(&g);
+        let you = *id_map.get(&g.you.id).unwrap();
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let native = |pos| compact.native_from_position(pos);
+
+        let mut food = compact.get_all_food_as_positions();
+        food.sort_by_key(|p| (p.x, p.y));
+        let mut expected = g.board.food.clone();
+        expected.sort_by_key(|p| (p.x, p.y));
+        assert_eq!(food, expected);
+        assert!(compact.position_is_snake_body(native(middle)));
+        assert!(compact.position_is_snake_body(native(tail)));
+
+        // moving off the tail leaves the food where it was, and it isn't free to spawn on
+        let instruments = Instruments;
+        let step = |board: &CellBoard4Snakes11x11, mv: Move| {
+            board
+                .simulate_with_moves(&instruments, vec![(you, [mv].as_slice())])
+                .next()
+                .unwrap()
+                .1
+        };
+        let moved = step(&compact, Move::Up);
+        assert!(!moved.position_is_snake_body(native(tail)));
+        assert!(moved.is_food(&native(tail)));
+        assert!(moved.is_food(&native(middle)));
+        assert!(!moved.get_empty_cells().any(|c| c == native(tail)));
+        assert_eq!(moved.get_length(&you), 7);
+
+        // and the next snake to put its head there eats it
+        let ate = step(&moved, Move::Right);
+        assert!(!ate.is_food(&native(tail)));
+        assert!(ate.is_food(&native(middle)));
+        assert_eq!(ate.get_length(&you), 8);
+        assert_eq!(ate.get_health(&you), 100);
+    }
+
+    #[test]
+    fn test_set_head() {
+        let mut c: Cell<u8> = Cell::empty();
+        c.set_head(SnakeId(3), CellIndex(17));
+        c.set_hazard();
+        assert!(c.is_head());
+        assert!(c.is_hazard());
+        assert!(c.get_snake_id().unwrap() == SnakeId(3));
+        assert!(c.get_idx() == CellIndex(17));
+    }
+
+    #[test]
+    fn test_food_queryable() {
+        let game_fixture = include_str!("../../../fixtures/late_stage.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        assert!(!compact.is_food(&CellIndex(6 * 11 + 4)));
+
+        assert!(compact.is_food(&CellIndex(2 * 11)));
+        assert!(compact.is_food(&CellIndex(9 * 11)));
+        assert!(compact.is_food(&CellIndex(3 * 11 + 4)));
+    }
+
+    #[test]
+    fn test_neighbors_and_possible_moves_start_of_game() {
+        let game_fixture = include_str!("../../../fixtures/start_of_game.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let head = compact.get_head_as_native_position(&SnakeId(0));
+        assert_eq!(head, CellIndex(8 * 11 + 5));
+
+        let expected_possible_moves = vec![
+            (Move::Up, CellIndex(9 * 11 + 5)),
+            (Move::Down, CellIndex(7 * 11 + 5)),
+            (Move::Left, CellIndex(8 * 11 + 4)),
+            (Move::Right, CellIndex(8 * 11 + 6)),
+        ];
+
+        assert_eq!(
+            compact.possible_moves(&head).collect::<Vec<_>>(),
+            expected_possible_moves
+        );
+
+        assert_eq!(
+            compact.neighbors(&head).collect::<Vec<_>>(),
+            expected_possible_moves
+                .into_iter()
+                .map(|(_, pos)| pos)
+                .collect::<Vec<_>>()
         );
     }
 
@@ -502,6 +2294,543 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_simulate_across_threads() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let shared = std::sync::Arc::new(compact);
+
+        let handles = Move::all_iter()
+            .map(|mv| {
+                let board = shared.clone();
+                std::thread::spawn(move || {
+                    board
+                        .simulate_with_moves(&Instruments, vec![(SnakeId(0), [mv].as_slice())])
+                        .count()
+                })
+            })
+            .collect_vec();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+
+        // the iterators themselves can be handed to another thread
+        let children = compact.simulate(&Instruments, compact.get_snake_ids());
+        let body = compact.get_snake_body_iter(&SnakeId(0));
+        let empty = compact.get_empty_cells();
+        let (children, body, empty) = std::thread::scope(|scope| {
+            scope
+                .spawn(move || (children.count(), body.count(), empty.count()))
+                .join()
+                .unwrap()
+        });
+        assert!(children > 0 && body > 0 && empty > 0);
+    }
+
+    fn owned_children(
+        board: CellBoard4Snakes11x11,
+    ) -> Box<dyn Iterator<Item = (Action<4>, CellBoard4Snakes11x11)>> {
+        let ids = board.get_snake_ids();
+        board.into_simulate(&Instruments, ids)
+    }
+
+    #[test]
+    fn test_into_simulate_matches_simulate() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+
+        let borrowed = compact
+            .simulate(&Instruments, compact.get_snake_ids())
+            .collect_vec();
+        let owned = owned_children(compact).collect_vec();
+        assert_eq!(borrowed, owned);
+    }
+
+    #[test]
+    fn test_squad_victory() {
+        let mut g = game_fixture(include_str!("../../../fixtures/4_snake_game.json"));
+        for (i, snake) in g.board.snakes.iter_mut().enumerate() {
+            snake.squad = Some(if i < 2 { "a" } else { "b" }.to_string());
+        }
+        let snake_ids = build_snake_id_map(&g);
+        let sid = |g: &DEGame, i: usize| *snake_ids.get(&g.board.snakes[i].id).unwrap();
+
+        let compact = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap();
+        assert!(compact.are_squadmates(sid(&g, 0), sid(&g, 1)));
+        assert!(!compact.are_squadmates(sid(&g, 1), sid(&g, 2)));
+        assert!(!compact.is_over());
+        assert!(!g.is_over());
+        assert!(compact.has_squads());
+        assert_eq!(
+            crate::compact_representation::core::CellBoard::from_packed_hash(
+                &compact.embedded.pack_as_hash()
+            ),
+            compact.embedded
+        );
+
+        g.board.snakes[2].health = 0;
+        g.board.snakes[3].health = 0;
+        let compact = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap();
+        assert!(compact.is_over());
+        assert!(g.is_over());
+        let winner = compact.get_winner().unwrap();
+        assert!(winner == sid(&g, 0) || winner == sid(&g, 1));
+        assert_eq!(g.get_winner(), Some(g.board.snakes[0].id.clone()));
+
+        // the last two squadmates starving on the same turn is a draw
+        g.board.snakes[0].health = 1;
+        g.board.snakes[1].health = 1;
+        g.board.food.clear();
+        let compact = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap();
+        let moves = compact
+            .reasonable_moves_for_each_snake()
+            .map(|(sid, mvs)| (sid, [mvs[0]]))
+            .collect_vec();
+        let (_, child) = compact
+            .simulate_with_moves(
+                &Instruments,
+                moves.iter().map(|(sid, mv)| (*sid, mv.as_slice())),
+            )
+            .collect_vec()
+            .remove(0);
+        assert_eq!(child.alive_snake_count(), 0);
+        assert!(child.is_over());
+        assert_eq!(child.get_winner(), None);
+
+        // a snake outside of any squad isn't on the squad named after its id
+        let mut g = game_fixture(include_str!("../../../fixtures/4_snake_game.json"));
+        g.board.snakes.truncate(2);
+        let solo_id = g.board.snakes[0].id.clone();
+        g.board.snakes[1].squad = Some(solo_id);
+        g.you = g.board.snakes[0].clone();
+        let snake_ids = build_snake_id_map(&g);
+        let compact = CellBoard4Snakes11x11::convert_from_game(g.clone(), &snake_ids).unwrap();
+        assert!(!g.is_over());
+        assert!(!compact.is_over());
+    }
+
+    #[test]
+    fn test_simulate_with_policy() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+
+        let policy = |sid: &SnakeId| {
+            if sid.0 == 0 {
+                vec![(Move::Up, 1.0)]
+            } else {
+                vec![
+                    (Move::Up, 0.5),
+                    (Move::Down, 0.25),
+                    (Move::Left, 0.25),
+                    (Move::Right, 0.0),
+                ]
+            }
+        };
+        let children = compact
+            .simulate_with_policy(&Instruments, compact.get_snake_ids(), policy)
+            .collect_vec();
+
+        let total: f32 = children.iter().map(|(_, w, _)| w).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+        assert!(children.iter().all(|(action, weight, _)| {
+            action.into_inner()[0] == Some(Move::Up) && *weight > 0.0
+        }));
+        assert!(children
+            .iter()
+            .all(|(action, _, _)| !action.into_inner().contains(&Some(Move::Right))));
+
+        // the snake at (0, 5) can't go Left, so its Left share is spread over Up and Down. The
+        // snake at (4, 1) keeps the 2:1:1 split
+        let p_up = |sid: usize| {
+            children
+                .iter()
+                .filter(|(a, _, _)| a.into_inner()[sid] == Some(Move::Up))
+                .map(|(_, w, _)| w)
+                .sum::<f32>()
+        };
+        assert!((p_up(1) - 2.0 / 3.0).abs() < 1e-5);
+        assert!((p_up(2) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_simulate_with_policy_all_zero() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_ids).unwrap();
+
+        let policy = |_: &SnakeId| Move::all_iter().map(|mv| (mv, 0.0)).collect_vec();
+        let children = compact
+            .simulate_with_policy(&Instruments, compact.get_snake_ids(), policy)
+            .collect_vec();
+
+        // the first move that doesn't go back in to the neck: you at (5, 8) and the snake at
+        // (9, 0) have their necks above them
+        assert_eq!(children.len(), 1);
+        let (action, weight, _) = &children[0];
+        assert!((weight - 1.0).abs() < 1e-5);
+        assert_eq!(
+            action.into_inner(),
+            [
+                Some(Move::Down),
+                Some(Move::Up),
+                Some(Move::Up),
+                Some(Move::Down)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duel_head_to_head() {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.snakes.truncate(2);
+        g.board.food.clear();
+        let other = &mut g.board.snakes[0];
+        other.head = Position::new(5, 6);
+        other.body = vec![
+            Position::new(5, 6),
+            Position::new(5, 5),
+            Position::new(5, 5),
+        ]
+        .into();
+
+        let snake_id_mapping = build_snake_id_map(&g);
+        let moves = [
+            (SnakeId(0), [Move::Down].as_slice()),
+            (SnakeId(1), [Move::Up].as_slice()),
+        ];
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let (_, equal_lengths) = compact
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+        assert!(!equal_lengths.is_alive(&SnakeId(0)));
+        assert!(!equal_lengths.is_alive(&SnakeId(1)));
+        let collision = CellIndex::new(Position::new(5, 7), 11);
+        assert!(equal_lengths.get_all_empty().any(|ci| ci == collision));
+
+        g.board.snakes[0].body = vec![
+            Position::new(5, 6),
+            Position::new(5, 5),
+            Position::new(5, 4),
+            Position::new(5, 4),
+        ]
+        .into();
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let (_, longer_other) = compact
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+        assert!(!longer_other.is_alive(&SnakeId(0)));
+        assert!(longer_other.is_alive(&SnakeId(1)));
+        assert_eq!(
+            longer_other.get_head_as_position(&SnakeId(1)),
+            Position::new(5, 7)
+        );
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn test_simulate_in_context_matches_simulate() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let moves = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all()))
+            .collect_vec();
+
+        let expected = compact
+            .simulate_with_moves(&Instruments, moves.clone())
+            .collect_vec();
+        let mut ctx = crate::compact_representation::SimulationContext::new();
+        for _ in 0..2 {
+            let actual = compact
+                .simulate_with_moves_in(&ctx, &Instruments, moves.clone())
+                .collect_vec();
+            assert_eq!(expected, actual);
+            ctx.reset();
+        }
+    }
+
+    #[test]
+    fn test_simulate_with_outcomes() {
+        for fixture in [
+            include_str!("../../../fixtures/head_to_head_on_food.json"),
+            include_str!("../../../fixtures/body_collision.json"),
+            include_str!("../../../fixtures/cornered.json"),
+            include_str!("../../../fixtures/late_stage.json"),
+        ] {
+            let g = game_fixture(fixture);
+            let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            let ids = board.get_snake_ids();
+            let plain = board.simulate(&Instruments, ids.clone()).collect_vec();
+            let moves = ids.iter().map(|sid| (*sid, Move::all())).collect_vec();
+            let with_outcomes = board
+                .simulate_with_outcomes(&Instruments, moves)
+                .collect_vec();
+            assert_eq!(plain.len(), with_outcomes.len());
+
+            for ((action, child), (outcome_action, outcome, outcome_child)) in
+                plain.iter().zip(with_outcomes.iter())
+            {
+                assert_eq!(action, outcome_action);
+                assert_eq!(child, outcome_child);
+                for (i, mv) in action.into_inner().iter().enumerate() {
+                    let sid = SnakeId(i as u8);
+                    let snake = outcome.get(sid);
+                    assert_eq!(mv.is_some(), snake.is_some());
+                    if let (Some(mv), Some(snake)) = (mv, snake) {
+                        assert_eq!(snake.died.is_some(), !child.is_alive(&sid));
+                        let new_head = board.get_head_as_position(&sid).add_vec(mv.to_vector());
+                        if !board.off_board(new_head) {
+                            let ci = board.native_from_position(new_head);
+                            assert_eq!(snake.ate_food, board.embedded.cell_is_food(ci));
+                        }
+                    }
+                }
+            }
+        }
+
+        let g = game_fixture(include_str!("../../../fixtures/head_to_head_on_food.json"));
+        let id_map = build_snake_id_map(&g);
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let moves = [
+            (id_map["long"], [Move::Right]),
+            (id_map["short"], [Move::Left]),
+            (id_map["left_twin"], [Move::Right]),
+            (id_map["right_twin"], [Move::Left]),
+        ];
+        let (_, outcome, _) = board
+            .simulate_with_outcomes(&Instruments, moves)
+            .next()
+            .unwrap();
+        let long = outcome.get(id_map["long"]).unwrap();
+        assert!(long.ate_food);
+        assert_eq!(long.damage_taken, 1);
+        assert_eq!(long.died, None);
+        for loser in ["short", "left_twin", "right_twin"] {
+            let snake = outcome.get(id_map[loser]).unwrap();
+            assert!(snake.ate_food);
+            assert_eq!(snake.died, Some(DeathCause::HeadToHead));
+        }
+    }
+
+    #[test]
+    fn test_death_causes() {
+        fn cause(board: &CellBoard4Snakes11x11, sid: SnakeId, mv: Move) -> Option<DeathCause> {
+            let (_, outcome, _) = board
+                .simulate_with_outcomes(&Instruments, [(sid, [mv])])
+                .next()
+                .unwrap();
+            outcome.get(sid).unwrap().died
+        }
+        let board = |g: &DEGame| -> CellBoard4Snakes11x11 {
+            g.as_cell_board(&build_snake_id_map(g)).unwrap()
+        };
+
+        // you have your head on (4, 6) with your neck below it and your body to the right, and the
+        // other snake's head is on (7, 7) next to your body
+        let mut g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let (you, other) = (SnakeId(0), SnakeId(1));
+        let mut late_stage = board(&g);
+        assert_eq!(cause(&late_stage, you, Move::Up), None);
+        for mv in [Move::Down, Move::Right] {
+            assert_eq!(cause(&late_stage, you, mv), Some(DeathCause::SelfCollision));
+        }
+        assert_eq!(
+            cause(&late_stage, other, Move::Left),
+            Some(DeathCause::BodyCollision)
+        );
+        assert_eq!(
+            cause(&late_stage.with_health(you, 1), you, Move::Left),
+            Some(DeathCause::Starvation)
+        );
+
+        late_stage.set_hazard(late_stage.native_from_position(Position::new(4, 7)));
+        assert_eq!(
+            cause(&late_stage.with_health(you, 10), you, Move::Up),
+            Some(DeathCause::Hazard)
+        );
+        assert_eq!(
+            cause(&late_stage.with_health(you, 1), you, Move::Up),
+            Some(DeathCause::Starvation)
+        );
+        g.board.hazards.push(Position::new(4, 7));
+        g.game
+            .ruleset
+            .settings
+            .as_mut()
+            .unwrap()
+            .hazard_damage_per_turn = 100;
+        // deadly hazards still aren't walls off the arcade maze map
+        assert_eq!(cause(&board(&g), you, Move::Up), Some(DeathCause::Hazard));
+        // the hazard does nothing to a snake eating on it
+        g.board.food.push(Position::new(4, 7));
+        let (_, outcome, child) = board(&g)
+            .with_health(you, 10)
+            .simulate_with_outcomes(&Instruments, [(you, [Move::Up])])
+            .next()
+            .unwrap();
+        let eaten = outcome.get(you).unwrap();
+        assert_eq!((eaten.died, eaten.damage_taken), (None, 1));
+        assert_eq!(child.get_health(&you), 100);
+
+        let cornered = board(&game_fixture(include_str!(
+            "../../../fixtures/cornered.json"
+        )));
+        assert_eq!(
+            cause(&cornered, you, Move::Up),
+            Some(DeathCause::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_simulate_with_post_turn_rule() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let board: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let ids = board.get_snake_ids();
+        let moves = ids.iter().map(|sid| (*sid, Move::all())).collect_vec();
+        let expected = board.simulate_with_moves(&Instruments, moves.clone());
+
+        // an aura that hurts everyone but you, and eliminates anyone left below 50 health
+        let aura = |_: &Action<4>, child: &mut CellBoard4Snakes11x11| {
+            for sid in child.get_snake_ids() {
+                let health = child.get_health(&sid);
+                if !child.is_you(&sid) {
+                    child.set_health(&sid, if health < 55 { 0 } else { health - 5 });
+                }
+            }
+        };
+        let actual = board.simulate_with_post_turn_rule(&Instruments, moves, aura);
+
+        let mut count = 0;
+        for ((expected_action, plain), (action, child)) in expected.zip(actual) {
+            count += 1;
+            assert_eq!(expected_action, action);
+            for sid in ids.iter() {
+                let health = plain.get_health(sid);
+                if !plain.is_alive(sid) || board.is_you(sid) {
+                    assert_eq!(child.get_health(sid), health);
+                } else if health < 55 {
+                    assert!(!child.is_alive(sid));
+                } else {
+                    assert_eq!(child.get_health(sid), health - 5);
+                }
+            }
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn test_simulate_batch_matches_simulate() {
+        let boards: Vec<CellBoard4Snakes11x11> = [
+            include_str!("../../../fixtures/late_stage.json"),
+            include_str!("../../../fixtures/start_of_game.json"),
+        ]
+        .iter()
+        .map(|fixture| {
+            let g = game_fixture(fixture);
+            g.as_cell_board(&build_snake_id_map(&g)).unwrap()
+        })
+        .collect();
+
+        let mut joint_moves = vec![];
+        let mut expected = vec![];
+        for board in boards.iter() {
+            let ids = board.get_snake_ids();
+            for (action, child) in board.simulate(&Instruments, ids) {
+                let moves = action
+                    .into_inner()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, m)| m.map(|m| (SnakeId(i as u8), m)))
+                    .collect_vec();
+                joint_moves.push((board, moves));
+                expected.push(child);
+            }
+        }
+
+        let grouped = joint_moves
+            .iter()
+            .map(|(board, moves)| (*board, moves.as_slice()))
+            .collect_vec();
+        let actual = CellBoard4Snakes11x11::simulate_batch(&grouped).collect_vec();
+        assert_eq!(actual, expected);
+
+        // alternating between boards means the state is prepared again for every entry
+        let (late, start): (Vec<_>, Vec<_>) =
+            (0..grouped.len()).partition(|i| std::ptr::eq(grouped[*i].0, &boards[0]));
+        let order = late
+            .iter()
+            .zip(start.iter())
+            .flat_map(|(a, b)| [*a, *b])
+            .collect_vec();
+        let interleaved = order.iter().map(|i| grouped[*i]).collect_vec();
+        let actual = CellBoard4Snakes11x11::simulate_batch(&interleaved).collect_vec();
+        assert_eq!(actual, order.iter().map(|i| expected[*i]).collect_vec());
+    }
+
+    #[test]
+    fn test_iter_cells() {
+        let count = |fixture: &str, matches: fn(&CellContents) -> bool| {
+            let g = game_fixture(fixture);
+            let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            compact
+                .iter_cells()
+                .filter(|(_, contents)| matches(contents))
+                .count()
+        };
+        // every snake is on one cell on turn 0, and has a doubled tail on turn 1
+        assert_eq!(
+            count(
+                include_str!("../../../fixtures/this_one_crashed.json"),
+                |c| matches!(c, CellContents::TripleStacked { .. })
+            ),
+            4
+        );
+        assert_eq!(
+            count(
+                include_str!("../../../fixtures/start_of_game.json"),
+                |c| matches!(c, CellContents::DoubleStacked { .. })
+            ),
+            4
+        );
+
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let cells = compact.iter_cells().collect_vec();
+        assert_eq!(cells.len(), 11 * 11);
+        for (idx, contents) in cells.iter() {
+            match *contents {
+                CellContents::Head { sid } => {
+                    assert_eq!(compact.get_head_as_native_position(&sid), *idx)
+                }
+                CellContents::BodySegment { sid } | CellContents::DoubleStacked { sid } => {
+                    assert!(compact.get_snake_body_vec(&sid).contains(idx))
+                }
+                CellContents::Food => assert!(compact.is_food(idx)),
+                CellContents::Hazard { count } => {
+                    assert!(!compact.position_is_snake_body(*idx));
+                    assert_eq!(compact.get_hazard_count(idx), count)
+                }
+                CellContents::Empty => assert!(compact.get_empty_cells().contains(idx)),
+                other => panic!("{:?} isn't in this fixture", other),
+            }
+        }
+        // (0, 0) is listed twice in the fixture's hazards
+        assert_eq!(cells[0].1, CellContents::Hazard { count: 2 });
+        let heads = cells
+            .iter()
+            .filter(|(_, contents)| matches!(contents, CellContents::Head { .. }))
+            .count();
+        assert_eq!(heads, 2);
+    }
+
     #[test]
     fn test_tail_chase() {
         let game_fixture = include_str!("../../../fixtures/tail_chase.json");
@@ -518,4 +2847,149 @@ mod test {
 
         assert_eq!(reasonable_moves_for_me, vec![Move::Up]);
     }
+
+    #[test]
+    #[cfg(feature = "snail-mode")]
+    fn test_snail_mode_trails() {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.game.map = Some("snail_mode".to_string());
+        let snake_id_mapping = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        assert!(compact.snail_mode());
+        let width = compact.embedded.get_actual_width();
+        let at = |x, y| CellIndex::new(Position::new(x, y), width);
+        let step = |board: &CellBoard4Snakes11x11| {
+            board
+                .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Down])])
+                .next()
+                .unwrap()
+                .1
+        };
+
+        // the tail is still stacked from the start of the game, so there's no trail yet
+        compact = step(&compact);
+        assert_eq!(compact.hazard_count(), 0);
+
+        compact = step(&compact);
+        assert_eq!(compact.get_hazard_count(&at(5, 9)), 3);
+        assert_eq!(compact.hazard_count(), 1);
+
+        // eating doesn't stop this turn's trail, and the last one decays
+        compact = step(&compact);
+        assert_eq!(compact.get_length(&SnakeId(0)), 4);
+        assert_eq!(compact.get_hazard_count(&at(5, 8)), 3);
+        assert_eq!(compact.get_hazard_count(&at(5, 9)), 2);
+
+        // stacked hazards deal damage once per hazard in the stack
+        let health = compact.get_health(&SnakeId(0));
+        compact.set_hazard_count(at(5, 4), 2);
+        compact = step(&compact);
+        let damage = compact.get_hazard_damage() as u16 * 2 + 1;
+        assert_eq!(
+            compact.get_health(&SnakeId(0)) as u16,
+            health as u16 - damage
+        );
+        assert_eq!(compact.get_hazard_count(&at(5, 4)), 1);
+
+        let mut off = compact;
+        off.set_snail_mode(false);
+        let next = step(&off);
+        assert_eq!(next.get_hazard_count(&at(5, 8)), 2);
+    }
+
+    #[test]
+    fn test_serde_round_trips_non_square_boards() {
+        let mut g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        g.board.height = 13;
+        let snake_id_mapping = build_snake_id_map(&g);
+        let board: CellBoard<u8, Custom, { 11 * 13 }, 4> =
+            g.as_cell_board(&snake_id_mapping).unwrap();
+        assert_eq!(board.get_height(), 13);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let back: CellBoard<u8, Custom, { 11 * 13 }, 4> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, board);
+        assert_eq!(back.get_height(), 13);
+
+        // packed hashes keep the height too
+        let packed = board.embedded.pack_as_hash();
+        assert_eq!(CCB::from_packed_hash(&packed), board.embedded);
+    }
+
+    #[test]
+    fn test_turn() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        assert_eq!(compact.turn(), g.turn());
+
+        let (_, child) = compact
+            .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Up])])
+            .next()
+            .unwrap();
+        assert_eq!(child.turn(), compact.turn() + 1);
+        let wrapped: wrapped::CellBoard4SnakesSquare11x11 = {
+            let mut wrapped_game = g.clone();
+            wrapped_game.game.ruleset.name = GameMode::Wrapped;
+            wrapped_game.as_wrapped_cell_board(&id_map).unwrap()
+        };
+        let (_, wrapped_child) = wrapped
+            .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Up])])
+            .next()
+            .unwrap();
+        assert_eq!(wrapped_child.turn(), child.turn());
+
+        let patch = compact.apply_moves(&[(SnakeId(0), Move::Up)]);
+        assert_eq!(compact, child);
+        compact.undo(patch);
+        assert_eq!(compact.turn(), g.turn());
+
+        let json = serde_json::to_string(&child).unwrap();
+        let back: CellBoard4Snakes11x11 = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.turn(), child.turn());
+        let packed = child.embedded.pack_as_hash();
+        assert_eq!(CCB::from_packed_hash(&packed), child.embedded);
+    }
+
+    #[test]
+    fn test_copy_with_tweaks() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let mut compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        compact.set_hazard(compact.native_from_position(Position::new(5, 7)));
+        let health_after_moving_down = |board: &CellBoard4Snakes11x11| {
+            let (_, child) = board
+                .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Down])])
+                .next()
+                .unwrap();
+            child.get_health(&SnakeId(0))
+        };
+
+        let health = compact.get_health(&SnakeId(0));
+        let harmless = compact.with_hazard_damage(0);
+        assert_eq!(harmless.get_hazard_damage(), 0);
+        assert_eq!(health_after_moving_down(&harmless), health - 1);
+        assert_eq!(
+            health_after_moving_down(&compact),
+            health - 1 - compact.get_hazard_damage()
+        );
+
+        let weakened = compact.with_health(SnakeId(1), 10);
+        assert_eq!(weakened.get_health(&SnakeId(1)), 10);
+        assert_eq!(compact.get_health(&SnakeId(1)), health);
+        let eliminated = compact.with_health(SnakeId(1), 0);
+        assert!(!eliminated.is_alive(&SnakeId(1)));
+        assert_eq!(eliminated.with_health(SnakeId(1), 50), eliminated);
+
+        assert_ne!(compact.with_healing_regen(7), compact);
+
+        let later = compact.with_turn(200);
+        assert_eq!(later.turn(), 200);
+        assert_eq!(compact.turn(), g.turn as u64);
+        let (_, child) = later
+            .simulate_with_moves(&Instruments, vec![(SnakeId(0), [Move::Down])])
+            .next()
+            .unwrap();
+        assert_eq!(child.turn(), 201);
+    }
 }
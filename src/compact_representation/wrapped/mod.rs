@@ -4,8 +4,10 @@ use crate::types::*;
 
 /// you almost certainly want to use the `convert_from_game` method to
 /// cast from a json represention to a `CellBoard`
+use crate::algorithms::flood_fill::AreaControlGame;
 use crate::types::{NeighborDeterminableGame, SnakeBodyGettableGame};
 use crate::wire_representation::Game;
+use fxhash::FxHasher64;
 use itertools::Itertools;
 use rand::seq::SliceRandom;
 use rand::Rng;
@@ -13,6 +15,7 @@ use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
 use crate::{
     types::{Action, Move, SimulableGame, SimulatorInstruments},
@@ -20,15 +23,23 @@ use crate::{
 };
 
 use super::core::{simulate_with_moves, EvaluateMode};
-use super::core::{CellBoard as CCB, CellIndex};
+use super::core::{CellBoard as CCB, CellIndex, ConversionScratch};
 use super::dimensions::{ArcadeMaze, Custom, Dimensions, Fixed, Square};
+use super::BestCellBoardVisitor;
+use super::BoardPatch;
+use super::CellContents;
 use super::CellNum as CN;
+use super::OverlayDisplay;
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// Serializes to a flat layout of plain integers that works with binary formats as well as JSON.
+/// The board type isn't written, deserializing in to a type too small for the board is an error
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent, bound = "")]
 pub struct CellBoard<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> {
-    embedded: CCB<T, D, BOARD_SIZE, MAX_SNAKES>,
+    pub(super) embedded: CCB<T, D, BOARD_SIZE, MAX_SNAKES>,
 }
 
 impl_common_board_traits!(CellBoard);
@@ -41,26 +52,444 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         self.embedded.assert_consistency()
     }
 
+    /// Reinterprets this board as a standard board, e.g. to reuse tooling that only supports
+    /// standard games. The cells, snakes, and hazards are kept as they are, including snake
+    /// bodies that currently cross an edge, only simulation changes: moving off an edge now
+    /// kills the snake rather than wrapping around
+    pub fn into_standard(self) -> super::standard::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES> {
+        super::standard::CellBoard {
+            embedded: self.embedded,
+        }
+    }
+
+    /// the width of the game being played
+    pub fn actual_width(&self) -> u8 {
+        self.embedded.get_actual_width()
+    }
+
+    /// the height of the game being played
+    pub fn actual_height(&self) -> u8 {
+        self.embedded.get_actual_height()
+    }
+
+    /// the number of cells per row in the cell array, i.e. the width to give [CellIndex::new]
+    /// and [CellIndex::into_position]. Rows aren't padded, so this is the actual width, but index
+    /// math should use this rather than assuming the board is square
+    pub fn stored_width(&self) -> u8 {
+        self.embedded.stored_width()
+    }
+
+    /// whether simulating applies the snail mode map's hazard trails, see
+    /// [CellBoard::set_snail_mode]
+    #[cfg(feature = "snail-mode")]
+    pub fn snail_mode(&self) -> bool {
+        self.embedded.snail_mode()
+    }
+
+    /// In snail mode, at the end of every turn each stack of hazards loses one hazard, then every
+    /// snake that moved leaves a stack of as many hazards as it was long on the cell its tail
+    /// left. A snake whose tail is stacked, because it just ate or spawned, leaves nothing.
+    /// Converting a game on the `snail_mode` map turns this on
+    #[cfg(feature = "snail-mode")]
+    pub fn set_snail_mode(&mut self, snail_mode: bool) {
+        self.embedded.set_snail_mode(snail_mode)
+    }
+
+    /// the least food on the board and the percent chance of spawning more, which
+    /// [StandardFoodPlaceableGame::place_food] uses
+    pub fn food_spawn_settings(&self) -> (u8, u8) {
+        self.embedded.food_spawn_settings()
+    }
+
+    /// Changes the settings [StandardFoodPlaceableGame::place_food] uses. They come from the
+    /// game's `minimumFood` and `foodSpawnChance` when converting, falling back to the engine's
+    /// defaults of 1 food and a 15 percent chance. Chances above 100 percent are treated as 100
+    pub fn set_food_spawn_settings(&mut self, min_food: u8, food_spawn_chance: u8) {
+        self.embedded
+            .set_food_spawn_settings(min_food, food_spawn_chance)
+    }
+
+    /// the size of this board type in bytes. Boards are `Copy` and never allocate, so this is
+    /// exactly what each board kept in e.g. a transposition table costs. See
+    /// [crate::compact_representation::cell_board_footprint] for picking a board size up front
+    pub const fn memory_footprint() -> usize {
+        std::mem::size_of::<Self>()
+    }
+
+    /// wraps a position on to this board, see [Position::wrap_to]
+    pub fn normalize_position(&self, pos: Position) -> Position {
+        self.embedded.normalize_position(pos)
+    }
+
+    /// the shortest displacement from `a` to `b` on this board, see [Position::torus_delta]
+    pub fn torus_delta(&self, a: Position, b: Position) -> Vector {
+        self.embedded.torus_delta(a, b)
+    }
+
+    /// A copy of this board with hazards dealing `hazard_damage`, for exploring counterfactuals
+    /// like "what if hazards did no damage" without converting from the wire representation
+    /// again
+    pub fn with_hazard_damage(self, hazard_damage: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_hazard_damage(hazard_damage),
+        }
+    }
+
+    /// a copy of this board with healing cells restoring `healing_regen`, see
+    /// [Self::with_hazard_damage]
+    pub fn with_healing_regen(self, healing_regen: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_healing_regen(healing_regen),
+        }
+    }
+
+    /// a copy of this board on `turn`, see [Self::with_hazard_damage]. Simulating counts up from
+    /// it, as from the game's turn when converting
+    pub fn with_turn(self, turn: u32) -> Self {
+        Self {
+            embedded: self.embedded.with_turn(turn),
+        }
+    }
+
+    /// A copy of this board with a snake's health changed, see [Self::with_hazard_damage]. Like
+    /// [HealthSettableGame::set_health], a health of 0 eliminates the snake and an eliminated
+    /// snake stays eliminated
+    pub fn with_health(self, snake_id: SnakeId, health: u8) -> Self {
+        Self {
+            embedded: self.embedded.with_health(snake_id, health),
+        }
+    }
+
+    /// the number of cells with food on them. Kept up to date as the board changes, so unlike
+    /// counting [FoodGettableGame::get_all_food_as_native_positions] it doesn't scan the board
+    pub fn food_count(&self) -> usize {
+        self.embedded.food_count()
+    }
+
+    /// the number of hazard cells, kept up to date like [Self::food_count]
+    pub fn hazard_count(&self) -> usize {
+        self.embedded.hazard_count()
+    }
+
+    /// the number of cells a snake's body covers, its length minus any segments stacked on its
+    /// tail. 0 for an eliminated snake
+    pub fn body_cell_count(&self, snake_id: SnakeId) -> u16 {
+        self.embedded.body_cell_count(snake_id)
+    }
+
+    /// draws `values`, indexed by [CellIndex], over this board as heat characters, e.g. to see
+    /// the visit counts behind a search's choice of move. See [OverlayDisplay]
+    pub fn display_overlay<'a, V>(
+        &'a self,
+        values: &'a [V],
+    ) -> OverlayDisplay<'a, T, D, V, BOARD_SIZE, MAX_SNAKES> {
+        self.embedded.display_overlay(values)
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but if there are more than `max_children`
+    /// joint moves only a random `max_children` of them are simulated. A safeguard for lobbies
+    /// with many snakes, where simulating every joint move (65536 of them for 8 snakes) is too
+    /// slow and uses too much memory
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_sampled<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        max_children: usize,
+        rng: &mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        Box::new(
+            super::core::simulate_with_moves_sampled(
+                self.embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Wrapped,
+                max_children,
+                rng,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but each child also comes with what happened
+    /// to every snake that moved, see [TurnOutcome]
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_outcomes<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, TurnOutcome<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_outcomes(
+                embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Wrapped,
+            )
+            .map(|(action, outcome, board)| (action, outcome, Self { embedded: board })),
+        )
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but `rule` is called on every child once the
+    /// standard rules have resolved the turn, with the joint move that produced it. This is the
+    /// place for rule add-ons like damage auras or scoring zones, e.g. through
+    /// [HealthSettableGame::set_health]. Children aren't checked for consistency after `rule`
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_post_turn_rule<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        rule: impl Fn(&Action<MAX_SNAKES>, &mut Self) + 'a,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_moves(
+                embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Wrapped,
+            )
+            .map(move |(action, board)| {
+                let mut board = Self { embedded: board };
+                rule(&action, &mut board);
+                (action, board)
+            }),
+        )
+    }
+
+    /// Applies one joint move in place instead of copying the board like
+    /// [SimulableGame::simulate_with_moves] does, for searches that walk down and back up a
+    /// large board. Pass the returned patch to [Self::undo] to get back to this board
+    pub fn apply_moves(&mut self, moves: &[(SnakeId, Move)]) -> BoardPatch<T, MAX_SNAKES> {
+        self.embedded.apply_moves(moves, EvaluateMode::Wrapped)
+    }
+
+    /// undoes an [Self::apply_moves], patches have to be undone in the reverse order they were
+    /// applied
+    pub fn undo(&mut self, patch: BoardPatch<T, MAX_SNAKES>) {
+        self.embedded.undo_patch(patch)
+    }
+
+    /// Evaluates one joint move on each board in `batch`, e.g. the leaves an MCTS expands in
+    /// parallel, yielding the children in order without boxing. The prepared move state is
+    /// reused while consecutive entries share a board, so keep each board's joint moves
+    /// together. Moves that kill a snake are applied rather than pruned
+    pub fn simulate_batch<'a>(
+        batch: &'a [(&'a Self, &'a [(SnakeId, Move)])],
+    ) -> impl Iterator<Item = Self> + 'a {
+        super::core::CellBoard::simulate_batch(
+            batch.iter().map(|(board, moves)| (&board.embedded, *moves)),
+            EvaluateMode::Wrapped,
+        )
+        .map(|embedded| Self { embedded })
+    }
+
+    /// every cell on the board along with what's in it, in index order, see [CellContents]
+    pub fn iter_cells(&self) -> impl Iterator<Item = (CellIndex<T>, CellContents)> + '_ {
+        self.embedded.iter_cells()
+    }
+
+    /// Like [SimulableGame::simulate_with_moves], but every temporary buffer used while
+    /// simulating is taken from `ctx` rather than the global allocator
+    #[cfg(feature = "bumpalo")]
+    #[allow(clippy::type_complexity)]
+    pub fn simulate_with_moves_in<'a, I: SimulatorInstruments, S: Borrow<[Move]>>(
+        &'a self,
+        ctx: &'a super::SimulationContext,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + 'a> {
+        let embedded = &self.embedded;
+        Box::new(
+            super::core::simulate_with_moves_in(
+                embedded,
+                ctx,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Wrapped,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+
     /// creates a wrapped board from a Wire Representation game
     pub fn convert_from_game(game: Game, snake_ids: &SnakeIDMap) -> Result<Self, Box<dyn Error>> {
-        if game.game.ruleset.name != "wrapped" {
+        if !game.is_wrapped() {
             return Err("only wrapped games are supported".into());
         }
         let embedded = CCB::convert_from_game(game, snake_ids)?;
         Ok(CellBoard { embedded })
     }
 
+    /// [CellBoard::convert_from_game] reusing the buffers in `scratch`, for converting many games
+    pub fn convert_from_game_with(
+        game: &Game,
+        snake_ids: &SnakeIDMap,
+        scratch: &mut ConversionScratch,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !game.is_wrapped() {
+            return Err("only wrapped games are supported".into());
+        }
+        let embedded = CCB::convert_from_game_with(game, snake_ids, scratch)?;
+        Ok(CellBoard { embedded })
+    }
+
+    /// Converts every game with the same `snake_ids`, in order, sharing one set of scratch buffers
+    /// between them instead of allocating them for each game
+    pub fn convert_many<'a, I>(
+        games: I,
+        snake_ids: &'a SnakeIDMap,
+    ) -> impl Iterator<Item = Result<Self, Box<dyn Error>>> + 'a
+    where
+        I: IntoIterator<Item = Game>,
+        I::IntoIter: 'a,
+    {
+        let mut scratch = ConversionScratch::default();
+        games
+            .into_iter()
+            .map(move |game| Self::convert_from_game_with(&game, snake_ids, &mut scratch))
+    }
+
+    /// [CellBoard::convert_many] spread over rayon's thread pool, with a set of scratch buffers per
+    /// thread. The boards are in the same order as `games`
+    #[cfg(feature = "rayon")]
+    pub fn par_convert_many(
+        games: &[Game],
+        snake_ids: &SnakeIDMap,
+    ) -> Vec<Result<Self, Box<dyn Error + Send + Sync>>> {
+        use rayon::prelude::*;
+
+        games
+            .par_iter()
+            .map_init(ConversionScratch::default, |scratch, game| {
+                Self::convert_from_game_with(game, snake_ids, scratch)
+                    .map_err(|e| e.to_string().into())
+            })
+            .collect()
+    }
+
     /// for debugging, packs this board into a custom json representation
     pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
         self.embedded.pack_as_hash()
     }
 
     /// for debugging, unloads a board from a custom json representation
+    ///
+    /// # Panics
+    /// if a required key is missing, see [CellBoard::try_from_packed_hash]
     pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
         Self {
             embedded: CCB::from_packed_hash(hash),
         }
     }
+
+    /// like `from_packed_hash`, but returns an error if a required key is missing or empty
+    pub fn try_from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            embedded: CCB::try_from_packed_hash(hash)?,
+        })
+    }
+
+    /// Displays this board with `~` around the edges to mark that they wrap. With `ghost_cells`
+    /// the markers are instead the cells on the opposite edge, so e.g. the first column is
+    /// repeated after the last, which is where a snake moving right off the board ends up
+    pub fn display_wrapped(
+        &self,
+        ghost_cells: bool,
+    ) -> WrappedDisplay<'_, T, D, BOARD_SIZE, MAX_SNAKES> {
+        WrappedDisplay {
+            board: self,
+            ghost_cells,
+        }
+    }
+}
+
+/// A wrapped board along with markers for its wrapping edges, see [CellBoard::display_wrapped]
+#[derive(Debug, Clone, Copy)]
+pub struct WrappedDisplay<
+    'a,
+    T: CN,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+> {
+    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    ghost_cells: bool,
+}
+
+impl<'a, T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    WrappedDisplay<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// writes the cell at (x, y) for ghost cells, otherwise an edge marker
+    fn fmt_ghost(&self, f: &mut std::fmt::Formatter<'_>, x: u8, y: u8) -> std::fmt::Result {
+        if self.ghost_cells {
+            let width = self.board.embedded.get_actual_width();
+            let position = Position::new(x as i32, y as i32);
+            self.board
+                .embedded
+                .fmt_cell(f, CellIndex::new(position, width))
+        } else {
+            write!(f, "~")
+        }
+    }
+
+    /// writes the edge above or below the board, `y` being the row it wraps around to
+    fn fmt_edge(&self, f: &mut std::fmt::Formatter<'_>, y: u8) -> std::fmt::Result {
+        write!(f, "{}", if self.ghost_cells { "    " } else { "  " })?;
+        for x in 0..self.board.embedded.get_actual_width() {
+            self.fmt_ghost(f, x, y)?;
+            write!(f, " ")?;
+        }
+        writeln!(f)
+    }
+
+    fn fmt_rule(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ghost_cells {
+            let width = self.board.embedded.get_actual_width() as usize;
+            writeln!(f, "    {}", "-".repeat(width * 2 - 1))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Display
+    for WrappedDisplay<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let embedded = &self.board.embedded;
+        let width = embedded.get_actual_width();
+        let height = embedded.get_actual_height();
+        let (left, right) = if self.ghost_cells {
+            (" | ", "| ")
+        } else {
+            (" ", "")
+        };
+        writeln!(f)?;
+
+        self.fmt_edge(f, 0)?;
+        self.fmt_rule(f)?;
+        for y in (0..height).rev() {
+            self.fmt_ghost(f, width - 1, y)?;
+            write!(f, "{}", left)?;
+            for x in 0..width {
+                let position = Position::new(x as i32, y as i32);
+                embedded.fmt_cell(f, CellIndex::new(position, width))?;
+                write!(f, " ")?;
+            }
+            write!(f, "{}", right)?;
+            self.fmt_ghost(f, 0, y)?;
+            writeln!(f)?;
+        }
+        self.fmt_rule(f)?;
+        self.fmt_edge(f, height - 1)?;
+
+        writeln!(
+            f,
+            "{}",
+            serde_json::to_string(&embedded.pack_as_hash()).unwrap()
+        )
+    }
 }
 
 /// 7x7 board with 4 snakes
@@ -79,6 +508,13 @@ pub type CellBoard8SnakesSquare25x25 = CellBoard<u16, Custom, { 25 * 25 }, 8>;
 /// Used to represent an absolutely silly game board
 pub type CellBoard16SnakesSquare50x50 = CellBoard<u16, Custom, { 50 * 50 }, 16>;
 
+/// Used to represent a royale free-for-all on the largest UI Selectable board, with up to 32
+/// snakes
+pub type CellBoard32SnakesSquare25x25 = CellBoard<u16, Custom, { 25 * 25 }, 32>;
+
+/// Used to represent a royale free-for-all on an absolutely silly game board
+pub type CellBoard32SnakesSquare50x50 = CellBoard<u16, Custom, { 50 * 50 }, 32>;
+
 /// Enum that holds a Cell Board sized right for the given game
 #[derive(Debug)]
 pub enum BestCellBoard {
@@ -102,25 +538,60 @@ pub enum BestCellBoard {
     Large(Box<CellBoard8SnakesSquare25x25>),
     /// A game that can have a max height and width of 50x50 and 16 snakes
     Silly(Box<CellBoard16SnakesSquare50x50>),
+    /// A game that can have a max height and width of 25x25 and 32 snakes
+    LargeRoyale(Box<CellBoard32SnakesSquare25x25>),
+    /// A game that can have a max height and width of 50x50 and 32 snakes
+    SillyRoyale(Box<CellBoard32SnakesSquare50x50>),
+    /// A exactly 7x7 board with 2 snakes
+    SmallExactDuel(Box<CellBoard<u8, Fixed<7, 7>, { 7 * 7 }, 2>>),
+    /// A game that can have a max height and width of 11x11 and 2 snakes
+    StandardDuel(Box<CellBoard<u8, Square, { 11 * 11 }, 2>>),
+    /// A exactly 11x11 board with 2 snakes
+    MediumExactDuel(Box<CellBoard<u8, Fixed<11, 11>, { 11 * 11 }, 2>>),
+    /// A exactly 19x19 board with 2 snakes
+    LargeExactDuel(Box<CellBoard<u16, Fixed<19, 19>, { 19 * 19 }, 2>>),
 }
 
+impl_best_cell_board!();
+
 /// Trait to get the best sized cellboard for the given game. It returns the smallest Compact board
-/// that has enough room to fit the given Wire game. If the game can't fit in any of our Compact
-/// boards we panic. However the largest board available is MUCH larger than the biggest selectable
-/// board in the Battlesnake UI
+/// that has enough room to fit the given Wire game, using the 2 snake boards for duels. If the game
+/// can't fit in any of our Compact boards an error is returned. However the largest board available
+/// is MUCH larger than the biggest selectable board in the Battlesnake UI
 pub trait ToBestCellBoard {
     #[allow(missing_docs)]
     fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>>;
 }
 
 impl ToBestCellBoard for Game {
-    fn to_best_cell_board(self) -> Result<BestCellBoard, Box<dyn Error>> {
+    fn to_best_cell_board(mut self) -> Result<BestCellBoard, Box<dyn Error>> {
         let width = self.board.width;
         let height = self.board.height;
-        let num_snakes = self.board.snakes.len();
+        // most ranked games are duels, and the smaller arrays of the 2 snake boards are kinder to
+        // the cache. Dead snakes don't need a slot, so they're dropped to fit
+        let alive_ids = 1 + self
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0 && s.id != self.you.id)
+            .count();
+        let duel = alive_ids <= 2;
+        if duel {
+            self.board.snakes.retain(|s| s.health > 0);
+        }
         let id_map = build_snake_id_map(&self);
+        // you always take an id, even once you've been eliminated and left off the board
+        let num_snakes = id_map.len();
 
-        let best_board = if width == 7 && height == 7 && num_snakes <= 4 {
+        let best_board = if duel && width == 7 && height == 7 {
+            BestCellBoard::SmallExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width == 11 && height == 11 {
+            BestCellBoard::MediumExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width <= 11 && height <= 11 {
+            BestCellBoard::StandardDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if duel && width == 19 && height == 19 {
+            BestCellBoard::LargeExactDuel(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width == 7 && height == 7 && num_snakes <= 4 {
             BestCellBoard::SmallExact(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width <= 7 && height <= 7 && num_snakes <= 4 {
             BestCellBoard::Tiny(Box::new(CellBoard::convert_from_game(self, &id_map)?))
@@ -136,18 +607,33 @@ impl ToBestCellBoard for Game {
             BestCellBoard::ArcadeMaze(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width == 19 && height == 21 && num_snakes <= 8 {
             BestCellBoard::ArcadeMaze8Snake(Box::new(CellBoard::convert_from_game(self, &id_map)?))
-        } else if width <= 25 && height < 25 && num_snakes <= 8 {
+        } else if width <= 25 && height <= 25 && num_snakes <= 8 {
             BestCellBoard::Large(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width <= 25 && height <= 25 && num_snakes <= 32 {
+            BestCellBoard::LargeRoyale(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else if width <= 50 && height <= 50 && num_snakes <= 16 {
             BestCellBoard::Silly(Box::new(CellBoard::convert_from_game(self, &id_map)?))
+        } else if width <= 50 && height <= 50 && num_snakes <= 32 {
+            BestCellBoard::SillyRoyale(Box::new(CellBoard::convert_from_game(self, &id_map)?))
         } else {
-            panic!("No board was big enough")
+            return Err("no board was big enough".into());
         };
 
         Ok(best_board)
     }
 }
 
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> TranspositionKeyGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn key(&self, turn: u64) -> TranspositionKey {
+        // the turn the board tracks isn't part of the position, only the parity passed in is
+        let mut hasher = FxHasher64::default();
+        self.with_turn(0).hash(&mut hasher);
+        TranspositionKey::new(hasher.finish(), turn, true)
+    }
+}
+
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     RandomReasonableMovesGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
@@ -167,42 +653,52 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Rea
 {
     fn reasonable_moves_for_each_snake(
         &self,
-    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Vec<Move>)> + '_> {
-        let width = self.embedded.get_actual_width();
+    ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Vec<Move>)> + Send + '_> {
+        let hazard_damage = self.get_hazard_damage();
         Box::new(
             self.embedded
                 .iter_healths()
                 .enumerate()
                 .filter(|(_, health)| **health > 0)
-                .map(move |(idx, _)| {
-                    let head_pos = self.get_head_as_position(&SnakeId(idx as u8));
+                .map(move |(idx, health)| {
+                    let sid = SnakeId(idx as u8);
+                    let head_pos = self.get_head_as_position(&sid);
+                    // the same index the simulator moves the head to, so a tail that wraps
+                    // around the edge is found as the tail
+                    let wrapped_index = |mv: Move| {
+                        self.embedded
+                            .as_wrapped_cell_index(head_pos.add_vec(mv.to_vector()))
+                    };
 
-                    let mvs = IntoIterator::into_iter(Move::all())
+                    let mvs = Move::all_iter()
                         .filter(|mv| {
-                            let mut new_head = head_pos.add_vec(mv.to_vector());
-                            let wrapped_x = new_head.x.rem_euclid(self.get_width() as i32);
-                            let wrapped_y = new_head.y.rem_euclid(self.get_height() as i32);
-
-                            new_head = Position {
-                                x: wrapped_x,
-                                y: wrapped_y,
-                            };
-
-                            let ci = CellIndex::new(new_head, width);
-
-                            if self.off_board(new_head) {
+                            let ci = wrapped_index(*mv);
+                            if self.embedded.cell_is_wall(ci) {
                                 return false;
-                            };
+                            }
+                            // snakes eating on a hazard don't take its damage
+                            let lethal_hazard = self.embedded.cell_is_hazard(ci)
+                                && !self.embedded.cell_is_food(ci)
+                                && hazard_is_lethal(
+                                    *health as i64,
+                                    hazard_damage as i64,
+                                    self.get_hazard_count(&ci) as i64,
+                                );
 
-                            !self.off_board(new_head)
-                                && ((!self.embedded.cell_is_body(ci)
-                                    && !self.embedded.cell_is_snake_head(ci))
-                                    || self.embedded.cell_is_single_tail(ci))
+                            (!self.embedded.cell_is_body(ci) || self.will_be_vacated_this_turn(&ci))
+                                && !self.embedded.cell_is_snake_head(ci)
+                                && !lethal_hazard
                         })
                         .collect_vec();
-                    let mvs = if mvs.is_empty() { vec![Move::Up] } else { mvs };
+                    let mvs = if mvs.is_empty() {
+                        let fallback =
+                            Move::all_iter().find(|mv| !self.is_neck(&sid, &wrapped_index(*mv)));
+                        vec![fallback.unwrap_or(Move::Up)]
+                    } else {
+                        mvs
+                    };
 
-                    (SnakeId(idx as u8), mvs)
+                    (sid, mvs)
                 }),
         )
     }
@@ -221,13 +717,15 @@ impl<
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + Send + '_>
     where
         S: Borrow<[Move]>,
     {
+        // borrow rather than copy the embedded board, this iterator is tied to self anyway
+        let embedded = &self.embedded;
         Box::new(
             simulate_with_moves(
-                &self.embedded,
+                embedded,
                 instruments,
                 snake_ids_and_moves,
                 EvaluateMode::Wrapped,
@@ -238,6 +736,39 @@ impl<
             }),
         )
     }
+
+    #[allow(clippy::type_complexity)]
+    fn into_simulate_with_moves<S>(
+        self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + Send>
+    where
+        S: Borrow<[Move]>,
+        Self: 'static,
+    {
+        Box::new(
+            simulate_with_moves(
+                self.embedded,
+                instruments,
+                snake_ids_and_moves,
+                EvaluateMode::Wrapped,
+            )
+            .map(|(action, board)| (action, Self { embedded: board })),
+        )
+    }
+}
+
+impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> AreaControlGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn spread_from_heads(&self) -> Vec<(SnakeId, usize)> {
+        let counts = self.embedded.spread_from_heads(EvaluateMode::Wrapped);
+        self.get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, counts[sid.as_usize()] as usize))
+            .collect()
+    }
 }
 
 impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -246,7 +777,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + Send + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -258,16 +789,16 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 
                     debug_assert!(!self.embedded.off_board(ci.into_position(width)));
 
-                    (mv, new_head, ci)
+                    (mv, ci)
                 })
-                .map(|(mv, _, ci)| (mv, ci)),
+                .filter(move |(_, ci)| !self.embedded.cell_is_wall(*ci)),
         )
     }
 
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + Send + 'a> {
         Box::new(self.possible_moves(pos).map(|(_, ci)| ci))
     }
 }
@@ -283,14 +814,15 @@ mod test {
         compact_representation::core::Cell,
         game_fixture,
         types::{
-            build_snake_id_map, HeadGettableGame, HealthGettableGame, Move,
-            NeighborDeterminableGame, RandomReasonableMovesGame, ReasonableMovesGame,
-            SimulableGame, SimulatorInstruments, SnakeId,
+            build_snake_id_map, HeadGettableGame, HealthGettableGame, HealthSettableGame, Move,
+            NeighborDeterminableGame, PositionGettableGame, RandomReasonableMovesGame,
+            ReasonableMovesGame, SimulableGame, SimulatorInstruments, SnakeId,
+            TranspositionKeyGame,
         },
-        wire_representation::Position,
+        wire_representation::{GameMode, Position},
     };
 
-    use super::{CellBoard4SnakesSquare11x11, CellIndex};
+    use super::{ArcadeMaze, CellBoard, CellBoard4SnakesSquare11x11, CellIndex, Square};
 
     #[derive(Debug)]
     struct Instruments {}
@@ -299,6 +831,80 @@ mod test {
         fn observe_simulation(&self, _: std::time::Duration) {}
     }
 
+    #[test]
+    fn test_transposition_keys() {
+        let g = game_fixture(include_str!("../../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let standard: crate::compact_representation::StandardCellBoard4Snakes11x11 =
+            g.as_cell_board(&id_map).unwrap();
+        let mut wrapped_game = g.clone();
+        wrapped_game.game.ruleset.name = GameMode::Wrapped;
+        let wrapped: CellBoard4SnakesSquare11x11 =
+            wrapped_game.as_wrapped_cell_board(&id_map).unwrap();
+
+        // the same layout under both rulesets, at both parities, gets four different keys
+        let keys = [
+            standard.key(4),
+            standard.key(5),
+            wrapped.key(4),
+            wrapped.key(5),
+        ];
+        assert!(keys.iter().all_unique());
+        assert_eq!(standard.key(4), standard.key(6));
+        // the same position reached on a later turn shares the key
+        assert_eq!(standard.with_turn(40).key(4), standard.key(4));
+        assert_eq!(wrapped.with_turn(41).key(5), wrapped.key(5));
+        assert!(!keys[0].is_wrapped() && !keys[0].is_odd_turn());
+        assert!(keys[3].is_wrapped() && keys[3].is_odd_turn());
+
+        // the wire key ignores metadata but not the position
+        let mut renamed = g.clone();
+        renamed.game.id = "another game".to_string();
+        renamed.board.snakes[1].shout = Some("hi".to_string());
+        assert_eq!(renamed.key(0), g.key(0));
+        renamed.board.snakes[1].health -= 1;
+        assert_ne!(renamed.key(0), g.key(0));
+        assert_ne!(wrapped_game.key(0), g.key(0));
+    }
+
+    #[test]
+    fn test_widths_of_non_square_boards() {
+        let g = game_fixture(include_str!("../../../fixtures/arcade_maze_map.json"));
+        let id_map = build_snake_id_map(&g);
+        let board: CellBoard<u16, ArcadeMaze, { 19 * 21 }, 4> =
+            g.as_wrapped_cell_board(&id_map).unwrap();
+        assert_eq!((board.actual_width(), board.actual_height()), (19, 21));
+
+        // index math done outside of the board agrees with the board's own
+        let corner = Position::new(18, 20);
+        let index = CellIndex::<u16>::new(corner, board.stored_width());
+        assert_eq!(index, board.native_from_position(corner));
+        assert_eq!(index.into_position(board.stored_width()), corner);
+        assert_eq!(index.as_usize(), 19 * 21 - 1);
+    }
+
+    #[test]
+    fn test_display_wrapped_edges() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 = g.as_wrapped_cell_board(&id_map).unwrap();
+
+        let markers = compact.display_wrapped(false).to_string();
+        let lines = markers.lines().collect_vec();
+        assert_eq!(lines[1].trim_end(), format!("  {}", ["~"; 11].join(" ")));
+        assert_eq!(lines[2], "~ . s . . . . . . . . . ~");
+        assert_eq!(lines[12], "~ . 1 . . . . . . . . . ~");
+
+        // snake 1 crosses the bottom edge, so its head shows up again above the top row and its
+        // body below the bottom row
+        let ghosts = compact.display_wrapped(true).to_string();
+        let lines = ghosts.lines().collect_vec();
+        assert_eq!(lines[1].trim_end(), "    . 1 . . . . . . . . .");
+        assert_eq!(lines[3], ". | . s . . . . . . . . . | .");
+        assert_eq!(lines[13], ". | . 1 . . . . . . . . . | .");
+        assert_eq!(lines[15].trim_end(), "    . s . . . . . . . . .");
+    }
+
     #[test]
     fn test_to_hash_round_trips() {
         let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
@@ -319,6 +925,37 @@ mod test {
             CellBoard4SnakesSquare11x11::from_packed_hash(&hash),
             orig_wrapped_cell
         );
+
+        let mut incomplete = hash.clone();
+        incomplete.remove("heads");
+        assert!(CellBoard4SnakesSquare11x11::try_from_packed_hash(&incomplete).is_err());
+        let mut too_many_cells = hash;
+        too_many_cells.get_mut("cells").unwrap().push(0);
+        assert!(CellBoard4SnakesSquare11x11::try_from_packed_hash(&too_many_cells).is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trips() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let mut board: CellBoard4SnakesSquare11x11 = g.as_wrapped_cell_board(&snake_ids).unwrap();
+        board.set_health(&SnakeId(1), 42);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let back: CellBoard4SnakesSquare11x11 = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, board);
+
+        // the layout is the same whether or not the board wraps
+        let standard: crate::compact_representation::StandardCellBoard4Snakes11x11 =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(standard, board.into_standard());
+
+        // a board doesn't fit in a board type that's too small for it
+        let too_small: Result<CellBoard<u8, Square, { 7 * 7 }, 4>, _> = serde_json::from_str(&json);
+        assert!(too_small.is_err());
+        let too_few_snakes: Result<CellBoard<u8, Square, { 11 * 11 }, 2>, _> =
+            serde_json::from_str(&json);
+        assert!(too_few_snakes.is_err());
     }
 
     #[test]
@@ -329,6 +966,38 @@ mod test {
         assert_eq!(c, Cell::from_u32(as_u32));
     }
 
+    #[test]
+    fn test_into_standard_and_back() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let wrapped: CellBoard4SnakesSquare11x11 = g.as_wrapped_cell_board(&snake_ids).unwrap();
+        let standard = wrapped.into_standard();
+        assert_eq!(standard.into_wrapped(), wrapped);
+
+        // snake 1 starts on the bottom edge, two moves left takes it off the left edge
+        let moves = [(SnakeId(1), [Move::Left].as_slice())];
+        let instruments = Instruments {};
+        let mut wrapped_after = wrapped;
+        let mut standard_after = standard;
+        for _ in 0..2 {
+            wrapped_after = wrapped_after
+                .simulate_with_moves(&instruments, moves)
+                .collect_vec()[0]
+                .1;
+            standard_after = standard_after
+                .simulate_with_moves(&instruments, moves)
+                .collect_vec()[0]
+                .1;
+        }
+
+        assert_eq!(
+            wrapped_after.get_head_as_position(&SnakeId(1)),
+            Position { x: 10, y: 0 }
+        );
+        assert!(!standard_after.is_alive(&SnakeId(1)));
+        assert!(standard_after.is_alive(&SnakeId(0)));
+    }
+
     #[test]
     fn test_wrapping_simulation_works() {
         let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
@@ -336,7 +1005,7 @@ mod test {
         let snake_ids = build_snake_id_map(&g);
         let orig_wrapped_cell: CellBoard4SnakesSquare11x11 =
             g.as_wrapped_cell_board(&snake_ids).unwrap();
-        let mut rng = rand::thread_rng();
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(7);
         run_move_test(
             orig_wrapped_cell,
             snake_ids.clone(),
@@ -387,11 +1056,9 @@ mod test {
         );
 
         let mut wrapped = orig_wrapped_cell;
-        let mut rng = rand::rngs::SmallRng::from_entropy();
         for _ in 0..15 {
             let move_map = wrapped
                 .random_reasonable_move_for_each_snake(&mut rng)
-                .into_iter()
                 .map(|(sid, mv)| (sid, [mv]))
                 .collect_vec();
             wrapped = wrapped
@@ -525,6 +1192,112 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_tail_chase_across_the_edge() {
+        // a board built by hand from wrapped_fixture rather than recorded: both snakes are 2x2
+        // loops across an edge, you across the left and right edges and the other snake across
+        // the top and bottom, so each can follow its tail around the edge
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_tail_chase.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+
+        let reasonable = compact.reasonable_moves_for_each_snake().collect_vec();
+        assert_eq!(
+            reasonable,
+            g.reasonable_moves_for_each_snake()
+                .map(|(id, mvs)| (snake_id_mapping[&id], mvs))
+                .collect_vec()
+        );
+        for (sid, mv) in [(SnakeId(0), Move::Left), (SnakeId(1), Move::Down)] {
+            let (_, mvs) = reasonable.iter().find(|(id, _)| *id == sid).unwrap();
+            assert!(mvs.contains(&mv), "{:?} can't chase its tail {:?}", sid, mv);
+        }
+
+        let (_, child) = compact
+            .simulate_with_moves(
+                &Instruments {},
+                vec![(SnakeId(0), [Move::Left]), (SnakeId(1), [Move::Down])],
+            )
+            .next()
+            .unwrap();
+        assert!(child.get_health(&SnakeId(0)) > 0);
+        assert!(child.get_health(&SnakeId(1)) > 0);
+        assert_eq!(
+            child.get_head_as_position(&SnakeId(0)),
+            Position::new(10, 5)
+        );
+        assert_eq!(
+            child.get_head_as_position(&SnakeId(1)),
+            Position::new(5, 10)
+        );
+    }
+
+    #[test]
+    fn test_tail_chase_across_the_edge_in_recorded_games() {
+        // frames of a real wrapped game where a snake's head is next to its own tail across an
+        // edge, with the move that chases the tail
+        for (fixture, id, chase) in [
+            (
+                include_str!("../../../fixtures/debug_wrapped/debug_game_193.json"),
+                "gs_7FqQrCPpGTSB73ThR7db9Sg4",
+                Move::Right,
+            ),
+            (
+                include_str!("../../../fixtures/debug_wrapped/debug_game_59.json"),
+                "gs_7FqQrCPpGTSB73ThR7db9Sg4",
+                Move::Left,
+            ),
+            (
+                include_str!("../../../fixtures/debug_wrapped/debug_game_97.json"),
+                "gs_tgdyvHmjvTYht3PYyQxYg66Y",
+                Move::Up,
+            ),
+            (
+                include_str!("../../../fixtures/debug_wrapped/debug_game_25.json"),
+                "gs_Qh9JjQMGyycyppxjxjGyxHSB",
+                Move::Down,
+            ),
+        ] {
+            let g = game_fixture(fixture);
+            let snake_id_mapping = build_snake_id_map(&g);
+            let compact: CellBoard4SnakesSquare11x11 =
+                g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+            let sid = snake_id_mapping[id];
+
+            let reasonable = compact.reasonable_moves_for_each_snake().collect_vec();
+            assert_eq!(
+                reasonable,
+                g.reasonable_moves_for_each_snake()
+                    .map(|(id, mvs)| (snake_id_mapping[&id], mvs))
+                    .collect_vec()
+            );
+            let (_, mvs) = reasonable.iter().find(|(id, _)| *id == sid).unwrap();
+            assert!(
+                mvs.contains(&chase),
+                "{} can't chase its tail {:?}",
+                id,
+                chase
+            );
+
+            let tail = *g
+                .board
+                .snakes
+                .iter()
+                .find(|s| s.id == id)
+                .unwrap()
+                .body
+                .back()
+                .unwrap();
+            let (_, child) = compact
+                .simulate_with_moves(&Instruments {}, vec![(sid, [chase])])
+                .next()
+                .unwrap();
+            assert!(child.get_health(&sid) > 0);
+            assert_eq!(child.get_head_as_position(&sid), tail);
+        }
+    }
+
     #[test]
     fn test_neighbors_and_possible_moves_cornered() {
         let g = game_fixture(include_str!("../../../fixtures/cornered_wrapped.json"));
@@ -573,4 +1346,27 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_normalize_position_and_torus_delta() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 =
+            g.as_wrapped_cell_board(&snake_id_mapping).unwrap();
+
+        assert_eq!(
+            compact.normalize_position(Position::new(-1, 11)),
+            Position::new(10, 0)
+        );
+        assert_eq!(
+            compact.normalize_position(Position::new(3, 4)),
+            Position::new(3, 4)
+        );
+        let delta = compact.torus_delta(Position::new(10, 2), Position::new(0, 9));
+        assert_eq!((delta.x, delta.y), (1, -4));
+        assert_eq!(
+            compact.embedded.as_wrapped_cell_index(Position::new(11, 5)),
+            CellIndex::new(Position::new(0, 5), 11)
+        );
+    }
 }
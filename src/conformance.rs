@@ -0,0 +1,380 @@
+//! A conformance suite for board implementations, available with the `test-utils` feature.
+//! Alternative representations (e.g. boards living on a GPU) can run [verify_board_impl] against
+//! the same fixtures this crate tests its own boards with, using the wire representation as the
+//! reference implementation.
+//!
+//! [verify_trait_laws] and [verify_simulation_laws] check invariants every implementation has to
+//! hold on its own, so they run against the wire representation as well as the compact boards.
+
+use std::error::Error;
+
+use itertools::Itertools;
+
+use crate::{
+    fixtures::{self, FixtureMetadata},
+    types::{
+        Board, HealingQueryableGame, Move, NeighborDeterminableGame, NoopInstruments,
+        ReasonableMovesGame, SimulableBoard,
+    },
+    wire_representation::{Game, Position},
+};
+
+/// fixtures for standard (non wrapped) 11x11 games with up to 4 snakes
+pub fn standard_fixtures() -> Vec<Game> {
+    fixtures::matching(|m| m.ruleset != "wrapped" && fits_11x11(m))
+        .iter()
+        .map(|f| f.game())
+        .collect()
+}
+
+/// fixtures for wrapped 11x11 games with up to 4 snakes
+pub fn wrapped_fixtures() -> Vec<Game> {
+    fixtures::matching(|m| m.ruleset == "wrapped" && fits_11x11(m))
+        .iter()
+        .map(|f| f.game())
+        .collect()
+}
+
+fn fits_11x11(metadata: &FixtureMetadata) -> bool {
+    metadata.width == 11 && metadata.height == 11 && metadata.snake_count <= 4
+}
+
+fn dedup_body(body: impl IntoIterator<Item = Position>) -> Vec<Position> {
+    body.into_iter().dedup().collect()
+}
+
+/// Checks that the boards built by `convert` agree with the wire representation of every
+/// fixture: snakes (matched up by head position), food, hazards, neighbors, reasonable moves,
+/// and that simulating each snake's first reasonable move lands its head where expected.
+/// Returns an error describing the first mismatch.
+pub fn verify_board_impl<B, const N_SNAKES: usize>(
+    fixtures: &[Game],
+    convert: impl Fn(&Game) -> Result<B, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>>
+where
+    B: SimulableBoard<N_SNAKES>,
+{
+    for (fixture_index, game) in fixtures.iter().enumerate() {
+        let board = convert(game)?;
+        let context = |msg: String| -> Box<dyn Error> {
+            format!("fixture {} ({}): {}", fixture_index, game.game.id, msg).into()
+        };
+
+        if (board.get_width(), board.get_height()) != (game.board.width, game.board.height) {
+            return Err(context("board size differs".to_string()));
+        }
+
+        let snake_ids = board.get_snake_ids();
+        if snake_ids.len() != game.board.snakes.len() {
+            return Err(context(format!(
+                "expected {} snakes, got {}",
+                game.board.snakes.len(),
+                snake_ids.len()
+            )));
+        }
+
+        let wire_reasonable_moves = game.reasonable_moves_for_each_snake().collect_vec();
+        let reasonable_moves = board.reasonable_moves_for_each_snake().collect_vec();
+
+        for sid in snake_ids.iter() {
+            let head = board.get_head_as_position(sid);
+            let wire_snake = game
+                .board
+                .snakes
+                .iter()
+                .find(|s| s.head == head)
+                .ok_or_else(|| context(format!("no snake has its head at {:?}", head)))?;
+
+            if board.is_you(sid) != (wire_snake.id == game.you.id) {
+                return Err(context(format!("you mismatch for snake {}", wire_snake.id)));
+            }
+            if board.get_health_i64(sid) != wire_snake.health as i64 {
+                return Err(context(format!("health mismatch for {}", wire_snake.id)));
+            }
+            if board.get_length_i64(sid) != wire_snake.body.len() as i64 {
+                return Err(context(format!("length mismatch for {}", wire_snake.id)));
+            }
+            let body = dedup_body(
+                board
+                    .get_snake_body_vec(sid)
+                    .into_iter()
+                    .map(|pos| board.position_from_native(pos)),
+            );
+            if body != dedup_body(wire_snake.body.iter().copied()) {
+                return Err(context(format!("body mismatch for {}", wire_snake.id)));
+            }
+
+            let expected_moves = wire_reasonable_moves
+                .iter()
+                .find(|(id, _)| *id == wire_snake.id)
+                .map(|(_, mvs)| mvs);
+            let actual_moves = reasonable_moves
+                .iter()
+                .find(|(id, _)| id == sid)
+                .map(|(_, mvs)| mvs);
+            if expected_moves != actual_moves {
+                return Err(context(format!(
+                    "reasonable moves for {}: expected {:?}, got {:?}",
+                    wire_snake.id, expected_moves, actual_moves
+                )));
+            }
+        }
+
+        let mut food = board.get_all_food_as_positions();
+        let mut wire_food = game.board.food.clone();
+        food.sort();
+        wire_food.sort();
+        if food != wire_food {
+            return Err(context("food differs".to_string()));
+        }
+
+        for x in 0..game.board.width as i32 {
+            for y in 0..game.board.height as i32 {
+                let pos = Position { x, y };
+                let native = board.native_from_position(pos);
+
+                if board.is_hazard(&native) != game.board.hazards.contains(&pos) {
+                    return Err(context(format!("hazard mismatch at {:?}", pos)));
+                }
+
+                let neighbors = board
+                    .neighbors(&native)
+                    .map(|n| board.position_from_native(n))
+                    .sorted()
+                    .collect_vec();
+                let wire_neighbors = game.neighbors(&pos).sorted().collect_vec();
+                if neighbors != wire_neighbors {
+                    return Err(context(format!("neighbor mismatch at {:?}", pos)));
+                }
+            }
+        }
+
+        let first_moves = reasonable_moves
+            .iter()
+            .map(|(sid, mvs)| (sid.clone(), [mvs[0]]))
+            .collect_vec();
+        let children = board
+            .simulate_with_moves(
+                &NoopInstruments,
+                first_moves
+                    .iter()
+                    .map(|(sid, mv)| (sid.clone(), mv.as_slice())),
+            )
+            .collect_vec();
+        if children.len() != 1 {
+            return Err(context(format!(
+                "expected one child simulating one move per snake, got {}",
+                children.len()
+            )));
+        }
+
+        let (_, child) = &children[0];
+        for (sid, mv) in first_moves.iter() {
+            if !child.is_alive(sid) {
+                continue;
+            }
+            let expected_head = expected_head(game, board.get_head_as_position(sid), mv[0]);
+            if child.get_head_as_position(sid) != expected_head {
+                return Err(context(format!(
+                    "snake {:?} moved {:?} but its head is not at {:?}",
+                    sid, mv[0], expected_head
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the invariants of the traits that don't involve simulating, on one board:
+/// - every cell's neighbors are on the board, and are the adjacent cells (wrapping around the edges
+///   when `wrapped`) other than walls. Walls are always hazards, so only a hazard can be missing.
+///   `possible_moves` gives the same neighbors, each with a different move
+/// - every alive snake has at least one reasonable move, without repeats, and when it has more
+///   than one they are all possible moves. A single move may be the fallback for a snake with
+///   nowhere to go, which can go off the board
+pub fn verify_trait_laws<B: Board>(board: &B, wrapped: bool) -> Result<(), Box<dyn Error>> {
+    for x in 0..board.get_width() as i32 {
+        for y in 0..board.get_height() as i32 {
+            let pos = Position { x, y };
+            let native = board.native_from_position(pos);
+            let neighbors = board
+                .neighbors(&native)
+                .map(|n| board.position_from_native(n))
+                .sorted()
+                .collect_vec();
+            if neighbors.len() > 4 {
+                return Err(format!("{:?} has {} neighbors", pos, neighbors.len()).into());
+            }
+            for mv in Move::all_iter() {
+                let mut adjacent = pos.add_vec(mv.to_vector());
+                if wrapped {
+                    adjacent.x = adjacent.x.rem_euclid(board.get_width() as i32);
+                    adjacent.y = adjacent.y.rem_euclid(board.get_height() as i32);
+                }
+                if !board.off_board(adjacent)
+                    && !neighbors.contains(&adjacent)
+                    && !board.is_hazard(&board.native_from_position(adjacent))
+                {
+                    return Err(format!("{:?} is missing its neighbor {:?}", pos, adjacent).into());
+                }
+            }
+            if let Some(off) = neighbors.iter().find(|n| board.off_board(**n)) {
+                return Err(format!("{:?} has a neighbor off the board at {:?}", pos, off).into());
+            }
+
+            let possible_moves = board.possible_moves(&native).collect_vec();
+            let moved_to = possible_moves
+                .iter()
+                .map(|(_, n)| board.position_from_native(n.clone()))
+                .sorted()
+                .collect_vec();
+            if moved_to != neighbors {
+                return Err(
+                    format!("{:?} has possible moves that aren't its neighbors", pos).into(),
+                );
+            }
+            if !possible_moves.iter().map(|(mv, _)| mv).all_unique() {
+                return Err(format!("{:?} has a repeated possible move", pos).into());
+            }
+        }
+    }
+
+    for (sid, moves) in board.reasonable_moves_for_each_snake() {
+        if moves.is_empty() || !moves.iter().all_unique() {
+            return Err(format!("snake {:?} has reasonable moves {:?}", sid, moves).into());
+        }
+        let head = board.get_head_as_native_position(&sid);
+        let possible = board.possible_moves(&head).map(|(mv, _)| mv).collect_vec();
+        if moves.len() > 1 && moves.iter().any(|mv| !possible.contains(mv)) {
+            return Err(format!(
+                "snake {:?} has reasonable moves {:?} but can only move {:?}",
+                sid, moves, possible
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the invariants of simulating every combination of moves from one board:
+/// - snakes that are eliminated are no longer alive or listed in the child's snake ids
+/// - a surviving snake that ate is at full health and one longer, one that didn't eat is the
+///   same length and has lost health, unless it's on a healing cell. So in total the snakes
+///   cover as many cells as before, plus one per food eaten, minus the eliminated snakes
+pub fn verify_simulation_laws<B, const N_SNAKES: usize>(board: &B) -> Result<(), Box<dyn Error>>
+where
+    B: SimulableBoard<N_SNAKES> + HealingQueryableGame,
+{
+    let food = board.get_all_food_as_native_positions();
+    let moves = board
+        .get_snake_ids()
+        .into_iter()
+        .filter(|sid| board.is_alive(sid))
+        .map(|sid| (sid, Move::all()))
+        .collect_vec();
+
+    for (action, child) in board.simulate_with_moves(&NoopInstruments, moves.clone()) {
+        let context = |msg: String| -> Box<dyn Error> { format!("{:?}: {}", action, msg).into() };
+        let child_ids = child.get_snake_ids();
+        for (sid, _) in moves.iter() {
+            if !child.is_alive(sid) {
+                if child_ids.contains(sid) && board.get_snake_ids().len() != child_ids.len() {
+                    return Err(context(format!(
+                        "eliminated snake {:?} is still listed",
+                        sid
+                    )));
+                }
+                continue;
+            }
+
+            let head = child.get_head_as_native_position(sid);
+            let health = child.get_health_i64(sid);
+            let length = child.get_length_i64(sid);
+            let old_health = board.get_health_i64(sid);
+            let old_length = board.get_length_i64(sid);
+            if food.contains(&head) {
+                if health != 100 || length != old_length + 1 {
+                    return Err(context(format!(
+                        "{:?} ate but has health {} and length {} (was {})",
+                        sid, health, length, old_length
+                    )));
+                }
+            } else {
+                if length != old_length {
+                    return Err(context(format!(
+                        "{:?} didn't eat but its length went from {} to {}",
+                        sid, old_length, length
+                    )));
+                }
+                if health >= old_health && !child.is_healing(&head) {
+                    return Err(context(format!(
+                        "{:?} didn't eat but its health went from {} to {}",
+                        sid, old_health, health
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn expected_head(game: &Game, head: Position, mv: Move) -> Position {
+    let new_head = head.add_vec(mv.to_vector());
+    if game.is_wrapped() {
+        new_head.wrap_to(game.board.width, game.board.height)
+    } else {
+        new_head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        types::build_snake_id_map,
+    };
+
+    #[test]
+    fn test_trait_laws() {
+        fixtures::check_each(fixtures::all().iter().copied(), |fixture| {
+            let game = fixture.game();
+            verify_trait_laws(&game, game.is_wrapped())
+        })
+        .unwrap();
+
+        for game in standard_fixtures() {
+            let board: StandardCellBoard4Snakes11x11 =
+                game.as_cell_board(&build_snake_id_map(&game)).unwrap();
+            verify_trait_laws(&board, false).unwrap();
+            verify_simulation_laws(&board).unwrap();
+        }
+        for game in wrapped_fixtures() {
+            let board: WrappedCellBoard4Snakes11x11 = game
+                .as_wrapped_cell_board(&build_snake_id_map(&game))
+                .unwrap();
+            verify_trait_laws(&board, true).unwrap();
+            verify_simulation_laws(&board).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compact_boards_conform() {
+        verify_board_impl(&standard_fixtures(), |game| {
+            let board: StandardCellBoard4Snakes11x11 =
+                game.as_cell_board(&build_snake_id_map(game))?;
+            Ok(board)
+        })
+        .unwrap();
+
+        verify_board_impl(&wrapped_fixtures(), |game| {
+            let board: WrappedCellBoard4Snakes11x11 =
+                game.as_wrapped_cell_board(&build_snake_id_map(game))?;
+            Ok(board)
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+//! Checks that a compact board hasn't been corrupted, describing what's wrong rather than just
+//! saying that something is, see [verify]. With the `test-utils` feature `fuzz_simulate` plays
+//! random games on a compact board and the wire representation side by side, so engines can run
+//! it against their own fixtures in CI.
+
+use std::{error::Error, fmt::Display};
+
+use crate::{types::SnakeId, wire_representation::Position};
+#[cfg(any(test, feature = "test-utils"))]
+use crate::{
+    types::{
+        NoopInstruments, ReasonableMovesGame, SimulableBoard, SimulableGame, SnakeIDGettableGame,
+        SnakeIDMap, StandardFoodPlaceableGame, TurnDeterminableGame, VictorDeterminableGame,
+    },
+    wire_representation::Game,
+};
+
+/// What's wrong with an inconsistent board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InconsistencyReport {
+    /// the snake that's wrong, None if it's one of the board's counts
+    pub snake: Option<SnakeId>,
+    /// the cell that's wrong, None if it's a count
+    pub cell: Option<Position>,
+    #[allow(missing_docs)]
+    pub expected: String,
+    #[allow(missing_docs)]
+    pub actual: String,
+}
+
+impl Display for InconsistencyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(snake) = self.snake {
+            write!(f, "snake {} ", snake.0)?;
+        }
+        if let Some(cell) = self.cell {
+            write!(f, "at {:?} ", cell)?;
+        }
+        write!(f, "expected {}, found {}", self.expected, self.actual)
+    }
+}
+
+impl Error for InconsistencyReport {}
+
+/// A board that can check its own internal bookkeeping
+pub trait ConsistencyVerifiable {
+    /// the first inconsistency found on this board, if any
+    fn verify_consistency(&self) -> Result<(), InconsistencyReport>;
+}
+
+/// Checks that every live snake's body links up from its tail to its head and covers as many
+/// cells as its length says, that no other cell claims to be part of a snake, and that the
+/// board's cached counts match its cells
+pub fn verify<B: ConsistencyVerifiable + ?Sized>(board: &B) -> Result<(), InconsistencyReport> {
+    board.verify_consistency()
+}
+
+/// Plays `rollouts` random games of up to `max_turns` turns from `game`, simulating each turn on
+/// the wire representation and on the board `convert` builds, and checks after every turn that
+/// the board is consistent and agrees with the wire game, see [crate::oracle::diff]. Snakes pick
+/// among their reasonable moves and food spawns after every turn as in the engine, both seeded by
+/// `seed` so a failure can be replayed. Returns an error describing the first disagreement
+#[cfg(any(test, feature = "test-utils"))]
+pub fn fuzz_simulate<B, const N_SNAKES: usize>(
+    game: &Game,
+    convert: impl Fn(&Game, &SnakeIDMap) -> Result<B, Box<dyn Error>>,
+    rollouts: usize,
+    max_turns: usize,
+    seed: u64,
+) -> Result<(), Box<dyn Error>>
+where
+    B: SimulableBoard<N_SNAKES>
+        + SnakeIDGettableGame<SnakeIDType = SnakeId>
+        + TurnDeterminableGame
+        + StandardFoodPlaceableGame
+        + ConsistencyVerifiable,
+{
+    use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+
+    let id_map = crate::types::build_snake_id_map(game);
+    let mut rng = SmallRng::seed_from_u64(seed);
+    for rollout in 0..rollouts {
+        let mut wire = game.clone();
+        let mut board = convert(&wire, &id_map)?;
+        for turn in 0..max_turns {
+            if wire.is_over() {
+                break;
+            }
+            let moves = wire
+                .reasonable_moves_for_each_snake()
+                .map(|(id, moves)| (id, [*moves.choose(&mut rng).unwrap()]))
+                .collect::<Vec<_>>();
+            let context = |msg: String| -> Box<dyn Error> {
+                format!(
+                    "rollout {} turn {} moving {:?}: {}",
+                    rollout, turn, moves, msg
+                )
+                .into()
+            };
+
+            let (_, mut next_wire): (_, Game) = SimulableGame::<_, N_SNAKES>::simulate_with_moves(
+                &wire,
+                &NoopInstruments,
+                moves.clone(),
+            )
+            .next()
+            .ok_or_else(|| context("the wire game has no child".to_string()))?;
+            let compact_moves = moves
+                .iter()
+                .map(|(id, mv)| (id_map[id], *mv))
+                .collect::<Vec<_>>();
+            let (_, mut next_board) = board
+                .simulate_with_moves(&NoopInstruments, compact_moves)
+                .next()
+                .ok_or_else(|| context("the board has no child".to_string()))?;
+            // both place food from the empty cells in the same order, so the same rng spawns the
+            // same food on each
+            let food_seed = rng.gen();
+            next_wire.place_food(&mut SmallRng::seed_from_u64(food_seed));
+            next_board.place_food(&mut SmallRng::seed_from_u64(food_seed));
+
+            verify(&next_board).map_err(|report| context(report.to_string()))?;
+            let divergences = crate::oracle::diff(&next_wire, &next_board, &id_map);
+            if let Some(divergence) = divergences.first() {
+                return Err(context(divergence.to_string()));
+            }
+            wire = next_wire;
+            board = next_board;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::{wrapped, StandardCellBoard4Snakes11x11},
+        conformance::{standard_fixtures, wrapped_fixtures},
+        game_fixture,
+        types::build_snake_id_map,
+    };
+
+    #[test]
+    fn test_verify() {
+        for g in standard_fixtures() {
+            let board: StandardCellBoard4Snakes11x11 =
+                g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+            assert_eq!(verify(&board), Ok(()), "{}", g.game.id);
+        }
+
+        let report = InconsistencyReport {
+            snake: Some(SnakeId(1)),
+            cell: Some(Position::new(2, 3)),
+            expected: "snake 1's head".to_string(),
+            actual: "an empty cell".to_string(),
+        };
+        assert_eq!(
+            report.to_string(),
+            "snake 1 at Position { x: 2, y: 3 } expected snake 1's head, found an empty cell"
+        );
+    }
+
+    #[test]
+    fn test_fuzz_simulate() {
+        for g in standard_fixtures() {
+            fuzz_simulate::<StandardCellBoard4Snakes11x11, 4>(
+                &g,
+                |g, id_map| g.as_cell_board(id_map),
+                5,
+                50,
+                42,
+            )
+            .unwrap();
+        }
+        for g in wrapped_fixtures() {
+            fuzz_simulate::<wrapped::CellBoard4SnakesSquare11x11, 4>(
+                &g,
+                |g, id_map| g.as_wrapped_cell_board(id_map),
+                5,
+                50,
+                42,
+            )
+            .unwrap();
+        }
+
+        // a board that drops the food disagrees with the wire game
+        let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+        let result = fuzz_simulate::<StandardCellBoard4Snakes11x11, 4>(
+            &g,
+            |g, id_map| {
+                let mut g = g.clone();
+                g.board.food.clear();
+                g.as_cell_board(id_map)
+            },
+            1,
+            1,
+            0,
+        );
+        assert!(result.unwrap_err().to_string().contains("food"));
+    }
+}
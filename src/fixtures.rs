@@ -0,0 +1,248 @@
+//! The JSON fixtures this crate uses for its own tests, so that downstream projects can reuse them
+//! in their regression tests rather than copying the files. Only available with the `test-utils`
+//! feature.
+
+use std::error::Error;
+
+use crate::{
+    compact_representation::{standard, wrapped},
+    wire_representation::Game,
+};
+
+/// One of the crate's wire representation fixtures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// the file name of the fixture, without the `.json` extension
+    pub name: &'static str,
+    /// the raw JSON
+    pub json: &'static str,
+}
+
+/// Summary of a fixture, for picking the fixtures relevant to a test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureMetadata {
+    /// the ruleset name, e.g. `standard`, `royale` or `wrapped`
+    pub ruleset: String,
+    /// the map, if the game has one
+    pub map: Option<String>,
+    /// how many snakes are on the board
+    pub snake_count: usize,
+    #[allow(missing_docs)]
+    pub width: u32,
+    #[allow(missing_docs)]
+    pub height: u32,
+}
+
+/// The smallest compact board a fixture fits in, standard or wrapped depending on its ruleset
+#[derive(Debug)]
+pub enum BestCompactBoard {
+    #[allow(missing_docs)]
+    Standard(standard::BestCellBoard),
+    #[allow(missing_docs)]
+    Wrapped(wrapped::BestCellBoard),
+}
+
+macro_rules! fixtures {
+    ($($name:literal),* $(,)?) => {
+        &[$(Fixture {
+            name: $name,
+            json: include_str!(concat!("../fixtures/", $name, ".json")),
+        }),*]
+    };
+}
+
+const FIXTURES: &[Fixture] = fixtures![
+    "4_snake_game",
+    "7x7board",
+    "8x8board",
+    "all-options-dead-prefer-out-of-bounds",
+    "another_crash",
+    "arcade_maze_map",
+    "bench/early_4_snake",
+    "bench/eight_snakes_19x21",
+    "bench/late_duel",
+    "bench/royale_heavy_hazard",
+    "body_collision",
+    "cornered",
+    "cornered_wrapped",
+    "e80b70e7-a916-40ca-82d2-ad76e074efe1_0",
+    "empty_str_hazard",
+    "goes_for_food",
+    "hazard_map_settings",
+    "head_to_head_on_food",
+    "late_stage",
+    "mojave_12_18_12_34",
+    "solo_minimal",
+    "stacked_tail",
+    "start_of_game",
+    "tail_chase",
+    "this_one_crashed",
+    "tree_search_collision",
+    "wrapped_fixture",
+    "wrapped_panic",
+    "wrapped_tail_chase",
+];
+
+/// One of the positions in [bench_fixtures]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchFixture {
+    /// the kind of position this is, e.g. `late_duel`
+    pub scenario: &'static str,
+    #[allow(missing_docs)]
+    pub fixture: Fixture,
+}
+
+macro_rules! bench_fixtures {
+    ($($scenario:literal => $name:literal),* $(,)?) => {
+        &[$(BenchFixture {
+            scenario: $scenario,
+            fixture: Fixture {
+                name: $name,
+                json: include_str!(concat!("../fixtures/", $name, ".json")),
+            },
+        }),*]
+    };
+}
+
+const BENCH_FIXTURES: &[BenchFixture] = bench_fixtures![
+    "early_4_snake" => "bench/early_4_snake",
+    "late_duel" => "bench/late_duel",
+    "royale_heavy_hazard" => "bench/royale_heavy_hazard",
+    "arcade_maze" => "arcade_maze_map",
+    "eight_snakes_19x21" => "bench/eight_snakes_19x21",
+    "wrapped_duel" => "mojave_12_18_12_34",
+];
+
+/// A fixed set of positions to measure performance against, one for each kind of game engines
+/// commonly see, so that changes to the representations aren't only judged on the start of a
+/// game. Positions are only ever added to this list, never changed, so that results stay
+/// comparable across versions
+pub fn bench_fixtures() -> &'static [BenchFixture] {
+    BENCH_FIXTURES
+}
+
+/// every wire representation fixture in the crate
+pub fn all() -> &'static [Fixture] {
+    FIXTURES
+}
+
+/// looks up a fixture by its file name, without the `.json` extension
+pub fn by_name(name: &str) -> Option<Fixture> {
+    FIXTURES.iter().find(|f| f.name == name).copied()
+}
+
+/// the fixtures whose metadata matches `filter`
+pub fn matching(filter: impl Fn(&FixtureMetadata) -> bool) -> Vec<Fixture> {
+    FIXTURES
+        .iter()
+        .filter(|f| filter(&f.metadata()))
+        .copied()
+        .collect()
+}
+
+impl Fixture {
+    /// parses the fixture in to a wire game
+    pub fn game(&self) -> Game {
+        crate::game_fixture(self.json)
+    }
+
+    /// summarises the fixture
+    pub fn metadata(&self) -> FixtureMetadata {
+        let game = self.game();
+        FixtureMetadata {
+            ruleset: game.game.ruleset.name.to_string(),
+            map: game.game.map,
+            snake_count: game.board.snakes.len(),
+            width: game.board.width,
+            height: game.board.height,
+        }
+    }
+
+    /// converts the fixture in to the smallest compact board that fits it
+    pub fn best_compact_board(&self) -> Result<BestCompactBoard, Box<dyn Error>> {
+        let game = self.game();
+        if game.is_wrapped() {
+            wrapped::ToBestCellBoard::to_best_cell_board(game).map(BestCompactBoard::Wrapped)
+        } else {
+            standard::ToBestCellBoard::to_best_cell_board(game).map(BestCompactBoard::Standard)
+        }
+    }
+}
+
+/// Runs `check` against every fixture, table test style. Unlike asserting in a loop every fixture
+/// is checked, and the returned error names each fixture that failed along with its error.
+pub fn check_each(
+    fixtures: impl IntoIterator<Item = Fixture>,
+    mut check: impl FnMut(&Fixture) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let failures: Vec<String> = fixtures
+        .into_iter()
+        .filter_map(|fixture| {
+            check(&fixture)
+                .err()
+                .map(|e| format!("{}: {}", fixture.name, e))
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_fixtures_load() {
+        check_each(all().iter().copied(), |fixture| {
+            fixture.best_compact_board().map(|_| ())
+        })
+        .unwrap();
+
+        let wrapped = matching(|m| m.ruleset == "wrapped");
+        assert!(wrapped.contains(&by_name("wrapped_fixture").unwrap()));
+        assert!(wrapped.iter().all(|f| f.game().is_wrapped()));
+        assert_eq!(
+            by_name("arcade_maze_map")
+                .unwrap()
+                .metadata()
+                .map
+                .as_deref(),
+            Some("arcade_maze")
+        );
+    }
+
+    #[test]
+    fn test_bench_fixtures_cover_their_scenarios() {
+        let metadata = |scenario: &str| {
+            bench_fixtures()
+                .iter()
+                .find(|b| b.scenario == scenario)
+                .unwrap()
+                .fixture
+                .metadata()
+        };
+        check_each(bench_fixtures().iter().map(|b| b.fixture), |fixture| {
+            fixture.best_compact_board().map(|_| ())
+        })
+        .unwrap();
+
+        assert_eq!(metadata("early_4_snake").snake_count, 4);
+        assert_eq!(metadata("late_duel").snake_count, 2);
+        assert_eq!(metadata("royale_heavy_hazard").ruleset, "royale");
+        assert_eq!(metadata("arcade_maze").map.as_deref(), Some("arcade_maze"));
+        let eight = metadata("eight_snakes_19x21");
+        assert_eq!((eight.snake_count, eight.width, eight.height), (8, 19, 21));
+        let wrapped = metadata("wrapped_duel");
+        assert_eq!(
+            (wrapped.ruleset.as_str(), wrapped.snake_count),
+            ("wrapped", 2)
+        );
+
+        let hazards = bench_fixtures()[2].fixture.game().board.hazards.len();
+        assert!(hazards > 100);
+    }
+}
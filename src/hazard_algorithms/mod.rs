@@ -1,10 +1,25 @@
 //! traits and structs for simulating hazard algorithms in battlesnake
 //! implements non-deterministic forecasting for randomized algorithms
 
-use std::error::Error;
+pub mod replay;
+
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    error::Error,
+    marker::PhantomData,
+};
+
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
 
 use crate::{
-    types::Move,
+    types::{
+        hazard_is_lethal, Action, FoodQueryableGame, HazardQueryableGame, HazardSettableGame,
+        HeadGettableGame, HealthGettableGame, Move, NeighborDeterminableGame,
+        RandomReasonableMovesGame, ReasonableMovesGame, SimulableGame, SimulatorInstruments,
+        SnakeIDGettableGame, StandardFoodPlaceableGame, VictorDeterminableGame,
+        YouDeterminableGame,
+    },
     wire_representation::{Game, Position},
 };
 
@@ -106,7 +121,7 @@ fn next_perfect_odd_square(n: u16) -> u16 {
     // next square base
     // e.g. if the input is 2, this is 2
     let mut next_base = current_base + 1;
-    if next_base % 2 == 0 {
+    if next_base.is_multiple_of(2) {
         next_base += 1;
     }
 
@@ -164,7 +179,10 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
 
     fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
         self.current_turn += 1;
-        if self.current_turn % self.hazard_every_turns as u16 == 0 {
+        if self
+            .current_turn
+            .is_multiple_of(self.hazard_every_turns as u16)
+        {
             let turns_elapsed = self.current_turn - self.first_turn_seen;
             // plus 1 because the seed cell
             let spawns_elapsed = (turns_elapsed / self.hazard_every_turns as u16) + 1;
@@ -206,16 +224,939 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
     }
 }
 
+/// The state both royale algorithms share: the shrink interval, the turn, and the safe area left,
+/// the rectangle of cells that aren't hazards yet. [RoyaleHazard] and [RoyaleShrink] only differ
+/// in how they pick the side it loses on each shrink
+#[derive(Debug, Clone, Copy)]
+struct RoyaleSafeArea {
+    shrink_every_n_turns: u32,
+    current_turn: usize,
+    width: i32,
+    height: i32,
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl RoyaleSafeArea {
+    fn new() -> Self {
+        RoyaleSafeArea {
+            shrink_every_n_turns: 0,
+            current_turn: 0,
+            width: 0,
+            height: 0,
+            min_x: 0,
+            max_x: -1,
+            min_y: 0,
+            max_y: -1,
+        }
+    }
+
+    /// takes the shrink interval from the game's royale settings, and the safe area from the
+    /// smallest rectangle holding every cell that isn't a hazard. With `wait_for_hazards` a game
+    /// without hazards returns no positions and leaves this unready
+    fn observe(
+        &mut self,
+        game: &Game,
+        wait_for_hazards: bool,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        if self.is_ready() {
+            return Err("already ready for inc".into());
+        }
+        let shrink_every_n_turns = game
+            .game
+            .ruleset
+            .settings
+            .as_ref()
+            .and_then(|s| s.royale)
+            .map(|r| r.shrink_every_n_turns)
+            .filter(|n| *n > 0)
+            .ok_or("game has no royale shrink settings")?;
+
+        let hazards = game.board.hazards.iter().copied().collect::<HashSet<_>>();
+        if wait_for_hazards && hazards.is_empty() {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        let (width, height) = (game.board.width as i32, game.board.height as i32);
+        let safe = (0..width)
+            .flat_map(|x| (0..height).map(move |y| Position { x, y }))
+            .filter(|pos| !hazards.contains(pos))
+            .collect::<Vec<_>>();
+        self.width = width;
+        self.height = height;
+        self.min_x = safe.iter().map(|p| p.x).min().unwrap_or(0);
+        self.max_x = safe.iter().map(|p| p.x).max().unwrap_or(-1);
+        self.min_y = safe.iter().map(|p| p.y).min().unwrap_or(0);
+        self.max_y = safe.iter().map(|p| p.y).max().unwrap_or(-1);
+        self.shrink_every_n_turns = shrink_every_n_turns as u32;
+        self.current_turn = game.turn.max(0) as usize;
+
+        Ok(Box::new(hazards.into_iter()))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.shrink_every_n_turns != 0
+    }
+
+    /// how many times each side has been engulfed, as the move pointing at it
+    fn engulfed(&self) -> [(Move, i32); 4] {
+        [
+            (Move::Left, self.min_x),
+            (Move::Right, self.width - 1 - self.max_x),
+            (Move::Down, self.min_y),
+            (Move::Up, self.height - 1 - self.max_y),
+        ]
+    }
+
+    /// Winds the turn forward by one, engulfing the side `pick_side` chooses if the safe area
+    /// shrinks this turn. Returns the newly engulfed cells
+    fn inc_turn(
+        &mut self,
+        pick_side: impl FnOnce(&Self) -> Move,
+    ) -> Box<dyn Iterator<Item = Position>> {
+        self.current_turn += 1;
+        if !self
+            .current_turn
+            .is_multiple_of(self.shrink_every_n_turns as usize)
+            || self.min_x > self.max_x
+            || self.min_y > self.max_y
+        {
+            return Box::new(std::iter::empty());
+        }
+
+        let (xs, ys) = (self.min_x..=self.max_x, self.min_y..=self.max_y);
+        let (xs, ys) = match pick_side(self) {
+            Move::Left => {
+                self.min_x += 1;
+                (*xs.start()..=*xs.start(), ys)
+            }
+            Move::Right => {
+                self.max_x -= 1;
+                (*xs.end()..=*xs.end(), ys)
+            }
+            Move::Down => {
+                self.min_y += 1;
+                (xs, *ys.start()..=*ys.start())
+            }
+            Move::Up => {
+                self.max_y -= 1;
+                (xs, *ys.end()..=*ys.end())
+            }
+        };
+        Box::new(xs.flat_map(move |x| ys.clone().map(move |y| Position { x, y })))
+    }
+}
+
+/// The royale hazard algorithm: every `shrinkEveryNTurns` turns one side of the safe area, picked
+/// at random, becomes hazardous. The engine's choice of side can't be predicted, so this picks
+/// sides with its own seeded rng, and every clone of it picks the same sides. Call
+/// [ForwardOnlyHazardAlgorithm::observe] once with the current game, after which it's ready for
+/// inc calls
+#[derive(Debug, Clone)]
+pub struct RoyaleHazard {
+    area: RoyaleSafeArea,
+    rng: SmallRng,
+}
+
+impl RoyaleHazard {
+    /// Construct an unitialized royale hazard algorithm, picking sides to shrink from `seed`
+    pub fn new(seed: u64) -> Self {
+        RoyaleHazard {
+            area: RoyaleSafeArea::new(),
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<Position> for RoyaleHazard {
+    /// takes the shrink interval from the game's royale settings, and the safe area from the
+    /// smallest rectangle holding every cell that isn't a hazard
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        self.area.observe(game, false)
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.area.is_ready()
+    }
+
+    fn current_turn(&self) -> usize {
+        self.area.current_turn
+    }
+
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
+        let rng = &mut self.rng;
+        self.area
+            .inc_turn(|area| area.engulfed()[rng.gen_range(0..4)].0)
+    }
+}
+
+/// The royale hazard algorithm, predicted deterministically. Unlike [RoyaleHazard] this waits
+/// for the first shrink: [ForwardOnlyHazardAlgorithm::observe] can be called every turn until a
+/// game with royale hazards on it is seen, after which it's ready for inc calls. Every
+/// `shrinkEveryNTurns` turns it engulfs the side that has been engulfed the fewest times so
+/// far, ties going left, right, down then up, which keeps the safe area as close to the shape
+/// a random engine is most likely to leave it in as possible. Since the engine actually picks
+/// the side at random, [RoyaleShrink::hazard_probabilities] forecasts how likely each cell is
+/// to be a hazard instead
+#[derive(Debug, Clone, Copy)]
+pub struct RoyaleShrink {
+    area: RoyaleSafeArea,
+}
+
+impl Default for RoyaleShrink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoyaleShrink {
+    /// Construct an unitialized royale shrink algorithm
+    pub fn new() -> Self {
+        RoyaleShrink {
+            area: RoyaleSafeArea::new(),
+        }
+    }
+
+    /// the side that will be engulfed on the next shrink, as the move pointing at it
+    pub fn predicted_side(&self) -> Move {
+        Self::fewest_engulfed(&self.area)
+    }
+
+    fn fewest_engulfed(area: &RoyaleSafeArea) -> Move {
+        area.engulfed()
+            .iter()
+            .min_by_key(|(_, n)| *n)
+            .map(|(side, _)| *side)
+            .unwrap()
+    }
+
+    /// the probability of each cell in the current safe area being a hazard `turns` turns from
+    /// now, if each shrink picks one of the four sides uniformly at random. Cells that can't be
+    /// reached by the shrinks in that time are left out
+    pub fn hazard_probabilities(&self, turns: usize) -> HashMap<Position, f32> {
+        let mut probabilities = HashMap::new();
+        if !self.is_ready_for_inc() {
+            return probabilities;
+        }
+        let area = &self.area;
+        let n = area.shrink_every_n_turns as usize;
+        let shrinks = (area.current_turn + turns) / n - area.current_turn / n;
+
+        // the chance of each side having been engulfed (left, right, down, up) times
+        let mut outcomes = HashMap::new();
+        outcomes.insert([0i32; 4], 1.0f32);
+        for _ in 0..shrinks {
+            let mut next = HashMap::new();
+            for (engulfed, p) in outcomes {
+                for side in 0..4 {
+                    let mut engulfed = engulfed;
+                    engulfed[side] += 1;
+                    *next.entry(engulfed).or_insert(0.0) += p / 4.0;
+                }
+            }
+            outcomes = next;
+        }
+
+        for ([left, right, down, up], p) in outcomes {
+            for x in area.min_x..=area.max_x {
+                for y in area.min_y..=area.max_y {
+                    if x < area.min_x + left
+                        || x > area.max_x - right
+                        || y < area.min_y + down
+                        || y > area.max_y - up
+                    {
+                        *probabilities.entry(Position { x, y }).or_insert(0.0) += p;
+                    }
+                }
+            }
+        }
+        probabilities
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<Position> for RoyaleShrink {
+    /// takes the shrink interval from the game's royale settings, and the safe area from the
+    /// smallest rectangle holding every cell that isn't a hazard. Until the game has hazards on
+    /// it this returns no positions and stays unready
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        self.area.observe(game, true)
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.area.is_ready()
+    }
+
+    fn current_turn(&self) -> usize {
+        self.area.current_turn
+    }
+
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
+        self.area.inc_turn(Self::fewest_engulfed)
+    }
+}
+
+/// A hazard algorithm for maps that scatter hazards on random cells. Every so many turns the
+/// same number of hazards appear on cells that aren't hazards yet, picked uniformly at random.
+/// Call [ForwardOnlyHazardAlgorithm::observe] every turn until two spawns have been seen, which
+/// gives the cadence and the number of hazards per spawn, after which it's ready for inc calls.
+/// Inc calls sample the spawned cells with a seeded rng, so a search can sample a different
+/// future per seed, and [ScatterHazard::forecast] gives the chance of each cell being a hazard
+#[derive(Debug, Clone)]
+pub struct ScatterHazard {
+    width: i32,
+    height: i32,
+    hazards: HashSet<Position>,
+    last_observed_turn: Option<usize>,
+    last_spawn_turn: Option<usize>,
+    spawn_every_n_turns: usize,
+    hazards_per_spawn: usize,
+    current_turn: usize,
+    rng: SmallRng,
+}
+
+impl ScatterHazard {
+    /// Construct an unitialized scatter hazard algorithm, sampling spawned cells from `seed`
+    pub fn new(seed: u64) -> Self {
+        ScatterHazard {
+            width: 0,
+            height: 0,
+            hazards: HashSet::new(),
+            last_observed_turn: None,
+            last_spawn_turn: None,
+            spawn_every_n_turns: 0,
+            hazards_per_spawn: 0,
+            current_turn: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// the number of turns between spawns, 0 until two spawns have been observed
+    pub fn spawn_every_n_turns(&self) -> usize {
+        self.spawn_every_n_turns
+    }
+
+    /// the number of hazards that appear on each spawn
+    pub fn hazards_per_spawn(&self) -> usize {
+        self.hazards_per_spawn
+    }
+
+    /// the cells that aren't hazards yet, sorted so sampling from them is deterministic
+    fn free_cells(&self) -> Vec<Position> {
+        let (width, height) = (self.width, self.height);
+        (0..width)
+            .flat_map(|x| (0..height).map(move |y| Position { x, y }))
+            .filter(|pos| !self.hazards.contains(pos))
+            .collect()
+    }
+
+    fn spawns_by(&self, turn: usize) -> usize {
+        match self.last_spawn_turn {
+            Some(last) if self.spawn_every_n_turns > 0 => (turn - last) / self.spawn_every_n_turns,
+            _ => 0,
+        }
+    }
+
+    /// The probability of each cell being a hazard `turns_ahead` turns from now. Current hazards
+    /// have a probability of 1.0 and the other cells share the spawns between them. Cells that
+    /// can't be a hazard by then are left out
+    pub fn forecast(&self, turns_ahead: usize) -> impl Iterator<Item = (Position, f32)> {
+        let spawns =
+            self.spawns_by(self.current_turn + turns_ahead) - self.spawns_by(self.current_turn);
+        let free = self.free_cells();
+        let mut still_free = 1.0f32;
+        for spawn in 0..spawns {
+            let remaining = free.len().saturating_sub(spawn * self.hazards_per_spawn);
+            if remaining == 0 {
+                still_free = 0.0;
+                break;
+            }
+            still_free *= 1.0 - (self.hazards_per_spawn.min(remaining) as f32 / remaining as f32);
+        }
+
+        let hazards = self.hazards.clone().into_iter().map(|pos| (pos, 1.0));
+        let spawned = free
+            .into_iter()
+            .map(move |pos| (pos, 1.0 - still_free))
+            .filter(|(_, p)| *p > 0.0);
+        hazards.chain(spawned)
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<Position> for ScatterHazard {
+    /// records the turns hazards appear on and how many appear at once, every hazard on the
+    /// board is returned each time
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        if self.is_ready_for_inc() {
+            return Err("already ready for inc".into());
+        }
+        let turn = game.turn.max(0) as usize;
+        if let Some(last) = self.last_observed_turn.filter(|last| turn <= *last) {
+            return Err(format!("observed turn {} after turn {}", turn, last).into());
+        }
+
+        let hazards = game.board.hazards.iter().copied().collect::<HashSet<_>>();
+        let spawned = hazards.difference(&self.hazards).count();
+        if self.last_observed_turn.is_some() && spawned > 0 {
+            if let Some(last_spawn) = self.last_spawn_turn {
+                self.spawn_every_n_turns = turn - last_spawn;
+            }
+            self.last_spawn_turn = Some(turn);
+            self.hazards_per_spawn = spawned;
+        }
+        self.width = game.board.width as i32;
+        self.height = game.board.height as i32;
+        self.hazards = hazards.clone();
+        self.last_observed_turn = Some(turn);
+        self.current_turn = turn;
+
+        Ok(Box::new(hazards.into_iter()))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.spawn_every_n_turns != 0
+    }
+
+    fn current_turn(&self) -> usize {
+        self.current_turn
+    }
+
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
+        self.current_turn += 1;
+        if self.spawns_by(self.current_turn) == self.spawns_by(self.current_turn - 1) {
+            return Box::new(std::iter::empty());
+        }
+
+        let spawned = self
+            .free_cells()
+            .choose_multiple(&mut self.rng, self.hazards_per_spawn)
+            .copied()
+            .collect::<Vec<_>>();
+        self.hazards.extend(spawned.iter().copied());
+        Box::new(spawned.into_iter())
+    }
+}
+
+/// The turn the sinkhole seed hazard appears on
+const SINKHOLE_START_TURN: usize = 1;
+
+/// The sinkhole hazard algorithm, used by the sinkholes map: a hazard appears in the middle of
+/// the board on turn 1, and every `shrinkEveryNTurns` turns (10 if unset) another box of hazards,
+/// one cell further out with its corners left off, is stacked on top. That stacks hazards up the
+/// most in the middle. It stops after 3 boxes on a 7 wide board, 7 on a 19 wide one and 5
+/// otherwise. Each position is emitted with the number of hazards stacked on it once it's
+/// spawned, for [HazardSettableGame::set_hazard_count], which is how a [HazardProgressingBoard]
+/// applies them. Call
+/// [ForwardOnlyHazardAlgorithm::observe] every turn until the middle hazard is seen, after which
+/// it's ready for inc calls
+#[derive(Debug, Clone)]
+pub struct SinkholeHazard {
+    spawn_every_n_turns: usize,
+    max_rings: usize,
+    center: Position,
+    width: i32,
+    height: i32,
+    current_turn: usize,
+    stacks: HashMap<Position, u8>,
+}
+
+impl Default for SinkholeHazard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SinkholeHazard {
+    /// Construct an unitialized sinkhole hazard algorithm
+    pub fn new() -> Self {
+        SinkholeHazard {
+            spawn_every_n_turns: 0,
+            max_rings: 0,
+            center: Position { x: 0, y: 0 },
+            width: 0,
+            height: 0,
+            current_turn: 0,
+            stacks: HashMap::new(),
+        }
+    }
+
+    /// the number of hazards stacked on a position
+    pub fn stack_count(&self, pos: &Position) -> u8 {
+        self.stacks.get(pos).copied().unwrap_or(0)
+    }
+
+    /// the cells of the box `ring` cells out from the middle, without its corners
+    fn ring(&self, ring: i32) -> impl Iterator<Item = Position> {
+        let (center, width, height) = (self.center, self.width, self.height);
+        let xs = center.x - ring..=center.x + ring;
+        xs.flat_map(move |x| (center.y - ring..=center.y + ring).map(move |y| Position { x, y }))
+            .filter(move |pos| {
+                (pos.x - center.x).abs() != ring || (pos.y - center.y).abs() != ring || ring == 0
+            })
+            .filter(move |pos| pos.x >= 0 && pos.x < width && pos.y >= 0 && pos.y < height)
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<(Position, u8)> for SinkholeHazard {
+    /// takes the spawn interval from the game's royale settings, and the stacks from the hazards
+    /// on the board, see [SinkholeHazard::stack_count]. Each hazard cell is returned once. Until
+    /// the middle of the board is a hazard this returns nothing and stays unready
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        if self.is_ready_for_inc() {
+            return Err("already ready for inc".into());
+        }
+        let center = Position {
+            x: game.board.width as i32 / 2,
+            y: game.board.height as i32 / 2,
+        };
+        if !game.board.hazards.contains(&center) {
+            return Ok(Box::new(std::iter::empty()));
+        }
+
+        self.spawn_every_n_turns = game
+            .game
+            .ruleset
+            .settings
+            .as_ref()
+            .and_then(|s| s.royale)
+            .map(|r| r.shrink_every_n_turns)
+            .filter(|n| *n > 0)
+            .unwrap_or(10) as usize;
+        self.max_rings = match game.board.width {
+            7 => 3,
+            19 => 7,
+            _ => 5,
+        };
+        self.center = center;
+        self.width = game.board.width as i32;
+        self.height = game.board.height as i32;
+        self.current_turn = game.turn.max(0) as usize;
+        self.stacks.clear();
+        for pos in &game.board.hazards {
+            *self.stacks.entry(*pos).or_insert(0) += 1;
+        }
+
+        Ok(Box::new(
+            self.stacks.keys().copied().collect::<Vec<_>>().into_iter(),
+        ))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.spawn_every_n_turns != 0
+    }
+
+    fn current_turn(&self) -> usize {
+        self.current_turn
+    }
+
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = (Position, u8)>> {
+        self.current_turn += 1;
+        let since_start = self.current_turn.saturating_sub(SINKHOLE_START_TURN);
+        let ring = since_start / self.spawn_every_n_turns;
+        if since_start == 0
+            || !since_start.is_multiple_of(self.spawn_every_n_turns)
+            || ring > self.max_rings
+        {
+            return Box::new(std::iter::empty());
+        }
+
+        let spawned = self
+            .ring(ring as i32)
+            .map(|pos| {
+                let stack = self.stacks.entry(pos).or_insert(0);
+                *stack = stack.saturating_add(1);
+                (pos, *stack)
+            })
+            .collect::<Vec<_>>();
+        Box::new(spawned.into_iter())
+    }
+}
+
+/// A board paired with the hazards a [ForwardOnlyHazardAlgorithm] forecasts for it. This lets a
+/// search tell apart a cell that is hazardous now from one that will only become hazardous in a
+/// few turns, rather than treating future hazards as either present or absent.
+#[derive(Debug, Clone)]
+pub struct ForecastedBoard<B> {
+    board: B,
+    start_turn: usize,
+    forecast_horizon: usize,
+    first_hazarded_turn: HashMap<Position, usize>,
+}
+
+impl<B: HazardQueryableGame> ForecastedBoard<B> {
+    /// forecasts `turns` turns ahead by winding a clone of `algorithm` forward from its current
+    /// turn. If the algorithm isn't ready for inc calls yet nothing is forecast.
+    pub fn new<H: ForwardOnlyHazardAlgorithm<Position>>(
+        board: B,
+        algorithm: &H,
+        turns: usize,
+    ) -> Self {
+        let mut algorithm = algorithm.clone();
+        let mut first_hazarded_turn = HashMap::new();
+        let start_turn = algorithm.current_turn();
+        let mut forecast_horizon = start_turn;
+
+        if algorithm.is_ready_for_inc() {
+            for _ in 0..turns {
+                for pos in algorithm.inc_turn() {
+                    first_hazarded_turn
+                        .entry(pos)
+                        .or_insert_with(|| algorithm.current_turn());
+                }
+            }
+            forecast_horizon = algorithm.current_turn();
+        }
+
+        ForecastedBoard {
+            board,
+            start_turn,
+            forecast_horizon,
+            first_hazarded_turn,
+        }
+    }
+
+    /// the wrapped board
+    pub fn board(&self) -> &B {
+        &self.board
+    }
+
+    /// unwraps this back in to the board
+    pub fn into_board(self) -> B {
+        self.board
+    }
+
+    /// the last turn covered by the forecast, hazards spawning after this turn are unknown
+    pub fn forecast_horizon(&self) -> usize {
+        self.forecast_horizon
+    }
+
+    /// is the given position hazardous on the given turn? Hazards currently on the board are
+    /// assumed to stay put, hazards past the forecast horizon are not known about
+    pub fn is_hazard_at_turn(&self, pos: &B::NativePositionType, turn: usize) -> bool {
+        self.board.is_hazard(pos)
+            || self
+                .first_hazarded_turn(pos)
+                .is_some_and(|first_turn| first_turn <= turn)
+    }
+
+    /// the first turn on which the given position becomes hazardous, if that happens within the
+    /// forecast horizon. Only forecast hazards are considered, hazards already on the board
+    /// are handled by [ForecastedBoard::is_hazard_at_turn]
+    pub fn first_hazarded_turn(&self, pos: &B::NativePositionType) -> Option<usize> {
+        let pos = self.board.position_from_native(pos.clone());
+        self.first_hazarded_turn.get(&pos).copied()
+    }
+}
+
+impl<B: SnakeIDGettableGame> SnakeIDGettableGame for ForecastedBoard<B> {
+    type SnakeIDType = B::SnakeIDType;
+
+    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+        self.board.get_snake_ids()
+    }
+}
+
+/// The board's reasonable moves, without the moves on to cells forecast to be a hazard next turn
+/// that would eliminate the snake, counted the way [ReasonableMovesGame] counts hazards already on
+/// the board. Rollout policies built on the board's reasonable moves otherwise happily walk in to
+/// a royale shrink they can't survive. If that leaves a snake with no moves it keeps the moves the
+/// board gave it
+impl<B> ReasonableMovesGame for ForecastedBoard<B>
+where
+    B: ReasonableMovesGame
+        + HeadGettableGame
+        + HealthGettableGame
+        + NeighborDeterminableGame
+        + HazardQueryableGame
+        + FoodQueryableGame
+        + Sync,
+{
+    fn reasonable_moves_for_each_snake(
+        &self,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + Send + '_> {
+        let next_turn = self.start_turn + 1;
+        let hazard_damage = self.board.get_hazard_damage() as i64;
+        Box::new(
+            self.board
+                .reasonable_moves_for_each_snake()
+                .map(move |(sid, moves)| {
+                    let health = self.board.get_health_i64(&sid);
+                    let head = self.board.get_head_as_native_position(&sid);
+                    let deadly = self
+                        .board
+                        .possible_moves(&head)
+                        .filter(|(_, pos)| {
+                            // a cell the forecast adds a hazard to has at least one
+                            let stacks = self.board.get_hazard_count(pos).max(1) as i64;
+                            self.is_hazard_at_turn(pos, next_turn)
+                                && !self.board.is_food(pos)
+                                && hazard_is_lethal(health, hazard_damage, stacks)
+                        })
+                        .map(|(mv, _)| mv)
+                        .collect::<Vec<_>>();
+                    let safe = moves
+                        .iter()
+                        .copied()
+                        .filter(|mv| !deadly.contains(mv))
+                        .collect::<Vec<_>>();
+                    if safe.is_empty() {
+                        (sid, moves)
+                    } else {
+                        (sid, safe)
+                    }
+                }),
+        )
+    }
+}
+
+impl<B> RandomReasonableMovesGame for ForecastedBoard<B>
+where
+    B: ReasonableMovesGame
+        + HeadGettableGame
+        + HealthGettableGame
+        + NeighborDeterminableGame
+        + HazardQueryableGame
+        + FoodQueryableGame
+        + Sync,
+{
+    fn random_reasonable_move_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
+        Box::new(
+            self.reasonable_moves_for_each_snake()
+                .map(move |(sid, moves)| (sid, *moves.choose(rng).unwrap())),
+        )
+    }
+}
+
+/// A hazard spawned by a [ForwardOnlyHazardAlgorithm], which a [HazardProgressingBoard] knows how
+/// to put on its board
+pub trait HazardSpawn: std::fmt::Debug {
+    /// where the hazard spawned
+    fn position(&self) -> Position;
+
+    /// puts the hazard on `board` at `native`, the board's own form of [HazardSpawn::position]
+    fn apply<B: HazardSettableGame>(&self, board: &mut B, native: B::NativePositionType);
+}
+
+impl HazardSpawn for Position {
+    fn position(&self) -> Position {
+        *self
+    }
+
+    fn apply<B: HazardSettableGame>(&self, board: &mut B, native: B::NativePositionType) {
+        board.set_hazard(native);
+    }
+}
+
+/// a position along with the number of hazards stacked on it once spawned, see [SinkholeHazard]
+impl HazardSpawn for (Position, u8) {
+    fn position(&self) -> Position {
+        self.0
+    }
+
+    fn apply<B: HazardSettableGame>(&self, board: &mut B, native: B::NativePositionType) {
+        board.set_hazard_count(native, self.1);
+    }
+}
+
+/// A board whose hazards progress as it's simulated: each child gets the hazards its
+/// [ForwardOnlyHazardAlgorithm] spawns on the next turn, and carries its own copy of the algorithm
+/// wound forward to that turn. Without this hazards stay where they are during simulation, which
+/// makes deep rollouts of e.g. royale games increasingly wrong. Hazards stay static if the
+/// algorithm isn't ready for inc calls.
+///
+/// `T` is what the algorithm spawns, see [HazardSpawn]. Algorithms spawning stacks, like
+/// [SinkholeHazard], have their counts set with [HazardSettableGame::set_hazard_count]
+#[derive(Debug, Clone)]
+pub struct HazardProgressingBoard<B, H, T = Position> {
+    board: B,
+    algorithm: H,
+    spawns: PhantomData<fn() -> T>,
+}
+
+impl<B: HazardSettableGame, H: ForwardOnlyHazardAlgorithm<T>, T: HazardSpawn>
+    HazardProgressingBoard<B, H, T>
+{
+    /// pairs a board with a hazard algorithm that's at the board's turn
+    pub fn new(board: B, algorithm: H) -> Self {
+        HazardProgressingBoard {
+            board,
+            algorithm,
+            spawns: PhantomData,
+        }
+    }
+
+    /// the wrapped board
+    pub fn board(&self) -> &B {
+        &self.board
+    }
+
+    /// unwraps this back in to the board
+    pub fn into_board(self) -> B {
+        self.board
+    }
+
+    /// the algorithm, at this board's turn
+    pub fn algorithm(&self) -> &H {
+        &self.algorithm
+    }
+
+    /// winds a copy of the algorithm forward a turn and applies its new hazards to `board`
+    fn progressed(&self, mut board: B) -> Self {
+        let mut algorithm = self.algorithm.clone();
+        if algorithm.is_ready_for_inc() {
+            for spawn in algorithm.inc_turn() {
+                let pos = spawn.position();
+                if !board.off_board(pos) {
+                    let native = board.native_from_position(pos);
+                    spawn.apply(&mut board, native);
+                }
+            }
+        }
+        HazardProgressingBoard::new(board, algorithm)
+    }
+}
+
+impl<B, H, T, I, const N_SNAKES: usize> SimulableGame<I, N_SNAKES>
+    for HazardProgressingBoard<B, H, T>
+where
+    B: SimulableGame<I, N_SNAKES> + HazardSettableGame,
+    H: ForwardOnlyHazardAlgorithm<T> + Send + Sync,
+    T: HazardSpawn,
+    I: SimulatorInstruments,
+{
+    fn simulate_with_moves<S>(
+        &self,
+        instruments: &I,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send + '_>
+    where
+        S: Borrow<[Move]>,
+    {
+        Box::new(
+            self.board
+                .simulate_with_moves(instruments, snake_ids_and_moves)
+                .map(move |(action, child)| (action, self.progressed(child))),
+        )
+    }
+}
+
+impl<B: SnakeIDGettableGame, H, T> SnakeIDGettableGame for HazardProgressingBoard<B, H, T> {
+    type SnakeIDType = B::SnakeIDType;
+
+    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+        self.board.get_snake_ids()
+    }
+}
+
+impl<B: YouDeterminableGame, H: std::fmt::Debug, T: std::fmt::Debug> YouDeterminableGame
+    for HazardProgressingBoard<B, H, T>
+{
+    fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.board.is_you(snake_id)
+    }
+
+    fn you_id(&self) -> &Self::SnakeIDType {
+        self.board.you_id()
+    }
+}
+
+impl<B: VictorDeterminableGame, H: std::fmt::Debug, T: std::fmt::Debug> VictorDeterminableGame
+    for HazardProgressingBoard<B, H, T>
+{
+    fn is_over(&self) -> bool {
+        self.board.is_over()
+    }
+
+    fn get_winner(&self) -> Option<Self::SnakeIDType> {
+        self.board.get_winner()
+    }
+
+    fn alive_snake_count(&self) -> usize {
+        self.board.alive_snake_count()
+    }
+
+    fn is_solo(&self) -> bool {
+        self.board.is_solo()
+    }
+}
+
+impl<B: HealthGettableGame, H, T> HealthGettableGame for HazardProgressingBoard<B, H, T> {
+    type HealthType = B::HealthType;
+    const ZERO: Self::HealthType = B::ZERO;
+
+    fn get_health(&self, snake_id: &Self::SnakeIDType) -> Self::HealthType {
+        self.board.get_health(snake_id)
+    }
+
+    fn get_health_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
+        self.board.get_health_i64(snake_id)
+    }
+
+    fn is_alive(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.board.is_alive(snake_id)
+    }
+}
+
+impl<B: RandomReasonableMovesGame, H, T> RandomReasonableMovesGame
+    for HazardProgressingBoard<B, H, T>
+{
+    fn random_reasonable_move_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
+        self.board.random_reasonable_move_for_each_snake(rng)
+    }
+}
+
+impl<B: StandardFoodPlaceableGame, H, T> StandardFoodPlaceableGame
+    for HazardProgressingBoard<B, H, T>
+{
+    fn place_food(&mut self, rng: &mut impl Rng) {
+        self.board.place_food(rng)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashSet, fs, iter::FromIterator, path};
+    use std::{
+        collections::{HashMap, HashSet},
+        fs,
+        iter::FromIterator,
+        path,
+    };
 
     use crate::{
+        game_fixture,
         types::Move,
         wire_representation::{Game, Position},
     };
 
-    use super::{ForwardOnlyHazardAlgorithm, SpiralHazard};
+    use super::{
+        ForecastedBoard, ForwardOnlyHazardAlgorithm, HazardProgressingBoard, NoopHazard,
+        RoyaleHazard, RoyaleShrink, ScatterHazard, SinkholeHazard, SpiralHazard,
+    };
+    use crate::algorithms::rollout::RolloutGame;
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::types::{
+        build_snake_id_map, HazardQueryableGame, HazardSettableGame, PositionGettableGame,
+        RandomReasonableMovesGame, ReasonableMovesGame, SimulableGame, SimulatorInstruments,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
 
     #[test]
     fn test_next_perfect_square() {
@@ -340,10 +1281,418 @@ mod tests {
             } else {
                 let new_hazards = hazard_alg.inc_turn();
                 maintained_hazards.extend(new_hazards);
-                let hazards_set = HashSet::from_iter(game.board.hazards.into_iter());
+                let hazards_set = HashSet::from_iter(game.board.hazards);
                 assert!(hazard_alg.current_turn == game.turn as u16);
                 assert!(hazards_set == maintained_hazards);
             }
         }
     }
+
+    #[test]
+    fn test_forecasted_board() {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.board.hazards = vec![Position { x: 5, y: 5 }];
+        let spiral = SpiralHazard {
+            hazard_every_turns: 3,
+            seed_cell: Position { x: 5, y: 5 },
+            first_turn_seen: 3,
+            current_turn: 3,
+            next_hazard_cell: Position { x: 5, y: 6 },
+            direction: Move::Right,
+        };
+        let forecast = ForecastedBoard::new(game.clone(), &spiral, 6);
+
+        assert_eq!(forecast.forecast_horizon(), 9);
+        assert!(forecast.is_hazard_at_turn(&Position { x: 5, y: 5 }, 3));
+        assert!(!forecast.is_hazard_at_turn(&Position { x: 5, y: 6 }, 5));
+        assert!(forecast.is_hazard_at_turn(&Position { x: 5, y: 6 }, 6));
+        assert_eq!(
+            forecast.first_hazarded_turn(&Position { x: 6, y: 6 }),
+            Some(9)
+        );
+        assert!(!forecast.is_hazard_at_turn(&Position { x: 6, y: 5 }, 100));
+
+        let noop = ForecastedBoard::new(game, &NoopHazard(), 6);
+        assert!(!noop.is_hazard_at_turn(&Position { x: 5, y: 6 }, 100));
+        assert!(noop.is_hazard_at_turn(&Position { x: 5, y: 5 }, 0));
+    }
+
+    #[test]
+    fn test_forecasted_reasonable_moves() {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let you = game.you.id.clone();
+        let hazard_damage = game.get_hazard_damage() as i32;
+        // (6, 8), to the right of your head, becomes a hazard next turn
+        let spiral = SpiralHazard {
+            hazard_every_turns: 3,
+            seed_cell: Position { x: 6, y: 7 },
+            first_turn_seen: 3,
+            current_turn: 5,
+            next_hazard_cell: Position { x: 6, y: 8 },
+            direction: Move::Right,
+        };
+        let moves_of = |game: &Game| {
+            ForecastedBoard::new(game.clone(), &spiral, 3)
+                .reasonable_moves_for_each_snake()
+                .find(|(sid, _)| sid == &you)
+                .unwrap()
+                .1
+        };
+
+        assert!(moves_of(&game).contains(&Move::Right));
+        let set_health = |game: &mut Game, health: i32| {
+            for snake in game.board.snakes.iter_mut().filter(|s| s.id == you) {
+                snake.health = health;
+            }
+        };
+        // the move takes 1 health before the hazard does its damage
+        set_health(&mut game, hazard_damage + 2);
+        assert!(moves_of(&game).contains(&Move::Right));
+        set_health(&mut game, hazard_damage + 1);
+        let moves = moves_of(&game);
+        assert!(!moves.contains(&Move::Right));
+        assert!(!moves.is_empty());
+
+        // eating cancels the damage
+        let mut with_food = game.clone();
+        with_food.board.food.push(Position { x: 6, y: 8 });
+        assert!(moves_of(&with_food).contains(&Move::Right));
+
+        // each hazard stacked on the cell does its damage
+        let mut stacked = game.clone();
+        stacked.board.hazards = vec![Position { x: 6, y: 8 }; 2];
+        set_health(&mut stacked, 2 * hazard_damage + 1);
+        assert!(!moves_of(&stacked).contains(&Move::Right));
+        set_health(&mut stacked, 2 * hazard_damage + 2);
+        assert!(moves_of(&stacked).contains(&Move::Right));
+
+        let forecast = ForecastedBoard::new(game, &spiral, 3);
+        let mut rng = SmallRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let (_, mv) = forecast
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .find(|(sid, _)| sid == &you)
+                .unwrap();
+            assert_ne!(mv, Move::Right);
+        }
+    }
+
+    #[test]
+    fn test_royale_shrink() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let mut before_first_shrink = g.clone();
+        before_first_shrink.board.hazards.clear();
+        let mut shrink = RoyaleShrink::new();
+        assert!(shrink
+            .observe(&before_first_shrink)
+            .unwrap()
+            .next()
+            .is_none());
+        assert!(!shrink.is_ready_for_inc());
+        assert!(shrink.hazard_probabilities(100).is_empty());
+        assert!(shrink
+            .observe(&game_fixture(include_str!(
+                "../../fixtures/start_of_game.json"
+            )))
+            .is_err());
+
+        // the safe area is x 1..=9, y 2..=8, so the left and right have been engulfed the fewest
+        assert_eq!(shrink.observe(&g).unwrap().count(), 11 * 11 - 9 * 7);
+        assert!(shrink.is_ready_for_inc());
+        assert_eq!(shrink.predicted_side(), Move::Left);
+
+        let probabilities = shrink.hazard_probabilities(25);
+        assert_eq!(probabilities[&Position { x: 1, y: 5 }], 0.25);
+        assert_eq!(probabilities[&Position { x: 1, y: 2 }], 0.5);
+        assert!(!probabilities.contains_key(&Position { x: 5, y: 5 }));
+        assert!(shrink.hazard_probabilities(24).is_empty());
+        let two_shrinks = shrink.hazard_probabilities(50);
+        assert_eq!(two_shrinks[&Position { x: 2, y: 5 }], 1.0 / 16.0);
+        assert_eq!(two_shrinks[&Position { x: 1, y: 5 }], 1.0 - 0.75 * 0.75);
+
+        let mut engulfed = vec![];
+        for _ in 0..24 {
+            engulfed.extend(shrink.inc_turn());
+        }
+        assert!(engulfed.is_empty());
+        engulfed.extend(shrink.inc_turn());
+        assert_eq!(
+            engulfed,
+            (2..=8).map(|y| Position { x: 1, y }).collect::<Vec<_>>()
+        );
+        assert_eq!(shrink.predicted_side(), Move::Right);
+        let next = (0..25).flat_map(|_| shrink.inc_turn()).collect::<Vec<_>>();
+        assert!(next.iter().all(|pos| pos.x == 9));
+        assert_eq!(shrink.predicted_side(), Move::Left);
+    }
+
+    #[test]
+    fn test_scatter_hazard() {
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let frame = |turn, hazards: &[(i32, i32)]| {
+            let mut g = start.clone();
+            g.turn = turn;
+            g.board.hazards = hazards.iter().map(|(x, y)| Position::new(*x, *y)).collect();
+            g
+        };
+        let mut scatter = ScatterHazard::new(1);
+        for g in [
+            frame(1, &[]),
+            frame(3, &[(0, 0), (5, 5)]),
+            frame(4, &[(0, 0), (5, 5)]),
+        ] {
+            assert!(scatter.observe(&g).is_ok());
+            assert!(!scatter.is_ready_for_inc());
+        }
+        assert!(scatter.observe(&frame(4, &[])).is_err());
+        let observed = scatter
+            .observe(&frame(6, &[(0, 0), (5, 5), (1, 2), (7, 3)]))
+            .unwrap()
+            .count();
+        assert_eq!(observed, 4);
+        assert!(scatter.is_ready_for_inc());
+        assert_eq!(scatter.spawn_every_n_turns(), 3);
+        assert_eq!(scatter.hazards_per_spawn(), 2);
+
+        let now = scatter.forecast(2).collect::<Vec<_>>();
+        assert_eq!(now.len(), 4);
+        assert!(now.iter().all(|(_, p)| *p == 1.0));
+        let next_spawn = scatter.forecast(3).collect::<HashMap<_, _>>();
+        assert_eq!(next_spawn.len(), 121);
+        assert_eq!(next_spawn[&Position::new(0, 0)], 1.0);
+        assert!((next_spawn[&Position::new(10, 10)] - 2.0 / 117.0).abs() < 1e-6);
+        let two_spawns = scatter.forecast(6).collect::<HashMap<_, _>>();
+        assert!((two_spawns[&Position::new(10, 10)] - 4.0 / 117.0).abs() < 1e-6);
+
+        let mut sampled = scatter.clone();
+        assert!(sampled.inc_turn().next().is_none());
+        assert!(sampled.inc_turn().next().is_none());
+        let spawned = sampled.inc_turn().collect::<HashSet<_>>();
+        assert_eq!(spawned.len(), 2);
+        assert!(spawned.iter().all(|pos| next_spawn[pos] < 1.0));
+        let mut again = scatter.clone();
+        let resampled = (0..3)
+            .flat_map(|_| again.inc_turn())
+            .collect::<HashSet<_>>();
+        assert_eq!(spawned, resampled);
+    }
+
+    #[test]
+    fn test_sinkhole_hazard() {
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let mut sinkhole = SinkholeHazard::new();
+        assert!(sinkhole.observe(&start).unwrap().next().is_none());
+        assert!(!sinkhole.is_ready_for_inc());
+
+        let mut seeded = start.clone();
+        seeded.board.hazards = vec![Position::new(5, 5)];
+        let observed = sinkhole.observe(&seeded).unwrap().collect::<Vec<_>>();
+        assert_eq!(observed, vec![Position::new(5, 5)]);
+        assert!(sinkhole.is_ready_for_inc());
+        assert!(sinkhole.observe(&seeded).is_err());
+
+        let mut spawns = vec![];
+        for _ in 0..60 {
+            let turn = sinkhole.current_turn() + 1;
+            let spawned = sinkhole.inc_turn().collect::<HashMap<_, _>>();
+            if !spawned.is_empty() {
+                spawns.push((turn, spawned));
+            }
+        }
+        let turns = spawns.iter().map(|(turn, _)| *turn).collect::<Vec<_>>();
+        assert_eq!(turns, vec![11, 21, 31, 41, 51]);
+
+        let first = &spawns[0].1;
+        assert_eq!(first.len(), 5);
+        assert_eq!(first[&Position::new(5, 5)], 2);
+        assert_eq!(first[&Position::new(5, 6)], 1);
+        assert!(!first.contains_key(&Position::new(4, 4)));
+        let second = &spawns[1].1;
+        assert_eq!(second.len(), 21);
+        assert_eq!(second[&Position::new(5, 5)], 3);
+        assert_eq!(second[&Position::new(5, 6)], 2);
+        assert_eq!(second[&Position::new(4, 4)], 1);
+        // the last box runs off the board on every side
+        assert_eq!(spawns[4].1.len(), 11 * 11 - 4);
+        assert_eq!(sinkhole.stack_count(&Position::new(5, 5)), 6);
+        assert_eq!(sinkhole.stack_count(&Position::new(0, 5)), 1);
+
+        // picking up the stacks part way through, and applying them to a stacked hazard board
+        let mut mid_game = seeded.clone();
+        mid_game.turn = 15;
+        mid_game.board.hazards = vec![Position::new(5, 5), Position::new(5, 5)];
+        mid_game
+            .board
+            .hazards
+            .extend(first.keys().filter(|pos| pos.x != 5 || pos.y != 5));
+        let mut resumed = SinkholeHazard::new();
+        assert_eq!(resumed.observe(&mid_game).unwrap().count(), 5);
+        assert_eq!(resumed.stack_count(&Position::new(5, 5)), 2);
+
+        let id_map = build_snake_id_map(&mid_game);
+        let mut compact: StandardCellBoard4Snakes11x11 = mid_game.as_cell_board(&id_map).unwrap();
+        for _ in 0..6 {
+            for (pos, count) in resumed.inc_turn() {
+                let native = compact.native_from_position(pos);
+                compact.set_hazard_count(native, count);
+            }
+        }
+        let center = compact.native_from_position(Position::new(5, 5));
+        assert_eq!(compact.get_hazard_count(&center), 3);
+        let corner = compact.native_from_position(Position::new(4, 4));
+        assert_eq!(compact.get_hazard_count(&corner), 1);
+    }
+
+    #[test]
+    fn test_sinkhole_stacks_progress_during_simulation() {
+        #[derive(Debug)]
+        struct Instruments;
+        impl SimulatorInstruments for Instruments {
+            fn observe_simulation(&self, _: std::time::Duration) {}
+        }
+
+        // turn 20 of a sinkholes game, the next turn stacks the second box on the first
+        let mut g = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        g.turn = 20;
+        g.board.hazards = vec![Position::new(5, 5), Position::new(5, 5)];
+        g.board.hazards.extend([
+            Position::new(4, 5),
+            Position::new(6, 5),
+            Position::new(5, 4),
+            Position::new(5, 6),
+        ]);
+        let mut sinkhole = SinkholeHazard::new();
+        assert_eq!(sinkhole.observe(&g).unwrap().count(), 5);
+
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let moves = compact
+            .reasonable_moves_for_each_snake()
+            .map(|(sid, moves)| (sid, [moves[0]]))
+            .collect::<Vec<_>>();
+        let board = HazardProgressingBoard::new(compact, sinkhole);
+        let (_, child) = board
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+
+        assert_eq!(child.algorithm().current_turn(), 21);
+        let count = |x, y| {
+            let native = child.board().native_from_position(Position::new(x, y));
+            child.board().get_hazard_count(&native)
+        };
+        assert_eq!(count(5, 5), 3);
+        assert_eq!(count(5, 6), 2);
+        assert_eq!(count(4, 4), 1);
+        assert_eq!(count(3, 5), 1);
+        assert_eq!(count(3, 3), 0);
+        // the second box is 5 by 5 without its corners
+        assert_eq!(child.board().hazard_count(), 21);
+        assert_eq!(
+            child.algorithm().stack_count(&Position::new(5, 5)),
+            count(5, 5)
+        );
+    }
+
+    #[test]
+    fn test_royale_hazards_progress_during_simulation() {
+        #[derive(Debug)]
+        struct Instruments;
+        impl SimulatorInstruments for Instruments {
+            fn observe_simulation(&self, _: std::time::Duration) {}
+        }
+
+        // turn 150 of a royale game shrinking every 25 turns, the safe area is x 1..=9, y 2..=8
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let mut royale = RoyaleHazard::new(1);
+        let observed = royale.observe(&g).unwrap().collect::<HashSet<_>>();
+        assert!(royale.is_ready_for_inc());
+        assert!(royale.observe(&g).is_err());
+        assert_eq!(royale.current_turn(), 150);
+        assert_eq!(observed.len(), 11 * 11 - 9 * 7);
+
+        let mut wound = royale.clone();
+        let mut spawned = vec![];
+        for _ in 0..24 {
+            spawned.extend(wound.inc_turn());
+        }
+        assert!(spawned.is_empty());
+        let before_shrink = wound.clone();
+        spawned.extend(wound.inc_turn());
+        assert!(spawned.len() == 9 || spawned.len() == 7);
+        assert!(spawned.iter().all(|pos| !observed.contains(pos)));
+
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let moves = compact
+            .reasonable_moves_for_each_snake()
+            .map(|(sid, moves)| (sid, [moves[0]]))
+            .collect::<Vec<_>>();
+        let board = HazardProgressingBoard::new(compact, before_shrink);
+        let (_, child) = board
+            .simulate_with_moves(&Instruments, moves.clone())
+            .next()
+            .unwrap();
+        let (_, static_child) = compact
+            .simulate_with_moves(&Instruments, moves)
+            .next()
+            .unwrap();
+
+        assert_eq!(child.algorithm().current_turn(), 175);
+        assert_eq!(static_child.hazard_count(), compact.hazard_count());
+        assert_eq!(
+            child.board().hazard_count(),
+            compact.hazard_count() + spawned.len()
+        );
+        assert!(spawned.iter().all(|pos| child
+            .board()
+            .is_hazard(&child.board().native_from_position(*pos))));
+
+        // and it can be rolled out like any other board
+        let mut rng = SmallRng::seed_from_u64(2);
+        let outcome = board.random_rollout(&Instruments, &mut rng, 100);
+        assert!(outcome.turns <= 100);
+    }
+
+    #[test]
+    fn test_hazard_progressing_board_several_turns() {
+        #[derive(Debug)]
+        struct Instruments;
+        impl SimulatorInstruments for Instruments {
+            fn observe_simulation(&self, _: std::time::Duration) {}
+        }
+
+        // the late stage royale game, shrinking every other turn instead of every 25
+        let mut g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let settings = g.game.ruleset.settings.as_mut().unwrap();
+        settings.royale.as_mut().unwrap().shrink_every_n_turns = 2;
+        let mut shrink = RoyaleShrink::new();
+        let mut expected = shrink.observe(&g).unwrap().collect::<HashSet<_>>();
+
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let mut board = HazardProgressingBoard::new(compact, shrink);
+        let mut wound = shrink;
+        for _ in 0..6 {
+            let moves = board
+                .board()
+                .reasonable_moves_for_each_snake()
+                .map(|(sid, moves)| (sid, [moves[0]]))
+                .collect::<Vec<_>>();
+            let (_, child) = board
+                .simulate_with_moves(&Instruments, moves)
+                .next()
+                .unwrap();
+            board = child;
+            expected.extend(wound.inc_turn());
+
+            assert_eq!(board.algorithm().current_turn(), wound.current_turn());
+            assert_eq!(board.board().hazard_count(), expected.len());
+            assert!(expected.iter().all(|pos| board
+                .board()
+                .is_hazard(&board.board().native_from_position(*pos))));
+        }
+        // three shrinks of the 9 by 7 safe area, each taking one of its 7 cell tall sides
+        assert_eq!(expected.len(), 11 * 11 - 6 * 7);
+    }
 }
@@ -0,0 +1,188 @@
+//! replays a recorded game through a [ForwardOnlyHazardAlgorithm] to find the first turn its
+//! hazards stop matching the engine's, for debugging an algorithm against a new map. Recorded
+//! games can also be turned in to a stream of [GameEvent]s with [replay_events]
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use super::ForwardOnlyHazardAlgorithm;
+use crate::{
+    algorithms::events::{EventDiffableGame, GameEvent},
+    types::TurnDeterminableGame,
+    wire_representation::{Game, Position},
+};
+
+/// The first frame of a recorded game where the hazards a [ForwardOnlyHazardAlgorithm] has
+/// produced so far differ from the hazards on the board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardDivergence {
+    /// the turn of the frame that didn't match
+    pub turn: u64,
+    /// cells the algorithm has as hazards which the frame doesn't, sorted
+    pub unexpected: Vec<Position>,
+    /// hazards in the frame the algorithm didn't produce, sorted
+    pub missing: Vec<Position>,
+}
+
+impl Display for HazardDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hazards diverged on turn {}: unexpected {:?}, missing {:?}",
+            self.turn, self.unexpected, self.missing
+        )
+    }
+}
+
+impl Error for HazardDivergence {}
+
+/// Replays `frames`, one wire game per turn in turn order, through `algorithm`. Frames are
+/// observed until the algorithm is ready for inc calls, after which it's wound forward one turn
+/// per frame. After every frame the hazards produced so far are compared against the frame's,
+/// and the first mismatch is returned. Returns None if every frame matches, and an error if
+/// observing fails or the frames skip a turn once the algorithm is being wound forward.
+pub fn replay_hazards<H: ForwardOnlyHazardAlgorithm<Position>>(
+    mut algorithm: H,
+    frames: impl IntoIterator<Item = Game>,
+) -> Result<Option<HazardDivergence>, Box<dyn Error>> {
+    let mut maintained = HashSet::new();
+
+    for game in frames {
+        if algorithm.is_ready_for_inc() {
+            maintained.extend(algorithm.inc_turn());
+            if algorithm.current_turn() as u64 != game.turn() {
+                return Err(format!(
+                    "expected a frame for turn {} but got turn {}",
+                    algorithm.current_turn(),
+                    game.turn()
+                )
+                .into());
+            }
+        } else {
+            maintained.extend(algorithm.observe(&game)?);
+        }
+
+        let on_board: HashSet<Position> = game.board.hazards.iter().copied().collect();
+        if on_board != maintained {
+            return Ok(Some(HazardDivergence {
+                turn: game.turn(),
+                unexpected: maintained.difference(&on_board).copied().sorted().collect(),
+                missing: on_board.difference(&maintained).copied().sorted().collect(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Pairs each frame of a recorded game, in turn order, with the [GameEvent]s of the turn that
+/// led up to it. The first frame has no events
+pub fn replay_events(frames: &[Game]) -> Vec<(&Game, Vec<GameEvent<String, Position>>)> {
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| match i.checked_sub(1) {
+            Some(previous) => (frame, frame.events_since(&frames[previous])),
+            None => (frame, vec![]),
+        })
+        .collect()
+}
+
+/// Loads every `.json` wire game in `dir`, e.g. `fixtures/debug_wrapped`, sorted by turn for
+/// [replay_hazards]
+pub fn load_recorded_game(dir: impl AsRef<Path>) -> Result<Vec<Game>, Box<dyn Error>> {
+    let mut frames = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let game: Game = serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        frames.push(game);
+    }
+    frames.sort_by_key(|g| g.turn());
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hazard_algorithms::{NoopHazard, SpiralHazard};
+
+    fn debug_wrapped() -> Vec<Game> {
+        load_recorded_game(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/debug_wrapped"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replay_finds_first_divergence() {
+        let recorded = debug_wrapped();
+        assert_eq!(recorded.len(), 193);
+        assert_eq!(
+            replay_hazards(SpiralHazard::new(), recorded.clone()).unwrap(),
+            None
+        );
+
+        let mut skipped_turn = recorded.clone();
+        skipped_turn.remove(150);
+        assert!(replay_hazards(SpiralHazard::new(), skipped_turn).is_err());
+
+        // drop a hazard from one frame onwards, as an algorithm that misses a spawn would
+        let mut frames = recorded;
+        let dropped = *frames[100].board.hazards.last().unwrap();
+        for frame in frames[100..].iter_mut() {
+            frame.board.hazards.retain(|h| *h != dropped);
+        }
+        let divergence = replay_hazards(SpiralHazard::new(), frames.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.turn, frames[100].turn());
+        assert_eq!(divergence.unexpected, vec![dropped]);
+        assert!(divergence.missing.is_empty());
+
+        let noop = replay_hazards(NoopHazard(), frames).unwrap().unwrap();
+        assert!(noop.unexpected.is_empty());
+        assert_eq!(noop.missing.len(), 1);
+    }
+
+    #[test]
+    fn test_replay_events() {
+        let recorded = debug_wrapped();
+        let events = replay_events(&recorded);
+        assert_eq!(events.len(), recorded.len());
+        assert!(events[0].1.is_empty());
+
+        for ((frame, turn_events), previous) in events.iter().skip(1).zip(recorded.iter()) {
+            // every snake still on the board made a move
+            let moved = turn_events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::Moved(..)))
+                .count();
+            assert_eq!(moved, frame.board.snakes.len());
+
+            let spawned = turn_events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::HazardSpawned(_)))
+                .count();
+            let new_hazards = frame
+                .board
+                .hazards
+                .iter()
+                .filter(|h| !previous.board.hazards.contains(h))
+                .count();
+            assert_eq!(spawned, new_hazards);
+        }
+        assert!(events.iter().any(|(_, e)| e
+            .iter()
+            .any(|e| matches!(e, GameEvent::FoodEaten(Some(_), _)))));
+    }
+}
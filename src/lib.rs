@@ -28,16 +28,94 @@
 //! vec late stage          time:   [21.124 us 21.337 us 21.592 us]
 //! Found 14 outliers among 100 measurements (14.00%)
 //! ```
+//!
+//! All of the board types in this crate, along with `SnakeIDMap`, are `Send + Sync`, so a board
+//! can be shared between threads (e.g. behind an `Arc`) for parallel search. This is checked at
+//! compile time. The iterators returned by `neighbors`, `possible_moves`,
+//! `reasonable_moves_for_each_snake`, `get_snake_body_iter`, `get_empty_cells` and the
+//! `SimulableGame` methods are `Send` too, so they can be handed to another thread. The one
+//! exception is `random_reasonable_move_for_each_snake`, whose iterator borrows the caller's rng,
+//! and rngs like `ThreadRng` aren't `Send`.
+//!
+//! Since 0.18 `CellNum` and `Dimensions` require `Send + Sync`, `SimulableGame` requires the game
+//! to be `Send + Sync`, and implementations of the traits above have to return `Send` iterators.
+//! These are breaking for types outside this crate implementing those traits.
+//!
+//! ## Allocation
+//! This crate never sets a global allocator, if you want something like `mimalloc` set it in your
+//! binary. With the `bumpalo` feature enabled the compact boards gain a `simulate_with_moves_in`
+//! method which takes a `compact_representation::SimulationContext` and bump allocates all of
+//! the temporary buffers used during simulation out of it. Reset the context between searches
+//! to reuse the memory. The `simulation_context` bench compares it with `simulate_with_moves`.
+//!
+//! Compact boards are plain arrays and never allocate. `memory_footprint` on a board type gives
+//! its exact size in bytes, and `compact_representation::cell_board_footprint` gives the size of
+//! the board `cell_board!` picks for a width, height and number of snakes as a const fn, for
+//! budgeting e.g. transposition tables.
+//!
+//! ## Async
+//! With the `async-simulation` feature the `background` module simulates boards on a worker
+//! thread and hands back a future of the children, which works with any async runtime.
+//!
+//! ## Stability
+//! The traits in `types` only change with a major release, and a trait method that's replaced
+//! keeps a default implementation delegating to its replacement until the next one. Changes to
+//! the trait surface that are still being worked out, like returning iterators without boxing
+//! them and `MoveSet`, are made in the `v2` module first. Games can implement the `v2` traits
+//! directly, and `v2::Adapter` gives any game implementing the `types` traits the `v2` ones too,
+//! so engines can migrate one trait at a time.
 
 use wire_representation::Game;
 
+pub mod algorithms;
+#[cfg(feature = "async-simulation")]
+pub mod background;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod builder;
 pub mod compact_representation;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod conformance;
+pub mod consistency;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fixtures;
 pub mod hazard_algorithms;
+pub mod maps;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod oracle;
 pub mod types;
+pub mod v2;
 pub mod wire_representation;
 
 /// Loads a fixture from a given string
+///
+/// # Panics
+/// if the string is not a valid wire game
 pub fn game_fixture(game_fixture: &str) -> Game {
     let g: Result<Game, _> = serde_json::from_str(game_fixture);
     g.expect("the json literal is valid")
 }
+
+const fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+
+#[allow(dead_code)]
+fn assert_generic_boards_send_sync<
+    T: compact_representation::CellNum,
+    D: compact_representation::dimensions::Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>() {
+    assert_send_sync::<compact_representation::StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>();
+    assert_send_sync::<compact_representation::WrappedCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>();
+    assert_send_sync::<types::Action<MAX_SNAKES>>();
+}
+
+const _: () = {
+    assert_send_sync::<Game>();
+    assert_send_sync::<types::SnakeIDMap>();
+    assert_send_sync::<types::SnakeId>();
+    assert_send_sync::<types::Move>();
+    assert_send_sync::<compact_representation::standard::BestCellBoard>();
+    assert_send_sync::<compact_representation::wrapped::BestCellBoard>();
+    assert_send_sync::<compact_representation::AnyCellBoard>();
+    assert_send_sync::<hazard_algorithms::SpiralHazard>();
+};
@@ -0,0 +1,85 @@
+//! rules that come from the map a game is played on rather than the wire payload, e.g. healing
+//! springs. Register them per map name in a [MapRegistry] and [MapRegistry::apply] it to each
+//! incoming game before converting or simulating it
+
+use std::collections::HashMap;
+
+use crate::wire_representation::{Game, Position};
+
+/// A cell defined by a map that restores health to a snake whose head ends a turn on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HealingCell {
+    #[allow(missing_docs)]
+    pub position: Position,
+    /// how much health is restored each turn, capped at 100
+    pub regen: u8,
+}
+
+/// The map defined rules known about, by map name
+#[derive(Debug, Clone, Default)]
+pub struct MapRegistry {
+    healing_cells: HashMap<String, Vec<HealingCell>>,
+}
+
+impl MapRegistry {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds healing cells to the given map, replacing any already registered on the same
+    /// position
+    pub fn register_healing_cells(
+        &mut self,
+        map: &str,
+        cells: impl IntoIterator<Item = HealingCell>,
+    ) {
+        let registered = self.healing_cells.entry(map.to_string()).or_default();
+        for cell in cells {
+            registered.retain(|c| c.position != cell.position);
+            registered.push(cell);
+        }
+    }
+
+    /// the healing cells registered for the given map
+    pub fn healing_cells(&self, map: &str) -> &[HealingCell] {
+        self.healing_cells.get(map).map_or(&[], |cells| cells)
+    }
+
+    /// fills in the rules registered for `game`'s map, games without a map are left alone
+    pub fn apply(&self, game: &mut Game) {
+        if let Some(map) = game.game.map.as_deref() {
+            game.board.healing_cells = self.healing_cells(map).to_vec();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+
+    #[test]
+    fn test_registry_applies_by_map() {
+        let mut registry = MapRegistry::new();
+        let spring = |x, y, regen| HealingCell {
+            position: Position::new(x, y),
+            regen,
+        };
+        registry.register_healing_cells("arcade_maze", vec![spring(1, 1, 5), spring(2, 2, 5)]);
+        registry.register_healing_cells("arcade_maze", vec![spring(1, 1, 10)]);
+        assert_eq!(
+            registry.healing_cells("arcade_maze"),
+            &[spring(2, 2, 5), spring(1, 1, 10)]
+        );
+        assert!(registry.healing_cells("standard").is_empty());
+
+        let mut g = game_fixture(include_str!("../fixtures/arcade_maze_map.json"));
+        registry.apply(&mut g);
+        assert_eq!(g.board.healing_cells.len(), 2);
+
+        let mut no_map = game_fixture(include_str!("../fixtures/start_of_game.json"));
+        registry.apply(&mut no_map);
+        assert!(no_map.board.healing_cells.is_empty());
+    }
+}
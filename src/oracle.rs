@@ -0,0 +1,364 @@
+//! Uses the wire simulator as an oracle for a compact board, available with the `test-utils`
+//! feature: [diff_moves] simulates one joint move on both and lists every way the children
+//! disagree. Both take their hazard damage from the game's settings, heal when it's negative and
+//! skip it for a snake moving on to food, so the two should always agree.
+
+use std::{error::Error, fmt::Display};
+
+use itertools::Itertools;
+
+use crate::{
+    types::{
+        build_snake_id_map, Board, Move, NoopInstruments, SimulableBoard, SimulableGame,
+        SnakeIDMap, SnakeId, TurnDeterminableGame,
+    },
+    wire_representation::{Game, Position},
+};
+
+/// One way the compact board's child differs from the wire game's. Snakes are named by their
+/// wire id
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Divergence {
+    /// the snake is eliminated in one child but not the other
+    Eliminated {
+        snake: String,
+        wire: bool,
+        compact: bool,
+    },
+    Health {
+        snake: String,
+        wire: i64,
+        compact: i64,
+    },
+    Length {
+        snake: String,
+        wire: i64,
+        compact: i64,
+    },
+    /// the cells the snake covers, head first, without its stacked tail
+    Body {
+        snake: String,
+        wire: Vec<Position>,
+        compact: Vec<Position>,
+    },
+    /// the food left on the board, sorted
+    Food {
+        wire: Vec<Position>,
+        compact: Vec<Position>,
+    },
+    Turn {
+        wire: u64,
+        compact: u64,
+    },
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::Eliminated {
+                snake,
+                wire,
+                compact,
+            } => write!(
+                f,
+                "{} eliminated on the wire game: {}, on the board: {}",
+                snake, wire, compact
+            ),
+            Divergence::Health {
+                snake,
+                wire,
+                compact,
+            } => write!(
+                f,
+                "{}'s health is {} on the wire game, {} on the board",
+                snake, wire, compact
+            ),
+            Divergence::Length {
+                snake,
+                wire,
+                compact,
+            } => write!(
+                f,
+                "{}'s length is {} on the wire game, {} on the board",
+                snake, wire, compact
+            ),
+            Divergence::Body {
+                snake,
+                wire,
+                compact,
+            } => write!(
+                f,
+                "{}'s body is {:?} on the wire game, {:?} on the board",
+                snake, wire, compact
+            ),
+            Divergence::Food { wire, compact } => write!(
+                f,
+                "the food is {:?} on the wire game, {:?} on the board",
+                wire, compact
+            ),
+            Divergence::Turn { wire, compact } => write!(
+                f,
+                "the turn is {} on the wire game, {} on the board",
+                wire, compact
+            ),
+        }
+    }
+}
+
+/// Both children of a joint move, see [diff_moves]
+#[derive(Debug, Clone)]
+pub struct OracleResult<B> {
+    #[allow(missing_docs)]
+    pub wire: Game,
+    #[allow(missing_docs)]
+    pub compact: B,
+    /// every way `compact` differs from `wire`, empty if they agree
+    pub divergences: Vec<Divergence>,
+}
+
+impl<B> OracleResult<B> {
+    #[allow(missing_docs)]
+    pub fn agrees(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Every way `board` differs from `wire`: which snakes are alive, their health, length and body,
+/// the food and the turn. Snakes on `board` are found by the ids `id_map` gives their wire ids
+pub fn diff<B: Board<SnakeIDType = SnakeId> + TurnDeterminableGame>(
+    wire: &Game,
+    board: &B,
+    id_map: &SnakeIDMap,
+) -> Vec<Divergence> {
+    let mut divergences = vec![];
+    for (id, sid) in id_map.iter().sorted_by_key(|(_, sid)| sid.0) {
+        let wire_snake = wire
+            .board
+            .snakes
+            .iter()
+            .find(|s| s.id == *id && s.health > 0);
+        let wire_snake = match wire_snake {
+            Some(snake) if board.is_alive(sid) => snake,
+            None if !board.is_alive(sid) => continue,
+            _ => {
+                divergences.push(Divergence::Eliminated {
+                    snake: id.clone(),
+                    wire: wire_snake.is_none(),
+                    compact: !board.is_alive(sid),
+                });
+                continue;
+            }
+        };
+
+        let (wire_health, health) = (wire_snake.health as i64, board.get_health_i64(sid));
+        if wire_health != health {
+            divergences.push(Divergence::Health {
+                snake: id.clone(),
+                wire: wire_health,
+                compact: health,
+            });
+        }
+        let (wire_length, length) = (wire_snake.body.len() as i64, board.get_length_i64(sid));
+        if wire_length != length {
+            divergences.push(Divergence::Length {
+                snake: id.clone(),
+                wire: wire_length,
+                compact: length,
+            });
+        }
+        let wire_body = wire_snake.body.iter().copied().dedup().collect_vec();
+        let body = board
+            .get_snake_body_vec(sid)
+            .into_iter()
+            .map(|native| board.position_from_native(native))
+            .dedup()
+            .collect_vec();
+        if wire_body != body {
+            divergences.push(Divergence::Body {
+                snake: id.clone(),
+                wire: wire_body,
+                compact: body,
+            });
+        }
+    }
+
+    let wire_food = wire.board.food.iter().copied().sorted().collect_vec();
+    let food = board
+        .get_all_food_as_positions()
+        .into_iter()
+        .sorted()
+        .collect_vec();
+    if wire_food != food {
+        divergences.push(Divergence::Food {
+            wire: wire_food,
+            compact: food,
+        });
+    }
+    let wire_turn = wire.turn.max(0) as u64;
+    if wire_turn != board.turn() {
+        divergences.push(Divergence::Turn {
+            wire: wire_turn,
+            compact: board.turn(),
+        });
+    }
+    divergences
+}
+
+/// Simulates the snakes in `game` making the moves in `joint`, given by wire id, on the wire
+/// game and on the board `convert` builds from it, and diffs the children, see [diff]. Snakes
+/// without a move stay put on both. Errors if `convert` does or either simulation produces no
+/// child
+pub fn diff_moves<B, const N_SNAKES: usize>(
+    game: &Game,
+    joint: &[(String, Move)],
+    convert: impl Fn(&Game, &SnakeIDMap) -> Result<B, Box<dyn Error>>,
+) -> Result<OracleResult<B>, Box<dyn Error>>
+where
+    B: SimulableBoard<N_SNAKES, SnakeIDType = SnakeId> + TurnDeterminableGame,
+{
+    let id_map = build_snake_id_map(game);
+    let board = convert(game, &id_map)?;
+
+    let (_, wire): (_, Game) = SimulableGame::<_, N_SNAKES>::simulate_with_moves(
+        game,
+        &NoopInstruments,
+        joint.iter().map(|(id, mv)| (id.clone(), [*mv])),
+    )
+    .next()
+    .ok_or("the wire game has no child")?;
+    let moves = joint
+        .iter()
+        .map(|(id, mv)| {
+            id_map
+                .get(id)
+                .map(|sid| (*sid, [*mv]))
+                .ok_or_else(|| format!("{} isn't in the game", id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (_, compact) = board
+        .simulate_with_moves(&NoopInstruments, moves)
+        .next()
+        .ok_or("the board has no child")?;
+
+    let divergences = diff(&wire, &compact, &id_map);
+    Ok(OracleResult {
+        wire,
+        compact,
+        divergences,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11, game_fixture,
+        types::ReasonableMovesGame,
+    };
+
+    #[test]
+    fn test_diff_moves() {
+        let convert = |g: &Game, id_map: &SnakeIDMap| g.as_cell_board(id_map);
+        for json in [
+            include_str!("../fixtures/late_stage.json"),
+            include_str!("../fixtures/head_to_head_on_food.json"),
+            include_str!("../fixtures/hazard_map_settings.json"),
+        ] {
+            let g = game_fixture(json);
+            let options = g.reasonable_moves_for_each_snake().collect_vec();
+            for joint in options
+                .iter()
+                .map(|(id, moves)| moves.iter().map(move |mv| (id.clone(), *mv)))
+                .multi_cartesian_product()
+            {
+                let result =
+                    diff_moves::<StandardCellBoard4Snakes11x11, 4>(&g, &joint, convert).unwrap();
+                assert!(result.agrees(), "{:?}: {:?}", joint, result.divergences);
+                assert_eq!(result.wire.turn, g.turn + 1);
+            }
+        }
+
+        // you move back in to your own neck, and the other snake doesn't move on the board
+        let g = game_fixture(include_str!("../fixtures/late_stage.json"));
+        let other = g.board.snakes.iter().find(|s| s.id != g.you.id).unwrap();
+        let result = diff_moves::<StandardCellBoard4Snakes11x11, 4>(
+            &g,
+            &[(g.you.id.clone(), Move::Down)],
+            |g, id_map| {
+                let mut g = g.clone();
+                let you = g.you.id.clone();
+                g.board.snakes.retain(|s| s.id == you);
+                g.as_cell_board(id_map)
+            },
+        )
+        .unwrap();
+        assert!(result
+            .wire
+            .eliminations
+            .iter()
+            .any(|e| e.snake_id == g.you.id));
+        assert_eq!(
+            result.divergences,
+            vec![Divergence::Eliminated {
+                snake: other.id.clone(),
+                wire: false,
+                compact: true,
+            }]
+        );
+        assert!(!result.agrees());
+        assert!(result.divergences[0].to_string().contains(&other.id));
+
+        // a board that lost track of the turn
+        let result = diff_moves::<StandardCellBoard4Snakes11x11, 4>(
+            &g,
+            &[(g.you.id.clone(), Move::Up)],
+            |g, id_map| g.as_cell_board(id_map).map(|b| b.with_turn(0)),
+        )
+        .unwrap();
+        assert_eq!(
+            result.divergences,
+            vec![Divergence::Turn {
+                wire: g.turn as u64 + 1,
+                compact: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_moves_hazard_damage() {
+        let convert = |g: &Game, id_map: &SnakeIDMap| g.as_cell_board(id_map);
+        for (damage, health, food) in [
+            (14, 50, false),
+            (-10, 50, false),
+            (-10, 95, false),
+            (300, 100, false),
+            (15, 15, false),
+            // hazards do nothing to a snake eating, even one they'd otherwise eliminate
+            (15, 1, true),
+            (100, 10, true),
+        ] {
+            let mut builder = crate::builder::BoardBuilder::new(11, 11)
+                .snake(&[Position::new(2, 2), Position::new(2, 1)], health)
+                .hazard(Position::new(2, 3))
+                .hazard_damage(damage);
+            if food {
+                builder = builder.food(Position::new(2, 3));
+            }
+            let g = builder.build();
+            let result = diff_moves::<StandardCellBoard4Snakes11x11, 4>(
+                &g,
+                &[("snake0".to_string(), Move::Up)],
+                convert,
+            )
+            .unwrap();
+            assert!(
+                result.agrees(),
+                "{} damage at {} health: {:?}",
+                damage,
+                health,
+                result.divergences
+            );
+        }
+    }
+}
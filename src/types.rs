@@ -1,6 +1,6 @@
 //! various types that are useful for working with battlesnake
 use crate::wire_representation::{Game, Position};
-use rand::Rng;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize, Serializer};
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -13,7 +13,7 @@ use std::time::Duration;
 pub type SnakeIDMap = HashMap<String, SnakeId>;
 
 /// A vector with which to do positional math
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Vector {
     /// x position
     pub x: i64,
@@ -60,13 +60,21 @@ impl Move {
     }
 
     /// create a Move from the given vector
+    ///
+    /// # Panics
+    /// if the vector isn't one step along an axis, see [Move::try_from_vector]
     pub fn from_vector(vector: Vector) -> Self {
+        Self::try_from_vector(vector).expect("the vector is one step along an axis")
+    }
+
+    /// create a Move from the given vector, None if the vector isn't one step along an axis
+    pub fn try_from_vector(vector: Vector) -> Option<Self> {
         match vector {
-            Vector { x: -1, y: 0 } => Self::Left,
-            Vector { x: 1, y: 0 } => Self::Right,
-            Vector { x: 0, y: 1 } => Self::Up,
-            Vector { x: 0, y: -1 } => Self::Down,
-            _ => panic!(),
+            Vector { x: -1, y: 0 } => Some(Self::Left),
+            Vector { x: 1, y: 0 } => Some(Self::Right),
+            Vector { x: 0, y: 1 } => Some(Self::Up),
+            Vector { x: 0, y: -1 } => Some(Self::Down),
+            _ => None,
         }
     }
 
@@ -91,13 +99,21 @@ impl Move {
     }
 
     /// converts a usize index to a move
+    ///
+    /// # Panics
+    /// if the index is not below [N_MOVES], see [Move::try_from_index]
     pub fn from_index(index: usize) -> Move {
+        Self::try_from_index(index).expect("invalid index")
+    }
+
+    /// converts a usize index to a move, None if the index is not below [N_MOVES]
+    pub fn try_from_index(index: usize) -> Option<Move> {
         match index {
-            0 => Move::Up,
-            1 => Move::Down,
-            2 => Move::Left,
-            3 => Move::Right,
-            _ => panic!("invalid index"),
+            0 => Some(Move::Up),
+            1 => Some(Move::Down),
+            2 => Some(Move::Left),
+            3 => Some(Move::Right),
+            _ => None,
         }
     }
 
@@ -157,7 +173,8 @@ impl Serialize for SnakeId {
 /// calling this on every game you are given, you should call
 /// this function once per game at the start, and store the result
 /// that way you can stabally have integer IDs for a given snake
-/// throughout a game
+/// throughout a game. You still get ID 0 after you've been eliminated, even
+/// if the payload no longer lists you in the board's snakes
 pub fn build_snake_id_map(g: &Game) -> SnakeIDMap {
     let mut hm = HashMap::new();
     hm.insert(g.you.id.clone(), SnakeId(0));
@@ -187,6 +204,14 @@ pub trait SimulatorInstruments: std::fmt::Debug {
     fn observe_simulation(&self, duration: Duration);
 }
 
+/// Instruments which don't record anything
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopInstruments;
+
+impl SimulatorInstruments for NoopInstruments {
+    fn observe_simulation(&self, _: Duration) {}
+}
+
 /// A game for which "you" is determinable
 pub trait YouDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
     /// determines for a given game if a given snake id is you.
@@ -198,12 +223,28 @@ pub trait YouDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
 
 /// A game which can have it's winner determined
 pub trait VictorDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
-    #[allow(missing_docs)]
+    /// whether the game is over for you: outside of squads, once there's one snake left or you
+    /// have been eliminated. The snakes that are left can still be simulated after you're
+    /// eliminated, e.g. to spectate the rest of a lost game
     fn is_over(&self) -> bool;
 
     /// get the winner for a given game, will return None in the case of a draw, or if the game is not over
+    ///
+    /// A game that's over because you were eliminated has no winner until only one snake is left
+    ///
+    /// When the snakes are playing in squads the game is over once only one squad has living
+    /// members, and the winner is the first of those members still alive. If the
+    /// last snakes all die at once it's a draw
     fn get_winner(&self) -> Option<Self::SnakeIDType>;
 
+    /// Whether this game is using the solo ruleset, the sandbox mode for single snake training
+    /// runs where the goal is to survive as long as possible. Outside of solo a game with one
+    /// snake left is already over, in solo it only ends once you die, and there is never a
+    /// winner. Wire games opt in with the ruleset name "solo"
+    fn is_solo(&self) -> bool {
+        false
+    }
+
     /// How many snakes are alive
     fn alive_snake_count(&self) -> usize;
 }
@@ -237,8 +278,16 @@ impl<const N_SNAKES: usize> Action<N_SNAKES> {
     }
 
     /// gets your move
+    ///
+    /// # Panics
+    /// if this action has no move for you, see [Action::try_own_move]
     pub fn own_move(&self) -> Move {
-        self.moves[0].unwrap()
+        self.try_own_move().expect("the action has a move for you")
+    }
+
+    /// gets your move, None if this action doesn't have one
+    pub fn try_own_move(&self) -> Option<Move> {
+        self.moves[0]
     }
     /// construct an OtherAction of the other sankes moves
     pub fn other_moves(&self) -> OtherAction<N_SNAKES> {
@@ -253,9 +302,64 @@ impl<const N_SNAKES: usize> Action<N_SNAKES> {
     }
 }
 
-/// a game for which future states can be simulated
+/// Why a snake was eliminated during a turn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DeathCause {
+    /// moved off the edge of a board that doesn't wrap
+    OutOfBounds,
+    /// ran out of health from the one health every move costs
+    Starvation,
+    /// ran out of health from moving on to a hazard, which it would have survived otherwise
+    Hazard,
+    /// moved on to a wall, see [WallQueryableGame]
+    Wall,
+    /// moved in to its own body, its neck included
+    SelfCollision,
+    /// moved in to another snake's body
+    BodyCollision,
+    /// lost, or tied, a head to head
+    HeadToHead,
+}
+
+/// What happened to one snake during a turn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SnakeOutcome {
+    /// whether the snake moved on to food, which it eats even if it's then eliminated
+    pub ate_food: bool,
+    /// the health the snake lost to starving and hazards before eating or healing
+    pub damage_taken: u8,
+    /// why the snake was eliminated, None if it survived the turn
+    pub died: Option<DeathCause>,
+}
+
+/// What happened to each snake that moved during a simulated turn, indexed like [Action], for
+/// reward shaping without having to compare the boards before and after
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TurnOutcome<const N_SNAKES: usize> {
+    snakes: [Option<SnakeOutcome>; N_SNAKES],
+}
+
+impl<const N_SNAKES: usize> TurnOutcome<N_SNAKES> {
+    /// create a new outcome from what happened to each snake, None for snakes that didn't move
+    pub fn new(snakes: [Option<SnakeOutcome>; N_SNAKES]) -> Self {
+        Self { snakes }
+    }
+
+    /// what happened to a snake, None if it didn't move this turn
+    pub fn get(&self, snake_id: SnakeId) -> Option<SnakeOutcome> {
+        self.snakes[snake_id.as_usize()]
+    }
+
+    /// Get the inner array back
+    pub fn into_inner(self) -> [Option<SnakeOutcome>; N_SNAKES] {
+        self.snakes
+    }
+}
+
+/// a game for which future states can be simulated. The games are `Send + Sync` so that the
+/// returned iterators can be handed to another thread
 pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
-    std::fmt::Debug + Sized + SnakeIDGettableGame
+    std::fmt::Debug + Sized + Send + Sync + SnakeIDGettableGame
 {
     /// simulates all possible future games for a given game returning the snake ids, moves that
     /// got to a given state, plus that state
@@ -264,7 +368,7 @@ pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
         &self,
         instruments: &T,
         snake_ids: Vec<Self::SnakeIDType>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_> {
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send + '_> {
         let moves_to_simulate = Move::all();
         let build = snake_ids
             .into_iter()
@@ -272,15 +376,144 @@ pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
         self.simulate_with_moves(instruments, build)
     }
     /// simulates the next possible states for a a game with a given set of snakes and moves, producing a list of the new games,
-    /// along with the moves that got to that position
+    /// along with the moves that got to that position. Children are produced lazily, so a caller
+    /// that stops consuming the iterator early (e.g. when its time budget runs out) doesn't pay
+    /// for the joint moves it never looked at
     #[allow(clippy::type_complexity)]
     fn simulate_with_moves<S>(
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_>
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send + '_>
     where
         S: Borrow<[Move]>;
+
+    /// Simulates the moves each snake's `policy` gives a non zero probability, attaching to each
+    /// child the probability of the joint move that produced it: the product of every snake's
+    /// probability for the move it made. The simulator drops moves where a snake kills itself
+    /// while it has an alternative, the probability of those is spread over the snake's remaining
+    /// moves in proportion, so with a policy that sums to 1 per snake the child weights sum to 1
+    /// too. This is what expectimax style search over an opponent model needs. Unlike
+    /// `simulate_with_moves` the children are simulated eagerly.
+    ///
+    /// A snake whose policy gives no move a probability above zero is simulated with the first
+    /// move in [Move::all] order which doesn't go back in to its neck, the same fallback
+    /// [ReasonableMovesGame] uses, with probability 1
+    #[allow(clippy::type_complexity)]
+    fn simulate_with_policy(
+        &self,
+        instruments: &T,
+        snake_ids: Vec<Self::SnakeIDType>,
+        policy: impl Fn(&Self::SnakeIDType) -> Vec<(Move, f32)>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, f32, Self)> + Send + '_>
+    where
+        Self: SnakeIDGettableGame<SnakeIDType = SnakeId>
+            + HeadGettableGame
+            + NeckQueryableGame
+            + NeighborDeterminableGame,
+    {
+        let weighted_moves = snake_ids
+            .into_iter()
+            .map(|sid| {
+                let moves = policy(&sid)
+                    .into_iter()
+                    .filter(|(_, p)| *p > 0.0)
+                    .collect::<Vec<_>>();
+                if !moves.is_empty() {
+                    return (sid, moves);
+                }
+                let head = self.get_head_as_native_position(&sid);
+                let neck_move = self
+                    .possible_moves(&head)
+                    .find(|(_, pos)| self.is_neck(&sid, pos))
+                    .map(|(mv, _)| mv);
+                let fallback = Move::all_iter()
+                    .find(|mv| Some(*mv) != neck_move)
+                    .unwrap_or(Move::Up);
+                (sid, vec![(fallback, 1.0)])
+            })
+            .collect::<Vec<_>>();
+        let moves_to_simulate = weighted_moves
+            .iter()
+            .map(|(sid, moves)| (*sid, moves.iter().map(|(mv, _)| *mv).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let children = self
+            .simulate_with_moves(instruments, moves_to_simulate)
+            .map(|(action, board)| (action.into_inner(), board))
+            .collect::<Vec<_>>();
+
+        let mut simulated = [[false; N_MOVES]; N_SNAKES];
+        for (moves, _) in children.iter() {
+            for (sid, mv) in moves.iter().enumerate() {
+                if let Some(mv) = mv {
+                    simulated[sid][mv.as_index()] = true;
+                }
+            }
+        }
+        let weighted_moves = weighted_moves
+            .into_iter()
+            .map(|(sid, moves)| {
+                let total: f32 = moves
+                    .iter()
+                    .filter(|(mv, _)| simulated[sid.as_usize()][mv.as_index()])
+                    .map(|(_, p)| p)
+                    .sum();
+                let normalized = moves
+                    .into_iter()
+                    .map(|(mv, p)| (mv, p / total))
+                    .collect::<Vec<_>>();
+                (sid, normalized)
+            })
+            .collect::<Vec<_>>();
+
+        Box::new(children.into_iter().map(move |(moves, board)| {
+            let weight = weighted_moves
+                .iter()
+                .filter_map(|(sid, weights)| {
+                    let mv = moves[sid.as_usize()]?;
+                    weights.iter().find(|(m, _)| *m == mv).map(|(_, p)| *p)
+                })
+                .product();
+            (Action::new(moves), weight, board)
+        }))
+    }
+
+    /// like `simulate`, but the returned iterator owns this game instead of borrowing it
+    #[allow(clippy::type_complexity)]
+    fn into_simulate(
+        self,
+        instruments: &T,
+        snake_ids: Vec<Self::SnakeIDType>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send>
+    where
+        Self: 'static,
+    {
+        let moves_to_simulate = Move::all();
+        let build = snake_ids
+            .into_iter()
+            .map(|s| (s, moves_to_simulate.as_slice()));
+        self.into_simulate_with_moves(instruments, build)
+    }
+
+    /// like `simulate_with_moves`, but the returned iterator owns this game instead of borrowing
+    /// it, so it can be returned from functions that only hold the game locally. The default
+    /// implementation simulates every state eagerly, implementations are encouraged to override it
+    /// with a lazy one
+    #[allow(clippy::type_complexity)]
+    fn into_simulate_with_moves<S>(
+        self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send>
+    where
+        S: Borrow<[Move]>,
+        Self: 'static,
+    {
+        let results = self
+            .simulate_with_moves(instruments, snake_ids_and_moves)
+            .collect::<Vec<_>>();
+        Box::new(results.into_iter())
+    }
 }
 
 /// A game where positions can be checked for hazards
@@ -290,6 +523,37 @@ pub trait HazardQueryableGame: PositionGettableGame {
 
     /// how much damage do hazards do?
     fn get_hazard_damage(&self) -> u8;
+
+    /// How many hazards are stacked on this position, 0 if it isn't a hazard. Maps like snail
+    /// mode stack hazards, and a snake whose head ends a turn on a stack takes the hazard damage
+    /// once for each hazard in it
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.is_hazard(pos) as u8
+    }
+}
+
+/// A game that can tell walls apart from other hazards. Maps like arcade maze build their walls
+/// out of hazards that deal enough damage to kill any snake, which searches want to treat as
+/// impassable rather than as a cost. Walls are never neighbors or reasonable moves, so searches
+/// built on [NeighborDeterminableGame] don't go through them
+pub trait WallQueryableGame: HazardQueryableGame {
+    /// Is this position a wall? Walls are always hazards as well
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool;
+}
+
+/// A game where the map can define healing cells, which restore health to a snake whose head
+/// ends a turn on them. Pathfinders can use this to find somewhere to rest
+pub trait HealingQueryableGame: PositionGettableGame {
+    /// how much health this position restores each turn, 0 if it isn't a healing cell
+    fn healing_at(&self, pos: &Self::NativePositionType) -> u8;
+
+    /// every healing cell on the board
+    fn healing_cells(&self) -> Vec<Self::NativePositionType>;
+
+    /// Is this position a healing cell?
+    fn is_healing(&self, pos: &Self::NativePositionType) -> bool {
+        self.healing_at(pos) > 0
+    }
 }
 
 /// A game where positions can be checked for food
@@ -314,6 +578,31 @@ pub trait HazardSettableGame: PositionGettableGame {
 
     /// clear this position of being a hazard
     fn clear_hazard(&mut self, pos: Self::NativePositionType);
+
+    /// Stack this many hazards on this position, 0 clears it. Games that can't stack hazards
+    /// make the position a single hazard for any count above 0
+    fn set_hazard_count(&mut self, pos: Self::NativePositionType, count: u8) {
+        if count == 0 {
+            self.clear_hazard(pos);
+        } else {
+            self.set_hazard(pos);
+        }
+    }
+}
+
+/// A game that stores one byte of the caller's own data per cell, e.g. to update an influence map
+/// incrementally. The data is copied along with the board, so children produced by simulating
+/// start with their parent's values, and is part of the board's equality and hash
+#[cfg(feature = "cell-metadata")]
+pub trait CellMetadataGame: PositionGettableGame {
+    /// the data stored for this position, 0 if none has been set
+    fn get_cell_metadata(&self, pos: &Self::NativePositionType) -> u8;
+
+    /// store data for this position
+    fn set_cell_metadata(&mut self, pos: &Self::NativePositionType, value: u8);
+
+    /// reset the data for every position to 0
+    fn clear_cell_metadata(&mut self);
 }
 
 /// A game for which board positions can be identified and returned
@@ -345,6 +634,18 @@ pub trait HeadGettableGame: PositionGettableGame + SnakeIDGettableGame {
     /// get the head position for a given snake as some "native" type for this game
     fn get_head_as_native_position(&self, snake_id: &Self::SnakeIDType)
         -> Self::NativePositionType;
+
+    /// get the head of a given snake, None if the snake is unknown. The other head getters may
+    /// panic or return a meaningless position for such a snake. The default only checks
+    /// [SnakeIDGettableGame::get_snake_ids], the boards in this crate return None for dead snakes
+    /// too
+    fn try_get_head(&self, snake_id: &Self::SnakeIDType) -> Option<Self::NativePositionType> {
+        if self.get_snake_ids().contains(snake_id) {
+            Some(self.get_head_as_native_position(snake_id))
+        } else {
+            None
+        }
+    }
 }
 
 /// A game for which the food on the board can be queries
@@ -366,6 +667,17 @@ pub trait LengthGettableGame: SnakeIDGettableGame {
 
     /// get the length for a given snake
     fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64;
+
+    /// get the length for a given snake, None if the snake is unknown. The default only checks
+    /// [SnakeIDGettableGame::get_snake_ids], the boards in this crate return None for dead snakes
+    /// too
+    fn try_get_length(&self, snake_id: &Self::SnakeIDType) -> Option<Self::LengthType> {
+        if self.get_snake_ids().contains(snake_id) {
+            Some(self.get_length(snake_id))
+        } else {
+            None
+        }
+    }
 }
 
 /// A game for which the health of the current snake can be got.
@@ -388,36 +700,174 @@ pub trait HealthGettableGame: SnakeIDGettableGame {
     }
 }
 
+/// A game that can predict how long its snakes will grow
+pub trait GrowthPredictableGame: LengthGettableGame {
+    /// Whether this snake's tail is stacked, because it just ate or the game just started, so its
+    /// tail stays put next turn and it will cover one more cell than it does now. Moving on to
+    /// such a tail is a collision, see [SnakeBodyGettableGame::will_be_vacated_this_turn]. False
+    /// for eliminated snakes
+    fn will_grow_next_turn(&self, snake_id: &Self::SnakeIDType) -> bool;
+
+    /// The length a snake is expected to have `turn_delta` turns from now if it eats
+    /// `assumed_food_rate` food a turn on average, e.g. 0.1 for a snake that eats every ten turns.
+    /// The rate is clamped to `0.0..=1.0`, as a snake eats at most once a turn
+    fn predicted_length_at(
+        &self,
+        snake_id: &Self::SnakeIDType,
+        turn_delta: u32,
+        assumed_food_rate: f32,
+    ) -> f32 {
+        self.get_length_i64(snake_id) as f32 + turn_delta as f32 * assumed_food_rate.clamp(0.0, 1.0)
+    }
+}
+
+/// A game where snakes can be marked as having just eaten
+pub trait GrowthMarkableGame: GrowthPredictableGame {
+    /// Marks a snake as having just eaten, stacking its tail and growing it by one as the engine
+    /// does when a snake eats, so that its tail is treated as staying put next turn. Useful for
+    /// boards built from sources that don't stack tails. Health is left alone. Does nothing if
+    /// the snake is eliminated or its tail is already stacked, as a snake eats at most once a
+    /// turn
+    fn mark_will_grow(&mut self, snake_id: &Self::SnakeIDType);
+}
+
+/// A game where a snake's health can be changed, for when it isn't known exactly. Some relays
+/// leave opponent health out of the payload, so a search may want to branch on what it could be
+pub trait HealthSettableGame: HealthGettableGame {
+    /// set the health of a snake that's alive, setting it to zero eliminates the snake. Snakes
+    /// that have already been eliminated are left alone
+    fn set_health(&mut self, snake_id: &Self::SnakeIDType, health: Self::HealthType);
+
+    /// a copy of this game where the given snake has `health`, so a probe can branch on an
+    /// opponent's health without rebuilding the board from the wire representation
+    fn with_assumed_health(&self, snake_id: &Self::SnakeIDType, health: Self::HealthType) -> Self
+    where
+        Self: Clone,
+    {
+        let mut game = self.clone();
+        game.set_health(snake_id, health);
+        game
+    }
+
+    /// copies of this game with the given snake's health at the bottom and the top of the range
+    /// it's known to be in. Simulating both bounds what can happen to the snake, the low copy
+    /// being the first to starve and the high copy the last
+    fn with_health_range(
+        &self,
+        snake_id: &Self::SnakeIDType,
+        low: Self::HealthType,
+        high: Self::HealthType,
+    ) -> (Self, Self)
+    where
+        Self: Clone,
+    {
+        (
+            self.with_assumed_health(snake_id, low),
+            self.with_assumed_health(snake_id, high),
+        )
+    }
+}
+
 /// a game for which random reasonable moves for a given snake can be determined. e.g. do not collide with yourself
+///
+/// Implementations in this crate pick uniformly (via [rand::seq::SliceRandom::choose]) from the
+/// moves [ReasonableMovesGame::reasonable_moves_for_each_snake] would return, visiting snakes in
+/// the same order. The fallback move is deterministic and doesn't touch the rng, so the same
+/// seeded rng picks the same moves on the wire and compact representations of a position.
 pub trait RandomReasonableMovesGame: SnakeIDGettableGame {
     #[allow(missing_docs)]
     fn random_reasonable_move_for_each_snake<'a>(
         &'a self,
         rng: &'a mut impl Rng,
     ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a>;
+
+    /// [RandomReasonableMovesGame::random_reasonable_move_for_each_snake] with a [SmallRng]
+    /// seeded from `seed`, useful for reproducible rollouts
+    fn seeded_random_reasonable_move_for_each_snake(
+        &self,
+        seed: u64,
+    ) -> Vec<(Self::SnakeIDType, Move)> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        self.random_reasonable_move_for_each_snake(&mut rng)
+            .collect()
+    }
+}
+
+/// Whether a snake with `health` is eliminated by moving on to `stacks` hazards that each deal
+/// `hazard_damage`, without food on the cell. Like the engine, the move takes 1 health before the
+/// hazards do their damage, so a snake needs more than `hazard_damage * stacks + 1` health to
+/// survive
+pub(crate) fn hazard_is_lethal(health: i64, hazard_damage: i64, stacks: i64) -> bool {
+    hazard_damage * stacks >= health - 1
 }
 
 /// a game for which reasonable moves for a given snake can be determined. e.g. do not collide with yourself
+///
+/// A move is reasonable when the new head (after wrapping, on wrapped boards) is on the board,
+/// isn't a wall, isn't part of any snake other than a tail which will move out of the way this
+/// turn, and isn't a hazard without food whose damage, on top of the 1 health every move costs,
+/// leaves the snake with no health.
+/// A snake with no reasonable moves gets the first move in [Move::all] order which doesn't go
+/// back in to its neck. Alive snakes are visited in [SnakeId] order, i.e. you first and then the
+/// remaining snakes in the order they appear on the board.
 pub trait ReasonableMovesGame: SnakeIDGettableGame {
     #[allow(missing_docs)]
     fn reasonable_moves_for_each_snake(
         &self,
-    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + '_>;
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + Send + '_>;
 }
 
-/// a game for which the neighbors of a given Position can be determined
+/// A game for which the moves each snake can make under the official rules can be determined.
+/// Unlike [ReasonableMovesGame] nothing is guessed: a move is legal when the new head is on the
+/// board (wrapping around on wrapped boards), isn't a wall and isn't a cell any snake will still
+/// be on after moving. So a tail is legal to chase unless its snake ate last turn and the tail is
+/// stacked, see [SnakeBodyGettableGame::will_be_vacated_this_turn]. Hazards and head to heads
+/// are left to the caller. The default method works for any game, the compact boards and the
+/// wire game implement it
+pub trait LegalMovesGame:
+    NeighborDeterminableGame + SnakeBodyGettableGame + HeadGettableGame + HealthGettableGame
+{
+    /// The legal moves of every alive snake, in [SnakeIDGettableGame::get_snake_ids] order, each
+    /// in [Move::all] order. A snake that can't move anywhere legally gets no moves
+    fn legal_moves_for_each_snake(&self) -> Vec<(Self::SnakeIDType, Vec<Move>)> {
+        let ids = self.get_snake_ids();
+        let heads = ids
+            .iter()
+            .map(|sid| self.get_head_as_native_position(sid))
+            .collect::<Vec<_>>();
+        ids.into_iter()
+            .zip(heads.iter())
+            .filter(|(sid, _)| self.get_health_i64(sid) > 0)
+            .map(|(sid, head)| {
+                let moves = self
+                    .possible_moves(head)
+                    .filter(|(_, to)| {
+                        let occupied =
+                            heads.contains(to) || self.position_is_snake_body(to.clone());
+                        !occupied || self.will_be_vacated_this_turn(to)
+                    })
+                    .map(|(mv, _)| mv)
+                    .collect();
+                (sid, moves)
+            })
+            .collect()
+    }
+}
+
+/// a game for which the neighbors of a given Position can be determined. Cells off the board
+/// and walls, see [WallQueryableGame], are never neighbors
 pub trait NeighborDeterminableGame: PositionGettableGame {
     /// returns the neighboring positions
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a>;
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + 'a>;
 
     /// returns the neighboring positions, and the Move required to get to each
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a>;
+    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + Send + 'a>;
 }
 
 /// a game for which each snakes shout can be determined
@@ -440,6 +890,55 @@ pub trait TurnDeterminableGame {
     fn turn(&self) -> u64;
 }
 
+/// A key for a board in a transposition table. Besides a hash of the board it records the parity
+/// of the turn and whether the board is wrapped, so boards with the same layout at different
+/// points of a turn or under different rules never share a key, and one table can be shared
+/// between standard and wrapped searches. Keys are plain `u64`s and the hash is deterministic, so
+/// they're the same across threads and processes.
+///
+/// Keys are only comparable between boards of the same type: a compact board and the wire game it
+/// was built from get different keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TranspositionKey(u64);
+
+impl TranspositionKey {
+    const ODD_TURN: u64 = 1;
+    const WRAPPED: u64 = 1 << 1;
+
+    /// builds a key from a hash of the board, dropping the hash's two highest bits to make room
+    /// for the turn parity and the ruleset
+    pub fn new(board_hash: u64, turn: u64, wrapped: bool) -> Self {
+        let mut key = board_hash << 2 | turn & Self::ODD_TURN;
+        if wrapped {
+            key |= Self::WRAPPED;
+        }
+        Self(key)
+    }
+
+    /// the key as a single integer, e.g. to pick a slot in a table
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// whether the board was on an odd turn
+    pub fn is_odd_turn(self) -> bool {
+        self.0 & Self::ODD_TURN != 0
+    }
+
+    /// whether the board was wrapped
+    pub fn is_wrapped(self) -> bool {
+        self.0 & Self::WRAPPED != 0
+    }
+}
+
+/// a game that can be stored in a transposition table
+pub trait TranspositionKeyGame {
+    /// the key of this board at the given turn. Only the parity of `turn` is kept, and the turn
+    /// compact boards track isn't hashed (unlike their `Hash` and `Eq`), so the same position
+    /// reached at different search depths of the same parity shares a key
+    fn key(&self, turn: u64) -> TranspositionKey;
+}
+
 /// A game where an entire snake body is gettable
 pub trait SnakeBodyGettableGame: PositionGettableGame + SnakeIDGettableGame {
     /// return a Vec of the positions for a given snake body, in order from head to tail
@@ -452,7 +951,34 @@ pub trait SnakeBodyGettableGame: PositionGettableGame + SnakeIDGettableGame {
     fn get_snake_body_iter(
         &self,
         snake_id: &Self::SnakeIDType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_>;
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_>;
+
+    /// like `get_snake_body_vec`, but None if the snake is unknown. The default only checks
+    /// [SnakeIDGettableGame::get_snake_ids], the boards in this crate return None for dead snakes
+    /// too
+    fn try_get_snake_body_vec(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> Option<Vec<Self::NativePositionType>> {
+        if self.get_snake_ids().contains(snake_id) {
+            Some(self.get_snake_body_vec(snake_id))
+        } else {
+            None
+        }
+    }
+
+    /// whether `pos` is the tail of a snake that will move out of it this turn whatever that
+    /// snake does. A tail stacked because its snake ate last turn stays put for a turn, so moving
+    /// on to it is a collision. A single tail moves even if its snake eats this turn, as the
+    /// engine grows snakes by duplicating the new tail after moving
+    fn will_be_vacated_this_turn(&self, pos: &Self::NativePositionType) -> bool {
+        self.get_snake_ids()
+            .iter()
+            .any(|sid| match self.get_snake_body_vec(sid).as_slice() {
+                [.., before_tail, tail] => tail == pos && before_tail != pos,
+                _ => false,
+            })
+    }
 }
 
 /// A marker trait that can be used to specify the number of snakes this board can support
@@ -460,22 +986,459 @@ pub trait MaxSnakes<const MAX_SNAKES: usize> {}
 
 /// A game where we can get all the empty cells
 pub trait EmptyCellGettableGame: PositionGettableGame {
-    /// get the empty cells on the board
-    fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_>;
+    /// get the empty cells on the board, the ones without a snake, food or a wall
+    fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_>;
 }
 
 /// A game that can place food following the standard rules
 ///
 /// - If the number of Food on the board is less than the minimum spawn enough food to reach the miniumum.
-/// - Otherwise there is a 15% chance of spawning a single food
+/// - Otherwise there is a `foodSpawnChance` percent chance of spawning a single food
 /// - Otherwise no food spawns
 ///
 /// - When food spawns place it randomly on the empty cells of the board
+///
+/// The minimum and the chance come from the game's `minimumFood` and `foodSpawnChance` settings,
+/// see [crate::wire_representation::Game::food_spawn_settings], falling back to the engine
+/// defaults of 1 and 15% for games without settings
 pub trait StandardFoodPlaceableGame {
     /// place food on the board according to the standard rules
     fn place_food(&mut self, rng: &mut impl Rng);
+
+    /// Runs [StandardFoodPlaceableGame::place_food] on `k` copies of this board and returns
+    /// each distinct result along with the fraction of the samples that produced it, so that a
+    /// rollout can weight its children by how likely each spawn is instead of trusting a single
+    /// sample. The probabilities sum to 1, the vec is empty when `k` is 0
+    fn sample_food_placements(&self, k: usize, rng: &mut impl Rng) -> Vec<(Self, f64)>
+    where
+        Self: Sized + Clone + PartialEq,
+    {
+        let mut samples: Vec<(Self, usize)> = vec![];
+        for _ in 0..k {
+            let mut board = self.clone();
+            board.place_food(rng);
+            match samples.iter_mut().find(|(b, _)| *b == board) {
+                Some((_, count)) => *count += 1,
+                None => samples.push((board, 1)),
+            }
+        }
+
+        samples
+            .into_iter()
+            .map(|(board, count)| (board, count as f64 / k as f64))
+            .collect()
+    }
+}
+
+/// Shorthands for the questions most evaluation functions start by asking about "you". Implemented
+/// for every game with the underlying getters
+pub trait YouQueryableGame:
+    YouDeterminableGame + HealthGettableGame + LengthGettableGame + HeadGettableGame
+{
+    /// your health
+    fn my_health(&self) -> Self::HealthType {
+        self.get_health(self.you_id())
+    }
+
+    /// your length
+    fn my_length(&self) -> Self::LengthType {
+        self.get_length(self.you_id())
+    }
+
+    /// your head, as the native position type for this game
+    fn my_head(&self) -> Self::NativePositionType {
+        self.get_head_as_native_position(self.you_id())
+    }
+
+    /// the length of the longest opponent still alive, or None if there are no opponents left
+    fn longest_opponent_length(&self) -> Option<Self::LengthType> {
+        self.get_snake_ids()
+            .iter()
+            .filter(|sid| !self.is_you(sid) && self.is_alive(sid))
+            .map(|sid| self.get_length(sid))
+            .max()
+    }
+}
+
+impl<G> YouQueryableGame for G where
+    G: YouDeterminableGame + HealthGettableGame + LengthGettableGame + HeadGettableGame + ?Sized
+{
+}
+
+/// Where each snake stands relative to the others, for reward shaping and scoring the end of a
+/// game. Implemented for every game with health and length getters
+pub trait StandingsGame: HealthGettableGame + LengthGettableGame {
+    /// all snakes, best first: alive snakes before dead ones, then longest first, then healthiest
+    /// first. Ties keep the order of `get_snake_ids`
+    fn standings(&self) -> Vec<Self::SnakeIDType> {
+        let mut ids = self.get_snake_ids();
+        ids.sort_by_cached_key(|sid| {
+            std::cmp::Reverse((
+                self.is_alive(sid),
+                self.get_length_i64(sid),
+                self.get_health_i64(sid),
+            ))
+        });
+        ids
+    }
+
+    /// how many living snakes are strictly longer than the given snake, so the longest snake(s)
+    /// have rank 0
+    fn length_rank(&self, snake_id: &Self::SnakeIDType) -> usize {
+        let length = self.get_length_i64(snake_id);
+        self.get_snake_ids()
+            .iter()
+            .filter(|sid| self.is_alive(sid) && self.get_length_i64(sid) > length)
+            .count()
+    }
+}
+
+impl<G> StandingsGame for G where G: HealthGettableGame + LengthGettableGame + ?Sized {}
+
+/// The minimum set of traits a board representation needs for the tooling in this crate. External
+/// crates can implement the supertraits for their own representation and get this for free,
+/// `conformance::verify_board_impl` (with the `test-utils` feature) checks an implementation
+/// against this crate's fixtures.
+pub trait Board:
+    Clone
+    + SnakeIDGettableGame
+    + YouDeterminableGame
+    + VictorDeterminableGame
+    + PositionGettableGame
+    + HeadGettableGame
+    + LengthGettableGame
+    + HealthGettableGame
+    + FoodGettableGame
+    + HazardQueryableGame
+    + SnakeBodyGettableGame
+    + NeighborDeterminableGame
+    + ReasonableMovesGame
+    + SizeDeterminableGame
+{
+}
+
+impl<B> Board for B where
+    B: Clone
+        + SnakeIDGettableGame
+        + YouDeterminableGame
+        + VictorDeterminableGame
+        + PositionGettableGame
+        + HeadGettableGame
+        + LengthGettableGame
+        + HealthGettableGame
+        + FoodGettableGame
+        + HazardQueryableGame
+        + SnakeBodyGettableGame
+        + NeighborDeterminableGame
+        + ReasonableMovesGame
+        + SizeDeterminableGame
+{
+}
+
+/// A [Board] which can also be simulated
+pub trait SimulableBoard<const N_SNAKES: usize>:
+    Board + SimulableGame<NoopInstruments, N_SNAKES>
+{
+}
+
+impl<B, const N_SNAKES: usize> SimulableBoard<N_SNAKES> for B where
+    B: Board + SimulableGame<NoopInstruments, N_SNAKES>
+{
+}
+
+/// Forwards the getter traits through a pointer type, so that boards stored behind references and
+/// smart pointers satisfy the same trait bounds as the boards themselves
+macro_rules! impl_getter_traits_for_pointer {
+    (<$($lt:lifetime,)? $b:ident> $ptr:ty) => {
+        impl<$($lt,)? $b: SnakeIDGettableGame + ?Sized> SnakeIDGettableGame for $ptr {
+            type SnakeIDType = <$b as SnakeIDGettableGame>::SnakeIDType;
+
+            fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+                (**self).get_snake_ids()
+            }
+        }
+
+        impl<$($lt,)? $b: YouDeterminableGame + ?Sized> YouDeterminableGame for $ptr {
+            fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool {
+                (**self).is_you(snake_id)
+            }
+
+            fn you_id(&self) -> &Self::SnakeIDType {
+                (**self).you_id()
+            }
+        }
+
+        impl<$($lt,)? $b: VictorDeterminableGame + ?Sized> VictorDeterminableGame for $ptr {
+            fn is_over(&self) -> bool {
+                (**self).is_over()
+            }
+
+            fn get_winner(&self) -> Option<Self::SnakeIDType> {
+                (**self).get_winner()
+            }
+
+            fn is_solo(&self) -> bool {
+                (**self).is_solo()
+            }
+
+            fn alive_snake_count(&self) -> usize {
+                (**self).alive_snake_count()
+            }
+        }
+
+        impl<$($lt,)? $b: PositionGettableGame + ?Sized> PositionGettableGame for $ptr {
+            type NativePositionType = <$b as PositionGettableGame>::NativePositionType;
+
+            fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool {
+                (**self).position_is_snake_body(pos)
+            }
+
+            fn position_from_native(&self, native: Self::NativePositionType) -> Position {
+                (**self).position_from_native(native)
+            }
+
+            fn native_from_position(&self, pos: Position) -> Self::NativePositionType {
+                (**self).native_from_position(pos)
+            }
+
+            fn off_board(&self, pos: Position) -> bool {
+                (**self).off_board(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: HazardQueryableGame + ?Sized> HazardQueryableGame for $ptr {
+            fn is_hazard(&self, pos: &Self::NativePositionType) -> bool {
+                (**self).is_hazard(pos)
+            }
+
+            fn get_hazard_damage(&self) -> u8 {
+                (**self).get_hazard_damage()
+            }
+
+            fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+                (**self).get_hazard_count(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: WallQueryableGame + ?Sized> WallQueryableGame for $ptr {
+            fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+                (**self).is_wall(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: HealingQueryableGame + ?Sized> HealingQueryableGame for $ptr {
+            fn healing_at(&self, pos: &Self::NativePositionType) -> u8 {
+                (**self).healing_at(pos)
+            }
+
+            fn healing_cells(&self) -> Vec<Self::NativePositionType> {
+                (**self).healing_cells()
+            }
+
+            fn is_healing(&self, pos: &Self::NativePositionType) -> bool {
+                (**self).is_healing(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: FoodQueryableGame + ?Sized> FoodQueryableGame for $ptr {
+            fn is_food(&self, pos: &Self::NativePositionType) -> bool {
+                (**self).is_food(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: NeckQueryableGame + ?Sized> NeckQueryableGame for $ptr {
+            fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool {
+                (**self).is_neck(sid, pos)
+            }
+        }
+
+        impl<$($lt,)? $b: HeadGettableGame + ?Sized> HeadGettableGame for $ptr {
+            fn get_head_as_position(&self, snake_id: &Self::SnakeIDType) -> Position {
+                (**self).get_head_as_position(snake_id)
+            }
+
+            fn get_head_as_native_position(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Self::NativePositionType {
+                (**self).get_head_as_native_position(snake_id)
+            }
+
+            fn try_get_head(&self, snake_id: &Self::SnakeIDType) -> Option<Self::NativePositionType> {
+                (**self).try_get_head(snake_id)
+            }
+        }
+
+        impl<$($lt,)? $b: FoodGettableGame + ?Sized> FoodGettableGame for $ptr {
+            fn get_all_food_as_positions(&self) -> Vec<Position> {
+                (**self).get_all_food_as_positions()
+            }
+
+            fn get_all_food_as_native_positions(&self) -> Vec<Self::NativePositionType> {
+                (**self).get_all_food_as_native_positions()
+            }
+        }
+
+        impl<$($lt,)? $b: LengthGettableGame + ?Sized> LengthGettableGame for $ptr {
+            type LengthType = <$b as LengthGettableGame>::LengthType;
+
+            fn get_length(&self, snake_id: &Self::SnakeIDType) -> Self::LengthType {
+                (**self).get_length(snake_id)
+            }
+
+            fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
+                (**self).get_length_i64(snake_id)
+            }
+
+            fn try_get_length(&self, snake_id: &Self::SnakeIDType) -> Option<Self::LengthType> {
+                (**self).try_get_length(snake_id)
+            }
+        }
+
+        impl<$($lt,)? $b: GrowthPredictableGame + ?Sized> GrowthPredictableGame for $ptr {
+            fn will_grow_next_turn(&self, snake_id: &Self::SnakeIDType) -> bool {
+                (**self).will_grow_next_turn(snake_id)
+            }
+
+            fn predicted_length_at(
+                &self,
+                snake_id: &Self::SnakeIDType,
+                turn_delta: u32,
+                assumed_food_rate: f32,
+            ) -> f32 {
+                (**self).predicted_length_at(snake_id, turn_delta, assumed_food_rate)
+            }
+        }
+
+        impl<$($lt,)? $b: HealthGettableGame + ?Sized> HealthGettableGame for $ptr {
+            type HealthType = <$b as HealthGettableGame>::HealthType;
+            const ZERO: Self::HealthType = <$b as HealthGettableGame>::ZERO;
+
+            fn get_health(&self, snake_id: &Self::SnakeIDType) -> Self::HealthType {
+                (**self).get_health(snake_id)
+            }
+
+            fn get_health_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
+                (**self).get_health_i64(snake_id)
+            }
+
+            fn is_alive(&self, snake_id: &Self::SnakeIDType) -> bool {
+                (**self).is_alive(snake_id)
+            }
+        }
+
+        impl<$($lt,)? $b: ReasonableMovesGame + ?Sized> ReasonableMovesGame for $ptr {
+            fn reasonable_moves_for_each_snake(
+                &self,
+            ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + Send + '_> {
+                (**self).reasonable_moves_for_each_snake()
+            }
+        }
+
+        impl<$($lt,)? $b: RandomReasonableMovesGame + ?Sized> RandomReasonableMovesGame for $ptr {
+            fn random_reasonable_move_for_each_snake<'r>(
+                &'r self,
+                rng: &'r mut impl Rng,
+            ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'r> {
+                (**self).random_reasonable_move_for_each_snake(rng)
+            }
+        }
+
+        impl<$($lt,)? $b: LegalMovesGame + ?Sized> LegalMovesGame for $ptr {
+            fn legal_moves_for_each_snake(&self) -> Vec<(Self::SnakeIDType, Vec<Move>)> {
+                (**self).legal_moves_for_each_snake()
+            }
+        }
+
+        impl<$($lt,)? $b: NeighborDeterminableGame + ?Sized> NeighborDeterminableGame for $ptr {
+            fn neighbors<'n>(
+                &'n self,
+                pos: &Self::NativePositionType,
+            ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + 'n> {
+                (**self).neighbors(pos)
+            }
+
+            fn possible_moves<'n>(
+                &'n self,
+                pos: &Self::NativePositionType,
+            ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + Send + 'n> {
+                (**self).possible_moves(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: ShoutGettableGame + ?Sized> ShoutGettableGame for $ptr {
+            fn get_shout(&self, snake_id: &Self::SnakeIDType) -> Option<String> {
+                (**self).get_shout(snake_id)
+            }
+        }
+
+        impl<$($lt,)? $b: SizeDeterminableGame + ?Sized> SizeDeterminableGame for $ptr {
+            fn get_width(&self) -> u32 {
+                (**self).get_width()
+            }
+
+            fn get_height(&self) -> u32 {
+                (**self).get_height()
+            }
+        }
+
+        impl<$($lt,)? $b: TurnDeterminableGame + ?Sized> TurnDeterminableGame for $ptr {
+            fn turn(&self) -> u64 {
+                (**self).turn()
+            }
+        }
+
+        impl<$($lt,)? $b: TranspositionKeyGame + ?Sized> TranspositionKeyGame for $ptr {
+            fn key(&self, turn: u64) -> TranspositionKey {
+                (**self).key(turn)
+            }
+        }
+
+        impl<$($lt,)? $b: SnakeBodyGettableGame + ?Sized> SnakeBodyGettableGame for $ptr {
+            fn get_snake_body_vec(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Vec<Self::NativePositionType> {
+                (**self).get_snake_body_vec(snake_id)
+            }
+
+            fn get_snake_body_iter(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
+                (**self).get_snake_body_iter(snake_id)
+            }
+
+            fn try_get_snake_body_vec(
+                &self,
+                snake_id: &Self::SnakeIDType,
+            ) -> Option<Vec<Self::NativePositionType>> {
+                (**self).try_get_snake_body_vec(snake_id)
+            }
+
+            fn will_be_vacated_this_turn(&self, pos: &Self::NativePositionType) -> bool {
+                (**self).will_be_vacated_this_turn(pos)
+            }
+        }
+
+        impl<$($lt,)? $b: EmptyCellGettableGame + ?Sized> EmptyCellGettableGame for $ptr {
+            fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
+                (**self).get_empty_cells()
+            }
+        }
+
+        impl<$($lt,)? $b: MaxSnakes<MAX_SNAKES> + ?Sized, const MAX_SNAKES: usize>
+            MaxSnakes<MAX_SNAKES> for $ptr
+        {
+        }
+    };
 }
 
+impl_getter_traits_for_pointer!(<'a, B> &'a B);
+impl_getter_traits_for_pointer!(<B> Box<B>);
+impl_getter_traits_for_pointer!(<B> std::sync::Arc<B>);
+impl_getter_traits_for_pointer!(<B> crate::v2::Adapter<B>);
+
 #[cfg(test)]
 mod test {
 
@@ -485,4 +1448,224 @@ mod test {
     fn test_move_all_order_matches_iter() {
         assert_eq!(Move::all().to_vec(), Move::all_iter().collect::<Vec<_>>());
     }
+
+    #[test]
+    fn test_try_variants() {
+        for mv in Move::all() {
+            assert_eq!(Move::try_from_vector(mv.to_vector()), Some(mv));
+            assert_eq!(Move::try_from_index(mv.as_index()), Some(mv));
+        }
+        assert_eq!(Move::try_from_vector(Vector { x: 1, y: 1 }), None);
+        assert_eq!(Move::try_from_index(N_MOVES), None);
+
+        assert_eq!(Action::<4>::new([None; 4]).try_own_move(), None);
+        assert_eq!(
+            Action::<4>::new([Some(Move::Left), None, None, None]).try_own_move(),
+            Some(Move::Left)
+        );
+    }
+
+    fn you_health<G: YouDeterminableGame + HealthGettableGame>(game: G) -> i64 {
+        game.get_health_i64(game.you_id())
+    }
+
+    #[test]
+    fn test_you_queryable() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+
+        let g = crate::game_fixture(include_str!("../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let opponent = g.board.snakes.iter().find(|s| s.id != g.you.id).unwrap();
+
+        assert_eq!(g.my_health(), 58);
+        assert_eq!(compact.my_health(), 58);
+        assert_eq!(g.my_length() as u16, compact.my_length());
+        assert_eq!(compact.position_from_native(compact.my_head()), g.my_head());
+        assert_eq!(g.longest_opponent_length(), Some(opponent.body.len()));
+        assert_eq!(
+            compact.longest_opponent_length(),
+            Some(opponent.body.len() as u16)
+        );
+    }
+
+    #[test]
+    fn test_standings() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+
+        let mut g = crate::game_fixture(include_str!("../fixtures/4_snake_game.json"));
+        g.board.snakes[3].health = 0;
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        let standings = g.standings();
+        assert_eq!(standings.len(), 4);
+        assert_eq!(standings[3], g.board.snakes[3].id);
+        for pair in standings[..3].windows(2) {
+            let better = (g.get_length(&pair[0]), g.get_health(&pair[0]));
+            let worse = (g.get_length(&pair[1]), g.get_health(&pair[1]));
+            assert!(better >= worse);
+        }
+        assert_eq!(g.length_rank(&standings[0]), 0);
+
+        let compact_standings = compact
+            .standings()
+            .into_iter()
+            .filter(|sid| compact.is_alive(sid))
+            .map(|sid| (compact.get_length_i64(&sid), compact.get_health_i64(&sid)))
+            .collect::<Vec<_>>();
+        let wire_standings = standings[..3]
+            .iter()
+            .map(|sid| (g.get_length_i64(sid), g.get_health_i64(sid)))
+            .collect::<Vec<_>>();
+        assert_eq!(compact_standings, wire_standings);
+        for snake in g.board.snakes[..3].iter() {
+            assert_eq!(
+                g.length_rank(&snake.id),
+                compact.length_rank(id_map.get(&snake.id).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_food_placements() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+
+        let g = crate::game_fixture(include_str!("../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let food_count = compact.get_all_food_as_native_positions().len();
+        assert!(food_count > 0);
+
+        let samples = compact.sample_food_placements(200, &mut rng);
+        assert!(samples.len() > 1);
+        let total: f64 = samples.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // with food already on the board nothing spawns 85% of the time
+        let (unchanged, p) = samples.iter().find(|(b, _)| *b == compact).unwrap();
+        assert_eq!(
+            unchanged.get_all_food_as_native_positions().len(),
+            food_count
+        );
+        assert!(*p > 0.7);
+        for (board, _) in samples.iter().filter(|(b, _)| *b != compact) {
+            assert_eq!(
+                board.get_all_food_as_native_positions().len(),
+                food_count + 1
+            );
+        }
+
+        assert!(g.sample_food_placements(0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_try_getters_for_dead_and_unknown_snakes() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+
+        let mut g = crate::game_fixture(include_str!("../fixtures/4_snake_game.json"));
+        g.board.snakes[3].health = 0;
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let alive = g.board.snakes[0].clone();
+        let dead = g.board.snakes[3].id.clone();
+
+        assert_eq!(g.try_get_head(&alive.id), Some(alive.head));
+        assert_eq!(g.try_get_length(&alive.id), Some(alive.body.len()));
+        assert_eq!(g.try_get_head(&dead), None);
+        assert_eq!(g.try_get_length(&dead), None);
+        assert_eq!(g.try_get_snake_body_vec(&"unknown".to_string()), None);
+
+        let alive_sid = id_map.get(&alive.id).unwrap();
+        let dead_sid = id_map.get(&dead).unwrap();
+        assert_eq!(
+            compact
+                .try_get_head(alive_sid)
+                .map(|h| compact.position_from_native(h)),
+            Some(alive.head)
+        );
+        assert_eq!(
+            compact.try_get_length(alive_sid),
+            Some(alive.body.len() as u16)
+        );
+        assert!(compact.try_get_snake_body_vec(alive_sid).is_some());
+        assert_eq!(compact.try_get_head(dead_sid), None);
+        assert_eq!(compact.try_get_length(dead_sid), None);
+        assert_eq!(compact.try_get_snake_body_vec(dead_sid), None);
+        assert_eq!(compact.try_get_head(&SnakeId(200)), None);
+        assert_eq!(
+            LengthGettableGame::try_get_length(&&compact, &SnakeId(200)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_getters_through_pointers() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+        use std::sync::Arc;
+
+        let g = crate::game_fixture(include_str!("../fixtures/late_stage.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+
+        assert_eq!(you_health(&g), 58);
+        assert_eq!(you_health(Box::new(g.clone())), 58);
+        assert_eq!(you_health(Arc::new(g)), 58);
+
+        assert_eq!(you_health::<&StandardCellBoard4Snakes11x11>(&compact), 58);
+        assert_eq!(you_health(Box::new(compact)), 58);
+        assert_eq!(you_health(Arc::new(compact)), 58);
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        use crate::compact_representation::{
+            StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11,
+        };
+        use crate::wire_representation::Position;
+
+        let fits =
+            crate::fixtures::matching(|m| m.width == 11 && m.height == 11 && m.snake_count <= 4);
+        crate::fixtures::check_each(fits, |fixture| {
+            let g = fixture.game();
+            let id_map = build_snake_id_map(&g);
+            let compact = if g.is_wrapped() {
+                let board: WrappedCellBoard4Snakes11x11 = g.as_wrapped_cell_board(&id_map)?;
+                board.legal_moves_for_each_snake()
+            } else {
+                let board: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map)?;
+                board.legal_moves_for_each_snake()
+            };
+            let mut wire = g
+                .legal_moves_for_each_snake()
+                .into_iter()
+                .map(|(id, moves)| (id_map[&id], moves))
+                .collect::<Vec<_>>();
+            wire.sort_by_key(|(sid, _)| sid.as_usize());
+            if wire != compact {
+                return Err(format!("wire {:?} != compact {:?}", wire, compact).into());
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        // you, alone, curled up with your tail just right of your head
+        let mut g = crate::game_fixture(include_str!("../fixtures/start_of_game.json"));
+        let body = [(1, 1), (1, 2), (2, 2), (2, 1)]
+            .iter()
+            .map(|&(x, y)| Position::new(x, y))
+            .collect::<std::collections::VecDeque<_>>();
+        g.you.head = body[0];
+        g.you.body = body;
+        g.board.snakes = vec![g.you.clone()];
+        let moves =
+            |g: &crate::wire_representation::Game| g.legal_moves_for_each_snake().remove(0).1;
+        assert_eq!(moves(&g), vec![Move::Down, Move::Left, Move::Right]);
+
+        // having just eaten the tail is stacked and stays put
+        g.you.body.push_back(Position::new(2, 1));
+        g.board.snakes = vec![g.you.clone()];
+        assert_eq!(moves(&g), vec![Move::Down, Move::Left]);
+    }
 }
@@ -0,0 +1,357 @@
+//! The next version of the game traits. These are still settling and may change between minor
+//! releases, while the traits in [crate::types] only change with a major release.
+//!
+//! A game can implement the traits here alongside the ones in [crate::types], e.g. to return an
+//! unboxed iterator. A game that only implements the current traits can be wrapped in an
+//! [Adapter], which implements the traits here by delegating to them, so an engine can move its
+//! search code over one trait at a time without touching its board types. Once the traits here
+//! are stable they'll replace the ones in [crate::types], and the old traits will be kept around,
+//! deprecated, with implementations that delegate to these for one major release. That hasn't
+//! happened yet: the traits in [crate::types] don't have default implementations delegating to
+//! the ones here, so a game implementing only the traits here doesn't get the current ones.
+
+use std::ops::Deref;
+
+use crate::types::{self, Move, PositionGettableGame, SnakeIDGettableGame, N_MOVES};
+
+/// Wraps a game implementing the traits in [crate::types] so that it implements the traits in
+/// this module too, each delegating to its current counterpart. The wrapper also implements the
+/// traits in [crate::types] that only read the game, the same ones `&G`, `Box<G>` and `Arc<G>`
+/// forward, when the game does, and derefs to the game.
+///
+/// Traits that change the game or make new ones aren't forwarded, e.g.
+/// [types::SimulableGame], [types::HealthSettableGame], [types::HazardSettableGame] and
+/// [types::StandardFoodPlaceableGame], as their results would be unwrapped games. Call those on
+/// the wrapped game, `.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Adapter<G: ?Sized>(pub G);
+
+impl<G: ?Sized> Deref for Adapter<G> {
+    type Target = G;
+
+    fn deref(&self) -> &G {
+        &self.0
+    }
+}
+
+/// A set of moves stored as a bitmask, a `Copy` replacement for the `Vec<Move>`s the current
+/// traits return
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MoveSet {
+    bits: u8,
+}
+
+impl MoveSet {
+    /// the set with no moves in it
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// the set with every move in it
+    pub const fn all() -> Self {
+        Self {
+            bits: (1 << N_MOVES) - 1,
+        }
+    }
+
+    /// add a move to this set
+    pub fn insert(&mut self, m: Move) {
+        self.bits |= 1 << m.as_index();
+    }
+
+    /// take a move out of this set
+    pub fn remove(&mut self, m: Move) {
+        self.bits &= !(1 << m.as_index());
+    }
+
+    /// whether the move is in this set
+    pub fn contains(&self, m: Move) -> bool {
+        self.bits & (1 << m.as_index()) != 0
+    }
+
+    /// the number of moves in this set
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// whether this set has no moves in it
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// the moves in this set, in [Move::all] order
+    pub fn iter(&self) -> impl Iterator<Item = Move> {
+        let set = *self;
+        Move::all_iter().filter(move |m| set.contains(*m))
+    }
+}
+
+impl std::iter::FromIterator<Move> for MoveSet {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+        let mut set = Self::empty();
+        for m in iter {
+            set.insert(m);
+        }
+        set
+    }
+}
+
+impl From<&[Move]> for MoveSet {
+    fn from(moves: &[Move]) -> Self {
+        moves.iter().copied().collect()
+    }
+}
+
+/// [types::NeighborDeterminableGame] returning its iterators by their own type instead of boxed,
+/// so implementations can avoid an allocation per call
+pub trait NeighborDeterminableGame: PositionGettableGame {
+    /// the iterator returned by [NeighborDeterminableGame::neighbors]
+    type Neighbors<'a>: Iterator<Item = Self::NativePositionType>
+    where
+        Self: 'a;
+
+    /// the iterator returned by [NeighborDeterminableGame::possible_moves]
+    type PossibleMoves<'a>: Iterator<Item = (Move, Self::NativePositionType)>
+    where
+        Self: 'a;
+
+    /// returns the neighboring positions
+    fn neighbors(&self, pos: &Self::NativePositionType) -> Self::Neighbors<'_>;
+
+    /// returns the neighboring positions, and the Move required to get to each
+    fn possible_moves(&self, pos: &Self::NativePositionType) -> Self::PossibleMoves<'_>;
+}
+
+impl<G: types::NeighborDeterminableGame + ?Sized> NeighborDeterminableGame for Adapter<G> {
+    type Neighbors<'a>
+        = Box<dyn Iterator<Item = Self::NativePositionType> + 'a>
+    where
+        Self: 'a;
+    type PossibleMoves<'a>
+        = Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a>
+    where
+        Self: 'a;
+
+    fn neighbors(&self, pos: &Self::NativePositionType) -> Self::Neighbors<'_> {
+        types::NeighborDeterminableGame::neighbors(&self.0, pos)
+    }
+
+    fn possible_moves(&self, pos: &Self::NativePositionType) -> Self::PossibleMoves<'_> {
+        types::NeighborDeterminableGame::possible_moves(&self.0, pos)
+    }
+}
+
+/// [types::ReasonableMovesGame] with each snake's moves as a [MoveSet]. Which moves are reasonable
+/// is the same as for [types::ReasonableMovesGame]
+pub trait ReasonableMovesGame: SnakeIDGettableGame {
+    /// the reasonable moves of every alive snake, in [types::SnakeId] order
+    fn reasonable_moves_for_each_snake(&self) -> Vec<(Self::SnakeIDType, MoveSet)>;
+}
+
+impl<G: types::ReasonableMovesGame + ?Sized> ReasonableMovesGame for Adapter<G> {
+    fn reasonable_moves_for_each_snake(&self) -> Vec<(Self::SnakeIDType, MoveSet)> {
+        types::ReasonableMovesGame::reasonable_moves_for_each_snake(&self.0)
+            .map(|(sid, moves)| (sid, MoveSet::from(moves.as_slice())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_fixture,
+        types::{
+            build_snake_id_map, HeadGettableGame, SimulableGame, SimulatorInstruments,
+            SnakeIDGettableGame, VictorDeterminableGame,
+        },
+        wire_representation::{Game, Position},
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    // the current trait surface that engines implement and call. If one of these stops compiling
+    // the change needs a major release, or a defaulted method that delegates to its replacement
+    #[allow(dead_code, clippy::type_complexity)]
+    fn current_trait_surface() {
+        let _: for<'a> fn(&'a Game, &Position) -> Box<dyn Iterator<Item = Position> + Send + 'a> =
+            <Game as types::NeighborDeterminableGame>::neighbors;
+        let _: for<'a> fn(
+            &'a Game,
+            &Position,
+        ) -> Box<dyn Iterator<Item = (Move, Position)> + Send + 'a> =
+            <Game as types::NeighborDeterminableGame>::possible_moves;
+        let _: fn(&Game) -> Box<dyn Iterator<Item = (String, Vec<Move>)> + Send + '_> =
+            <Game as types::ReasonableMovesGame>::reasonable_moves_for_each_snake;
+        let _: fn(&Game) -> bool = <Game as VictorDeterminableGame>::is_solo;
+    }
+
+    // a board from outside the crate that only implements the required methods of the current
+    // traits, and so relies on every defaulted method staying defaulted
+    #[derive(Debug)]
+    struct DownstreamBoard;
+
+    impl SnakeIDGettableGame for DownstreamBoard {
+        type SnakeIDType = u8;
+
+        fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+            vec![0]
+        }
+    }
+
+    impl PositionGettableGame for DownstreamBoard {
+        type NativePositionType = i32;
+
+        fn position_is_snake_body(&self, _: Self::NativePositionType) -> bool {
+            false
+        }
+
+        fn position_from_native(&self, pos: Self::NativePositionType) -> Position {
+            Position::new(pos, 0)
+        }
+
+        fn native_from_position(&self, pos: Position) -> Self::NativePositionType {
+            pos.x
+        }
+
+        fn off_board(&self, pos: Position) -> bool {
+            pos.y != 0
+        }
+    }
+
+    impl types::NeighborDeterminableGame for DownstreamBoard {
+        fn neighbors<'a>(
+            &'a self,
+            pos: &Self::NativePositionType,
+        ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + 'a> {
+            Box::new(vec![pos - 1, pos + 1].into_iter())
+        }
+
+        fn possible_moves<'a>(
+            &'a self,
+            pos: &Self::NativePositionType,
+        ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + Send + 'a> {
+            Box::new(vec![(Move::Left, pos - 1), (Move::Right, pos + 1)].into_iter())
+        }
+    }
+
+    impl types::ReasonableMovesGame for DownstreamBoard {
+        fn reasonable_moves_for_each_snake(
+            &self,
+        ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + Send + '_> {
+            Box::new(std::iter::once((0, vec![Move::Right, Move::Left])))
+        }
+    }
+
+    impl VictorDeterminableGame for DownstreamBoard {
+        fn is_over(&self) -> bool {
+            false
+        }
+
+        fn get_winner(&self) -> Option<Self::SnakeIDType> {
+            None
+        }
+
+        fn alive_snake_count(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_move_set() {
+        let mut set = MoveSet::empty();
+        assert!(set.is_empty());
+        set.insert(Move::Right);
+        set.insert(Move::Up);
+        set.insert(Move::Up);
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(Move::Up) && !set.contains(Move::Down));
+        assert_eq!(set.iter().collect_vec(), vec![Move::Up, Move::Right]);
+        set.remove(Move::Up);
+        assert_eq!(set, MoveSet::from([Move::Right].as_slice()));
+        assert_eq!(MoveSet::all().iter().collect_vec(), Move::all().to_vec());
+    }
+
+    // a downstream board can implement the v2 traits itself, with unboxed iterators, alongside
+    // the current ones
+    impl NeighborDeterminableGame for DownstreamBoard {
+        type Neighbors<'a> = std::array::IntoIter<i32, 2>;
+        type PossibleMoves<'a> = std::array::IntoIter<(Move, i32), 2>;
+
+        fn neighbors(&self, pos: &i32) -> Self::Neighbors<'_> {
+            IntoIterator::into_iter([pos - 1, pos + 1])
+        }
+
+        fn possible_moves(&self, pos: &i32) -> Self::PossibleMoves<'_> {
+            IntoIterator::into_iter([(Move::Left, pos - 1), (Move::Right, pos + 1)])
+        }
+    }
+
+    #[test]
+    fn test_adapter_delegates_to_current_traits() {
+        let board = Adapter(DownstreamBoard);
+        assert!(!board.is_solo());
+        assert_eq!(
+            NeighborDeterminableGame::neighbors(&board, &3).collect_vec(),
+            types::NeighborDeterminableGame::neighbors(&DownstreamBoard, &3).collect_vec()
+        );
+        assert_eq!(
+            NeighborDeterminableGame::possible_moves(&board, &3).collect_vec(),
+            types::NeighborDeterminableGame::possible_moves(&DownstreamBoard, &3).collect_vec()
+        );
+        assert_eq!(
+            ReasonableMovesGame::reasonable_moves_for_each_snake(&board),
+            types::ReasonableMovesGame::reasonable_moves_for_each_snake(&DownstreamBoard)
+                .map(|(sid, moves)| (sid, MoveSet::from(moves.as_slice())))
+                .collect_vec()
+        );
+        assert_eq!(
+            ReasonableMovesGame::reasonable_moves_for_each_snake(&board),
+            vec![(0, [Move::Left, Move::Right].iter().copied().collect())]
+        );
+
+        // the board's own v2 impl agrees with the adapted current one
+        assert_eq!(
+            NeighborDeterminableGame::possible_moves(&DownstreamBoard, &3).collect_vec(),
+            NeighborDeterminableGame::possible_moves(&board, &3).collect_vec()
+        );
+    }
+
+    #[test]
+    fn test_v2_matches_current_traits() {
+        let g = game_fixture(include_str!("../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&g);
+        let compact: StandardCellBoard4Snakes11x11 = g.as_cell_board(&id_map).unwrap();
+        let (_, compact) = compact
+            .simulate_with_moves(&Instruments, vec![(types::SnakeId(0), [Move::Left])])
+            .next()
+            .unwrap();
+        let adapted = Adapter(compact);
+
+        let v1 = types::ReasonableMovesGame::reasonable_moves_for_each_snake(&compact)
+            .map(|(sid, moves)| (sid, moves.into_iter().collect::<MoveSet>()))
+            .collect_vec();
+        assert_eq!(
+            ReasonableMovesGame::reasonable_moves_for_each_snake(&adapted),
+            v1
+        );
+
+        let head = adapted.get_head_as_native_position(&types::SnakeId(0));
+        assert_eq!(
+            NeighborDeterminableGame::possible_moves(&adapted, &head).collect_vec(),
+            types::NeighborDeterminableGame::possible_moves(&compact, &head).collect_vec()
+        );
+        // the read only current traits are forwarded
+        assert_eq!(
+            types::LegalMovesGame::legal_moves_for_each_snake(&adapted),
+            types::LegalMovesGame::legal_moves_for_each_snake(&compact)
+        );
+    }
+}
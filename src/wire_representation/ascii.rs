@@ -0,0 +1,689 @@
+//! parses the boards the `Display` impls draw back in to games, so a board pasted from a log or
+//! a bug report can be turned straight in to a test
+
+use std::{collections::VecDeque, error::Error};
+
+use super::{BattleSnake, Board, Game, NestedGame, Position, Ruleset};
+use crate::types::Move;
+
+/// the letters bodies of snakes 0 to 9 are drawn with, `f` is left out as it's food
+const BODY_LETTERS: &str = "abcdeghijk";
+
+/// who a cell of a drawn body belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    Snake(usize),
+    Unknown,
+}
+
+impl Game {
+    /// Parses a board drawn the way [Game]'s and the compact boards' `Display` impls draw them,
+    /// the top row first. Cells can be separated by spaces, and lines that aren't part of the
+    /// board are skipped:
+    /// - `.` an empty cell
+    /// - `f` food
+    /// - `x` a hazard
+    /// - `0` to `9` the head of that snake, snake 0 is `you`
+    /// - `H` the head of a snake, or `S` the head of `you`, numbered after any digit heads in the
+    ///   order they're drawn
+    /// - `s` part of some snake's body
+    /// - `a` to `k`, skipping `f`, part of the body of snake 0 to 9, so snake 5's letter is `g`
+    ///
+    /// Bodies are followed out from their heads, and it's an error if there's more than one way
+    /// to do that, e.g. when two bodies run side by side or one is coiled up. Draw a body with
+    /// its snake's letter to say whose it is. Snakes are named `snake0`, `snake1` and so on and
+    /// have full health, unless the diagram ends with the
+    /// `(id health: .. length: .. head: .. tail: .. body: [..])` summary [Game]'s `Display`
+    /// writes. Its lengths and tails also tell bodies apart and put back stacked tails, and a
+    /// snake with its body listed is taken as listed, so [Game]'s `Display` always reads back.
+    /// Following bodies gives up with an error on diagrams with too many ways to try. Cells
+    /// under a snake or food can't be drawn as hazards, so those are lost. The game is on turn 0
+    /// with the given ruleset
+    pub fn from_ascii(diagram: &str, ruleset: Ruleset) -> Result<Game, Box<dyn Error>> {
+        let rows = diagram
+            .lines()
+            .map(|line| line.split_whitespace().collect::<String>())
+            .filter(|row| {
+                !row.is_empty()
+                    && row
+                        .chars()
+                        .all(|c| ".fxHSs0123456789".contains(c) || BODY_LETTERS.contains(c))
+            })
+            .collect::<Vec<_>>();
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if height == 0 || rows.iter().any(|row| row.chars().count() != width) {
+            return Err("every row of the board must be the same length".into());
+        }
+
+        let mut food = vec![];
+        let mut hazards = vec![];
+        let mut numbered = vec![];
+        let mut unnumbered = vec![];
+        let mut cells = vec![];
+        for (i, row) in rows.iter().enumerate() {
+            let y = (height - i - 1) as i32;
+            for (x, c) in row.chars().enumerate() {
+                let pos = Position::new(x as i32, y);
+                match c {
+                    'f' => food.push(pos),
+                    'x' => hazards.push(pos),
+                    '0'..='9' => numbered.push((c as usize - '0' as usize, pos)),
+                    'H' | 'S' => unnumbered.push((c == 'S', pos)),
+                    's' => cells.push((Owner::Unknown, pos)),
+                    _ => {
+                        if let Some(snake) = BODY_LETTERS.find(c) {
+                            cells.push((Owner::Snake(snake), pos));
+                        }
+                    }
+                }
+            }
+        }
+
+        numbered.sort();
+        let mut heads = numbered
+            .iter()
+            .enumerate()
+            .map(|(expected, (n, pos))| {
+                if *n == expected {
+                    Ok(*pos)
+                } else {
+                    Err(format!("snake {} has no head", expected))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut you = 0;
+        for (is_you, pos) in unnumbered {
+            if is_you {
+                you = heads.len();
+            }
+            heads.push(pos);
+        }
+        if heads.is_empty() {
+            return Err("the board has no snakes".into());
+        }
+
+        let summaries = summaries(diagram);
+        let summary_of = |head: &Position| summaries.iter().find(|summary| summary.head == *head);
+        let ends = heads
+            .iter()
+            .map(|head| summary_of(head).map_or((None, None), |s| (s.length, s.tail)))
+            .collect::<Vec<_>>();
+        let known = heads
+            .iter()
+            .map(|head| summary_of(head).and_then(|s| s.body.clone()))
+            .collect::<Vec<_>>();
+        let bodies = trace_bodies(&heads, &ends, &known, cells)?;
+        let mut snakes = bodies
+            .into_iter()
+            .enumerate()
+            .map(|(i, body)| BattleSnake {
+                id: format!("snake{}", i),
+                name: format!("snake{}", i),
+                head: body[0],
+                body,
+                health: 100,
+                shout: None,
+                squad: None,
+                actual_length: None,
+                extra: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        for summary in summaries {
+            if let Some(snake) = snakes.iter_mut().find(|s| s.head == summary.head) {
+                snake.name = summary.id.clone();
+                snake.id = summary.id;
+                snake.health = summary.health;
+            }
+        }
+
+        Ok(Game {
+            you: snakes[you].clone(),
+            board: Board {
+                height: height as u32,
+                width: width as u32,
+                food,
+                snakes,
+                hazards,
+                healing_cells: vec![],
+                extra: Default::default(),
+            },
+            turn: 0,
+            game: NestedGame {
+                id: String::new(),
+                ruleset,
+                timeout: 500,
+                map: None,
+                source: None,
+            },
+            eliminations: vec![],
+            extra: Default::default(),
+        })
+    }
+}
+
+/// how many steps [BodySearch] takes before giving up on a diagram
+const MAX_SEARCH_STEPS: usize = 1_000_000;
+
+/// Searches for the ways to follow every snake's body out from its head that use every drawn
+/// body cell, stopping once a second way is found
+struct BodySearch<'a> {
+    cells: &'a [(Owner, Position)],
+    /// the indices of the cells next to each cell
+    neighbors: Vec<Vec<usize>>,
+    ends: &'a [(Option<usize>, Option<Position>)],
+    known: &'a [bool],
+    used: Vec<bool>,
+    bodies: Vec<Vec<Position>>,
+    found: Vec<Vec<Vec<Position>>>,
+    steps: usize,
+}
+
+impl BodySearch<'_> {
+    fn can_take(&self, snake: usize, cell: usize) -> bool {
+        !self.used[cell]
+            && match self.cells[cell].0 {
+                Owner::Unknown => true,
+                Owner::Snake(s) => s == snake,
+            }
+    }
+
+    fn can_grow(&self, snake: usize) -> bool {
+        !self.known[snake]
+            && self.ends[snake]
+                .0
+                .is_none_or(|length| self.bodies[snake].len() < length)
+    }
+
+    /// Whether the cells left can still be shared out, with `snake` part way through its body
+    /// and the snakes after it yet to start. The cells left split in to groups that are joined
+    /// up, and every group has to be next to the end of a snake that can still grow in to it.
+    /// `snake` can only grow in to one group, so a group no later snake can reach has to be
+    /// all of the rest of its body: its cells must alternate colours on a chessboard starting
+    /// from the end of the body, and it has to fit the snake's length and tail
+    fn feasible(&self, snake: usize) -> bool {
+        let end = *self.bodies[snake].last().unwrap();
+        let grows = self.can_grow(snake);
+        let later_heads = (snake + 1..self.bodies.len())
+            .filter(|s| !self.known[*s])
+            .map(|s| self.bodies[s][0])
+            .collect::<Vec<_>>();
+
+        let mut group = vec![usize::MAX; self.cells.len()];
+        let mut own_groups = 0;
+        let mut exclusive_groups = 0;
+        for first in 0..self.cells.len() {
+            if self.used[first] || group[first] != usize::MAX {
+                continue;
+            }
+            group[first] = first;
+            let mut members = vec![first];
+            let mut i = 0;
+            while let Some(&cell) = members.get(i) {
+                i += 1;
+                for &next in &self.neighbors[cell] {
+                    if !self.used[next] && group[next] == usize::MAX {
+                        group[next] = first;
+                        members.push(next);
+                    }
+                }
+            }
+
+            let next_to = |pos: Position| members.iter().any(|c| adjacent(pos, self.cells[*c].1));
+            let reached_by_snake = grows && next_to(end);
+            let reached_later = later_heads.iter().any(|head| next_to(*head));
+            let mut has_own = false;
+            for cell in &members {
+                match self.cells[*cell].0 {
+                    Owner::Snake(s) if s < snake || self.known[s] => return false,
+                    Owner::Snake(s) if s == snake => has_own = true,
+                    Owner::Snake(_) if !reached_later => return false,
+                    _ => {}
+                }
+            }
+            if has_own {
+                own_groups += 1;
+                if !reached_by_snake {
+                    return false;
+                }
+            }
+            if !reached_by_snake && !reached_later {
+                return false;
+            }
+            if reached_by_snake && !reached_later {
+                exclusive_groups += 1;
+                if !self.fills(snake, end, &members) {
+                    return false;
+                }
+            }
+        }
+        own_groups <= 1 && exclusive_groups <= 1
+    }
+
+    /// whether `snake` can finish its body by going through exactly `members` from `end`
+    fn fills(&self, snake: usize, end: Position, members: &[usize]) -> bool {
+        let colour = |pos: Position| (pos.x + pos.y).rem_euclid(2);
+        // the cells the body goes through alternate colours, starting with the other colour to
+        // its end
+        let other = members
+            .iter()
+            .filter(|c| colour(self.cells[**c].1) != colour(end))
+            .count();
+        let same = members.len() - other;
+        if other != same && other != same + 1 {
+            return false;
+        }
+        let (length, tail) = self.ends[snake];
+        let drawn = self.bodies[snake].len() + members.len();
+        // a stacked tail isn't drawn, so a snake can be up to two longer than its drawn body
+        if length.is_some_and(|length| drawn > length || length > drawn + 2) {
+            return false;
+        }
+        tail.is_none_or(|tail| {
+            let last_colour = if other == same {
+                colour(end)
+            } else {
+                1 - colour(end)
+            };
+            members.iter().any(|c| self.cells[*c].1 == tail) && colour(tail) == last_colour
+        })
+    }
+
+    fn search(&mut self, snake: usize) {
+        if self.found.len() > 1 || self.steps > MAX_SEARCH_STEPS {
+            return;
+        }
+        self.steps += 1;
+        if snake == self.bodies.len() {
+            if self.used.iter().all(|used| *used) {
+                self.found.push(self.bodies.clone());
+            }
+            return;
+        }
+        if !self.feasible(snake) {
+            return;
+        }
+
+        if self.can_grow(snake) {
+            let last = *self.bodies[snake].last().unwrap();
+            for cell in 0..self.cells.len() {
+                if self.can_take(snake, cell) && adjacent(last, self.cells[cell].1) {
+                    self.used[cell] = true;
+                    self.bodies[snake].push(self.cells[cell].1);
+                    self.search(snake);
+                    self.bodies[snake].pop();
+                    self.used[cell] = false;
+                }
+            }
+        }
+
+        let drawn = self.bodies[snake].len();
+        let (length, tail) = self.ends[snake];
+        let long_enough = self.known[snake] || length.is_none_or(|length| length <= drawn + 2);
+        let own_left = (0..self.cells.len())
+            .any(|cell| !self.used[cell] && self.cells[cell].0 == Owner::Snake(snake));
+        let ends_at_tail =
+            self.known[snake] || tail.is_none_or(|tail| self.bodies[snake].last() == Some(&tail));
+        if long_enough && ends_at_tail && !own_left {
+            self.search(snake + 1);
+        }
+    }
+}
+
+fn adjacent(a: Position, b: Position) -> bool {
+    Move::all().iter().any(|mv| a.add_vec(mv.to_vector()) == b)
+}
+
+/// Follows every snake's body out from its head, through the cells drawn with its letter or as
+/// `s`, so that every drawn cell is used and each snake has its length and ends at its tail where
+/// they're known. Tails are stacked to make up the length. Snakes whose whole body is `known` are
+/// taken as they are. Errors unless there's exactly one way to do it
+fn trace_bodies(
+    heads: &[Position],
+    ends: &[(Option<usize>, Option<Position>)],
+    known: &[Option<VecDeque<Position>>],
+    cells: Vec<(Owner, Position)>,
+) -> Result<Vec<VecDeque<Position>>, Box<dyn Error>> {
+    if let Some((Owner::Snake(snake), pos)) = cells
+        .iter()
+        .find(|(owner, _)| matches!(owner, Owner::Snake(s) if *s >= heads.len()))
+    {
+        return Err(format!("snake {} at {:?} has no head", snake, pos).into());
+    }
+    let cells = cells
+        .into_iter()
+        .filter(|(_, pos)| !known.iter().flatten().any(|body| body.contains(pos)))
+        .collect::<Vec<_>>();
+    let neighbors = cells
+        .iter()
+        .map(|(_, pos)| {
+            (0..cells.len())
+                .filter(|other| adjacent(*pos, cells[*other].1))
+                .collect()
+        })
+        .collect();
+    let is_known = known.iter().map(Option::is_some).collect::<Vec<_>>();
+    let mut search = BodySearch {
+        cells: &cells,
+        neighbors,
+        ends,
+        known: &is_known,
+        used: vec![false; cells.len()],
+        bodies: heads.iter().map(|head| vec![*head]).collect(),
+        found: vec![],
+        steps: 0,
+    };
+    search.search(0);
+
+    match search.found.as_slice() {
+        [bodies] => Ok(bodies
+            .iter()
+            .zip(ends)
+            .zip(known)
+            .map(|((body, (length, _)), known)| {
+                if let Some(known) = known {
+                    return known.clone();
+                }
+                let mut body = body.iter().copied().collect::<VecDeque<_>>();
+                let tail = *body.back().unwrap();
+                body.resize(length.unwrap_or(0).max(body.len()), tail);
+                body
+            })
+            .collect()),
+        _ if search.steps > MAX_SEARCH_STEPS => Err(
+            "gave up following the snakes' bodies, draw them with their letters or give their \
+                  bodies in the summary"
+                .into(),
+        ),
+        [] => Err("the snakes' bodies can't be followed from their heads".into()),
+        _ => Err(
+            "the snakes' bodies can be followed more than one way, draw them with their \
+                  letters"
+                .into(),
+        ),
+    }
+}
+
+/// one of the `(id health: .. length: .. head: Position { x: .., y: .. } tail: .. body: [..])`
+/// entries [Game]'s `Display` writes after the board, the length, tail and body are optional
+#[derive(Debug)]
+struct Summary {
+    id: String,
+    health: i32,
+    length: Option<usize>,
+    head: Position,
+    tail: Option<Position>,
+    body: Option<VecDeque<Position>>,
+}
+
+fn summaries(diagram: &str) -> Vec<Summary> {
+    let position = |text: &str| -> Option<Position> {
+        let number = |name: &str| -> Option<i32> {
+            let (_, rest) = text.split_once(name)?;
+            rest.trim_start()
+                .split(|c: char| !c.is_ascii_digit() && c != '-')
+                .next()?
+                .parse()
+                .ok()
+        };
+        Some(Position::new(number("x:")?, number("y:")?))
+    };
+    diagram
+        .split('(')
+        .skip(1)
+        .filter_map(|entry| {
+            let entry = entry.split(')').next()?;
+            let (entry, body) = match entry.split_once(" body: ") {
+                Some((entry, body)) => {
+                    let body = body
+                        .split("Position")
+                        .skip(1)
+                        .map(position)
+                        .collect::<Option<VecDeque<_>>>()?;
+                    (entry, Some(body))
+                }
+                None => (entry, None),
+            };
+            let (id, rest) = entry.split_once(" health: ")?;
+            let (health, head) = rest.split_once(" head: ")?;
+            let (health, length) = match health.split_once(" length: ") {
+                Some((health, length)) => (health, Some(length.trim().parse().ok()?)),
+                None => (health, None),
+            };
+            let (head, tail) = match head.split_once(" tail: ") {
+                Some((head, tail)) => (head, Some(position(tail)?)),
+                None => (head, None),
+            };
+            let head = position(head)?;
+            Some(Summary {
+                id: id.to_string(),
+                health: health.trim().parse().ok()?,
+                length,
+                head,
+                tail,
+                body: body.filter(|body| body.front() == Some(&head)),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11, game_fixture,
+        types::build_snake_id_map, wire_representation::GameMode,
+    };
+
+    fn standard() -> Ruleset {
+        Ruleset {
+            name: GameMode::Standard,
+            version: String::new(),
+            settings: None,
+        }
+    }
+
+    #[test]
+    fn test_from_ascii() {
+        let game = Game::from_ascii(
+            "
+            f . . . x
+            . s s 0 .
+            . s . . .
+            s 1 . . f
+            ",
+            standard(),
+        )
+        .unwrap();
+        assert_eq!((game.board.width, game.board.height), (5, 4));
+        assert_eq!(game.you.id, "snake0");
+        assert_eq!(
+            game.you.body.iter().copied().collect::<Vec<_>>(),
+            vec![
+                Position::new(3, 2),
+                Position::new(2, 2),
+                Position::new(1, 2),
+                Position::new(1, 1),
+            ]
+        );
+        assert_eq!(
+            game.board.snakes[1]
+                .body
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Position::new(1, 0), Position::new(0, 0)]
+        );
+        assert_eq!(
+            game.board.food,
+            vec![Position::new(0, 3), Position::new(4, 0)]
+        );
+        assert_eq!(game.board.hazards, vec![Position::new(4, 3)]);
+
+        // bodies that could be either snake's need their letters
+        assert!(Game::from_ascii("0s1", standard()).is_err());
+        assert!(Game::from_ascii("0a1", standard()).is_ok());
+        let lettered = Game::from_ascii("0a\n1a\nbb", standard()).unwrap();
+        assert_eq!(lettered.board.snakes[0].body.len(), 3);
+        assert_eq!(
+            lettered.board.snakes[1]
+                .body
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![
+                Position::new(0, 1),
+                Position::new(0, 0),
+                Position::new(1, 0)
+            ]
+        );
+
+        assert!(Game::from_ascii("", standard()).is_err());
+        assert!(Game::from_ascii("0.\n.", standard()).is_err());
+        assert!(Game::from_ascii("1s", standard()).is_err());
+        assert!(Game::from_ascii("0.s", standard()).is_err());
+    }
+
+    #[test]
+    fn test_from_ascii_six_snakes() {
+        // snake 5 is drawn with `g`, as `f` is food
+        let game = Game::from_ascii(
+            "
+            0 1 2 3 4 5 6
+            a b c d e g h
+            a b c d e g h
+            f . . . . . x
+            ",
+            standard(),
+        )
+        .unwrap();
+        assert_eq!(game.board.snakes.len(), 7);
+        for (i, snake) in game.board.snakes.iter().enumerate() {
+            let x = i as i32;
+            assert_eq!(
+                snake.body.iter().copied().collect::<Vec<_>>(),
+                vec![
+                    Position::new(x, 3),
+                    Position::new(x, 2),
+                    Position::new(x, 1)
+                ]
+            );
+        }
+        assert_eq!(game.board.food, vec![Position::new(0, 0)]);
+        assert_eq!(game.board.hazards, vec![Position::new(6, 0)]);
+
+        let drawn = game.to_string();
+        let parsed = Game::from_ascii(&drawn, standard()).unwrap();
+        for snake in &game.board.snakes {
+            let parsed = parsed
+                .board
+                .snakes
+                .iter()
+                .find(|s| s.id == snake.id)
+                .unwrap();
+            assert_eq!(parsed.body, snake.body, "{}", drawn);
+        }
+        assert_eq!(parsed.board.food, game.board.food);
+    }
+
+    /// a snake coiled up in a `size` x `size` square, from its head in the top left corner
+    /// spiralling in clockwise
+    fn coiled(size: i32) -> Game {
+        let mut body = VecDeque::new();
+        let (mut lo, mut hi) = (0, size - 1);
+        while lo <= hi {
+            body.extend((lo..=hi).map(|x| Position::new(x, hi)));
+            body.extend((lo..hi).rev().map(|y| Position::new(hi, y)));
+            if lo < hi {
+                body.extend((lo..hi).rev().map(|x| Position::new(x, lo)));
+                body.extend((lo + 1..hi).map(|y| Position::new(lo, y)));
+            }
+            lo += 1;
+            hi -= 1;
+        }
+        let mut game = Game::from_ascii("0", standard()).unwrap();
+        game.board.height = size as u32;
+        game.board.width = size as u32;
+        game.board.snakes[0].head = body[0];
+        game.board.snakes[0].body = body;
+        game.you = game.board.snakes[0].clone();
+        game
+    }
+
+    #[test]
+    fn test_round_trips_coiled_snakes() {
+        for size in [3, 7] {
+            let game = coiled(size);
+            assert_eq!(game.you.body.len(), (size * size) as usize);
+            let drawn = game.to_string();
+            let parsed = Game::from_ascii(&drawn, standard()).unwrap();
+            assert_eq!(parsed.you.body, game.you.body, "{}", drawn);
+        }
+    }
+
+    #[test]
+    fn test_from_ascii_gives_up_quickly() {
+        // without a summary a coiled body can be followed many ways
+        let board = |game: &Game| game.to_string().split('(').next().unwrap().to_string();
+        let start = std::time::Instant::now();
+        assert!(Game::from_ascii(&board(&coiled(7)), standard()).is_err());
+
+        // a stray body cell off the end of the coil can never be used
+        let mut stray = coiled(6);
+        stray.board.width = 7;
+        let coil = board(&stray).replace('s', "a");
+        let stray_drawn = coil.replacen(". \n", "s \n", 1);
+        assert_ne!(coil, stray_drawn);
+        assert!(Game::from_ascii(&stray_drawn, standard()).is_err());
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_round_trips_display() {
+        for json in [
+            include_str!("../../fixtures/late_stage.json"),
+            include_str!("../../fixtures/body_collision.json"),
+            include_str!("../../fixtures/start_of_game.json"),
+        ] {
+            let g = game_fixture(json);
+            let drawn = g.to_string();
+            let parsed = Game::from_ascii(&drawn, g.game.ruleset.clone()).unwrap();
+            assert_eq!(parsed.you.id, g.you.id, "{}", drawn);
+            for snake in &g.board.snakes {
+                let parsed = parsed
+                    .board
+                    .snakes
+                    .iter()
+                    .find(|s| s.id == snake.id)
+                    .unwrap();
+                assert_eq!(parsed.health, snake.health);
+                assert_eq!(parsed.body, snake.body);
+            }
+            assert_eq!(
+                parsed.board.food.iter().collect::<HashSet<_>>(),
+                g.board.food.iter().collect::<HashSet<_>>()
+            );
+        }
+
+        // compact boards don't write lengths or tails, so only simple bodies can be read back
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let id_map = build_snake_id_map(&start);
+        let compact: StandardCellBoard4Snakes11x11 = start.as_cell_board(&id_map).unwrap();
+        let from_compact = Game::from_ascii(&compact.to_string(), standard()).unwrap();
+        let heads = |game: &Game| {
+            game.board
+                .snakes
+                .iter()
+                .map(|s| s.head)
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(heads(&from_compact), heads(&start));
+        assert_eq!(from_compact.you.head, start.you.head);
+        let late = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let compact: StandardCellBoard4Snakes11x11 =
+            late.as_cell_board(&build_snake_id_map(&late)).unwrap();
+        assert!(Game::from_ascii(&compact.to_string(), standard()).is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! the frames the game engine stores and the board viewer plays back, for uploading games that
+//! were played out locally, e.g. in self-play
+
+use serde::{Deserialize, Serialize};
+
+use super::{BattleSnake, Elimination, Game, Position};
+use crate::types::DeathCause;
+
+/// A position as the engine writes it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnginePoint {
+    #[serde(rename = "X")]
+    pub x: i32,
+    #[serde(rename = "Y")]
+    pub y: i32,
+}
+
+impl From<Position> for EnginePoint {
+    fn from(pos: Position) -> Self {
+        EnginePoint { x: pos.x, y: pos.y }
+    }
+}
+
+/// How and when a snake was eliminated. The cause is one of the engine's, e.g. "snake-collision",
+/// or "eliminated" for a snake that went missing between frames for reasons this crate can't tell
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineDeath {
+    pub cause: String,
+    pub turn: i32,
+    pub eliminated_by: String,
+}
+
+impl EngineDeath {
+    pub(crate) fn new(cause: &str, turn: i32) -> Self {
+        EngineDeath {
+            cause: cause.to_string(),
+            turn,
+            eliminated_by: String::new(),
+        }
+    }
+}
+
+/// The engine's name for a cause of death. The engine treats walls as hazards, so dying on one
+/// is dying to a hazard
+fn engine_cause(cause: DeathCause) -> &'static str {
+    match cause {
+        DeathCause::OutOfBounds => "wall-collision",
+        DeathCause::Starvation => "out-of-health",
+        DeathCause::Hazard | DeathCause::Wall => "hazard",
+        DeathCause::SelfCollision => "snake-self-collision",
+        DeathCause::BodyCollision => "snake-collision",
+        DeathCause::HeadToHead => "head-collision",
+    }
+}
+
+impl From<&Elimination> for EngineDeath {
+    fn from(elimination: &Elimination) -> Self {
+        EngineDeath::new(engine_cause(elimination.cause), elimination.turn)
+    }
+}
+
+/// A snake in an [EngineFrame]. Eliminated snakes keep the body and health they died with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineSnake {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub name: String,
+    pub body: Vec<EnginePoint>,
+    pub health: i32,
+    pub death: Option<EngineDeath>,
+    pub color: String,
+    pub head_type: String,
+    pub tail_type: String,
+    pub latency: String,
+    pub shout: String,
+    pub squad: String,
+    pub author: String,
+}
+
+impl EngineSnake {
+    pub(crate) fn new(id: String, name: String, body: Vec<EnginePoint>, health: i32) -> Self {
+        EngineSnake {
+            id,
+            name,
+            body,
+            health,
+            death: None,
+            color: String::new(),
+            head_type: String::new(),
+            tail_type: String::new(),
+            latency: "0".to_string(),
+            shout: String::new(),
+            squad: String::new(),
+            author: String::new(),
+        }
+    }
+}
+
+impl From<&BattleSnake> for EngineSnake {
+    fn from(snake: &BattleSnake) -> Self {
+        let mut engine = EngineSnake::new(
+            snake.id.clone(),
+            snake.name.clone(),
+            snake.body.iter().copied().map(EnginePoint::from).collect(),
+            snake.health,
+        );
+        engine.shout = snake.shout.clone().unwrap_or_default();
+        engine.squad = snake.squad.clone().unwrap_or_default();
+        engine
+    }
+}
+
+/// One turn of a game in the engine's frame format
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineFrame {
+    pub turn: i32,
+    pub snakes: Vec<EngineSnake>,
+    pub food: Vec<EnginePoint>,
+    pub hazards: Vec<EnginePoint>,
+}
+
+impl EngineFrame {
+    /// Carries the snakes of `previous` that are missing from this frame over in to it, marked
+    /// as eliminated on this turn if they weren't already. Frames of a game only list the snakes
+    /// still on the board, this puts back the ones that have been eliminated
+    pub(crate) fn carry_over(&mut self, previous: &EngineFrame) {
+        for (i, snake) in previous.snakes.iter().enumerate() {
+            if self.snakes.iter().any(|s| s.id == snake.id) {
+                continue;
+            }
+            let mut snake = snake.clone();
+            snake
+                .death
+                .get_or_insert(EngineDeath::new("eliminated", self.turn));
+            self.snakes.insert(i.min(self.snakes.len()), snake);
+        }
+    }
+}
+
+impl Game {
+    /// This game as a single engine frame. Snakes eliminated while simulating it (see
+    /// [Game::eliminations]) are listed with their death but no body, as it's no longer known.
+    /// Use [engine_frames] on every frame of a game to keep their bodies
+    pub fn to_engine_frame(&self) -> EngineFrame {
+        let mut snakes = self
+            .board
+            .snakes
+            .iter()
+            .map(|snake| {
+                let mut engine = EngineSnake::from(snake);
+                if snake.health <= 0 {
+                    engine.death = Some(EngineDeath::new(
+                        engine_cause(DeathCause::Starvation),
+                        self.turn,
+                    ));
+                }
+                engine
+            })
+            .collect::<Vec<_>>();
+        for elimination in &self.eliminations {
+            if snakes.iter().all(|s| s.id != elimination.snake_id) {
+                let mut engine = EngineSnake::new(
+                    elimination.snake_id.clone(),
+                    elimination.snake_id.clone(),
+                    vec![],
+                    0,
+                );
+                engine.death = Some(EngineDeath::from(elimination));
+                snakes.push(engine);
+            }
+        }
+        EngineFrame {
+            turn: self.turn,
+            snakes,
+            food: self
+                .board
+                .food
+                .iter()
+                .copied()
+                .map(EnginePoint::from)
+                .collect(),
+            hazards: self
+                .board
+                .hazards
+                .iter()
+                .copied()
+                .map(EnginePoint::from)
+                .collect(),
+        }
+    }
+}
+
+/// Every frame of a game, e.g. from simulating it, in the engine's frame format. Snakes stay in
+/// the frames after they're eliminated, with the body and health they had in the last frame
+/// they were alive in
+pub fn engine_frames<'a>(games: impl IntoIterator<Item = &'a Game>) -> Vec<EngineFrame> {
+    let mut frames: Vec<EngineFrame> = vec![];
+    for game in games {
+        let mut frame = game.to_engine_frame();
+        if let Some(previous) = frames.last() {
+            for snake in frame.snakes.iter_mut().filter(|s| s.body.is_empty()) {
+                if let Some(last) = previous.snakes.iter().find(|s| s.id == snake.id) {
+                    snake.body = last.body.clone();
+                    snake.health = last.health;
+                    snake.name = last.name.clone();
+                }
+            }
+            frame.carry_over(previous);
+        }
+        frames.push(frame);
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game_fixture,
+        types::{Action, Move, NoopInstruments, SimulableGame},
+        wire_representation::Position,
+    };
+
+    #[test]
+    fn test_engine_frames() {
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let frame = g.to_engine_frame();
+        let json = serde_json::to_value(&frame).unwrap();
+        assert_eq!(json["Turn"], g.turn);
+        assert_eq!(json["Food"].as_array().unwrap().len(), g.board.food.len());
+        assert_eq!(json["Hazards"][0]["X"], g.board.hazards[0].x);
+        let you_index = g
+            .board
+            .snakes
+            .iter()
+            .position(|s| s.id == g.you.id)
+            .unwrap();
+        let you = &json["Snakes"][you_index];
+        assert_eq!(you["ID"], g.you.id.as_str());
+        assert_eq!(you["Body"][0], serde_json::json!({"X": 4, "Y": 6}));
+        assert_eq!(you["Death"], serde_json::Value::Null);
+        let parsed: EngineFrame = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, frame);
+
+        // you move back in to your own neck
+        let other = g.board.snakes[1 - you_index].id.clone();
+        let (_, next): (Action<2>, Game) = g
+            .simulate_with_moves(
+                &NoopInstruments,
+                vec![(g.you.id.clone(), [Move::Down]), (other, [Move::Up])],
+            )
+            .next()
+            .unwrap();
+        assert!(next.board.snakes.iter().all(|s| s.id != g.you.id));
+        let lone = next.to_engine_frame();
+        let you = lone.snakes.iter().find(|s| s.id == g.you.id).unwrap();
+        assert!(you.body.is_empty());
+        assert_eq!(
+            you.death,
+            Some(EngineDeath::new("snake-self-collision", g.turn + 1))
+        );
+
+        let frames = engine_frames(&[g.clone(), next.clone(), next]);
+        assert_eq!(frames.len(), 3);
+        for frame in &frames[1..] {
+            assert_eq!(frame.snakes.len(), frames[0].snakes.len());
+            let you = frame.snakes.iter().find(|s| s.id == g.you.id).unwrap();
+            assert_eq!(you.body, frames[0].snakes[you_index].body);
+            assert_eq!(
+                you.death,
+                Some(EngineDeath::new("snake-self-collision", g.turn + 1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_engine_death_causes() {
+        let death = |game: &Game, moves: Vec<(String, [Move; 1])>, id: &str| {
+            let (_, next): (Action<2>, Game) = game
+                .simulate_with_moves(&NoopInstruments, moves)
+                .next()
+                .unwrap();
+            next.to_engine_frame()
+                .snakes
+                .into_iter()
+                .find(|s| s.id == id)
+                .unwrap()
+                .death
+                .map(|d| d.cause)
+        };
+
+        // the other snake runs in to your body
+        let g = game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let other = g.board.snakes.iter().find(|s| s.id != g.you.id).unwrap();
+        let moves = vec![
+            (g.you.id.clone(), [Move::Up]),
+            (other.id.clone(), [Move::Left]),
+        ];
+        assert_eq!(
+            death(&g, moves, &other.id).as_deref(),
+            Some("snake-collision")
+        );
+
+        let hazard_game = |health: i32, damage: i32| {
+            crate::builder::BoardBuilder::new(5, 5)
+                .snake(&[Position::new(2, 2), Position::new(2, 1)], health)
+                .snake(&[Position::new(0, 4), Position::new(0, 3)], 90)
+                .hazard(Position::new(2, 3))
+                .hazard_damage(damage)
+                .build()
+        };
+        let snake0 = |mv: Move| vec![("snake0".to_string(), [mv])];
+        for (game, mv, cause) in [
+            (hazard_game(50, 14), Move::Up, None),
+            (hazard_game(10, 14), Move::Up, Some("hazard")),
+            (hazard_game(1, 14), Move::Left, Some("out-of-health")),
+            (
+                hazard_game(50, 14),
+                Move::Down,
+                Some("snake-self-collision"),
+            ),
+        ] {
+            assert_eq!(death(&game, snake0(mv), "snake0").as_deref(), cause);
+        }
+        let edge = crate::builder::BoardBuilder::new(5, 5)
+            .snake(&[Position::new(0, 2), Position::new(1, 2)], 90)
+            .snake(&[Position::new(4, 4), Position::new(4, 3)], 90)
+            .build();
+        assert_eq!(
+            death(&edge, snake0(Move::Left), "snake0").as_deref(),
+            Some("wall-collision")
+        );
+
+        // two snakes of the same length meet head on
+        let meet = crate::builder::BoardBuilder::new(5, 5)
+            .snake(&[Position::new(1, 2), Position::new(0, 2)], 90)
+            .snake(&[Position::new(3, 2), Position::new(4, 2)], 90)
+            .build();
+        let moves = vec![
+            ("snake0".to_string(), [Move::Right]),
+            ("snake1".to_string(), [Move::Left]),
+        ];
+        assert_eq!(
+            death(&meet, moves, "snake1").as_deref(),
+            Some("head-collision")
+        );
+    }
+}
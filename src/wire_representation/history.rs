@@ -0,0 +1,257 @@
+//! bookkeeping over the frames of a game as they arrive, for what no single frame says: when each
+//! snake ate, when it was eliminated, and how its length and health changed
+
+use std::error::Error;
+
+use super::Game;
+
+/// What's known about one snake from the frames seen so far
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeHistory {
+    pub id: String,
+    /// the turns this snake ate on, ascending. A snake ate when it's longer than in the frame for
+    /// the turn before, so meals on turns whose frames, or the frames before them, weren't seen
+    /// are missing. Always empty in constrictor games, where snakes grow every turn
+    pub eaten_turns: Vec<i32>,
+    /// the turn of the first frame this snake was missing from or had no health in, or the turn
+    /// simulation recorded it being eliminated on, see [Game::eliminations]
+    pub eliminated_turn: Option<i32>,
+    /// the turn and length of each frame this snake was alive in
+    pub lengths: Vec<(i32, usize)>,
+    /// the turn and health of each frame this snake was alive in
+    pub healths: Vec<(i32, i32)>,
+}
+
+impl SnakeHistory {
+    fn new(id: String) -> Self {
+        SnakeHistory {
+            id,
+            eaten_turns: vec![],
+            eliminated_turn: None,
+            lengths: vec![],
+            healths: vec![],
+        }
+    }
+
+    /// the last turn this snake ate on, at or before `turn`
+    pub fn last_eaten_before(&self, turn: i32) -> Option<i32> {
+        self.eaten_turns.iter().rev().find(|t| **t <= turn).copied()
+    }
+}
+
+/// Collects [SnakeHistory]s from successive frames of one game, e.g. the start request and then
+/// every move request. Frames can be skipped, but have to be ingested in turn order. The engine
+/// sends the start request and the first move request on the same turn, so a frame on the same
+/// turn as the last one replaces it
+#[derive(Debug, Clone, Default)]
+pub struct GameHistory {
+    game_id: Option<String>,
+    last_turn: Option<i32>,
+    snakes: Vec<SnakeHistory>,
+}
+
+impl GameHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ingests every frame in order, see [GameHistory::ingest]
+    pub fn from_frames(frames: impl IntoIterator<Item = Game>) -> Result<Self, Box<dyn Error>> {
+        let mut history = Self::new();
+        for frame in frames {
+            history.ingest(&frame)?;
+        }
+        Ok(history)
+    }
+
+    /// Records a frame, replacing the last one if it's on the same turn. Errors if the frame is
+    /// from a different game than the frames before it, or is on an earlier turn than the last one
+    pub fn ingest(&mut self, game: &Game) -> Result<(), Box<dyn Error>> {
+        match &self.game_id {
+            Some(id) if *id != game.game.id => {
+                return Err(format!("frame is from game {}, not {}", game.game.id, id).into())
+            }
+            _ => {}
+        }
+        if let Some(last) = self.last_turn.filter(|last| game.turn < *last) {
+            return Err(format!("frame is on turn {}, after turn {}", game.turn, last).into());
+        }
+        let turn = game.turn;
+        if self.last_turn == Some(turn) {
+            for history in self.snakes.iter_mut() {
+                history.eaten_turns.retain(|t| *t != turn);
+                history.lengths.retain(|(t, _)| *t != turn);
+                history.healths.retain(|(t, _)| *t != turn);
+                if history.eliminated_turn == Some(turn) {
+                    history.eliminated_turn = None;
+                }
+            }
+        }
+        let detect_eating = !game.game.mode().is_constrictor();
+
+        for snake in game.board.snakes.iter().filter(|s| s.health > 0) {
+            let history = match self.snakes.iter().position(|h| h.id == snake.id) {
+                Some(i) => &mut self.snakes[i],
+                None => {
+                    self.snakes.push(SnakeHistory::new(snake.id.clone()));
+                    self.snakes.last_mut().unwrap()
+                }
+            };
+            // health isn't a reliable signal, as healing and negative hazard damage can take it
+            // back to 100 too. Growth since a skipped frame could be from any of the turns skipped
+            let grew = history
+                .lengths
+                .last()
+                .is_some_and(|(last_turn, last_length)| {
+                    *last_turn == turn - 1 && snake.body.len() > *last_length
+                });
+            if detect_eating && grew {
+                history.eaten_turns.push(turn);
+            }
+            history.lengths.push((turn, snake.body.len()));
+            history.healths.push((turn, snake.health));
+        }
+
+        for history in self
+            .snakes
+            .iter_mut()
+            .filter(|h| h.eliminated_turn.is_none())
+        {
+            let recorded = game
+                .eliminations
+                .iter()
+                .find(|e| e.snake_id == history.id)
+                .map(|e| e.turn);
+            let alive = game
+                .board
+                .snakes
+                .iter()
+                .any(|s| s.id == history.id && s.health > 0);
+            if recorded.is_some() || !alive {
+                history.eliminated_turn = recorded.or(Some(turn));
+            }
+        }
+
+        self.game_id = Some(game.game.id.clone());
+        self.last_turn = Some(turn);
+        Ok(())
+    }
+
+    /// the turn of the last frame ingested
+    pub fn last_turn(&self) -> Option<i32> {
+        self.last_turn
+    }
+
+    /// every snake seen so far, in the order they were first seen
+    pub fn snakes(&self) -> &[SnakeHistory] {
+        &self.snakes
+    }
+
+    /// the history of the snake with this id, None if it was never seen
+    pub fn snake(&self, id: &str) -> Option<&SnakeHistory> {
+        self.snakes.iter().find(|h| h.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+
+    #[test]
+    fn test_game_history() {
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let ids = start
+            .board
+            .snakes
+            .iter()
+            .map(|s| s.id.clone())
+            .collect::<Vec<_>>();
+
+        let mut ate = start.clone();
+        ate.turn = 2;
+        let tail = *ate.board.snakes[0].body.back().unwrap();
+        ate.board.snakes[0].body.push_back(tail);
+        ate.board.snakes[0].health = 100;
+        ate.board.snakes[1].health = 98;
+
+        // turn 3 is skipped, and the second snake is gone by turn 4
+        let mut eliminated = ate.clone();
+        eliminated.turn = 4;
+        eliminated.board.snakes.remove(1);
+
+        let mut history = GameHistory::from_frames(vec![start.clone(), ate, eliminated]).unwrap();
+        assert_eq!(history.last_turn(), Some(4));
+        assert_eq!(history.snakes().len(), 4);
+
+        // full health again on turn 4 isn't a meal, it didn't grow
+        let eater = history.snake(&ids[0]).unwrap();
+        assert_eq!(eater.eaten_turns, vec![2]);
+        assert_eq!(eater.lengths, vec![(1, 3), (2, 4), (4, 4)]);
+        assert_eq!(eater.last_eaten_before(3), Some(2));
+        assert_eq!(eater.eliminated_turn, None);
+
+        let gone = history.snake(&ids[1]).unwrap();
+        assert_eq!(gone.healths, vec![(1, 99), (2, 98)]);
+        assert!(gone.eaten_turns.is_empty());
+        assert_eq!(gone.eliminated_turn, Some(4));
+
+        assert!(history.ingest(&start).is_err());
+        let mut other_game = start;
+        other_game.turn = 5;
+        other_game.game.id = "another game".to_string();
+        assert!(history.ingest(&other_game).is_err());
+        assert!(history.snake("nobody").is_none());
+    }
+
+    #[test]
+    fn test_start_and_first_move_share_a_turn() {
+        let mut start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        start.turn = 0;
+        let id = start.board.snakes[0].id.clone();
+        let first_move = start.clone();
+        let mut ate = start.clone();
+        ate.turn = 1;
+        let tail = *ate.board.snakes[0].body.back().unwrap();
+        ate.board.snakes[0].body.push_back(tail);
+
+        let mut history = GameHistory::new();
+        history.ingest(&start).unwrap();
+        history.ingest(&first_move).unwrap();
+        assert_eq!(history.last_turn(), Some(0));
+        history.ingest(&ate).unwrap();
+
+        let snake = history.snake(&id).unwrap();
+        assert_eq!(snake.lengths, vec![(0, 3), (1, 4)]);
+        assert_eq!(snake.healths.len(), 2);
+        assert_eq!(snake.eaten_turns, vec![1]);
+        assert_eq!(history.snakes().len(), 4);
+        assert!(history.ingest(&first_move).is_err());
+    }
+
+    #[test]
+    fn test_healing_isnt_eating() {
+        let start = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let id = start.board.snakes[0].id.clone();
+        let mut hurt = start.clone();
+        hurt.turn = 2;
+        hurt.board.snakes[0].health = 80;
+
+        // back to full health on a healing cell, without growing
+        let mut healed = hurt.clone();
+        healed.turn = 3;
+        healed.board.snakes[0].health = 100;
+
+        // and then growing after a skipped turn, which could have been on either
+        let mut grown = healed.clone();
+        grown.turn = 5;
+        let tail = *grown.board.snakes[0].body.back().unwrap();
+        grown.board.snakes[0].body.push_back(tail);
+
+        let history = GameHistory::from_frames(vec![start, hurt, healed, grown]).unwrap();
+        let snake = history.snake(&id).unwrap();
+        assert!(snake.eaten_turns.is_empty());
+        assert_eq!(snake.healths[1..], [(2, 80), (3, 100), (5, 100)]);
+        assert_eq!(snake.lengths.last(), Some(&(5, 4)));
+    }
+}
@@ -1,33 +1,107 @@
 #![allow(missing_docs)]
 //! types to match the battlesnake wire representation
 
+use crate::algorithms::territory::TerritoryEvaluatableGame;
 use crate::compact_representation;
 use crate::compact_representation::dimensions::Dimensions;
 use crate::compact_representation::CellNum;
+use crate::compact_representation::ConversionScratch;
 use crate::compact_representation::StandardCellBoard;
+use crate::maps::HealingCell;
 use crate::types::*;
+use fxhash::FxHasher64;
+use itertools::Itertools;
 use rand::prelude::IteratorRandom;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+
+mod ascii;
+mod engine_frame;
+mod history;
+mod simulator;
+
+pub use engine_frame::{engine_frames, EngineDeath, EngineFrame, EnginePoint, EngineSnake};
+pub use history::{GameHistory, SnakeHistory};
 
 /// Struct that matches the `battlesnake` object from the wire representation
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct BattleSnake {
     pub id: String,
     pub name: String,
+    /// eliminated snakes, e.g. `you` once you've died, may be sent without a head or body
+    #[serde(default)]
     pub head: Position,
+    #[serde(default)]
     pub body: VecDeque<Position>,
+    /// some relays leave out opponent health, it's read as full health when they do. See
+    /// [HealthSettableGame] for branching on what it could be
+    #[serde(default = "full_health")]
     pub health: i32,
     pub shout: Option<String>,
+    /// only set in squad games
+    #[serde(
+        default,
+        deserialize_with = "non_empty_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub squad: Option<String>,
     #[serde(skip)]
     pub actual_length: Option<i32>,
+    /// fields this crate doesn't know about (e.g. `latency`), written back out when serialized
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl BattleSnake {
+    /// the health of this snake as the `u8` the compact boards store it as, saturating at the
+    /// bounds of `u8`. Along with the saturated value a `HealthOutOfRange` is returned if the
+    /// health didn't fit
+    pub fn saturating_health(&self) -> (u8, Option<HealthOutOfRange>) {
+        match u8::try_from(self.health) {
+            Ok(health) => (health, None),
+            Err(_) => (
+                self.health.clamp(0, u8::MAX as i32) as u8,
+                Some(HealthOutOfRange {
+                    snake_id: self.id.clone(),
+                    health: self.health,
+                }),
+            ),
+        }
+    }
 }
 
+/// A snake's wire health doesn't fit in the `u8` the compact boards use. Returned (boxed) as the
+/// error from converting to a compact board, or as a warning from `Game::saturate_healths`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthOutOfRange {
+    pub snake_id: String,
+    pub health: i32,
+}
+
+impl Display for HealthOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "snake {} has health {} which is outside of 0..={}",
+            self.snake_id,
+            self.health,
+            u8::MAX
+        )
+    }
+}
+
+impl Error for HealthOutOfRange {}
+
 /// Struct that matches the `position` object from the wire representation
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd, Default,
+)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -61,15 +135,55 @@ impl Position {
             y: self.y as i64,
         }
     }
+
+    /// wraps this position on to a torus of the given size, so e.g. `(-1, 11)` on an 11x11 board
+    /// is `(10, 0)`. Positions already on the board are returned as they are
+    pub fn wrap_to(&self, width: u32, height: u32) -> Position {
+        Position {
+            x: self.x.rem_euclid(width as i32),
+            y: self.y.rem_euclid(height as i32),
+        }
+    }
+
+    /// the shortest displacement from this position to `to` on a torus of the given size, going
+    /// across the edges of the board when that's shorter. When both ways around are the same
+    /// length the positive direction is picked, so the result is always in `-size/2..=size/2`
+    pub fn torus_delta(&self, to: &Position, width: u32, height: u32) -> Vector {
+        fn shortest(from: i32, to: i32, size: u32) -> i64 {
+            let size = size as i64;
+            let forward = (to as i64 - from as i64).rem_euclid(size);
+            if forward > size / 2 {
+                forward - size
+            } else {
+                forward
+            }
+        }
+
+        Vector {
+            x: shortest(self.x, to.x, width),
+            y: shortest(self.y, to.y, height),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Board {
     pub height: u32,
     pub width: u32,
+    /// defaults to empty, so minimal payloads (e.g. from training harnesses) can leave it out
+    #[serde(default)]
     pub food: Vec<Position>,
     pub snakes: Vec<BattleSnake>,
+    /// defaults to empty, like `food`
+    #[serde(default)]
     pub hazards: Vec<Position>,
+    /// cells the map heals snakes on, which the engine doesn't send. See
+    /// [crate::maps::MapRegistry]
+    #[serde(skip)]
+    pub healing_cells: Vec<HealingCell>,
+    /// fields this crate doesn't know about, written back out when serialized
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl fmt::Display for Board {
@@ -100,8 +214,13 @@ impl fmt::Display for Board {
         for snake in self.snakes.iter() {
             write!(
                 f,
-                "({} health: {} head: {:?}) ",
-                snake.id, snake.health, snake.head
+                "({} health: {} length: {} head: {:?} tail: {:?} body: {:?}) ",
+                snake.id,
+                snake.health,
+                snake.body.len(),
+                snake.head,
+                snake.body.back().unwrap_or(&snake.head),
+                snake.body
             )?;
         }
         Ok(())
@@ -119,13 +238,114 @@ pub struct NestedGame {
     pub source: Option<String>,
 }
 
+impl NestedGame {
+    /// the ruleset this game is played with
+    pub fn mode(&self) -> &GameMode {
+        &self.ruleset.name
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Ruleset {
-    pub name: String,
+    pub name: GameMode,
     pub version: String,
     pub settings: Option<Settings>,
 }
 
+/// The ruleset a game is played with, parsed from the ruleset's name. Names this crate doesn't
+/// know are kept as they were sent, and every mode is serialized back to its name
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum GameMode {
+    Standard,
+    Royale,
+    Squad,
+    Solo,
+    Wrapped,
+    Constrictor,
+    WrappedConstrictor,
+    /// any other ruleset
+    Other(String),
+}
+
+impl GameMode {
+    /// the ruleset name the engine sends for this mode
+    pub fn as_str(&self) -> &str {
+        match self {
+            GameMode::Standard => "standard",
+            GameMode::Royale => "royale",
+            GameMode::Squad => "squad",
+            GameMode::Solo => "solo",
+            GameMode::Wrapped => "wrapped",
+            GameMode::Constrictor => "constrictor",
+            GameMode::WrappedConstrictor => "wrapped_constrictor",
+            GameMode::Other(name) => name,
+        }
+    }
+
+    /// whether snakes that move off one edge of the board come back on the opposite edge
+    pub fn is_wrapped(&self) -> bool {
+        matches!(self, GameMode::Wrapped | GameMode::WrappedConstrictor)
+    }
+
+    /// whether snakes grow every turn, so their tails never move
+    pub fn is_constrictor(&self) -> bool {
+        matches!(self, GameMode::Constrictor | GameMode::WrappedConstrictor)
+    }
+}
+
+impl From<&str> for GameMode {
+    fn from(name: &str) -> Self {
+        match name {
+            "standard" => GameMode::Standard,
+            "royale" => GameMode::Royale,
+            "squad" => GameMode::Squad,
+            "solo" => GameMode::Solo,
+            "wrapped" => GameMode::Wrapped,
+            "constrictor" => GameMode::Constrictor,
+            "wrapped_constrictor" => GameMode::WrappedConstrictor,
+            other => GameMode::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for GameMode {
+    fn from(name: String) -> Self {
+        GameMode::from(name.as_str())
+    }
+}
+
+impl From<GameMode> for String {
+    fn from(mode: GameMode) -> Self {
+        match mode {
+            GameMode::Other(name) => name,
+            mode => mode.as_str().to_string(),
+        }
+    }
+}
+
+impl PartialEq<str> for GameMode {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for GameMode {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn full_health() -> i32 {
+    100
+}
+
 fn non_empty_str<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
     let o: Option<String> = Option::deserialize(d)?;
     Ok(o.filter(|s| !s.is_empty()))
@@ -148,6 +368,21 @@ pub struct Settings {
     )]
     pub hazard_map_author: Option<String>,
     pub royale: Option<RoyaleSettings>,
+    /// only used by the squad ruleset, see [Game::allows_body_collisions]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub squad: Option<SquadSettings>,
+    /// settings this crate doesn't know about (e.g. for a new ruleset), written back out when
+    /// serialized. See [Settings::get_extra]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Settings {
+    /// a setting this crate doesn't have a field for, None if it's missing or isn't a `T`
+    pub fn get_extra<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let value = self.extra.get(name)?;
+        serde_json::from_value(value.clone()).ok()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -156,6 +391,18 @@ pub struct RoyaleSettings {
     pub shrink_every_n_turns: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SquadSettings {
+    #[serde(default, rename = "allowBodyCollisions")]
+    pub allow_body_collisions: bool,
+    #[serde(default, rename = "sharedElimination")]
+    pub shared_elimination: bool,
+    #[serde(default, rename = "sharedHealth")]
+    pub shared_health: bool,
+    #[serde(default, rename = "sharedLength")]
+    pub shared_length: bool,
+}
+
 /// Root object from the battlesnake server in start, move, and end requests, you
 /// probably want to do:
 /// ```
@@ -170,9 +417,71 @@ pub struct Game {
     pub board: Board,
     pub turn: i32,
     pub game: NestedGame,
+    /// the snakes eliminated while simulating this game, in the order they were eliminated. The
+    /// engine doesn't send these, snakes that were already gone when the game was received aren't
+    /// in here
+    #[serde(skip)]
+    pub eliminations: Vec<Elimination>,
+    /// fields this crate doesn't know about, written back out when serialized so that proxies and
+    /// recorders pass along whatever the engine adds
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A snake that was eliminated while simulating a [Game]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Elimination {
+    pub snake_id: String,
+    /// the [SnakeId] the snake had in the [Action]s of the simulation that eliminated it, which
+    /// its children keep giving the snakes left, see [SimulableGame] for [Game]
+    pub simulation_id: SnakeId,
+    /// the turn the snake was eliminated on, the turn its last move led to
+    pub turn: i32,
+    /// why the snake was eliminated, which engine frames give as the engine's cause
+    pub cause: DeathCause,
+}
+
+/// Who won a game, and where every snake placed. See [Game::winner_info]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinnerInfo {
+    /// the same as [VictorDeterminableGame::get_winner], None if the game isn't over, was a draw
+    /// or is only over for you, as you were eliminated with more than one snake left
+    pub winner: Option<String>,
+    /// the snakes from first place to last, snakes that placed the same are grouped together.
+    /// The winner places first, then the snakes still alive, then the snakes eliminated while
+    /// simulating with the latest eliminated first
+    pub placements: Vec<Vec<String>>,
 }
 
 impl Game {
+    /// the winner of this game along with the placement of every snake, see [WinnerInfo]
+    pub fn winner_info(&self) -> WinnerInfo {
+        let winner = self.get_winner();
+        let mut placements = vec![];
+        placements.extend(winner.clone().map(|w| vec![w]));
+        let alive = self
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0 && Some(&s.id) != winner.as_ref())
+            .map(|s| s.id.clone())
+            .collect_vec();
+        if !alive.is_empty() {
+            placements.push(alive);
+        }
+        let by_turn = self
+            .eliminations
+            .iter()
+            .rev()
+            .group_by(|e| e.turn)
+            .into_iter()
+            .map(|(_, group)| group.map(|e| e.snake_id.clone()).collect_vec())
+            .collect_vec();
+        placements.extend(by_turn);
+
+        WinnerInfo { winner, placements }
+    }
+
     pub fn you_are_winner(&self) -> bool {
         if self.you.health == 0 {
             false
@@ -181,6 +490,22 @@ impl Game {
         }
     }
 
+    /// Clamps every snake's health in to the range the compact boards can represent, so that a
+    /// payload from a buggy server can still be converted. Returns a warning for each snake whose
+    /// health was changed
+    pub fn saturate_healths(&mut self) -> Vec<HealthOutOfRange> {
+        let mut warnings = vec![];
+        for snake in self.board.snakes.iter_mut() {
+            let (health, warning) = snake.saturating_health();
+            snake.health = health as i32;
+            warnings.extend(warning);
+        }
+        let (you_health, _) = self.you.saturating_health();
+        self.you.health = you_health as i32;
+
+        warnings
+    }
+
     pub fn as_cell_board<
         T: CellNum,
         D: Dimensions,
@@ -190,9 +515,15 @@ impl Game {
         &self,
         snake_ids: &SnakeIDMap,
     ) -> Result<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>, Box<dyn Error>> {
-        StandardCellBoard::convert_from_game(self.clone(), snake_ids)
+        StandardCellBoard::convert_from_game_with(
+            self,
+            snake_ids,
+            &mut ConversionScratch::default(),
+        )
     }
 
+    /// # Panics
+    /// if this game isn't wrapped, see [Game::try_as_wrapped_cell_board]
     pub fn as_wrapped_cell_board<
         T: compact_representation::CellNum,
         D: Dimensions,
@@ -206,12 +537,49 @@ impl Game {
         Box<dyn Error>,
     > {
         if self.is_wrapped() {
-            compact_representation::wrapped::CellBoard::convert_from_game(self.clone(), snake_ids)
+            compact_representation::wrapped::CellBoard::convert_from_game_with(
+                self,
+                snake_ids,
+                &mut ConversionScratch::default(),
+            )
         } else {
             panic!("Cannot convert a non-wrapped game to a wrapped game")
         }
     }
 
+    /// like `as_wrapped_cell_board`, but returns an error for a game that isn't wrapped
+    pub fn try_as_wrapped_cell_board<
+        T: compact_representation::CellNum,
+        D: Dimensions,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    >(
+        &self,
+        snake_ids: &SnakeIDMap,
+    ) -> Result<
+        compact_representation::wrapped::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+        Box<dyn Error>,
+    > {
+        compact_representation::wrapped::CellBoard::convert_from_game_with(
+            self,
+            snake_ids,
+            &mut ConversionScratch::default(),
+        )
+    }
+
+    /// the snake with the given id, if it's on the board and alive
+    fn live_snake(&self, snake_id: &str) -> Option<&BattleSnake> {
+        self.board
+            .snakes
+            .iter()
+            .find(|s| s.id == snake_id && s.health > 0)
+    }
+
+    /// whether the snakes in this game are playing in squads
+    pub fn has_squads(&self) -> bool {
+        self.board.snakes.iter().any(|s| s.squad.is_some())
+    }
+
     pub fn off_board(&self, position: Position) -> bool {
         position.x < 0
             || position.x >= self.board.width as i32
@@ -229,12 +597,133 @@ impl Game {
 
     /// Returns a boolean indicating whether this game is using the wrapped ruleset
     pub fn is_wrapped(&self) -> bool {
-        self.game.ruleset.name == "wrapped"
+        self.game.mode().is_wrapped()
+    }
+
+    fn wrap_if_needed(&self, pos: Position) -> Position {
+        if self.is_wrapped() {
+            pos.wrap_to(self.get_width(), self.get_height())
+        } else {
+            pos
+        }
     }
 
     pub fn is_arcade_maze_map(&self) -> bool {
         self.game.map == Some("arcade_maze".to_owned())
     }
+
+    /// whether this game is on the snail mode map, whose hazards trail behind snakes
+    pub fn is_snail_mode_map(&self) -> bool {
+        self.game.map.as_deref() == Some("snail_mode")
+    }
+
+    /// Whether this game's hazards are walls, which is only the case on the arcade maze map.
+    /// Hazards elsewhere aren't walls however much damage they deal, as a snake eating food on a
+    /// hazard takes no damage from it
+    pub fn has_hazard_walls(&self) -> bool {
+        self.is_arcade_maze_map()
+    }
+
+    /// Whether snakes on the same squad can move through each other. Every ruleset sends the squad
+    /// settings, but only the squad ruleset uses them
+    pub fn allows_body_collisions(&self) -> bool {
+        self.game.ruleset.name == GameMode::Squad
+            && self
+                .game
+                .ruleset
+                .settings
+                .as_ref()
+                .and_then(|s| s.squad)
+                .is_some_and(|s| s.allow_body_collisions)
+    }
+
+    /// The minimum food and the chance of spawning an extra food for this game, falling back to
+    /// the engine defaults (1 and 15%) when the ruleset doesn't carry settings
+    pub fn food_spawn_settings(&self) -> (usize, f64) {
+        self.game
+            .ruleset
+            .settings
+            .as_ref()
+            .map(|s| {
+                (
+                    s.minimum_food.max(0) as usize,
+                    s.food_spawn_chance.clamp(0, 100) as f64 / 100.0,
+                )
+            })
+            .unwrap_or((1, 0.15))
+    }
+
+    /// The health a snake loses for each hazard under its head at the end of a turn, falling back
+    /// to the engine default (15) when the ruleset doesn't carry settings. Negative damage heals.
+    /// [HazardQueryableGame::get_hazard_damage] can't be negative, so it clamps this to 0
+    pub fn hazard_damage_per_turn(&self) -> i32 {
+        self.game
+            .ruleset
+            .settings
+            .as_ref()
+            .map_or(15, |settings| settings.hazard_damage_per_turn)
+    }
+}
+
+impl ReasonableMovesGame for Game {
+    fn reasonable_moves_for_each_snake(
+        &self,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Vec<Move>)> + Send + '_> {
+        let hazard_damage: i64 = self.get_hazard_damage().into();
+        // you first and then everyone else in board order, the same order as build_snake_id_map
+        let snakes = self
+            .board
+            .snakes
+            .iter()
+            .filter(move |s| s.id == self.you.id)
+            .chain(
+                self.board
+                    .snakes
+                    .iter()
+                    .filter(move |s| s.id != self.you.id),
+            )
+            .filter(|s| s.health > 0);
+
+        Box::new(snakes.map(move |s| {
+            let destinations = || {
+                Move::all_iter()
+                    .map(move |mv| (mv, self.wrap_if_needed(s.head.add_vec(mv.to_vector()))))
+            };
+            let mvs = destinations()
+                .filter(|(_, new_head)| {
+                    let hits_snake = self.position_is_snake_body(*new_head)
+                        && !self.will_be_vacated_this_turn(new_head);
+
+                    // snakes eating on a hazard don't take its damage
+                    let lethal_hazard = self.board.hazards.contains(new_head)
+                        && !self.board.food.contains(new_head)
+                        && hazard_is_lethal(
+                            s.health as i64,
+                            hazard_damage,
+                            self.get_hazard_count(new_head) as i64,
+                        );
+
+                    !self.off_board(*new_head)
+                        && !self.is_wall(new_head)
+                        && !hits_snake
+                        && !lethal_hazard
+                })
+                .map(|(mv, _)| mv)
+                .collect_vec();
+
+            let mvs = if mvs.is_empty() {
+                let neck = s.body.get(1).filter(|neck| **neck != s.head);
+                let fallback = destinations()
+                    .find(|(_, new_head)| Some(new_head) != neck)
+                    .map_or(Move::Up, |(mv, _)| mv);
+                vec![fallback]
+            } else {
+                mvs
+            };
+
+            (s.id.clone(), mvs)
+        }))
+    }
 }
 
 impl RandomReasonableMovesGame for Game {
@@ -242,44 +731,10 @@ impl RandomReasonableMovesGame for Game {
         &'a self,
         rng: &'a mut impl rand::Rng,
     ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
-        Box::new(self.board.snakes.iter().map(move |s| {
-            let all_moves = Move::all();
-            let moves = all_moves.iter().filter(|mv| {
-                let mut new_head = s.head.add_vec(mv.to_vector());
-
-                if self.is_wrapped() {
-                    let wrapped_x = new_head.x.rem_euclid(self.get_width() as i32);
-                    let wrapped_y = new_head.y.rem_euclid(self.get_height() as i32);
-
-                    new_head = Position {
-                        x: wrapped_x,
-                        y: wrapped_y,
-                    };
-                }
-
-                let hazard_damage: i32 = self.get_hazard_damage().into();
-
-                let unreasonable = self.off_board(new_head)
-                    || self.board.snakes.iter().any(|s| s.body.contains(&new_head))
-                    || (self.board.hazards.contains(&new_head) && hazard_damage >= s.health);
-
-                !unreasonable
-            });
-            (
-                s.id.clone(),
-                moves.choose(rng).copied().unwrap_or_else(|| {
-                    Move::all()
-                        .iter()
-                        .filter(|mv| {
-                            let new_head = s.head.add_vec(mv.to_vector());
-                            new_head != s.body[1]
-                        })
-                        .choose(rng)
-                        .copied()
-                        .unwrap()
-                }),
-            )
-        }))
+        Box::new(
+            self.reasonable_moves_for_each_snake()
+                .map(move |(sid, mvs)| (sid, *mvs.choose(rng).unwrap())),
+        )
     }
 }
 
@@ -315,8 +770,13 @@ impl Display for Game {
         for snake in self.board.snakes.iter() {
             write!(
                 f,
-                "({} health: {} head: {:?}) ",
-                snake.id, snake.health, snake.head
+                "({} health: {} length: {} head: {:?} tail: {:?} body: {:?}) ",
+                snake.id,
+                snake.health,
+                snake.body.len(),
+                snake.head,
+                snake.body.back().unwrap_or(&snake.head),
+                snake.body
             )?;
         }
         Ok(())
@@ -325,21 +785,48 @@ impl Display for Game {
 
 impl VictorDeterminableGame for Game {
     fn is_over(&self) -> bool {
-        self.you.health == 0 || self.board.snakes.len() == 1
+        if self.is_solo() {
+            return self.you.health == 0;
+        }
+        if self.has_squads() {
+            // a snake outside of any squad plays as a squad of one, keyed apart from the squads
+            // so an id can't collide with a squad name
+            return self
+                .board
+                .snakes
+                .iter()
+                .filter(|s| s.health > 0)
+                .map(|s| match &s.squad {
+                    Some(squad) => (Some(squad), None),
+                    None => (None, Some(&s.id)),
+                })
+                .unique()
+                .count()
+                <= 1;
+        }
+        self.you.health == 0 || self.alive_snake_count() <= 1
     }
 
     fn get_winner(&self) -> Option<String> {
-        if self.is_over() {
-            Some(
-                self.snake_ids()
-                    .iter()
-                    .find(|s| s != &self.you_id())
-                    .unwrap_or_else(|| self.you_id())
-                    .clone(),
-            )
-        } else {
-            None
+        if self.is_solo() || !self.is_over() {
+            return None;
         }
+        // once you're eliminated the game is over for you, but nobody has won while more than
+        // one snake is left
+        if !self.has_squads() && self.alive_snake_count() > 1 {
+            return None;
+        }
+        // like the compact boards, the first snake still alive wins, counting from you
+        self.board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0)
+            .min_by_key(|s| s.id != self.you.id)
+            .map(|s| s.id.clone())
+    }
+
+    fn is_solo(&self) -> bool {
+        self.game.ruleset.name == GameMode::Solo
     }
 
     fn alive_snake_count(&self) -> usize {
@@ -375,6 +862,10 @@ impl LengthGettableGame for Game {
     fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
         self.get_length(snake_id) as i64
     }
+
+    fn try_get_length(&self, snake_id: &Self::SnakeIDType) -> Option<Self::LengthType> {
+        self.live_snake(snake_id).map(|s| s.body.len())
+    }
 }
 
 impl PositionGettableGame for Game {
@@ -407,6 +898,12 @@ impl FoodGettableGame for Game {
     }
 }
 
+impl FoodQueryableGame for Game {
+    fn is_food(&self, pos: &Self::NativePositionType) -> bool {
+        self.board.food.contains(pos)
+    }
+}
+
 impl HealthGettableGame for Game {
     type HealthType = i32;
     const ZERO: Self::HealthType = 0;
@@ -425,6 +922,49 @@ impl HealthGettableGame for Game {
     }
 }
 
+impl HealthSettableGame for Game {
+    fn set_health(&mut self, snake_id: &Self::SnakeIDType, health: Self::HealthType) {
+        for snake in self.board.snakes.iter_mut() {
+            if &snake.id == snake_id && snake.health > 0 {
+                snake.health = health;
+            }
+        }
+        if &self.you.id == snake_id && self.you.health > 0 {
+            self.you.health = health;
+        }
+    }
+}
+
+impl GrowthPredictableGame for Game {
+    fn will_grow_next_turn(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.live_snake(snake_id).is_some_and(|snake| {
+            let mut from_tail = snake.body.iter().rev();
+            matches!((from_tail.next(), from_tail.next()), (Some(tail), Some(before_tail)) if tail == before_tail)
+        })
+    }
+}
+
+impl GrowthMarkableGame for Game {
+    fn mark_will_grow(&mut self, snake_id: &Self::SnakeIDType) {
+        if self.live_snake(snake_id).is_none() || self.will_grow_next_turn(snake_id) {
+            return;
+        }
+        let stack_tail = |snake: &mut BattleSnake| {
+            if let Some(tail) = snake.body.back().copied() {
+                snake.body.push_back(tail);
+            }
+        };
+        for snake in self.board.snakes.iter_mut() {
+            if &snake.id == snake_id {
+                stack_tail(snake);
+            }
+        }
+        if &self.you.id == snake_id {
+            stack_tail(&mut self.you);
+        }
+    }
+}
+
 impl SnakeIDGettableGame for Game {
     type SnakeIDType = String;
     fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
@@ -451,6 +991,10 @@ impl HeadGettableGame for Game {
             .unwrap()
             .head
     }
+
+    fn try_get_head(&self, snake_id: &Self::SnakeIDType) -> Option<Self::NativePositionType> {
+        self.live_snake(snake_id).map(|s| s.head)
+    }
 }
 
 impl ShoutGettableGame for Game {
@@ -475,9 +1019,28 @@ impl SizeDeterminableGame for Game {
     }
 }
 
+impl TranspositionKeyGame for Game {
+    /// hashes the rules and everything on the board, but not metadata like names, shouts,
+    /// latencies or the game id, so the same position in two games gets the same key
+    fn key(&self, turn: u64) -> TranspositionKey {
+        let mut hasher = FxHasher64::default();
+        self.game.ruleset.name.as_str().hash(&mut hasher);
+        (self.board.width, self.board.height).hash(&mut hasher);
+        self.board.food.hash(&mut hasher);
+        self.board.hazards.hash(&mut hasher);
+        self.board.healing_cells.hash(&mut hasher);
+        for snake in self.board.snakes.iter() {
+            (&snake.id, snake.health, &snake.body, &snake.squad).hash(&mut hasher);
+        }
+        self.you.id.hash(&mut hasher);
+        TranspositionKey::new(hasher.finish(), turn, self.is_wrapped())
+    }
+}
+
 impl TurnDeterminableGame for Game {
+    /// a negative turn from a misbehaving server is treated as turn 0
     fn turn(&self) -> u64 {
-        self.turn.try_into().unwrap()
+        self.turn.try_into().unwrap_or(0)
     }
 }
 
@@ -489,7 +1052,7 @@ impl SnakeBodyGettableGame for Game {
     fn get_snake_body_iter(
         &self,
         snake_id: &Self::SnakeIDType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_> {
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
         Box::new(
             self.board
                 .snakes
@@ -501,6 +1064,14 @@ impl SnakeBodyGettableGame for Game {
                 .cloned(),
         )
     }
+
+    fn try_get_snake_body_vec(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> Option<Vec<Self::NativePositionType>> {
+        self.live_snake(snake_id)
+            .map(|s| s.body.iter().copied().collect())
+    }
 }
 
 impl HazardQueryableGame for Game {
@@ -509,12 +1080,36 @@ impl HazardQueryableGame for Game {
     }
 
     fn get_hazard_damage(&self) -> u8 {
-        self.game
-            .ruleset
-            .settings
-            .as_ref()
-            .map(|settings| settings.hazard_damage_per_turn)
-            .unwrap_or(15) as u8
+        self.hazard_damage_per_turn().clamp(0, u8::MAX as i32) as u8
+    }
+
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        let count = self.board.hazards.iter().filter(|p| *p == pos).count();
+        count.min(u8::MAX as usize) as u8
+    }
+}
+
+impl WallQueryableGame for Game {
+    fn is_wall(&self, pos: &Self::NativePositionType) -> bool {
+        self.has_hazard_walls() && self.is_hazard(pos)
+    }
+}
+
+impl HealingQueryableGame for Game {
+    fn healing_at(&self, pos: &Self::NativePositionType) -> u8 {
+        self.board
+            .healing_cells
+            .iter()
+            .find(|c| c.position == *pos)
+            .map_or(0, |c| c.regen)
+    }
+
+    fn healing_cells(&self) -> Vec<Self::NativePositionType> {
+        self.board
+            .healing_cells
+            .iter()
+            .map(|c| c.position)
+            .collect()
     }
 }
 
@@ -526,35 +1121,71 @@ impl HazardSettableGame for Game {
     fn clear_hazard(&mut self, pos: Self::NativePositionType) {
         self.board.hazards.retain(|p| p != &pos);
     }
+
+    fn set_hazard_count(&mut self, pos: Self::NativePositionType, count: u8) {
+        self.clear_hazard(pos);
+        self.board
+            .hazards
+            .extend(std::iter::repeat_n(pos, count as usize));
+    }
 }
 
+impl EmptyCellGettableGame for Game {
+    fn get_empty_cells(&self) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + '_> {
+        // iterate in the same order as the compact cell indices so that food placement consumes
+        // randomness identically across representations
+        Box::new(
+            (0..self.board.height as i32)
+                .flat_map(move |y| (0..self.board.width as i32).map(move |x| Position { x, y }))
+                .filter(move |pos| {
+                    !self.board.food.contains(pos)
+                        && !self.position_is_snake_body(*pos)
+                        && !self.is_wall(pos)
+                }),
+        )
+    }
+}
+
+impl StandardFoodPlaceableGame for Game {
+    fn place_food(&mut self, rng: &mut impl Rng) {
+        let (min_food, food_spawn_chance) = self.food_spawn_settings();
+
+        let food_to_add = if self.board.food.len() < min_food {
+            min_food - self.board.food.len()
+        } else {
+            usize::from(rng.gen_bool(food_spawn_chance))
+        };
+
+        if food_to_add == 0 {
+            return;
+        }
+
+        let new_food = self.get_empty_cells().choose_multiple(rng, food_to_add);
+        self.board.food.extend(new_food);
+    }
+}
+
+impl TerritoryEvaluatableGame for Game {}
+
+impl LegalMovesGame for Game {}
+
 impl NeighborDeterminableGame for Game {
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a> {
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + Send + 'a> {
         Box::new(self.possible_moves(pos).map(|(_m, pos)| pos))
     }
 
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a> {
+    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + Send + 'a> {
         let clone = *pos;
         Box::new(Move::all_iter().filter_map(move |m| {
             let v = m.to_vector();
 
-            let mut new_pos = clone.add_vec(v);
-
-            if self.is_wrapped() {
-                let wrapped_x = new_pos.x.rem_euclid(self.get_width() as i32);
-                let wrapped_y = new_pos.y.rem_euclid(self.get_height() as i32);
-
-                new_pos = Position {
-                    x: wrapped_x,
-                    y: wrapped_y,
-                };
-            }
+            let new_pos = self.wrap_if_needed(clone.add_vec(v));
 
             if self.off_board(new_pos) {
                 debug_assert!(
@@ -564,6 +1195,9 @@ impl NeighborDeterminableGame for Game {
 
                 return None;
             }
+            if self.is_wall(&new_pos) {
+                return None;
+            }
 
             Some((m, new_pos))
         }))
@@ -623,6 +1257,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let mut json = serde_json::to_value(fixture()).unwrap();
+        json["spectators"] = serde_json::json!(3);
+        json["board"]["portals"] = serde_json::json!([{"x": 1, "y": 2}]);
+        json["board"]["snakes"][0]["customizations"] = serde_json::json!({"color": "#ff00ff"});
+        let g: Game = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(g.extra["spectators"], 3);
+        assert_eq!(
+            g.board.snakes[0].extra["customizations"]["color"],
+            "#ff00ff"
+        );
+        assert_eq!(serde_json::to_value(&g).unwrap(), json);
+
+        // fields the engine sends which aren't modelled are kept too
+        assert_eq!(fixture().you.extra["latency"], "100");
+    }
+
+    #[test]
+    fn test_unknown_settings_round_trip() {
+        let fixture = include_str!("../../fixtures/hazard_map_settings.json");
+        let mut json: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        let settings = &mut json["game"]["ruleset"]["settings"];
+        settings["constrictor"] = serde_json::json!({"growEveryTurn": true});
+        settings["newKnob"] = serde_json::json!(3);
+        let expected = settings.clone();
+
+        let g: Game = serde_json::from_value(json).unwrap();
+        let parsed = g.game.ruleset.settings.as_ref().unwrap();
+        assert_eq!(parsed.get_extra::<i32>("newKnob"), Some(3));
+        assert_eq!(parsed.get_extra::<String>("newKnob"), None);
+        assert_eq!(parsed.get_extra::<i32>("missing"), None);
+        assert_eq!(parsed.squad, Some(SquadSettings::default()));
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            expected,
+            "known and unknown settings are both written back out"
+        );
+    }
+
+    #[test]
+    fn test_game_mode() {
+        let g = fixture();
+        assert_eq!(g.game.mode(), &GameMode::Royale);
+        assert!(!g.is_wrapped());
+
+        for name in ["wrapped", "wrapped_constrictor", "snail_racing"] {
+            let mut json = serde_json::to_value(fixture()).unwrap();
+            json["game"]["ruleset"]["name"] = serde_json::json!(name);
+            let g: Game = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(g.game.mode(), name);
+            assert_eq!(g.is_wrapped(), name.starts_with("wrapped"));
+            assert_eq!(serde_json::to_value(&g).unwrap(), json);
+        }
+        assert_eq!(
+            GameMode::from("snail_racing"),
+            GameMode::Other("snail_racing".to_string())
+        );
+        assert!(GameMode::WrappedConstrictor.is_constrictor());
+        assert!(!GameMode::Royale.is_constrictor());
+    }
+
+    #[test]
+    fn test_squad_body_collisions() {
+        let mut g = crate::game_fixture(include_str!("../../fixtures/hazard_map_settings.json"));
+        assert!(!g.allows_body_collisions());
+        let settings = g.game.ruleset.settings.as_mut().unwrap();
+        settings.squad = Some(SquadSettings {
+            allow_body_collisions: true,
+            ..Default::default()
+        });
+        // only the squad ruleset lets squads collide
+        assert!(!g.allows_body_collisions());
+        g.game.ruleset.name = GameMode::Squad;
+        assert!(g.allows_body_collisions());
+
+        let id_map = build_snake_id_map(&g);
+        let compact: Result<crate::compact_representation::StandardCellBoard4Snakes11x11, _> =
+            g.as_cell_board(&id_map);
+        assert!(compact.is_err());
+    }
+
+    #[test]
+    fn test_missing_opponent_health() {
+        let mut json = serde_json::to_value(fixture()).unwrap();
+        json["board"]["snakes"][1]
+            .as_object_mut()
+            .unwrap()
+            .remove("health");
+        let g: Game = serde_json::from_value(json).unwrap();
+        let opponent = g.board.snakes[1].id.clone();
+        assert_eq!(g.get_health(&opponent), 100);
+
+        let weak = g.with_assumed_health(&opponent, 7);
+        assert_eq!(weak.get_health(&opponent), 7);
+        assert_eq!(g.get_health(&opponent), 100);
+        let you = g.you_id().clone();
+        let (low, high) = g.with_health_range(&you, 1, 50);
+        assert_eq!(low.get_health(&you), 1);
+        assert_eq!(high.you.health, 50);
+    }
+
     #[test]
     fn test_game_you_determinable() {
         let g = fixture();
@@ -743,6 +1479,92 @@ mod tests {
         assert_eq!(possible_moves, expected);
     }
 
+    #[test]
+    fn test_place_food_matches_compact() {
+        use crate::compact_representation::StandardCellBoard4Snakes11x11;
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut wire = fixture();
+        let id_map = build_snake_id_map(&wire);
+        let mut compact: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&id_map).unwrap();
+
+        let mut wire_rng = SmallRng::seed_from_u64(42);
+        let mut compact_rng = SmallRng::seed_from_u64(42);
+        for _ in 0..50 {
+            wire.place_food(&mut wire_rng);
+            compact.place_food(&mut compact_rng);
+
+            let mut wire_food = wire.get_all_food_as_positions();
+            let mut compact_food = compact.get_all_food_as_positions();
+            wire_food.sort();
+            compact_food.sort();
+            assert_eq!(wire_food, compact_food);
+        }
+    }
+
+    fn assert_same_reasonable_moves<B>(wire: &Game, compact: &B)
+    where
+        B: RandomReasonableMovesGame<SnakeIDType = SnakeId> + ReasonableMovesGame,
+    {
+        let id_map = build_snake_id_map(wire);
+
+        assert_eq!(
+            wire.reasonable_moves_for_each_snake()
+                .map(|(id, mvs)| (id_map[&id], mvs))
+                .collect_vec(),
+            compact.reasonable_moves_for_each_snake().collect_vec()
+        );
+        for seed in 0..200 {
+            assert_eq!(
+                wire.seeded_random_reasonable_move_for_each_snake(seed)
+                    .into_iter()
+                    .map(|(id, mv)| (id_map[&id], mv))
+                    .collect_vec(),
+                compact.seeded_random_reasonable_move_for_each_snake(seed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_reasonable_moves_match_compact() {
+        use crate::compact_representation::{
+            StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11,
+        };
+
+        for fixture in [
+            include_str!("../../fixtures/late_stage.json"),
+            include_str!("../../fixtures/tail_chase.json"),
+            include_str!("../../fixtures/cornered.json"),
+        ] {
+            let wire = crate::game_fixture(fixture);
+            let compact: StandardCellBoard4Snakes11x11 =
+                wire.as_cell_board(&build_snake_id_map(&wire)).unwrap();
+            assert_same_reasonable_moves(&wire, &compact);
+        }
+
+        for fixture in [
+            include_str!("../../fixtures/wrapped_fixture.json"),
+            include_str!("../../fixtures/mojave_12_18_12_34.json"),
+            include_str!("../../fixtures/cornered_wrapped.json"),
+        ] {
+            let wire = crate::game_fixture(fixture);
+            let compact: WrappedCellBoard4Snakes11x11 = wire
+                .as_wrapped_cell_board(&build_snake_id_map(&wire))
+                .unwrap();
+            assert_same_reasonable_moves(&wire, &compact);
+        }
+    }
+
+    #[test]
+    fn test_place_food_reaches_minimum() {
+        let mut g = fixture();
+        g.board.food.clear();
+        let mut rng = rand::thread_rng();
+        g.place_food(&mut rng);
+        assert_eq!(g.board.food.len(), 1);
+        assert!(!g.position_is_snake_body(g.board.food[0]));
+    }
+
     #[test]
     fn test_map_json() {
         let game_fixture = include_str!("../../fixtures/arcade_maze_map.json");
@@ -751,4 +1573,38 @@ mod tests {
 
         assert!(g.is_arcade_maze_map());
     }
+
+    #[test]
+    fn test_wrap_to_and_torus_delta() {
+        assert_eq!(Position::new(-1, -1).wrap_to(11, 11), Position::new(10, 10));
+        assert_eq!(Position::new(23, 5).wrap_to(11, 7), Position::new(1, 5));
+        assert_eq!(Position::new(4, 4).wrap_to(11, 11), Position::new(4, 4));
+
+        let delta = |from: Position, to: Position, width, height| {
+            let v = from.torus_delta(&to, width, height);
+            (v.x, v.y)
+        };
+        assert_eq!(
+            delta(Position::new(0, 0), Position::new(10, 0), 11, 11),
+            (-1, 0)
+        );
+        assert_eq!(
+            delta(Position::new(2, 1), Position::new(4, 9), 11, 11),
+            (2, -3)
+        );
+        // halfway around an even board either way is as short, the positive way wins
+        assert_eq!(
+            delta(Position::new(0, 0), Position::new(5, 5), 10, 10),
+            (5, 5)
+        );
+        assert_eq!(
+            delta(Position::new(5, 5), Position::new(0, 0), 10, 10),
+            (5, 5)
+        );
+        // the delta leads back to the target once wrapped
+        let from = Position::new(9, 1);
+        let to = Position::new(0, 8);
+        let v = from.torus_delta(&to, 11, 11);
+        assert_eq!(from.add_vec(v).wrap_to(11, 11), to);
+    }
 }
@@ -0,0 +1,558 @@
+//! simulation for the wire representation, so that search code that's generic over
+//! [SimulableGame] can fall back to the wire game for boards the compact boards can't hold. The
+//! rules match the compact boards' simulation: no food is spawned, and moves that are certain to
+//! kill a snake are dropped while it has an alternative
+
+use std::{borrow::Borrow, time::Instant};
+
+use itertools::Itertools;
+
+use super::{BattleSnake, Elimination, Game, Position};
+use crate::types::{
+    Action, DeathCause, HazardQueryableGame, HealingQueryableGame, Move, SimulableGame,
+    SimulatorInstruments, SnakeIDMap, SnakeId, WallQueryableGame,
+};
+
+/// where a snake ends up after one of its moves, if the move doesn't kill it outright
+#[derive(Debug, Clone, Copy)]
+struct MoveResult {
+    new_head: Position,
+    health: i32,
+    ate_food: bool,
+}
+
+impl Game {
+    /// the result of `snake` moving `mv`, or why that kills it whatever the other snakes do:
+    /// moving off the board, back on to its neck, or running out of health
+    fn move_result(&self, snake: &BattleSnake, mv: Move) -> Result<MoveResult, DeathCause> {
+        let new_head = self.wrap_if_needed(snake.head.add_vec(mv.to_vector()));
+        if self.off_board(new_head) {
+            return Err(DeathCause::OutOfBounds);
+        }
+        let neck = snake.body.get(1).filter(|neck| **neck != snake.head);
+        if neck == Some(&new_head) {
+            return Err(DeathCause::SelfCollision);
+        }
+
+        // as in the engine, hazards do damage before the starvation check, but none to a snake
+        // moving on to food. Stacked hazards each do damage, and negative damage heals
+        let ate_food = self.board.food.contains(&new_head);
+        let mut health = (snake.health - 1).max(0);
+        if !ate_food {
+            let hazard_damage =
+                self.hazard_damage_per_turn() * self.get_hazard_count(&new_head) as i32;
+            health = (health - hazard_damage).clamp(0, 100);
+        }
+        // healing happens before elimination, so it can save a snake at 0 health
+        let healing = self.healing_at(&new_head) as i32;
+        if healing > 0 {
+            health = (health + healing).min(100);
+        }
+        if ate_food {
+            health = 100;
+        }
+
+        if health > 0 {
+            Ok(MoveResult {
+                new_head,
+                health,
+                ate_food,
+            })
+        } else if self.is_wall(&new_head) {
+            Err(DeathCause::Wall)
+        } else if snake.health > 1 {
+            Err(DeathCause::Hazard)
+        } else {
+            Err(DeathCause::Starvation)
+        }
+    }
+
+    /// the [SnakeId]s snakes have in this game's [Action]s: the ones [build_snake_id_map] gave them
+    /// in the game simulation started from, which eliminating a snake would otherwise shift
+    ///
+    /// [build_snake_id_map]: crate::types::build_snake_id_map
+    fn simulation_id_map(&self) -> SnakeIDMap {
+        let mut id_map = self
+            .eliminations
+            .iter()
+            .map(|e| (e.snake_id.clone(), e.simulation_id))
+            .collect::<SnakeIDMap>();
+        id_map.insert(self.you.id.clone(), SnakeId(0));
+        let taken = id_map.values().copied().collect_vec();
+        let mut free = (1..=u8::MAX)
+            .map(SnakeId)
+            .filter(|sid| !taken.contains(sid));
+        for snake in self.board.snakes.iter().filter(|s| s.id != self.you.id) {
+            let sid = free.next().expect("fewer than 256 snakes");
+            id_map.insert(snake.id.clone(), sid);
+        }
+        id_map
+    }
+
+    /// removes the snakes in `eliminated` from the board, recording that they were eliminated
+    /// this turn and why
+    fn eliminate(&mut self, eliminated: &[(String, DeathCause)], id_map: &SnakeIDMap) {
+        let turn = self.turn;
+        let cause = |id: &String| eliminated.iter().find(|(e, _)| e == id).map(|(_, c)| *c);
+        for snake in self.board.snakes.iter() {
+            if let Some(cause) = cause(&snake.id) {
+                self.eliminations.push(Elimination {
+                    snake_id: snake.id.clone(),
+                    simulation_id: id_map[&snake.id],
+                    turn,
+                    cause,
+                });
+            }
+        }
+        self.board.snakes.retain(|s| cause(&s.id).is_none());
+    }
+
+    /// the game after every snake in `joint` makes its move, an error result eliminates the snake
+    fn apply_moves(
+        &self,
+        joint: &[(String, Move, Result<MoveResult, DeathCause>)],
+        id_map: &SnakeIDMap,
+    ) -> Game {
+        let mut next = self.clone();
+        next.turn += 1;
+
+        // moves that kill a snake outright eliminate it before anyone moves
+        let eliminated_outright = joint
+            .iter()
+            .filter_map(|(id, _, result)| result.err().map(|cause| (id.clone(), cause)))
+            .collect_vec();
+        next.eliminate(&eliminated_outright, id_map);
+        let movers = || {
+            joint
+                .iter()
+                .filter_map(|(id, _, result)| result.ok().map(|r| (id, r)))
+        };
+
+        for (id, result) in movers() {
+            let snake = next
+                .board
+                .snakes
+                .iter_mut()
+                .find(|s| &s.id == id)
+                .expect("only live snakes have a move result");
+            snake.body.push_front(result.new_head);
+            snake.body.pop_back();
+            snake.head = result.new_head;
+            snake.health = result.health;
+            // food is consumed even if the snake dies in a collision, as feeding happens before
+            // elimination
+            if result.ate_food {
+                let tail = *snake.body.back().expect("a snake has a body");
+                snake.body.push_back(tail);
+                next.board.food.retain(|f| f != &result.new_head);
+            }
+        }
+
+        // collisions are all worked out before anyone is removed. As in the engine, running in to
+        // a body takes precedence over a head to head
+        let eliminated = movers()
+            .filter_map(|(id, result)| {
+                let length = |id: &String| {
+                    next.board
+                        .snakes
+                        .iter()
+                        .find(|s| &s.id == id)
+                        .map_or(0, |s| s.body.len())
+                };
+                let body_hit = next.board.snakes.iter().find(|other| {
+                    // a mover's head has already moved, that's a head to head
+                    let moved = movers().any(|(mover, _)| mover == &other.id);
+                    other
+                        .body
+                        .iter()
+                        .skip(moved as usize)
+                        .any(|p| *p == result.new_head)
+                });
+                // the longest snake in a head to head survives, if it's the only one that long
+                let loses_head_to_head = movers().any(|(rival, rival_result)| {
+                    rival != id
+                        && rival_result.new_head == result.new_head
+                        && length(rival) >= length(id)
+                });
+                let cause = match body_hit {
+                    Some(other) if &other.id == id => DeathCause::SelfCollision,
+                    Some(_) => DeathCause::BodyCollision,
+                    None if loses_head_to_head => DeathCause::HeadToHead,
+                    None => return None,
+                };
+                Some((id.clone(), cause))
+            })
+            .collect_vec();
+        next.eliminate(&eliminated, id_map);
+
+        match next.board.snakes.iter().find(|s| s.id == next.you.id) {
+            Some(you) => next.you = you.clone(),
+            None => next.you.health = 0,
+        }
+        next
+    }
+}
+
+/// Snakes are identified in each [Action] by the id [build_snake_id_map] gives them in the game
+/// being simulated, so the actions line up with a compact board converted from the same game.
+/// Children keep those ids after snakes are eliminated, as the compact board's children do.
+///
+/// # Panics
+/// if the game has more than `N_SNAKES` snakes
+///
+/// [build_snake_id_map]: crate::types::build_snake_id_map
+impl<T: SimulatorInstruments, const N_SNAKES: usize> SimulableGame<T, N_SNAKES> for Game {
+    fn simulate_with_moves<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + Send + '_>
+    where
+        S: Borrow<[Move]>,
+    {
+        let start = Instant::now();
+        let id_map = self.simulation_id_map();
+
+        let per_snake_moves = snake_ids_and_moves
+            .into_iter()
+            .map(|(id, moves)| {
+                let snake = self.live_snake(&id);
+                let results = moves
+                    .borrow()
+                    .iter()
+                    .map(|mv| {
+                        let result = snake.ok_or(DeathCause::Starvation);
+                        (*mv, result.and_then(|s| self.move_result(s, *mv)))
+                    })
+                    .collect_vec();
+                let first = results[0];
+                let mut alive = results
+                    .into_iter()
+                    .filter(|(_, result)| result.is_ok())
+                    .peekable();
+                let results = if alive.peek().is_none() {
+                    vec![first]
+                } else {
+                    alive.collect_vec()
+                };
+                results
+                    .into_iter()
+                    .map(|(mv, result)| (id.clone(), mv, result))
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let results = per_snake_moves
+            .into_iter()
+            .multi_cartesian_product()
+            .map(move |joint| {
+                let moves = joint
+                    .iter()
+                    .map(|(id, mv, _)| (id_map[id], *mv))
+                    .collect_vec();
+                (
+                    Action::collect_from(moves.iter()),
+                    self.apply_moves(&joint, &id_map),
+                )
+            });
+        instruments.observe_simulation(start.elapsed());
+        Box::new(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+    use crate::{
+        compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11},
+        fixtures::{self, check_each},
+        types::{
+            build_snake_id_map, FoodGettableGame, HeadGettableGame, HealthGettableGame,
+            SnakeBodyGettableGame, SnakeIDGettableGame, SnakeId, TurnDeterminableGame,
+            VictorDeterminableGame,
+        },
+    };
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    /// simulates every move for every snake in both representations, checking the children agree
+    fn check_matches_compact<B>(wire: &Game, compact: &B) -> Result<(), Box<dyn Error>>
+    where
+        B: SimulableGame<Instruments, 4>
+            + SnakeIDGettableGame<SnakeIDType = SnakeId>
+            + HealthGettableGame
+            + SnakeBodyGettableGame
+            + FoodGettableGame
+            + VictorDeterminableGame
+            + TurnDeterminableGame,
+    {
+        let id_map = build_snake_id_map(wire);
+        let wire_children = wire
+            .simulate(&Instruments, wire.get_snake_ids())
+            .collect_vec();
+        let compact_ids = wire.get_snake_ids().iter().map(|id| id_map[id]).collect();
+        let compact_children = compact.simulate(&Instruments, compact_ids).collect_vec();
+        if wire_children.len() != compact_children.len() {
+            return Err(format!(
+                "{} wire children but {} compact ones",
+                wire_children.len(),
+                compact_children.len()
+            )
+            .into());
+        }
+
+        for ((wire_action, wire_child), (compact_action, compact_child)) in
+            wire_children.iter().zip(compact_children.iter())
+        {
+            if wire_action != compact_action {
+                return Err(format!("{:?} != {:?}", wire_action, compact_action).into());
+            }
+            for (id, sid) in id_map.iter() {
+                let health = wire_child.get_health_i64(id);
+                if health != compact_child.get_health_i64(sid) {
+                    return Err(
+                        format!("{} has different health after {:?}", id, wire_action).into(),
+                    );
+                }
+                if health > 0 {
+                    let compact_body = compact_child
+                        .get_snake_body_vec(sid)
+                        .into_iter()
+                        .map(|p| compact_child.position_from_native(p))
+                        .collect_vec();
+                    if wire_child.get_snake_body_vec(id) != compact_body {
+                        return Err(
+                            format!("{} has a different body after {:?}", id, wire_action).into(),
+                        );
+                    }
+                }
+            }
+            let mut wire_food = wire_child.get_all_food_as_positions();
+            let mut compact_food = compact_child.get_all_food_as_positions();
+            wire_food.sort();
+            compact_food.sort();
+            if wire_food != compact_food {
+                return Err(format!("different food after {:?}", wire_action).into());
+            }
+            if wire_child.is_over() != compact_child.is_over()
+                || wire_child.get_winner().map(|id| id_map[&id]) != compact_child.get_winner()
+            {
+                return Err(format!("different winners after {:?}", wire_action).into());
+            }
+            if wire_child.turn != wire.turn + 1 {
+                return Err("the turn didn't advance".into());
+            }
+            if compact_child.turn() != wire_child.turn as u64 {
+                return Err(format!("different turns after {:?}", wire_action).into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_matches_compact() {
+        let fits = fixtures::matching(|m| m.width == 11 && m.height == 11 && m.snake_count <= 4);
+        assert!(fits.len() > 5);
+        check_each(fits, |fixture| {
+            let wire = fixture.game();
+            let id_map = build_snake_id_map(&wire);
+            if wire.is_wrapped() {
+                let compact: WrappedCellBoard4Snakes11x11 = wire.as_wrapped_cell_board(&id_map)?;
+                check_matches_compact(&wire, &compact)
+            } else {
+                let compact: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&id_map)?;
+                check_matches_compact(&wire, &compact)
+            }
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_simulation_eliminates_snakes() {
+        let wire = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        let you = wire.you.id.clone();
+        let children: Vec<(Action<4>, Game)> = wire
+            .simulate_with_moves(&Instruments, vec![(you.clone(), [Move::Down])])
+            .collect_vec();
+        assert_eq!(children.len(), 1);
+        let (action, child) = &children[0];
+        assert_eq!(action.own_move(), Move::Down);
+        assert_eq!(child.you.health, wire.you.health - 1);
+        assert_eq!(
+            child.you.head,
+            wire.you.head.add_vec(Move::Down.to_vector())
+        );
+        assert_eq!(child.turn, wire.turn + 1);
+
+        // your neck is above you, with nothing else to do you move on to it anyway
+        let (_, child): (Action<4>, Game) = wire
+            .simulate_with_moves(&Instruments, vec![(you.clone(), [Move::Up])])
+            .next()
+            .unwrap();
+        assert_eq!(child.you.health, 0);
+        assert!(child.board.snakes.iter().all(|s| s.id != you));
+        assert_eq!(child.board.snakes.len(), wire.board.snakes.len() - 1);
+    }
+
+    #[test]
+    fn test_simulation_ids_survive_eliminations() {
+        let wire = crate::builder::BoardBuilder::new(7, 7)
+            .snake(&[Position::new(1, 1), Position::new(1, 0)], 90)
+            .snake(&[Position::new(3, 3), Position::new(3, 2)], 90)
+            .snake(&[Position::new(5, 5), Position::new(5, 4)], 90)
+            .build();
+        let id_map = build_snake_id_map(&wire);
+        let compact: StandardCellBoard4Snakes11x11 = wire.as_cell_board(&id_map).unwrap();
+        let turns = [
+            // snake1 turns back in to its neck
+            [Move::Up, Move::Down, Move::Up],
+            [Move::Right, Move::Up, Move::Left],
+            [Move::Up, Move::Up, Move::Left],
+        ];
+
+        let (mut wire, mut compact) = (wire, compact);
+        for (turn, moves) in turns.iter().enumerate() {
+            let wire_moves = wire
+                .get_snake_ids()
+                .into_iter()
+                .map(|id| {
+                    let mv = moves[id_map[&id].as_usize()];
+                    (id, [mv])
+                })
+                .collect_vec();
+            let compact_moves = wire_moves
+                .iter()
+                .map(|(id, mv)| (id_map[id], *mv))
+                .collect_vec();
+            let (wire_action, wire_child): (Action<4>, Game) = wire
+                .simulate_with_moves(&Instruments, wire_moves)
+                .next()
+                .unwrap();
+            let (compact_action, compact_child) = compact
+                .simulate_with_moves(&Instruments, compact_moves)
+                .next()
+                .unwrap();
+            assert_eq!(wire_action, compact_action, "turn {}", turn);
+            wire = wire_child;
+            compact = compact_child;
+        }
+
+        assert_eq!(wire.board.snakes.len(), 2);
+        assert_eq!(wire.eliminations[0].simulation_id, SnakeId(1));
+        let snake2 = wire.board.snakes.iter().find(|s| s.id == "snake2").unwrap();
+        assert_eq!(snake2.head, Position::new(3, 6));
+        assert_eq!(compact.get_head_as_position(&SnakeId(2)), snake2.head);
+    }
+
+    #[test]
+    fn test_simulation_hazard_damage() {
+        let damaged = |damage: i32, health: i32, food: bool| {
+            let mut builder = crate::builder::BoardBuilder::new(5, 5)
+                .snake(&[Position::new(2, 2), Position::new(2, 1)], health)
+                .hazard(Position::new(2, 3))
+                .hazard_damage(damage);
+            if food {
+                builder = builder.food(Position::new(2, 3));
+            }
+            let game = builder.build();
+            let (_, child): (Action<1>, Game) = game
+                .simulate_with_moves(&Instruments, vec![("snake0".to_string(), [Move::Up])])
+                .next()
+                .unwrap();
+            child.board.snakes.first().map(|s| s.health)
+        };
+        assert_eq!(damaged(14, 50, false), Some(35));
+        assert_eq!(damaged(0, 50, false), Some(49));
+        assert_eq!(damaged(-10, 50, false), Some(59));
+        assert_eq!(damaged(-10, 95, false), Some(100));
+        // more damage than fits in a u8
+        assert_eq!(damaged(300, 100, false), None);
+        assert_eq!(damaged(15, 15, false), None);
+        assert_eq!(damaged(15, 1, true), Some(100));
+    }
+
+    #[test]
+    fn test_winner_info() {
+        let wire = crate::game_fixture(include_str!("../../fixtures/late_stage.json"));
+        let you = wire.you.id.clone();
+        let other = wire
+            .get_snake_ids()
+            .into_iter()
+            .find(|id| id != &you)
+            .unwrap();
+        let simulate = |you_move: Move, other_move: Move| {
+            let (_, child): (Action<2>, Game) = wire
+                .simulate_with_moves(
+                    &Instruments,
+                    vec![(you.clone(), [you_move]), (other.clone(), [other_move])],
+                )
+                .next()
+                .unwrap();
+            child
+        };
+
+        // the other snake runs in to your body, so you win
+        let won = simulate(Move::Up, Move::Left);
+        assert_eq!(won.get_winner(), Some(you.clone()));
+        assert_eq!(
+            won.eliminations,
+            vec![Elimination {
+                snake_id: other.clone(),
+                simulation_id: build_snake_id_map(&wire)[&other],
+                turn: wire.turn + 1,
+                cause: DeathCause::BodyCollision,
+            }]
+        );
+        let info = won.winner_info();
+        assert_eq!(info.winner, Some(you.clone()));
+        assert_eq!(
+            info.placements,
+            vec![vec![you.clone()], vec![other.clone()]]
+        );
+
+        // both snakes turning back in to their necks is a draw
+        let drawn = simulate(Move::Down, Move::Down);
+        assert!(drawn.is_over());
+        assert_eq!(drawn.get_winner(), None);
+        let info = drawn.winner_info();
+        assert_eq!(info.winner, None);
+        assert_eq!(info.placements.len(), 1);
+        assert_eq!(info.placements[0].len(), 2);
+
+        // with you eliminated and three snakes left the game is over for you, but nobody has won
+        let mut wire = crate::game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        wire.you.health = 0;
+        let you = wire.you.id.clone();
+        wire.board
+            .snakes
+            .iter_mut()
+            .find(|s| s.id == you)
+            .unwrap()
+            .health = 0;
+        assert!(wire.is_over());
+        assert_eq!(wire.alive_snake_count(), 3);
+        assert_eq!(wire.get_winner(), None);
+        let info = wire.winner_info();
+        assert_eq!(info.winner, None);
+        assert_eq!(info.placements.len(), 1);
+        assert_eq!(info.placements[0].len(), 3);
+        let compact: crate::compact_representation::StandardCellBoard4Snakes11x11 =
+            wire.as_cell_board(&build_snake_id_map(&wire)).unwrap();
+        assert!(compact.is_over());
+        assert_eq!(compact.get_winner(), None);
+
+        // once only one of them is left it wins
+        for snake in wire.board.snakes.iter_mut().skip(1) {
+            snake.health = 0;
+        }
+        let last = wire.board.snakes[0].id.clone();
+        assert_ne!(last, you);
+        assert_eq!(wire.get_winner(), Some(last));
+    }
+}